@@ -33,6 +33,7 @@ pub fn run(version: VersionInfo) -> sc_cli::Result<()> {
 			subcommand.run(
 				config,
 				|config: _| Ok(new_full_start!(config).0),
+				|_| sc_finality_grandpa::snapshot_aux_keys(),
 			)
 		},
 		None => {
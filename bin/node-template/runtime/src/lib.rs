@@ -175,6 +175,7 @@ impl DoughnutRuntime for Runtime {
 	type Call = Call;
 	type Doughnut = <Self as system::Trait>::Doughnut;
 	type TimestampProvider = timestamp::Module<Runtime>;
+	type Authorisations = ();
 }
 
 impl aura::Trait for Runtime {
@@ -16,7 +16,8 @@
 
 //! Substrate chain configurations.
 
-use grandpa_primitives::AuthorityId as GrandpaId;
+use codec::Encode;
+use grandpa_primitives::{AuthorityId as GrandpaId, VersionedAuthorityList, GRANDPA_AUTHORITIES_KEY};
 use hex_literal::hex;
 use node_runtime::{
 	constants::{
@@ -24,7 +25,7 @@ use node_runtime::{
 		currency::*,
 	},
 	AuthorityDiscoveryConfig, BabeConfig, BalancesConfig, Block, ContractsConfig, CouncilConfig, DemocracyConfig,
-	GenericAssetConfig, GrandpaConfig, ImOnlineConfig, SessionConfig, SessionKeys, SocietyConfig, StakerStatus,
+	Forcing, GenericAssetConfig, GrandpaConfig, ImOnlineConfig, SessionConfig, SessionKeys, SocietyConfig, StakerStatus,
 	StakingConfig, SudoConfig, SystemConfig, TechnicalCommitteeConfig, WASM_BINARY,
 };
 use pallet_generic_asset::AssetInfo;
@@ -35,9 +36,15 @@ use sc_telemetry::TelemetryEndpoints;
 use serde::{Deserialize, Serialize};
 use sp_authority_discovery::AuthorityId as AuthorityDiscoveryId;
 use sp_consensus_babe::AuthorityId as BabeId;
-use sp_core::{crypto::UncheckedInto, sr25519, Pair, Public};
+use sp_core::{
+	crypto::UncheckedInto,
+	hashing::{twox_128, twox_64},
+	sr25519,
+	storage::Storage,
+	Pair, Public,
+};
 use sp_runtime::{
-	traits::{IdentifyAccount, Verify},
+	traits::{IdentifyAccount, OpaqueKeys, Verify},
 	Perbill,
 };
 
@@ -321,6 +328,8 @@ pub fn testnet_genesis(
 			max_members: 999,
 		}),
 		pallet_vesting: Some(Default::default()),
+		pallet_evm: Some(Default::default()),
+		prml_node_authorization: Some(Default::default()),
 	}
 }
 
@@ -375,6 +384,55 @@ pub fn local_testnet_config() -> ChainSpec {
 	)
 }
 
+fn storage_key(module: &str, item: &str) -> Vec<u8> {
+	[twox_128(module.as_bytes()), twox_128(item.as_bytes())].concat()
+}
+
+fn map_key(module: &str, item: &str, key: impl Encode) -> Vec<u8> {
+	let key = key.encode();
+	[storage_key(module, item), twox_64(&key).to_vec(), key].concat()
+}
+
+/// Rewrite the state of a live chain so that `Alice` is the only validator of a chain forked off
+/// it: the sudo key, the session keys of the validators and the BABE, GRANDPA, ImOnline and
+/// authority discovery authorities are replaced by the development keys of `Alice`.
+///
+/// BABE starts over from the first block of the forked chain and new eras are no longer
+/// triggered, so that the validator set isn't elected again from the live stakes.
+pub fn use_dev_keys(storage: &mut Storage) {
+	let (stash, _, grandpa, babe, im_online, authority_discovery) = get_authority_keys_from_seed("Alice");
+	let keys = session_keys(grandpa.clone(), babe.clone(), im_online.clone(), authority_discovery.clone());
+	let top = &mut storage.top;
+
+	top.insert(storage_key("Sudo", "Key"), get_account_id_from_seed::<sr25519::Public>("Alice").encode());
+
+	let next_keys = storage_key("Session", "NextKeys");
+	let key_owner = storage_key("Session", "KeyOwner");
+	top.retain(|key, _| !key.starts_with(&next_keys) && !key.starts_with(&key_owner));
+	top.insert(storage_key("Session", "Validators"), vec![stash.clone()].encode());
+	top.insert(storage_key("Session", "QueuedKeys"), vec![(stash.clone(), keys.clone())].encode());
+	top.remove(&storage_key("Session", "QueuedChanged"));
+	top.remove(&storage_key("Session", "DisabledValidators"));
+	top.insert(map_key("Session", "NextKeys", &stash), keys.encode());
+	for id in SessionKeys::key_ids() {
+		top.insert(map_key("Session", "KeyOwner", (*id, keys.get_raw(*id))), stash.encode());
+	}
+
+	top.insert(storage_key("Babe", "Authorities"), vec![(babe, 1u64)].encode());
+	for item in &["EpochIndex", "GenesisSlot", "CurrentSlot"] {
+		top.remove(&storage_key("Babe", item));
+	}
+
+	top.insert(GRANDPA_AUTHORITIES_KEY.to_vec(), VersionedAuthorityList::from(vec![(grandpa, 1)]).encode());
+	for item in &["State", "PendingChange", "NextForced", "Stalled", "CurrentSetId"] {
+		top.remove(&storage_key("GrandpaFinality", item));
+	}
+
+	top.insert(storage_key("ImOnline", "Keys"), vec![im_online].encode());
+	top.insert(storage_key("AuthorityDiscovery", "Keys"), vec![authority_discovery].encode());
+	top.insert(storage_key("Staking", "ForceEra"), Forcing::ForceNone.encode());
+}
+
 #[cfg(test)]
 pub(crate) mod tests {
 	use super::*;
@@ -445,4 +503,25 @@ pub(crate) mod tests {
 	fn test_staging_test_net_chain_spec() {
 		staging_testnet_config().build_storage().unwrap();
 	}
+
+	#[test]
+	fn test_use_dev_keys() {
+		use node_runtime::{Babe, Grandpa, Session, Staking, Sudo};
+
+		let mut storage = staging_testnet_config().build_storage().unwrap();
+		use_dev_keys(&mut storage);
+
+		let (stash, _, grandpa, babe, im_online, authority_discovery) = get_authority_keys_from_seed("Alice");
+		let keys = session_keys(grandpa.clone(), babe.clone(), im_online, authority_discovery);
+
+		sp_io::TestExternalities::new(storage).execute_with(|| {
+			assert_eq!(Sudo::key(), get_account_id_from_seed::<sr25519::Public>("Alice"));
+			assert_eq!(Session::validators(), vec![stash.clone()]);
+			assert_eq!(Session::queued_keys(), vec![(stash, keys)]);
+			assert_eq!(Babe::authorities(), vec![(babe, 1)]);
+			assert_eq!(Babe::genesis_slot(), 0);
+			assert_eq!(Grandpa::grandpa_authorities(), vec![(grandpa, 1)]);
+			assert_eq!(Staking::force_era(), Forcing::ForceNone);
+		});
+	}
 }
@@ -49,8 +49,13 @@ where
 				node_runtime::Block, node_runtime::RuntimeApi, node_executor::Executor,
 			>(&config)?;
 			let inspect = node_inspect::Inspector::<node_runtime::Block>::new(client);
+			let executor = node_executor::NativeExecutor::<node_executor::Executor>::new(
+				config.wasm_method,
+				config.default_heap_pages,
+				config.max_runtime_instances,
+			);
 
-			cmd.run(inspect)
+			cmd.run::<_, _, node_runtime::Event>(inspect, &executor)
 		},
 		Some(Subcommand::Benchmark(cmd)) => {
 			cmd.init(&version)?;
@@ -103,12 +108,26 @@ where
 
 			Ok(())
 		},
+		Some(Subcommand::Base(sc_cli::Subcommand::ExportChainSpec(cmd))) => {
+			cmd.init(&version)?;
+			cmd.update_config(&mut config, load_spec, &version)?;
+			cmd.run(
+				config,
+				|config: sc_service::Configuration| Ok(new_full_start!(config).0),
+				|storage| Ok(crate::chain_spec::use_dev_keys(storage)),
+			)
+		},
 		Some(Subcommand::Base(subcommand)) => {
 			subcommand.init(&version)?;
 			subcommand.update_config(&mut config, load_spec, &version)?;
 			subcommand.run(
 				config,
 				|config: sc_service::Configuration| Ok(new_full_start!(config).0),
+				|hash| {
+					let mut keys = grandpa::snapshot_aux_keys();
+					keys.extend(sc_consensus_babe::snapshot_aux_keys(hash));
+					keys
+				},
 			)
 		},
 	}
@@ -59,6 +59,7 @@ impl<Number> FactoryState<Number> {
 			frame_system::CheckWeight::new(),
 			pallet_transaction_payment::ChargeTransactionPayment::from(0),
 			Default::default(),
+			Default::default(),
 		)
 	}
 }
@@ -105,11 +105,21 @@ macro_rules! new_full_start {
 			.with_rpc_extensions(|builder| -> Result<RpcExtension, _> {
 				let babe_link = import_setup.as_ref().map(|s| &s.2)
 					.expect("BabeLink is present for full services or set up failed; qed.");
+				// Unsafe RPCs are only served when every RPC server listens on a local interface.
+				let config = builder.config();
+				let local = config.rpc_http.iter().chain(&config.rpc_ws).all(|addr| addr.ip().is_loopback());
+				let deny_unsafe = if local {
+					node_rpc::DenyUnsafe::No
+				} else {
+					node_rpc::DenyUnsafe::Yes
+				};
 				let deps = node_rpc::FullDeps {
 					client: builder.client().clone(),
 					pool: builder.pool(),
+					graph_pool: builder.pool().pool().clone(),
 					select_chain: builder.select_chain().cloned()
 						.expect("SelectChain is present for full services or set up failed; qed."),
+					deny_unsafe,
 					babe: node_rpc::BabeDeps {
 						keystore: builder.keystore(),
 						babe_config: sc_consensus_babe::BabeLink::config(babe_link).clone(),
@@ -139,12 +149,18 @@ macro_rules! new_full {
 			name,
 			disable_grandpa,
 			sentry_nodes,
+			reserved_nodes,
+			reserved_only,
 		) = (
 			$config.roles.is_authority(),
 			$config.force_authoring,
 			$config.name.clone(),
 			$config.disable_grandpa,
 			$config.network.sentry_nodes.clone(),
+			$config.network.reserved_nodes.iter()
+				.filter_map(|node| sc_network::config::parse_str_addr(node).ok().map(|(peer_id, _)| peer_id))
+				.collect(),
+			$config.network.non_reserved_mode == sc_network::config::NonReservedPeerMode::Deny,
 		);
 
 		// sentry nodes announce themselves as authorities to the network
@@ -262,6 +278,18 @@ macro_rules! new_full {
 			)?;
 		}
 
+		// restrict the peers of the node to those authorized on chain, if any,
+		// besides the configured reserved nodes
+		service.spawn_task(
+			"node-authorization",
+			prml_node_authorization_client::run(
+				service.client(),
+				service.network(),
+				reserved_nodes,
+				reserved_only,
+			),
+		);
+
 		Ok((service, inherent_data_providers))
 	}};
 	($config:expr) => {{
@@ -581,11 +609,12 @@ mod tests {
 					check_weight,
 					payment,
 					Default::default(),
+					Default::default(),
 				);
 				let raw_payload = SignedPayload::from_raw(
 					function,
 					extra,
-					((), version, genesis_hash, genesis_hash, (), (), (), ())
+					((), version, genesis_hash, genesis_hash, (), (), (), (), ())
 				);
 				let signature = raw_payload.using_encoded(|payload|	{
 					signer.sign(payload)
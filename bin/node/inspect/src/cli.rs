@@ -16,7 +16,7 @@
 
 //! Structs to easily compose inspect sub-command for CLI.
 
-use std::fmt::Debug;
+use std::{fmt::Debug, path::PathBuf};
 use sc_cli::{ImportParams, SharedParams};
 use structopt::StructOpt;
 
@@ -59,4 +59,47 @@ pub enum InspectSubCmd {
 		#[structopt(value_name = "BLOCK:INDEX or BYTES")]
 		input: String,
 	},
+	/// Re-execute block on top of the state of its parent and print out the storage changes it made.
+	StateDiff {
+		/// Address of the block to re-execute.
+		///
+		/// Can be either a block hash (no 0x prefix) or a number to retrieve existing block,
+		/// or a 0x-prefixed bytes hex string, representing SCALE encoding of
+		/// a block whose parent exists.
+		#[structopt(value_name = "HASH or NUMBER or BYTES")]
+		input: String,
+	},
+	/// Print out the storage entries of a pallet, using the runtime metadata to locate them.
+	Storage {
+		/// Storage prefix of the pallet, as in the runtime metadata (e.g. `Balances`).
+		pallet: String,
+		/// Name of the storage item (e.g. `Account`). All items of the pallet are printed
+		/// out if omitted.
+		item: Option<String>,
+		/// Block to read the storage at, either a block hash (no 0x prefix) or a number.
+		///
+		/// Defaults to the best block.
+		#[structopt(long, value_name = "HASH or NUMBER")]
+		at: Option<String>,
+	},
+	/// Decode events deposited by a block with native version of runtime and print them out.
+	Events {
+		/// Address of the block, either a block hash (no 0x prefix) or a number.
+		#[structopt(value_name = "HASH or NUMBER")]
+		input: String,
+	},
+	/// Apply a runtime upgrade on top of the state of a block and print out the weight it
+	/// consumed, the storage changes it made and the storage values that fail to decode.
+	///
+	/// Nothing is written to the database.
+	UpgradeDryRun {
+		/// Path to the wasm code of the new runtime.
+		#[structopt(parse(from_os_str), value_name = "WASM")]
+		wasm: PathBuf,
+		/// Block to apply the upgrade on, either a block hash (no 0x prefix) or a number.
+		///
+		/// Defaults to the best block.
+		#[structopt(long, value_name = "HASH or NUMBER")]
+		at: Option<String>,
+	},
 }
@@ -53,13 +53,18 @@ impl InspectCmd {
 	}
 
 	/// Run the inspect command, passing the inspector.
-	pub fn run<B, P>(
+	///
+	/// `E` is the event type of the runtime, used to decode the events of a block, and
+	/// `executor` is used to execute the wasm code of a runtime upgrade.
+	pub fn run<B, P, E>(
 		self,
 		inspect: Inspector<B, P>,
+		executor: &dyn sp_core::traits::CallInWasm,
 	) -> sc_cli::Result<()> where
 		B: sp_runtime::traits::Block,
 		B::Hash: FromStr,
 		P: PrettyPrinter<B>,
+		E: sp_runtime::traits::Member + codec::Codec + codec::EncodeLike,
 	{
 		match self.command {
 			InspectSubCmd::Block { input } => {
@@ -76,6 +81,36 @@ impl InspectCmd {
 				println!("{}", res);
 				Ok(())
 			},
+			InspectSubCmd::StateDiff { input } => {
+				let input = input.parse()?;
+				let res = inspect.state_diff(input)
+					.map_err(|e| format!("{}", e))?;
+				println!("{}", res);
+				Ok(())
+			},
+			InspectSubCmd::Storage { pallet, item, at } => {
+				let at = at.map(|at| at.parse()).transpose()?;
+				let res = inspect.storage(&pallet, item.as_ref().map(|item| item.as_str()), at)
+					.map_err(|e| format!("{}", e))?;
+				println!("{}", res);
+				Ok(())
+			},
+			InspectSubCmd::Events { input } => {
+				let input = input.parse()?;
+				let res = inspect.events::<E>(input)
+					.map_err(|e| format!("{}", e))?;
+				println!("{}", res);
+				Ok(())
+			},
+			InspectSubCmd::UpgradeDryRun { wasm, at } => {
+				let code = std::fs::read(&wasm)
+					.map_err(|e| format!("Could not read {}: {}", wasm.display(), e))?;
+				let at = at.map(|at| at.parse()).transpose()?;
+				let res = inspect.upgrade_dry_run(&code, at, executor)
+					.map_err(|e| format!("{}", e))?;
+				println!("{}", res);
+				Ok(())
+			},
 		}
 	}
 }
@@ -19,11 +19,20 @@
 //!
 //! The blocks and extrinsics can either be retrieved from the database (on-chain),
 //! or a raw SCALE-encoding can be provided.
+//!
+//! The state of the database can be inspected as well: the storage changes made by
+//! a block, the storage entries of a runtime storage item and the events deposited
+//! by a block.
+//!
+//! A runtime upgrade can also be tried out on top of the state of an existing block,
+//! without writing anything to the database.
 
 #![warn(missing_docs)]
 
 pub mod cli;
 pub mod command;
+pub mod state;
+pub mod upgrade;
 
 use std::{
 	fmt,
@@ -32,13 +41,14 @@ use std::{
 use codec::{Encode, Decode};
 use sc_client_api::BlockBackend;
 use sp_blockchain::HeaderBackend;
-use sp_core::hexdisplay::HexDisplay;
+use sp_core::{hexdisplay::HexDisplay, storage::StorageKey, traits::CallInWasm};
 use sp_runtime::{
 	generic::BlockId,
-	traits::{Block, HashFor, NumberFor, Hash}
+	traits::{Block, Header, HashFor, NumberFor, Hash, Member}
 };
 
 use command::{BlockAddress, ExtrinsicAddress};
+use state::{StateAccess, StorageItem, storage_items};
 
 /// A helper type for a generic block input.
 pub type BlockAddressFor<TBlock> = BlockAddress<
@@ -86,6 +96,8 @@ pub enum Error {
 	Blockchain(sp_blockchain::Error),
 	/// Given block has not been found.
 	NotFound(String),
+	/// Given input can't be used for the requested operation.
+	InvalidInput(String),
 }
 
 impl std::error::Error for Error {
@@ -94,19 +106,21 @@ impl std::error::Error for Error {
 			Self::Codec(ref e) => Some(e),
 			Self::Blockchain(ref e) => Some(e),
 			Self::NotFound(_) => None,
+			Self::InvalidInput(_) => None,
 		}
 	}
 }
 
-/// A helper trait to access block headers and bodies.
+/// A helper trait to access block headers, bodies and state.
 pub trait ChainAccess<TBlock: Block>:
 	HeaderBackend<TBlock> +
-	BlockBackend<TBlock>
+	BlockBackend<TBlock> +
+	StateAccess<TBlock>
 {}
 
 impl<T, TBlock> ChainAccess<TBlock> for T where
 	TBlock: Block,
-	T: sp_blockchain::HeaderBackend<TBlock> + sc_client_api::BlockBackend<TBlock>,
+	T: sp_blockchain::HeaderBackend<TBlock> + sc_client_api::BlockBackend<TBlock> + StateAccess<TBlock>,
 {}
 
 /// Blockchain inspector.
@@ -204,4 +218,142 @@ impl<TBlock: Block, TPrinter: PrettyPrinter<TBlock>> Inspector<TBlock, TPrinter>
 
 		Ok(format!("{}", ExtrinsicPrinter(ext, &self.printer)))
 	}
+
+	/// Get the storage changes made by a block, re-executing it on top of the state of its parent.
+	pub fn state_diff(&self, input: BlockAddressFor<TBlock>) -> Result<String, Error> {
+		let block = self.get_block(input)?;
+		let hash = block.header().hash();
+		let parent = BlockId::hash(*block.header().parent_hash());
+		let items = storage_items(&self.chain.metadata(&parent)?)?;
+		let changes = self.chain.execute_block(block)?;
+
+		let mut count = 0;
+		let mut out = String::new();
+		for (key, value) in changes {
+			let old = self.chain.storage(&parent, &StorageKey(key.clone()))?.map(|data| data.0);
+			// the runtime may write back the value it read
+			if old == value {
+				continue
+			}
+			count += 1;
+			out.push_str(&format!("- {}\n", fmt_key(&items, &key)));
+			out.push_str(&format!("  old: {}\n", fmt_value(old.as_ref())));
+			out.push_str(&format!("  new: {}\n", fmt_value(value.as_ref())));
+		}
+
+		Ok(format!("Storage changes of block {:?} ({})\n{}", hash, count, out))
+	}
+
+	/// Get the storage entries of a pallet, or of one of its storage items, at given block.
+	///
+	/// The pallet is identified by its storage prefix in the runtime metadata and the
+	/// best block is used if none is given.
+	pub fn storage(
+		&self,
+		pallet: &str,
+		item: Option<&str>,
+		at: Option<BlockAddressFor<TBlock>>,
+	) -> Result<String, Error> {
+		let at = match at {
+			Some(at) => self.block_id(at)?,
+			None => BlockId::hash(self.chain.info().best_hash),
+		};
+		let items = storage_items(&self.chain.metadata(&at)?)?
+			.into_iter()
+			.filter(|i| i.pallet == pallet && item.map_or(true, |item| i.name == item))
+			.collect::<Vec<_>>();
+		if items.is_empty() {
+			return Err(Error::NotFound(match item {
+				Some(item) => format!("Could not find storage item {}::{} in runtime metadata", pallet, item),
+				None => format!("Could not find storage of pallet {} in runtime metadata", pallet),
+			}))
+		}
+
+		let mut out = String::new();
+		for item in items {
+			let pairs = self.chain.storage_pairs(&at, &StorageKey(item.prefix.clone()))?;
+			out.push_str(&format!("{}::{}: {} ({})\n", item.pallet, item.name, item.ty, pairs.len()));
+			for (key, value) in pairs {
+				let key = &key.0[item.prefix.len()..];
+				if key.is_empty() {
+					out.push_str(&format!("- 0x{}\n", HexDisplay::from(&value.0)));
+				} else {
+					out.push_str(&format!("- 0x{}: 0x{}\n", HexDisplay::from(&key), HexDisplay::from(&value.0)));
+				}
+			}
+		}
+
+		Ok(out)
+	}
+
+	/// Get the events deposited by a block, decoded with the given runtime event type.
+	pub fn events<TEvent>(&self, input: BlockAddressFor<TBlock>) -> Result<String, Error> where
+		TEvent: Member + codec::Codec + codec::EncodeLike,
+	{
+		let id = self.block_id(input)?;
+		let key = StorageKey(state::item_prefix("System", "Events"));
+		let events: Vec<frame_system::EventRecord<TEvent, TBlock::Hash>> = match self.chain.storage(&id, &key)? {
+			Some(data) => Decode::decode(&mut &*data.0)?,
+			None => Vec::new(),
+		};
+
+		let mut out = format!("Events ({})\n", events.len());
+		for (idx, record) in events.iter().enumerate() {
+			out.push_str(&format!("- {}: {:?}\n", idx, record.phase));
+			out.push_str(&format!(" {:?}\n", record.event));
+			if !record.topics.is_empty() {
+				out.push_str(&format!(" Topics: {:?}\n", record.topics));
+			}
+		}
+
+		Ok(out)
+	}
+
+	/// Apply a runtime upgrade to the given wasm code on top of the state of given block
+	/// and report what it changed, without writing anything to the database.
+	///
+	/// The best block is used if none is given.
+	pub fn upgrade_dry_run(
+		&self,
+		code: &[u8],
+		at: Option<BlockAddressFor<TBlock>>,
+		executor: &dyn CallInWasm,
+	) -> Result<String, Error> {
+		let at = match at {
+			Some(at) => self.block_id(at)?,
+			None => BlockId::hash(self.chain.info().best_hash),
+		};
+		let report = upgrade::dry_run(&*self.chain, &at, code, executor)?;
+
+		Ok(report.to_string())
+	}
+
+	fn block_id(&self, input: BlockAddressFor<TBlock>) -> Result<BlockId<TBlock>, Error> {
+		match input {
+			BlockAddress::Hash(hash) => Ok(BlockId::hash(hash)),
+			BlockAddress::Number(number) => Ok(BlockId::number(number)),
+			BlockAddress::Bytes(_) => Err(Error::InvalidInput(
+				"Expected an existing block hash or number, got block bytes".into()
+			)),
+		}
+	}
+}
+
+fn fmt_key(items: &[StorageItem], key: &[u8]) -> String {
+	match items.iter().find(|item| key.starts_with(&item.prefix)) {
+		Some(item) => format!(
+			"{}::{} 0x{}",
+			item.pallet,
+			item.name,
+			HexDisplay::from(&&key[item.prefix.len()..]),
+		),
+		None => format!("0x{}", HexDisplay::from(&key)),
+	}
+}
+
+fn fmt_value(value: Option<&Vec<u8>>) -> String {
+	match value {
+		Some(value) => format!("0x{}", HexDisplay::from(value)),
+		None => "<none>".into(),
+	}
 }
@@ -0,0 +1,304 @@
+// Copyright 2020 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Access to the state of blocks and to the storage layout described by the runtime metadata.
+
+use codec::Decode;
+use frame_metadata::{DecodeDifferent, RuntimeMetadata, RuntimeMetadataPrefixed, StorageEntryType};
+use sc_client_api::{backend::Backend, CallExecutor, StorageProvider};
+use sp_api::{ApiExt, Core, Metadata, ProvideRuntimeApi};
+use sp_blockchain::HeaderBackend;
+use sp_core::{
+	hashing::{blake2_256, twox_128},
+	storage::{StorageData, StorageKey},
+	traits::CallInWasm,
+	OpaqueMetadata,
+};
+use sp_runtime::{
+	generic::BlockId,
+	traits::{Block, Header, HashFor, NumberFor},
+};
+use sp_state_machine::{Ext, OverlayedChanges, StorageTransactionCache};
+
+use crate::Error;
+
+/// Changes made to the main storage, a value of `None` meaning that it was deleted.
+pub type StorageChanges = Vec<(Vec<u8>, Option<Vec<u8>>)>;
+
+/// A helper trait to read the state of blocks and to re-execute them.
+pub trait StateAccess<TBlock: Block> {
+	/// Get the value of a storage entry at given block.
+	fn storage(
+		&self,
+		at: &BlockId<TBlock>,
+		key: &StorageKey,
+	) -> sp_blockchain::Result<Option<StorageData>>;
+
+	/// Get all storage entries under given prefix at given block.
+	fn storage_pairs(
+		&self,
+		at: &BlockId<TBlock>,
+		prefix: &StorageKey,
+	) -> sp_blockchain::Result<Vec<(StorageKey, StorageData)>>;
+
+	/// Get the SCALE-encoded runtime metadata at given block.
+	fn metadata(&self, at: &BlockId<TBlock>) -> sp_blockchain::Result<OpaqueMetadata>;
+
+	/// Execute the block on top of the state of its parent and return the changes
+	/// it made to the main storage.
+	fn execute_block(&self, block: TBlock) -> sp_blockchain::Result<StorageChanges>;
+
+	/// Call the given runtime methods one after another with the given wasm code, on top
+	/// of the state of given block, and return their outputs together with the changes
+	/// they made to the main storage.
+	///
+	/// The changes are kept in memory and never written to the database.
+	fn call_with_code(
+		&self,
+		at: &BlockId<TBlock>,
+		code: &[u8],
+		executor: &dyn CallInWasm,
+		calls: &[(&str, Vec<u8>)],
+	) -> sp_blockchain::Result<(Vec<Vec<u8>>, StorageChanges)>;
+}
+
+impl<B, E, TBlock, RA> StateAccess<TBlock> for sc_client::Client<B, E, TBlock, RA> where
+	TBlock: Block,
+	B: Backend<TBlock>,
+	E: CallExecutor<TBlock>,
+	Self: ProvideRuntimeApi<TBlock> + HeaderBackend<TBlock>,
+	<Self as ProvideRuntimeApi<TBlock>>::Api: Core<TBlock, Error = sp_blockchain::Error>
+		+ Metadata<TBlock, Error = sp_blockchain::Error>
+		+ ApiExt<TBlock, StateBackend = B::State>,
+{
+	fn storage(
+		&self,
+		at: &BlockId<TBlock>,
+		key: &StorageKey,
+	) -> sp_blockchain::Result<Option<StorageData>> {
+		StorageProvider::storage(self, at, key)
+	}
+
+	fn storage_pairs(
+		&self,
+		at: &BlockId<TBlock>,
+		prefix: &StorageKey,
+	) -> sp_blockchain::Result<Vec<(StorageKey, StorageData)>> {
+		StorageProvider::storage_pairs(self, at, prefix)
+	}
+
+	fn metadata(&self, at: &BlockId<TBlock>) -> sp_blockchain::Result<OpaqueMetadata> {
+		self.runtime_api().metadata(at)
+	}
+
+	fn execute_block(&self, block: TBlock) -> sp_blockchain::Result<StorageChanges> {
+		let parent_hash = *block.header().parent_hash();
+		let parent = BlockId::Hash(parent_hash);
+
+		let runtime_api = self.runtime_api();
+		runtime_api.execute_block(&parent, block)?;
+
+		let state = self.state_at(&parent)?;
+		let changes = runtime_api.into_storage_changes(&state, None, parent_hash)
+			.map_err(sp_blockchain::Error::Msg)?;
+
+		Ok(changes.main_storage_changes)
+	}
+
+	fn call_with_code(
+		&self,
+		at: &BlockId<TBlock>,
+		code: &[u8],
+		executor: &dyn CallInWasm,
+		calls: &[(&str, Vec<u8>)],
+	) -> sp_blockchain::Result<(Vec<Vec<u8>>, StorageChanges)> {
+		let parent_hash = self.expect_block_hash_from_id(at)?;
+		let state = self.state_at(at)?;
+		let code_hash = blake2_256(code).to_vec();
+
+		let mut overlay = OverlayedChanges::default();
+		let mut results = Vec::with_capacity(calls.len());
+		for (method, data) in calls {
+			let mut cache = StorageTransactionCache::<_, HashFor<TBlock>, NumberFor<TBlock>>::default();
+			let mut ext = Ext::new(&mut overlay, &mut cache, &state, None, None);
+			let result = executor.call_in_wasm(code, Some(code_hash.clone()), method, data, &mut ext)
+				.map_err(|e| sp_blockchain::Error::Msg(format!("Calling {} failed: {}", method, e)))?;
+			results.push(result);
+			overlay.commit_prospective();
+		}
+
+		let changes = overlay.into_storage_changes::<_, _, NumberFor<TBlock>>(
+			&state,
+			None,
+			parent_hash,
+			Default::default(),
+		).map_err(sp_blockchain::Error::Msg)?;
+
+		Ok((results, changes.main_storage_changes))
+	}
+}
+
+/// A storage item of the runtime.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StorageItem {
+	/// Name of the pallet declaring the item.
+	pub pallet: String,
+	/// Name of the item.
+	pub name: String,
+	/// Type of the item, as declared in the runtime.
+	pub ty: String,
+	/// Type of the values of the item.
+	pub value: String,
+	/// Prefix of the keys of the item in storage.
+	pub prefix: Vec<u8>,
+}
+
+/// Returns the storage prefix of all the items of a pallet.
+pub fn pallet_prefix(pallet: &str) -> Vec<u8> {
+	twox_128(pallet.as_bytes()).to_vec()
+}
+
+/// Returns the storage prefix of a storage item.
+pub fn item_prefix(pallet: &str, item: &str) -> Vec<u8> {
+	let mut prefix = pallet_prefix(pallet);
+	prefix.extend_from_slice(&twox_128(item.as_bytes()));
+	prefix
+}
+
+/// Read the storage items of all pallets from SCALE-encoded runtime metadata.
+pub fn storage_items(metadata: &[u8]) -> Result<Vec<StorageItem>, Error> {
+	let metadata = match RuntimeMetadataPrefixed::decode(&mut &*metadata)?.1 {
+		RuntimeMetadata::V11(metadata) => metadata,
+		_ => return Err(Error::InvalidInput("Unsupported runtime metadata version".into())),
+	};
+
+	let mut items = Vec::new();
+	for module in decoded(metadata.modules) {
+		let storage = match module.storage {
+			Some(storage) => decoded(storage),
+			None => continue,
+		};
+		let pallet = decoded(storage.prefix);
+		for entry in decoded(storage.entries) {
+			let name = decoded(entry.name);
+			items.push(StorageItem {
+				prefix: item_prefix(&pallet, &name),
+				pallet: pallet.clone(),
+				name,
+				value: value_type(&entry.ty),
+				ty: describe(entry.ty),
+			});
+		}
+	}
+
+	Ok(items)
+}
+
+fn describe(ty: StorageEntryType) -> String {
+	match ty {
+		StorageEntryType::Plain(value) => decoded(value),
+		StorageEntryType::Map { hasher, key, value, .. } => format!(
+			"map {:?}({}) => {}",
+			hasher, decoded(key), decoded(value),
+		),
+		StorageEntryType::DoubleMap { hasher, key1, key2, value, key2_hasher } => format!(
+			"double_map {:?}({}), {:?}({}) => {}",
+			hasher, decoded(key1), key2_hasher, decoded(key2), decoded(value),
+		),
+	}
+}
+
+fn value_type(ty: &StorageEntryType) -> String {
+	match ty {
+		StorageEntryType::Plain(value)
+			| StorageEntryType::Map { value, .. }
+			| StorageEntryType::DoubleMap { value, .. } => decoded(value.clone()),
+	}
+}
+
+fn decoded<B, O>(value: DecodeDifferent<B, O>) -> O {
+	match value {
+		DecodeDifferent::Decoded(value) => value,
+		DecodeDifferent::Encode(_) => unreachable!("Decoded metadata only contains decoded values; qed"),
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use codec::Encode;
+	use frame_metadata::{
+		ExtrinsicMetadata, ModuleMetadata, RuntimeMetadataLastVersion, StorageEntryMetadata,
+		StorageEntryModifier, StorageHasher, StorageMetadata,
+	};
+
+	fn entry(name: &str, ty: StorageEntryType) -> StorageEntryMetadata {
+		StorageEntryMetadata {
+			name: DecodeDifferent::Decoded(name.into()),
+			modifier: StorageEntryModifier::Default,
+			ty,
+			default: DecodeDifferent::Decoded(vec![0]),
+			documentation: DecodeDifferent::Decoded(vec![]),
+		}
+	}
+
+	#[test]
+	fn should_read_storage_items_from_metadata() {
+		let module = ModuleMetadata {
+			name: DecodeDifferent::Decoded("Balances".into()),
+			storage: Some(DecodeDifferent::Decoded(StorageMetadata {
+				prefix: DecodeDifferent::Decoded("Balances".into()),
+				entries: DecodeDifferent::Decoded(vec![
+					entry("TotalIssuance", StorageEntryType::Plain(DecodeDifferent::Decoded("Balance".into()))),
+					entry("Account", StorageEntryType::Map {
+						hasher: StorageHasher::Blake2_128Concat,
+						key: DecodeDifferent::Decoded("AccountId".into()),
+						value: DecodeDifferent::Decoded("AccountData".into()),
+						unused: false,
+					}),
+				]),
+			})),
+			calls: None,
+			event: None,
+			constants: DecodeDifferent::Decoded(vec![]),
+			errors: DecodeDifferent::Decoded(vec![]),
+		};
+		let metadata: RuntimeMetadataPrefixed = RuntimeMetadataLastVersion {
+			modules: DecodeDifferent::Decoded(vec![module]),
+			extrinsic: ExtrinsicMetadata { version: 4, signed_extensions: vec![] },
+		}.into();
+
+		let items = storage_items(&metadata.encode()).unwrap();
+
+		assert_eq!(items, vec![
+			StorageItem {
+				pallet: "Balances".into(),
+				name: "TotalIssuance".into(),
+				ty: "Balance".into(),
+				value: "Balance".into(),
+				prefix: item_prefix("Balances", "TotalIssuance"),
+			},
+			StorageItem {
+				pallet: "Balances".into(),
+				name: "Account".into(),
+				ty: "map Blake2_128Concat(AccountId) => AccountData".into(),
+				value: "AccountData".into(),
+				prefix: item_prefix("Balances", "Account"),
+			},
+		]);
+		assert!(items[1].prefix.starts_with(&pallet_prefix("Balances")));
+	}
+}
@@ -0,0 +1,356 @@
+// Copyright 2020 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Dry run of a runtime upgrade on top of the state of an existing block.
+//!
+//! The block following the chosen one is initialized twice in memory: once with the
+//! current runtime, as a baseline, and once with the new one, which runs the
+//! `OnRuntimeUpgrade` hooks. The difference between both runs is attributed to the
+//! upgrade, and all the storage values are then checked against the types declared
+//! in the metadata of the new runtime.
+
+use std::{collections::{BTreeMap, BTreeSet}, fmt};
+
+use codec::{Compact, Decode, Encode};
+use sp_core::{
+	hexdisplay::HexDisplay,
+	storage::{well_known_keys, StorageKey},
+	traits::CallInWasm,
+	OpaqueMetadata,
+};
+use sp_runtime::{
+	generic::BlockId,
+	traits::{Block, Header, One},
+};
+use sp_version::RuntimeVersion;
+
+use crate::{
+	fmt_key, fmt_value,
+	state::{item_prefix, storage_items},
+	ChainAccess, Error,
+};
+
+/// A storage value that is not a valid encoding of the type of its storage item.
+#[derive(Debug)]
+pub struct DecodeFailure {
+	/// Storage key of the value, formatted with the name of its storage item.
+	pub key: String,
+	/// Type the value was expected to be.
+	pub ty: String,
+	/// Why the value could not be decoded.
+	pub error: String,
+}
+
+/// Outcome of a runtime upgrade dry run.
+#[derive(Debug)]
+pub struct UpgradeReport<Hash> {
+	/// Block whose state the upgrade was applied on.
+	pub at: Hash,
+	/// Version of the current runtime.
+	pub old_version: RuntimeVersion,
+	/// Version of the new runtime.
+	pub new_version: RuntimeVersion,
+	/// Weight consumed by the upgrade on top of a regular block initialization.
+	pub weight: u32,
+	/// Storage entries changed by the upgrade, with their old and new values.
+	pub changes: Vec<(String, Option<Vec<u8>>, Option<Vec<u8>>)>,
+	/// Number of storage values successfully decoded.
+	pub checked: usize,
+	/// Number of storage values whose type is unknown and which were not checked.
+	pub unchecked: usize,
+	/// Storage values that could not be decoded.
+	pub failures: Vec<DecodeFailure>,
+}
+
+impl<Hash: fmt::Debug> fmt::Display for UpgradeReport<Hash> {
+	fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+		writeln!(fmt, "Runtime upgrade dry run on top of block {:?}", self.at)?;
+		writeln!(fmt, "Old runtime: {}", self.old_version)?;
+		writeln!(fmt, "New runtime: {}", self.new_version)?;
+		writeln!(fmt, "Weight of the upgrade: {}", self.weight)?;
+		writeln!(fmt, "Storage changes ({})", self.changes.len())?;
+		for (key, old, new) in &self.changes {
+			writeln!(fmt, "- {}", key)?;
+			writeln!(fmt, "  old: {}", fmt_value(old.as_ref()))?;
+			writeln!(fmt, "  new: {}", fmt_value(new.as_ref()))?;
+		}
+		writeln!(
+			fmt,
+			"Storage values: {} decoded, {} of unknown types skipped",
+			self.checked,
+			self.unchecked,
+		)?;
+		writeln!(fmt, "Decode failures ({})", self.failures.len())?;
+		for failure in &self.failures {
+			writeln!(fmt, "- {}: {}: {}", failure.key, failure.ty, failure.error)?;
+		}
+		Ok(())
+	}
+}
+
+/// Apply the runtime upgrade to the given wasm code on top of the state of given block.
+///
+/// Nothing is written to the database.
+pub fn dry_run<TBlock: Block>(
+	chain: &dyn ChainAccess<TBlock>,
+	at: &BlockId<TBlock>,
+	code: &[u8],
+	executor: &dyn CallInWasm,
+) -> Result<UpgradeReport<TBlock::Hash>, Error> {
+	let header = chain.header(at.clone())?
+		.ok_or_else(|| Error::NotFound(format!("Could not find block {:?}", at)))?;
+	let hash = header.hash();
+	let next = TBlock::Header::new(
+		*header.number() + One::one(),
+		Default::default(),
+		Default::default(),
+		hash,
+		Default::default(),
+	);
+
+	let old_code = chain.storage(at, &StorageKey(well_known_keys::CODE.to_vec()))?
+		.ok_or_else(|| Error::NotFound("Could not find the runtime code in storage".into()))?
+		.0;
+	let (old_results, old_changes) = chain.call_with_code(at, &old_code, executor, &[
+		("Core_initialize_block", next.encode()),
+		("Core_version", Vec::new()),
+	])?;
+	let (new_results, new_changes) = chain.call_with_code(at, code, executor, &[
+		("Core_initialize_block", next.encode()),
+		("Core_version", Vec::new()),
+		("Metadata_metadata", Vec::new()),
+	])?;
+	let old_version = RuntimeVersion::decode(&mut &old_results[1][..])?;
+	let new_version = RuntimeVersion::decode(&mut &new_results[1][..])?;
+	let metadata = OpaqueMetadata::decode(&mut &new_results[2][..])?;
+	let items = storage_items(&metadata)?;
+
+	let old_changes = old_changes.into_iter().collect::<BTreeMap<_, _>>();
+	let new_changes = new_changes.into_iter().collect::<BTreeMap<_, _>>();
+
+	// the weight of the regular `on_initialize` hooks is registered by both runs
+	let weight_key = item_prefix("System", "AllExtrinsicsWeight");
+	let weight = weight_of(&new_changes, &weight_key).saturating_sub(weight_of(&old_changes, &weight_key));
+
+	let mut changes = Vec::new();
+	for key in old_changes.keys().chain(new_changes.keys()).collect::<BTreeSet<_>>() {
+		let stored = || chain.storage(at, &StorageKey(key.clone())).map(|data| data.map(|data| data.0));
+		let old = match old_changes.get(key) {
+			Some(value) => value.clone(),
+			None => stored()?,
+		};
+		let new = match new_changes.get(key) {
+			Some(value) => value.clone(),
+			None => stored()?,
+		};
+		if old != new {
+			changes.push((fmt_key(&items, key), old, new));
+		}
+	}
+
+	let mut checked = 0;
+	let mut unchecked = 0;
+	let mut failures = Vec::new();
+	for item in &items {
+		let mut values = chain.storage_pairs(at, &StorageKey(item.prefix.clone()))?
+			.into_iter()
+			.map(|(key, value)| (key.0, value.0))
+			.collect::<BTreeMap<_, _>>();
+		apply(&mut values, &new_changes, &item.prefix);
+
+		for (key, value) in values {
+			match check_value(&item.value, &value) {
+				None => unchecked += 1,
+				Some(Ok(())) => checked += 1,
+				Some(Err(error)) => failures.push(DecodeFailure {
+					key: format!("{} 0x{}", fmt_key(&items, &key), HexDisplay::from(&value)),
+					ty: item.value.clone(),
+					error,
+				}),
+			}
+		}
+	}
+
+	Ok(UpgradeReport {
+		at: hash,
+		old_version,
+		new_version,
+		weight,
+		changes,
+		checked,
+		unchecked,
+		failures,
+	})
+}
+
+fn weight_of(changes: &BTreeMap<Vec<u8>, Option<Vec<u8>>>, key: &[u8]) -> u32 {
+	// `Weight` is a `u32` in this version of the runtime
+	changes.get(key)
+		.and_then(|value| value.as_ref())
+		.and_then(|value| u32::decode(&mut &value[..]).ok())
+		.unwrap_or_default()
+}
+
+fn apply(values: &mut BTreeMap<Vec<u8>, Vec<u8>>, changes: &BTreeMap<Vec<u8>, Option<Vec<u8>>>, prefix: &[u8]) {
+	for (key, value) in changes.range(prefix.to_vec()..).take_while(|(key, _)| key.starts_with(prefix)) {
+		match value {
+			Some(value) => values.insert(key.clone(), value.clone()),
+			None => values.remove(key),
+		};
+	}
+}
+
+/// Check that `data` is the SCALE encoding of a value of the type named `ty`, as found
+/// in the runtime metadata.
+///
+/// Returns `None` if the type is unknown, that is neither a primitive type, a well-known
+/// type of the node nor a `Vec`, `Option`, `BTreeMap`, tuple or array of those.
+pub fn check_value(ty: &str, data: &[u8]) -> Option<Result<(), String>> {
+	let mut input = data;
+	match skip(ty, &mut input) {
+		Ok(None) => None,
+		Ok(Some(())) if input.is_empty() => Some(Ok(())),
+		Ok(Some(())) => Some(Err(format!("{} trailing bytes", input.len()))),
+		Err(e) => Some(Err(e.what().into())),
+	}
+}
+
+fn skip(ty: &str, input: &mut &[u8]) -> Result<Option<()>, codec::Error> {
+	let ty = ty.trim().trim_start_matches("T::");
+
+	if let Some(len) = fixed_size(ty) {
+		return take(input, len).map(Some)
+	}
+	if ty == "bool" {
+		return match u8::decode(input)? {
+			0 | 1 => Ok(Some(())),
+			_ => Err("Invalid boolean".into()),
+		}
+	}
+	if ty.starts_with('(') && ty.ends_with(')') {
+		return skip_all(split(&ty[1..ty.len() - 1], ','), input)
+	}
+	if ty.starts_with('[') && ty.ends_with(']') {
+		let parts = split(&ty[1..ty.len() - 1], ';');
+		return match (parts.as_slice(), parts.get(1).and_then(|len| len.parse::<usize>().ok())) {
+			([inner, _], Some(len)) => skip_all(std::iter::repeat(*inner).take(len), input),
+			_ => Ok(None),
+		}
+	}
+
+	let start = match ty.find('<') {
+		Some(start) if ty.ends_with('>') => start,
+		_ => return Ok(None),
+	};
+	let args = split(&ty[start + 1..ty.len() - 1], ',');
+	match (&ty[..start], args.as_slice()) {
+		("Vec", [inner]) => {
+			let len = Compact::<u32>::decode(input)?.0 as usize;
+			match fixed_size(inner) {
+				Some(size) => take(input, len.saturating_mul(size)).map(Some),
+				None => skip_all(std::iter::repeat(*inner).take(len), input),
+			}
+		},
+		("BTreeMap", [key, value]) => {
+			let len = Compact::<u32>::decode(input)?.0 as usize;
+			skip_all([*key, *value].iter().cloned().cycle().take(len.saturating_mul(2)), input)
+		},
+		("Option", [inner]) => match u8::decode(input)? {
+			0 => Ok(Some(())),
+			1 => skip(inner, input),
+			_ => Err("Invalid Option".into()),
+		},
+		("Compact", [_]) => Compact::<u128>::decode(input).map(|_| Some(())),
+		("BalanceOf", _) => take(input, 16).map(Some),
+		_ => Ok(None),
+	}
+}
+
+fn skip_all<'a>(types: impl IntoIterator<Item = &'a str>, input: &mut &[u8]) -> Result<Option<()>, codec::Error> {
+	for ty in types {
+		if skip(ty, input)?.is_none() {
+			return Ok(None)
+		}
+	}
+	Ok(Some(()))
+}
+
+fn fixed_size(ty: &str) -> Option<usize> {
+	Some(match ty {
+		"u8" | "i8" | "Percent" => 1,
+		"u16" | "i16" => 2,
+		"u32" | "i32" | "BlockNumber" | "Index" | "SessionIndex" | "EraIndex" | "Weight"
+			| "Perbill" | "Permill" => 4,
+		"u64" | "i64" | "Moment" | "SetId" => 8,
+		"u128" | "i128" | "Balance" => 16,
+		"H160" => 20,
+		"AccountId" | "Hash" | "H256" | "AuthorityId" => 32,
+		"H512" => 64,
+		_ => return None,
+	})
+}
+
+fn take(input: &mut &[u8], len: usize) -> Result<(), codec::Error> {
+	if input.len() < len {
+		return Err("Not enough data to fill buffer".into())
+	}
+	*input = &input[len..];
+	Ok(())
+}
+
+/// Split a list of types on the given separator, ignoring the separators of nested types.
+fn split(list: &str, separator: char) -> Vec<&str> {
+	let mut parts = Vec::new();
+	let mut depth = 0;
+	let mut start = 0;
+	for (i, c) in list.char_indices() {
+		match c {
+			'<' | '(' | '[' => depth += 1,
+			'>' | ')' | ']' => depth -= 1,
+			c if c == separator && depth == 0 => {
+				parts.push(list[start..i].trim());
+				start = i + 1;
+			},
+			_ => {},
+		}
+	}
+	parts.push(list[start..].trim());
+	parts.retain(|part| !part.is_empty());
+	parts
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn should_check_values_against_type_names() {
+		assert_eq!(check_value("T::BlockNumber", &5u32.encode()), Some(Ok(())));
+		assert_eq!(check_value("bool", &[2]), Some(Err("Invalid boolean".into())));
+		assert_eq!(check_value("u64", &5u32.encode()), Some(Err("Not enough data to fill buffer".into())));
+		assert_eq!(check_value("u32", &5u64.encode()), Some(Err("4 trailing bytes".into())));
+		assert_eq!(
+			check_value("Vec<(T::AccountId, BalanceOf<T>)>", &vec![([1u8; 32], 10u128)].encode()),
+			Some(Ok(())),
+		);
+		assert_eq!(check_value("Option<[u8; 4]>", &Some([1u8; 4]).encode()), Some(Ok(())));
+		assert_eq!(check_value("(u32, Vec<u8>,)", &(1u32, vec![1u8, 2]).encode()), Some(Ok(())));
+		assert_eq!(check_value("Compact<Balance>", &Compact(7u128).encode()), Some(Ok(())));
+		assert_eq!(check_value("Vec<Exposure<AccountId, Balance>>", &vec![0u8; 0].encode()), Some(Ok(())));
+		assert_eq!(check_value("Vec<Exposure<AccountId, Balance>>", &vec![0u8; 1].encode()), None);
+		assert_eq!(check_value("AccountData", &[0]), None);
+	}
+}
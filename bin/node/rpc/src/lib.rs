@@ -31,7 +31,8 @@
 
 use std::{sync::Arc, fmt};
 
-use node_primitives::{Block, BlockNumber, AccountId, Index, Balance, AssetId};
+use codec::Encode;
+use node_primitives::{Block, BlockNumber, AccountId, Index, Balance, AssetId, Hash};
 use sp_api::ProvideRuntimeApi;
 use sp_transaction_pool::TransactionPool;
 use sp_blockchain::{Error as BlockChainError, HeaderMetadata, HeaderBackend};
@@ -41,6 +42,10 @@ use sp_consensus_babe::BabeApi;
 use sc_consensus_epochs::SharedEpochChanges;
 use sc_consensus_babe::{Config, Epoch};
 use sc_consensus_babe_rpc::BabeRPCHandler;
+use sc_transaction_graph::ChainApi;
+use sp_runtime::OpaqueExtrinsic;
+
+pub use sc_rpc_api::DenyUnsafe;
 
 /// Light client extra dependencies.
 pub struct LightDeps<C, F, P> {
@@ -65,44 +70,69 @@ pub struct BabeDeps {
 }
 
 /// Full client dependencies.
-pub struct FullDeps<C, P, SC> {
+pub struct FullDeps<C, P, SC, A: ChainApi> {
 	/// The client instance to use.
 	pub client: Arc<C>,
 	/// Transaction pool instance.
 	pub pool: Arc<P>,
+	/// Transaction pool internals, for the pool inspection RPCs.
+	pub graph_pool: Arc<sc_transaction_graph::Pool<A>>,
 	/// The SelectChain Strategy
 	pub select_chain: SC,
+	/// Whether to deny unsafe calls
+	pub deny_unsafe: DenyUnsafe,
 	/// BABE specific dependencies.
 	pub babe: BabeDeps,
 }
 
+/// Wraps raw Ethereum transactions into unsigned `EVM::transact` extrinsics.
+pub struct TransactionConverter;
+
+impl pallet_evm_rpc::ConvertTransaction<OpaqueExtrinsic> for TransactionConverter {
+	fn convert_transaction(&self, transaction: &[u8]) -> Result<OpaqueExtrinsic, String> {
+		let transaction = pallet_evm::EthereumTransaction::decode_rlp(transaction)
+			.map_err(|e| format!("{:?}", e))?;
+		let call = node_runtime::Call::EVM(pallet_evm::Call::transact(transaction));
+		let extrinsic = node_runtime::UncheckedExtrinsic::new_unsigned(call);
+
+		Ok(OpaqueExtrinsic(extrinsic.encode()))
+	}
+}
+
 /// Instantiate all Full RPC extensions.
-pub fn create_full<C, P, M, SC>(
-	deps: FullDeps<C, P, SC>,
+pub fn create_full<C, P, M, SC, A>(
+	deps: FullDeps<C, P, SC, A>,
 ) -> jsonrpc_core::IoHandler<M> where
 	C: ProvideRuntimeApi<Block>,
 	C: HeaderBackend<Block> + HeaderMetadata<Block, Error=BlockChainError> + 'static,
+	C: sc_client::BlockBackend<Block>,
 	C: Send + Sync + 'static,
 	C::Api: substrate_frame_rpc_system::AccountNonceApi<Block, AccountId, Index>,
-	C::Api: pallet_contracts_rpc::ContractsRuntimeApi<Block, AccountId, Balance, BlockNumber>,
+	C::Api: pallet_contracts_rpc::ContractsRuntimeApi<Block, AccountId, Balance, BlockNumber, Hash>,
 	C::Api: pallet_transaction_payment_rpc::TransactionPaymentRuntimeApi<Block, Balance>,
 	C::Api: pallet_generic_asset_rpc::AssetMetaApi<Block, AssetId>,
+	C::Api: pallet_evm_rpc::EVMRuntimeApi<Block>,
 	C::Api: BabeApi<Block>,
 	<C::Api as sp_api::ApiErrorExt>::Error: fmt::Debug,
-	P: TransactionPool + 'static,
+	P: TransactionPool<Block=Block> + 'static,
 	M: jsonrpc_core::Metadata + Default,
 	SC: SelectChain<Block> +'static,
+	A: ChainApi + 'static,
 {
 	use substrate_frame_rpc_system::{FullSystem, SystemApi};
 	use pallet_contracts_rpc::{Contracts, ContractsApi};
 	use pallet_transaction_payment_rpc::{TransactionPayment, TransactionPaymentApi};
 	use pallet_generic_asset_rpc::{GenericAsset, GenericAssetApi};
+	use pallet_evm_rpc::{Debug, DebugApi, Eth, EthApi};
+	use sc_transaction_pool_rpc::{TxPool, TxPoolApi};
 
 	let mut io = jsonrpc_core::IoHandler::default();
 	let FullDeps {
 		client,
 		pool,
+		graph_pool,
 		select_chain,
+		deny_unsafe,
 		babe
 	} = deps;
 	let BabeDeps {
@@ -112,7 +142,7 @@ pub fn create_full<C, P, M, SC>(
 	} = babe;
 
 	io.extend_with(
-		SystemApi::to_delegate(FullSystem::new(client.clone(), pool))
+		SystemApi::to_delegate(FullSystem::new(client.clone(), pool.clone()))
 	);
 	// Making synchronous calls in light client freezes the browser currently,
 	// more context: https://github.com/paritytech/substrate/pull/3480
@@ -129,6 +159,9 @@ pub fn create_full<C, P, M, SC>(
 		)
 	);
 	io.extend_with(GenericAssetApi::to_delegate(GenericAsset::new(client.clone())));
+	io.extend_with(EthApi::to_delegate(Eth::new(client.clone(), pool, TransactionConverter)));
+	io.extend_with(DebugApi::to_delegate(Debug::new(client.clone())));
+	io.extend_with(TxPoolApi::to_delegate(TxPool::new(graph_pool, deny_unsafe)));
 
 	io
 }
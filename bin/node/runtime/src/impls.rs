@@ -16,11 +16,16 @@
 
 //! Some configurable implementations as associated type for the substrate runtime.
 
-use node_primitives::Balance;
+use sp_std::prelude::*;
+use codec::{Encode, Decode};
+use node_primitives::{AccountId, AssetId, Balance};
 use sp_runtime::traits::{Convert, Saturating};
-use sp_runtime::{Fixed64, Perbill};
+use sp_runtime::{DispatchError, Fixed64, Perbill};
 use frame_support::{traits::{OnUnbalanced, Currency, Get}, weights::Weight};
-use crate::{Balances, System, Authorship, MaximumBlockWeight, NegativeImbalance};
+use pallet_contracts::{ChainExtension, Environment, Gas};
+use crate::{
+	Balances, System, Authorship, MaximumBlockWeight, NegativeImbalance, Runtime, Call, GenericAsset,
+};
 
 pub struct Author;
 impl OnUnbalanced<NegativeImbalance> for Author {
@@ -45,6 +50,40 @@ impl Convert<u128, Balance> for CurrencyToVoteHandler {
 	fn convert(x: u128) -> Balance { x * Self::factor() }
 }
 
+/// Gas charged by `GenericAssetExtension` for reading a balance.
+const GENERIC_ASSET_READ_GAS: Gas = 100;
+
+/// Gas charged by `GenericAssetExtension` for a transfer, on top of the dispatch fee.
+const GENERIC_ASSET_TRANSFER_GAS: Gas = 100;
+
+/// Chain extension giving contracts access to the generic assets.
+///
+/// - `0`: takes `(AssetId, AccountId)` and returns the free balance of the account as `Balance`.
+/// - `1`: takes `(AssetId, AccountId, Balance)` and transfers the amount from the calling
+///   contract to the account, once the contract has returned successfully.
+pub struct GenericAssetExtension;
+
+impl ChainExtension<Runtime> for GenericAssetExtension {
+	fn call(func_id: u32, input: Vec<u8>, env: &mut Environment<Runtime>) -> Result<Vec<u8>, DispatchError> {
+		match func_id {
+			0 => {
+				env.charge_gas(GENERIC_ASSET_READ_GAS)?;
+				let (asset_id, who) = <(AssetId, AccountId)>::decode(&mut &input[..])
+					.map_err(|_| "invalid generic asset balance query")?;
+				Ok(GenericAsset::free_balance(&asset_id, &who).encode())
+			},
+			1 => {
+				env.charge_gas(GENERIC_ASSET_TRANSFER_GAS)?;
+				let (asset_id, to, amount) = <(AssetId, AccountId, Balance)>::decode(&mut &input[..])
+					.map_err(|_| "invalid generic asset transfer")?;
+				env.dispatch(Call::GenericAsset(pallet_generic_asset::Call::transfer(asset_id, to, amount)));
+				Ok(Vec::new())
+			},
+			_ => Err("unknown generic asset extension function".into()),
+		}
+	}
+}
+
 /// Convert from weight to balance via a simple coefficient multiplication
 /// The associated type C encapsulates a constant in units of balance per weight
 pub struct LinearWeightToFee<C>(sp_std::marker::PhantomData<C>);
@@ -29,7 +29,7 @@ use frame_support::{
 use sp_core::{
 	crypto::KeyTypeId,
 	u32_trait::{_1, _2, _3, _4},
-	OpaqueMetadata,
+	OpaqueMetadata, H160, H256, U256,
 };
 pub use node_primitives::{AccountId, AssetId, Signature};
 use node_primitives::{Balance, BlockNumber, Hash, Index, Moment};
@@ -54,7 +54,9 @@ use pallet_grandpa::fg_primitives;
 use pallet_im_online::sr25519::{AuthorityId as ImOnlineId};
 use sp_authority_discovery::AuthorityId as AuthorityDiscoveryId;
 use pallet_transaction_payment_rpc_runtime_api::RuntimeDispatchInfo;
-use pallet_contracts_rpc_runtime_api::ContractExecResult;
+use pallet_contracts_rpc_runtime_api::{
+	Code, CodeUploadResult, ContractExecResult, ContractInstantiateResult,
+};
 use pallet_session::{historical as pallet_session_historical};
 use frame_system::offchain::TransactionSubmitter;
 use sp_inherents::{InherentData, CheckInherentsResult};
@@ -65,11 +67,12 @@ pub use pallet_timestamp::Call as TimestampCall;
 pub use pallet_balances::Call as BalancesCall;
 pub use pallet_contracts::Gas;
 pub use frame_support::StorageValue;
-pub use pallet_staking::StakerStatus;
+pub use pallet_staking::{Forcing, StakerStatus};
 
 /// Implementations of some helper traits passed into runtime modules as associated types.
 pub mod impls;
-use impls::{Author, CurrencyToVoteHandler, LinearWeightToFee, TargetedFeeAdjustment};
+use pallet_evm::FeeCalculator;
+use impls::{Author, CurrencyToVoteHandler, GenericAssetExtension, LinearWeightToFee, TargetedFeeAdjustment};
 
 /// Constant values used within the runtime.
 pub mod constants;
@@ -88,7 +91,7 @@ pub const VERSION: RuntimeVersion = RuntimeVersion {
 	// and set impl_version to 0. If only runtime
 	// implementation changes and behavior does not, then leave spec_version as
 	// is and increment impl_version.
-	spec_version: 241,
+	spec_version: 249,
 	impl_version: 0,
 	apis: RUNTIME_API_VERSIONS,
 };
@@ -162,6 +165,7 @@ impl prml_doughnut::DoughnutRuntime for Runtime {
 	type Call = Call;
 	type Doughnut = <Self as frame_system::Trait>::Doughnut;
 	type TimestampProvider = pallet_timestamp::Module<Runtime>;
+	type Authorisations = Contracts;
 }
 
 impl pallet_generic_asset::Trait for Runtime {
@@ -468,6 +472,8 @@ parameter_types! {
 
 impl pallet_contracts::Trait for Runtime {
 	type Currency = Balances;
+	type MultiCurrency = GenericAsset;
+	type AssetId = AssetId;
 	type Time = Timestamp;
 	type Randomness = RandomnessCollectiveFlip;
 	type Call = Call;
@@ -493,6 +499,7 @@ impl pallet_contracts::Trait for Runtime {
 	type MaxDepth = pallet_contracts::DefaultMaxDepth;
 	type MaxValueSize = pallet_contracts::DefaultMaxValueSize;
 	type BlockGasLimit = pallet_contracts::DefaultBlockGasLimit;
+	type ChainExtension = GenericAssetExtension;
 }
 
 impl pallet_sudo::Trait for Runtime {
@@ -590,6 +597,7 @@ impl frame_system::offchain::CreateTransaction<Runtime, UncheckedExtrinsic> for
 			frame_system::CheckWeight::<Runtime>::new(),
 			pallet_transaction_payment::ChargeTransactionPayment::<Runtime>::from(tip),
 			Default::default(),
+			Default::default(),
 		);
 		let raw_payload = SignedPayload::new(call, extra).map_err(|e| {
 			debug::warn!("Unable to create signed payload: {:?}", e);
@@ -654,6 +662,46 @@ impl pallet_vesting::Trait for Runtime {
 	type MinVestedTransfer = MinVestedTransfer;
 }
 
+parameter_types! {
+	pub const EVMChainId: u64 = 42;
+	// 21_000 gas, the cost of a plain transfer, is about a cent.
+	pub const EVMMinGasPrice: u128 = MILLICENTS / 20;
+	pub const EVMBlockGasLimit: u32 = 10_000_000;
+	// 10_000_000 gas weighs half of the maximum block weight.
+	pub const EVMWeightPerGas: Weight = 50;
+}
+
+impl pallet_evm::Trait for Runtime {
+	type FeeCalculator = pallet_evm::FixedGasPrice<EVMMinGasPrice>;
+	type ConvertAccountId = pallet_evm::HashTruncateConvertAccountId<BlakeTwo256>;
+	type Currency = Balances;
+	type AccountBalances = pallet_evm::EVMAccountBalances<Self>;
+	type OnChargeEVMFee = pallet_evm::EVMCurrencyAdapter<DealWithFees>;
+	type FindAuthor = pallet_session::FindAccountFromAuthorIndex<Self, Babe>;
+	type Event = Event;
+	type Precompiles = (
+		pallet_evm_precompiles::GenericAssetPrecompiles<Self, pallet_evm::HashedAddressMapping<BlakeTwo256>>,
+		pallet_evm_precompiles::DoughnutPrecompile<Self>,
+	);
+	type ChainId = EVMChainId;
+	type GasToWeight = pallet_evm::LinearGasToWeight<EVMWeightPerGas>;
+	type BlockGasLimit = EVMBlockGasLimit;
+}
+
+parameter_types! {
+	pub const MaxWellKnownNodes: u32 = 64;
+	pub const MaxPeerIdLength: u32 = 128;
+	pub const MaxAdditionalConnections: u32 = 64;
+}
+
+impl prml_node_authorization::Trait for Runtime {
+	type Event = Event;
+	type MaxWellKnownNodes = MaxWellKnownNodes;
+	type MaxPeerIdLength = MaxPeerIdLength;
+	type MaxAdditionalConnections = MaxAdditionalConnections;
+	type AdminOrigin = frame_system::EnsureRoot<AccountId, Self::Doughnut>;
+}
+
 construct_runtime!(
 	pub enum Runtime where
 		Block = Block,
@@ -689,6 +737,8 @@ construct_runtime!(
 		Society: pallet_society::{Module, Call, Storage, Event<T>, Config<T>},
 		Recovery: pallet_recovery::{Module, Call, Storage, Event<T>},
 		Vesting: pallet_vesting::{Module, Call, Storage, Event<T>, Config<T>},
+		EVM: pallet_evm::{Module, Call, Storage, Config, Event<T>, ValidateUnsigned},
+		NodeAuthorization: prml_node_authorization::{Module, Call, Storage, Event<T>, Config<T>},
 	}
 );
 
@@ -713,6 +763,7 @@ pub type SignedExtra = (
 	frame_system::CheckWeight<Runtime>,
 	pallet_transaction_payment::ChargeTransactionPayment<Runtime>,
 	pallet_contracts::CheckBlockGasLimit<Runtime>,
+	pallet_evm::CheckBlockGasLimit<Runtime>,
 );
 /// Unchecked extrinsic type as expected by this runtime.
 pub type UncheckedExtrinsic = generic::UncheckedExtrinsic<Address, Call, Signature, SignedExtra>;
@@ -850,7 +901,7 @@ impl_runtime_apis! {
 		}
 	}
 
-	impl pallet_contracts_rpc_runtime_api::ContractsApi<Block, AccountId, Balance, BlockNumber>
+	impl pallet_contracts_rpc_runtime_api::ContractsApi<Block, AccountId, Balance, BlockNumber, Hash>
 		for Runtime
 	{
 		fn call(
@@ -871,6 +922,36 @@ impl_runtime_apis! {
 			}
 		}
 
+		fn instantiate(
+			origin: AccountId,
+			endowment: Balance,
+			gas_limit: u64,
+			code: Code<Hash>,
+			data: Vec<u8>,
+		) -> ContractInstantiateResult<AccountId> {
+			let (address, exec_result, gas_consumed, events) =
+				Contracts::bare_instantiate(origin, endowment, gas_limit, code, data);
+			ContractInstantiateResult {
+				address,
+				gas_consumed,
+				events: events.iter().map(codec::Encode::encode).collect(),
+				result: match exec_result {
+					Ok(v) => ContractExecResult::Success {
+						status: v.status,
+						data: v.data,
+					},
+					Err(_) => ContractExecResult::Error,
+				},
+			}
+		}
+
+		fn upload_code(code: Vec<u8>) -> CodeUploadResult<Hash> {
+			match Contracts::bare_upload_code(code) {
+				Ok((code_hash, gas_consumed)) => CodeUploadResult::Success { code_hash, gas_consumed },
+				Err(reason) => CodeUploadResult::Error(reason.as_bytes().to_vec()),
+			}
+		}
+
 		fn get_storage(
 			address: AccountId,
 			key: [u8; 32],
@@ -885,6 +966,94 @@ impl_runtime_apis! {
 		}
 	}
 
+	impl pallet_evm_rpc_runtime_api::EVMRuntimeApi<Block> for Runtime {
+		fn chain_id() -> u64 {
+			EVMChainId::get()
+		}
+
+		fn gas_price() -> U256 {
+			<Runtime as pallet_evm::Trait>::FeeCalculator::min_gas_price()
+		}
+
+		fn account_basic(address: H160) -> pallet_evm::Account {
+			EVM::account_basic(&address)
+		}
+
+		fn account_code_at(address: H160) -> Vec<u8> {
+			EVM::account_code_at(&address)
+		}
+
+		fn storage_at(address: H160, index: U256) -> H256 {
+			let mut key = [0u8; 32];
+			index.to_big_endian(&mut key);
+			EVM::account_storage_at(&address, H256::from(key))
+		}
+
+		fn block_gas_limit() -> U256 {
+			U256::from(EVMBlockGasLimit::get())
+		}
+
+		fn timestamp() -> u64 {
+			Timestamp::now() / 1000
+		}
+
+		fn block_receipts() -> Vec<pallet_evm::Receipt> {
+			EVM::current_receipts()
+		}
+
+		fn block_logs_bloom() -> pallet_evm::Bloom {
+			EVM::current_logs_bloom()
+		}
+
+		fn dry_run(
+			from: H160,
+			to: Option<H160>,
+			data: Vec<u8>,
+			value: U256,
+			gas_limit: U256,
+			gas_price: Option<U256>,
+		) -> Result<pallet_evm::ExecutionInfo, sp_runtime::DispatchError> {
+			EVM::dry_run(
+				from,
+				to,
+				data,
+				value,
+				gas_limit.min(U256::from(u32::max_value())).low_u32(),
+				gas_price.unwrap_or_else(<Runtime as pallet_evm::Trait>::FeeCalculator::min_gas_price),
+			).map_err(Into::into)
+		}
+
+		fn trace_call(
+			from: H160,
+			to: Option<H160>,
+			data: Vec<u8>,
+			value: U256,
+			gas_limit: U256,
+			gas_price: Option<U256>,
+		) -> Result<Option<pallet_evm::Trace>, sp_runtime::DispatchError> {
+			EVM::trace_call(
+				from,
+				to,
+				data,
+				value,
+				gas_limit.min(U256::from(u32::max_value())).low_u32(),
+				gas_price.unwrap_or_else(<Runtime as pallet_evm::Trait>::FeeCalculator::min_gas_price),
+			).map_err(Into::into)
+		}
+
+		fn trace_block(
+			header: <Block as BlockT>::Header,
+			extrinsics: Vec<<Block as BlockT>::Extrinsic>,
+		) -> Option<Vec<pallet_evm::Trace>> {
+			Executive::initialize_block(&header);
+			pallet_evm::tracing::trace(|| {
+				for extrinsic in extrinsics {
+					let _ = Executive::apply_extrinsic(extrinsic);
+				}
+			}).1
+		}
+	}
+
 	impl pallet_transaction_payment_rpc_runtime_api::TransactionPaymentApi<
 		Block,
 		Balance,
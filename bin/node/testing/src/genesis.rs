@@ -128,5 +128,7 @@ pub fn config_endowed(
 			max_members: 999,
 		}),
 		pallet_vesting: Some(Default::default()),
+		pallet_evm: Some(Default::default()),
+		prml_node_authorization: Some(Default::default()),
 	}
 }
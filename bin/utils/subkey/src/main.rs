@@ -685,6 +685,7 @@ fn create_extrinsic<C: Crypto>(
 			frame_system::CheckWeight::<Runtime>::new(),
 			pallet_transaction_payment::ChargeTransactionPayment::<Runtime>::from(f),
 			Default::default(),
+			Default::default(),
 		)
 	};
 	let raw_payload = SignedPayload::from_raw(
@@ -699,6 +700,7 @@ fn create_extrinsic<C: Crypto>(
 			(),
 			(),
 			(),
+			(),
 		),
 	);
 	let signature = raw_payload.using_encoded(|payload| signer.sign(payload)).into_runtime();
@@ -241,6 +241,16 @@ pub trait AuxStore {
 	fn get_aux(&self, key: &[u8]) -> sp_blockchain::Result<Option<Vec<u8>>>;
 }
 
+/// Auxiliary data key marking the justification of the block with the given hash as required,
+/// e.g. by a finality gadget for the blocks enacting authority set changes.
+///
+/// Blocks pruning never deletes the justifications of marked blocks.
+pub fn keep_justification_aux_key<Block: BlockT>(hash: &Block::Hash) -> Vec<u8> {
+	let mut key = b"keep_justification".to_vec();
+	key.extend_from_slice(hash.as_ref());
+	key
+}
+
 /// An `Iterator` that iterates keys in a given block under a prefix.
 pub struct KeyIterator<'a, State, Block> {
 	state: State,
@@ -34,6 +34,7 @@ enum GenesisSource<G> {
 	File(PathBuf),
 	Binary(Cow<'static, [u8]>),
 	Factory(Arc<dyn Fn() -> G + Send + Sync>),
+	Storage(Storage),
 }
 
 impl<G> Clone for GenesisSource<G> {
@@ -42,6 +43,7 @@ impl<G> Clone for GenesisSource<G> {
 			GenesisSource::File(ref path) => GenesisSource::File(path.clone()),
 			GenesisSource::Binary(ref d) => GenesisSource::Binary(d.clone()),
 			GenesisSource::Factory(ref f) => GenesisSource::Factory(f.clone()),
+			GenesisSource::Storage(ref storage) => GenesisSource::Storage(storage.clone()),
 		}
 	}
 }
@@ -67,6 +69,7 @@ impl<G: RuntimeGenesis> GenesisSource<G> {
 				Ok(genesis.genesis)
 			},
 			GenesisSource::Factory(f) => Ok(Genesis::Runtime(f())),
+			GenesisSource::Storage(storage) => Ok(Genesis::Raw(RawGenesis::from(storage.clone()))),
 		}
 	}
 }
@@ -122,6 +125,31 @@ struct RawGenesis {
 	children: HashMap<StorageKey, ChildRawStorage>,
 }
 
+impl From<Storage> for RawGenesis {
+	fn from(storage: Storage) -> Self {
+		let top = storage.top.into_iter()
+			.map(|(k, v)| (StorageKey(k), StorageData(v)))
+			.collect();
+		let children = storage.children.into_iter()
+			.map(|(sk, child)| {
+				let info = child.child_info.as_ref();
+				let (info, ci_type) = info.info();
+				(
+					StorageKey(sk),
+					ChildRawStorage {
+						data: child.data.into_iter()
+							.map(|(k, v)| (StorageKey(k), StorageData(v)))
+							.collect(),
+						child_info: info.to_vec(),
+						child_type: ci_type,
+					},
+			)})
+			.collect();
+
+		RawGenesis { top, children }
+	}
+}
+
 #[derive(Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 #[serde(deny_unknown_fields)]
@@ -210,6 +238,17 @@ impl<G, E> ChainSpec<G, E> {
 		self.client_spec.boot_nodes.push(addr.to_string())
 	}
 
+	/// Remove all bootnodes from the list.
+	pub fn clear_boot_nodes(&mut self) {
+		self.client_spec.boot_nodes.clear()
+	}
+
+	/// Replace the spec id and the network protocol id.
+	pub fn set_id(&mut self, id: &str, protocol_id: Option<&str>) {
+		self.client_spec.id = id.to_owned();
+		self.client_spec.protocol_id = protocol_id.map(str::to_owned);
+	}
+
 	/// Returns a reference to defined chain spec extensions.
 	pub fn extensions(&self) -> &E {
 		&self.client_spec.extensions
@@ -243,6 +282,11 @@ impl<G, E> ChainSpec<G, E> {
 			genesis: GenesisSource::Factory(Arc::new(constructor)),
 		}
 	}
+
+	/// Replace the genesis of the spec by the given raw storage.
+	pub fn set_storage(&mut self, storage: Storage) {
+		self.genesis = GenesisSource::Storage(storage);
+	}
 }
 
 impl<G, E: serde::de::DeserializeOwned> ChainSpec<G, E> {
@@ -281,29 +325,7 @@ impl<G: RuntimeGenesis, E: serde::Serialize + Clone> ChainSpec<G, E> {
 
 		};
 		let genesis = match (raw, self.genesis.resolve()?) {
-			(true, Genesis::Runtime(g)) => {
-				let storage = g.build_storage()?;
-				let top = storage.top.into_iter()
-					.map(|(k, v)| (StorageKey(k), StorageData(v)))
-					.collect();
-				let children = storage.children.into_iter()
-					.map(|(sk, child)| {
-						let info = child.child_info.as_ref();
-						let (info, ci_type) = info.info();
-						(
-							StorageKey(sk),
-							ChildRawStorage {
-								data: child.data.into_iter()
-									.map(|(k, v)| (StorageKey(k), StorageData(v)))
-									.collect(),
-								child_info: info.to_vec(),
-								child_type: ci_type,
-							},
-					)})
-					.collect();
-
-				Genesis::Raw(RawGenesis { top, children })
-			},
+			(true, Genesis::Runtime(g)) => Genesis::Raw(RawGenesis::from(g.build_storage()?)),
 			(_, genesis) => genesis,
 		};
 		let container = Container {
@@ -348,6 +370,14 @@ where
 		ChainSpec::add_boot_node(self, addr)
 	}
 
+	fn clear_boot_nodes(&mut self) {
+		ChainSpec::clear_boot_nodes(self)
+	}
+
+	fn set_id(&mut self, id: &str, protocol_id: Option<&str>) {
+		ChainSpec::set_id(self, id, protocol_id)
+	}
+
 	fn extensions(&self) -> &dyn GetExtension {
 		ChainSpec::extensions(self) as &dyn GetExtension
 	}
@@ -359,6 +389,10 @@ where
 	fn as_storage_builder(&self) -> &dyn BuildStorage {
 		self
 	}
+
+	fn set_storage(&mut self, storage: Storage) {
+		ChainSpec::set_storage(self, storage)
+	}
 }
 
 #[cfg(test)]
@@ -410,4 +444,41 @@ mod tests {
 
 		assert_eq!(spec.extensions().my_property, "Test Extension");
 	}
+
+	#[test]
+	fn should_use_raw_storage_as_genesis() {
+		let mut spec = TestSpec::from_json_bytes(Cow::Owned(
+			include_bytes!("../res/chain_spec.json").to_vec()
+		)).unwrap();
+		let mut storage = Storage::default();
+		storage.top.insert(b"key".to_vec(), b"value".to_vec());
+		storage.children.insert(b":child_storage:default:child".to_vec(), StorageChild {
+			data: vec![(b"child".to_vec(), b"value".to_vec())].into_iter().collect(),
+			child_info: ChildInfo::new_default(b"child").to_owned(),
+		});
+
+		spec.set_storage(storage.clone());
+		let json = spec.as_json(true).unwrap();
+		let spec = TestSpec::from_json_bytes(Cow::Owned(json.into_bytes())).unwrap();
+
+		let built = spec.build_storage().unwrap();
+		assert_eq!(built.top, storage.top);
+		assert_eq!(built.children.keys().collect::<Vec<_>>(), storage.children.keys().collect::<Vec<_>>());
+	}
+
+	#[test]
+	fn should_replace_id_and_clear_boot_nodes() {
+		let mut spec = TestSpec::from_json_bytes(Cow::Owned(
+			include_bytes!("../res/chain_spec.json").to_vec()
+		)).unwrap();
+
+		spec.clear_boot_nodes();
+		spec.set_id("fork", Some("frk"));
+		let json = spec.as_json(true).unwrap();
+		let spec = TestSpec::from_json_bytes(Cow::Owned(json.into_bytes())).unwrap();
+
+		assert!(spec.boot_nodes().is_empty());
+		assert_eq!(spec.id(), "fork");
+		assert_eq!(spec.protocol_id(), Some("frk"));
+	}
 }
@@ -117,6 +117,7 @@ pub use sc_chain_spec_derive::{ChainSpecExtension, ChainSpecGroup};
 
 use serde::{Serialize, de::DeserializeOwned};
 use sp_runtime::BuildStorage;
+use sp_core::storage::Storage;
 use sc_network::Multiaddr;
 use sc_telemetry::TelemetryEndpoints;
 
@@ -144,8 +145,14 @@ pub trait ChainSpec: BuildStorage + Send {
 	fn extensions(&self) -> &dyn GetExtension;
 	/// Add a bootnode to the list.
 	fn add_boot_node(&mut self, addr: Multiaddr);
+	/// Remove all bootnodes from the list.
+	fn clear_boot_nodes(&mut self);
+	/// Replace the spec id and the network protocol id.
+	fn set_id(&mut self, id: &str, protocol_id: Option<&str>);
 	/// Return spec as JSON.
 	fn as_json(&self, raw: bool) -> Result<String, String>;
 	/// Return StorageBuilder for this spec.
 	fn as_storage_builder(&self) -> &dyn BuildStorage;
+	/// Replace the genesis of the spec by the given raw storage.
+	fn set_storage(&mut self, storage: Storage);
 }
@@ -0,0 +1,121 @@
+// Copyright 2020 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+use std::io::Write;
+use std::fs;
+use std::path::PathBuf;
+use std::fmt::Debug;
+use log::info;
+use structopt::StructOpt;
+use sc_service::{
+	Configuration, ServiceBuilderCommand, ChainSpec,
+	config::DatabaseConfig, Roles,
+};
+use sp_core::storage::Storage;
+use sp_runtime::traits::{Block as BlockT, Header as HeaderT};
+
+use crate::error;
+use crate::VersionInfo;
+use crate::params::{SharedParams, PruningParams};
+use super::parse_block_id;
+
+/// The `export-chain-spec` command used to build a chain spec from the state of a block.
+#[derive(Debug, StructOpt, Clone)]
+pub struct ExportChainSpecCmd {
+	/// Hash or number of the block whose state becomes the genesis of the chain spec.
+	#[structopt(long = "at", value_name = "HASH or NUMBER")]
+	pub at: String,
+
+	/// Replace the sudo key, the session keys of the validators and the GRANDPA authorities
+	/// of the state by development keys.
+	#[structopt(long = "dev-keys")]
+	pub dev_keys: bool,
+
+	/// Output file name or stdout if unspecified.
+	#[structopt(long = "output", short = "o", parse(from_os_str))]
+	pub output: Option<PathBuf>,
+
+	#[allow(missing_docs)]
+	#[structopt(flatten)]
+	pub shared_params: SharedParams,
+
+	#[allow(missing_docs)]
+	#[structopt(flatten)]
+	pub pruning_params: PruningParams,
+}
+
+impl ExportChainSpecCmd {
+	/// Initialize
+	pub fn init(&self, version: &VersionInfo) -> error::Result<()> {
+		self.shared_params.init(version)
+	}
+
+	/// Run the export-chain-spec command, applying `dev_keys` to the state when development
+	/// keys are requested.
+	pub fn run<B, BC, BB>(
+		self,
+		config: Configuration,
+		builder: B,
+		dev_keys: impl FnOnce(&mut Storage) -> Result<(), sc_service::error::Error>,
+	) -> error::Result<()>
+	where
+		B: FnOnce(Configuration) -> Result<BC, sc_service::error::Error>,
+		BC: ServiceBuilderCommand<Block = BB> + Unpin,
+		BB: sp_runtime::traits::Block + Debug,
+		<<<BB as BlockT>::Header as HeaderT>::Number as std::str::FromStr>::Err: std::fmt::Debug,
+		<BB as BlockT>::Hash: std::str::FromStr,
+	{
+		if let DatabaseConfig::Path { ref path, .. } = config.expect_database() {
+			info!("DB path: {}", path.display());
+		}
+
+		let block_id = parse_block_id(&self.at)?;
+		let use_dev_keys = self.dev_keys;
+		let json = builder(config)?.export_chain_spec(block_id, |storage| {
+			if use_dev_keys {
+				dev_keys(storage)?;
+			}
+			Ok(())
+		})?;
+
+		match &self.output {
+			Some(filename) => fs::write(filename, json)?,
+			None => {
+				let mut stdout = std::io::stdout();
+				stdout.write_all(json.as_bytes())?;
+				stdout.flush()?;
+			},
+		}
+
+		Ok(())
+	}
+
+	/// Update and prepare a `Configuration` with command line parameters
+	pub fn update_config<F>(
+		&self,
+		mut config: &mut Configuration,
+		spec_factory: F,
+		version: &VersionInfo,
+	) -> error::Result<()> where
+		F: FnOnce(&str) -> Result<Box<dyn ChainSpec>, String>,
+	{
+		self.shared_params.update_config(&mut config, spec_factory, version)?;
+		self.pruning_params.update_config(&mut config, Roles::FULL, true)?;
+		config.use_in_memory_keystore()?;
+
+		Ok(())
+	}
+}
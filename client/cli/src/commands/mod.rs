@@ -21,12 +21,17 @@ mod import_blocks_cmd;
 mod check_block_cmd;
 mod revert_cmd;
 mod purge_chain_cmd;
+mod export_state_cmd;
+mod import_state_cmd;
+mod export_chain_spec_cmd;
 
 use std::fmt::Debug;
+use std::str::FromStr;
 use structopt::StructOpt;
 
 use sc_service::{ Configuration, ServiceBuilderCommand, ChainSpec };
 use sp_runtime::traits::{Block as BlockT, Header as HeaderT};
+use sp_runtime::generic::BlockId;
 
 use crate::error;
 use crate::VersionInfo;
@@ -39,6 +44,23 @@ pub use crate::commands::import_blocks_cmd::ImportBlocksCmd;
 pub use crate::commands::check_block_cmd::CheckBlockCmd;
 pub use crate::commands::revert_cmd::RevertCmd;
 pub use crate::commands::purge_chain_cmd::PurgeChainCmd;
+pub use crate::commands::export_state_cmd::ExportStateCmd;
+pub use crate::commands::import_state_cmd::ImportStateCmd;
+pub use crate::commands::export_chain_spec_cmd::ExportChainSpecCmd;
+
+/// Parse a block hash, with or without `0x` prefix, or a block number.
+fn parse_block_id<B: BlockT>(input: &str) -> error::Result<BlockId<B>> where
+	B::Hash: FromStr,
+{
+	let hash = if input.starts_with("0x") { &input[2..] } else { input };
+	match FromStr::from_str(hash) {
+		Ok(hash) => Ok(BlockId::hash(hash)),
+		Err(_) => match input.parse::<u32>() {
+			Ok(n) => Ok(BlockId::number(n.into())),
+			Err(_) => Err(error::Error::Input("Invalid hash or number specified".into())),
+		}
+	}
+}
 
 /// default sub directory to store network config
 const DEFAULT_NETWORK_CONFIG_PATH : &'static str = "network";
@@ -67,6 +89,15 @@ pub enum Subcommand {
 
 	/// Remove the whole chain data.
 	PurgeChain(purge_chain_cmd::PurgeChainCmd),
+
+	/// Export a snapshot of the state of a finalized block to a file.
+	ExportState(export_state_cmd::ExportStateCmd),
+
+	/// Seed an empty database with a state snapshot.
+	ImportState(import_state_cmd::ImportStateCmd),
+
+	/// Export a raw chain spec whose genesis is the state of a block.
+	ExportChainSpec(export_chain_spec_cmd::ExportChainSpecCmd),
 }
 
 impl Subcommand {
@@ -81,14 +112,21 @@ impl Subcommand {
 			CheckBlock(params) => &params.shared_params,
 			Revert(params) => &params.shared_params,
 			PurgeChain(params) => &params.shared_params,
+			ExportState(params) => &params.shared_params,
+			ImportState(params) => &params.shared_params,
+			ExportChainSpec(params) => &params.shared_params,
 		}
 	}
 
 	/// Run any `CoreParams` command.
+	///
+	/// `aux_keys` returns the keys of the auxiliary data the consensus engines of the node need
+	/// to continue from the block with the given hash, exported along with its state.
 	pub fn run<B, BC, BB>(
 		self,
 		config: Configuration,
 		builder: B,
+		aux_keys: impl FnOnce(&BB::Hash) -> Vec<Vec<u8>>,
 	) -> error::Result<()>
 	where
 		B: FnOnce(Configuration) -> Result<BC, sc_service::error::Error>,
@@ -104,6 +142,11 @@ impl Subcommand {
 			Subcommand::CheckBlock(cmd) => cmd.run(config, builder),
 			Subcommand::PurgeChain(cmd) => cmd.run(config),
 			Subcommand::Revert(cmd) => cmd.run(config, builder),
+			Subcommand::ExportState(cmd) => cmd.run(config, builder, aux_keys),
+			Subcommand::ImportState(cmd) => cmd.run::<BB>(config),
+			Subcommand::ExportChainSpec(cmd) => cmd.run(config, builder, |_| Err(
+				"Rewriting the keys of the state is not supported by this node".into()
+			)),
 		}
 	}
 
@@ -123,6 +166,9 @@ impl Subcommand {
 			Subcommand::CheckBlock(cmd) => cmd.update_config(&mut config, spec_factory, version),
 			Subcommand::PurgeChain(cmd) => cmd.update_config(&mut config, spec_factory, version),
 			Subcommand::Revert(cmd) => cmd.update_config(&mut config, spec_factory, version),
+			Subcommand::ExportState(cmd) => cmd.update_config(&mut config, spec_factory, version),
+			Subcommand::ImportState(cmd) => cmd.update_config(&mut config, spec_factory, version),
+			Subcommand::ExportChainSpec(cmd) => cmd.update_config(&mut config, spec_factory, version),
 		}
 	}
 
@@ -15,7 +15,7 @@
 // along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
 
 use structopt::StructOpt;
-use sc_service::{Configuration, PruningMode};
+use sc_service::{BlocksPruning, Configuration, PruningMode};
 
 use crate::error;
 
@@ -29,6 +29,21 @@ pub struct PruningParams {
 	/// 256 blocks.
 	#[structopt(long = "pruning", value_name = "PRUNING_MODE")]
 	pub pruning: Option<String>,
+
+	/// Specify the blocks pruning mode, a number of finalized blocks to keep the body of,
+	/// at least 1, or 'archive'.
+	///
+	/// This is independent of the state pruning mode. Default is to keep the bodies of
+	/// all blocks (i.e. 'archive').
+	#[structopt(long = "blocks-pruning", value_name = "BLOCKS_PRUNING_MODE")]
+	pub blocks_pruning: Option<String>,
+
+	/// Delete the justifications of the blocks whose body is pruned, except the ones
+	/// required by the finality gadget, such as those of authority set changes.
+	///
+	/// Requires a number of blocks to keep as blocks pruning mode.
+	#[structopt(long = "prune-justifications", requires = "blocks-pruning")]
+	pub prune_justifications: bool,
 }
 
 impl PruningParams {
@@ -61,6 +76,36 @@ impl PruningParams {
 			},
 		};
 
+		config.blocks_pruning = match &self.blocks_pruning {
+			None => BlocksPruning::ArchiveAll,
+			Some(ref s) if s == "archive" => {
+				if self.prune_justifications {
+					return Err(error::Error::Input(
+						"Justifications can only be pruned along with block bodies, \
+						`--prune-justifications` requires a number of blocks for `--blocks-pruning`".to_string()
+					));
+				}
+
+				BlocksPruning::ArchiveAll
+			},
+			Some(s) => {
+				let blocks: u32 = s.parse()
+					.map_err(|_| error::Error::Input("Invalid blocks pruning mode specified".to_string()))?;
+
+				// finalization notifications still read the body of the last finalized block
+				if blocks == 0 {
+					return Err(error::Error::Input(
+						"The number of finalized blocks to keep must be at least 1".to_string()
+					));
+				}
+
+				BlocksPruning::KeepFinalized {
+					blocks,
+					justifications: self.prune_justifications,
+				}
+			},
+		};
+
 		Ok(())
 	}
 }
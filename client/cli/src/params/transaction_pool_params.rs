@@ -15,9 +15,14 @@
 // along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
 
 use structopt::StructOpt;
-use sc_service::Configuration;
+use sc_service::{
+	Configuration, TransactionPoolJournalOptions, TransactionPoolLimit, TransactionPoolSenderLimit,
+};
 use crate::error;
 
+/// Path of the transaction pool journal in the chain directory.
+const DEFAULT_TRANSACTION_POOL_JOURNAL_PATH: &str = "transactions.journal";
+
 /// Parameters used to create the pool configuration.
 #[derive(Debug, StructOpt, Clone)]
 pub struct TransactionPoolParams {
@@ -27,6 +32,45 @@ pub struct TransactionPoolParams {
 	/// Maximum number of kilobytes of all transactions stored in the pool.
 	#[structopt(long = "pool-kbytes", value_name = "COUNT", default_value = "20480")]
 	pub pool_kbytes: usize,
+	/// Maximum number of transactions of a single sender in the transaction pool.
+	///
+	/// When the pool is full, the lowest priority transactions of the sender with most
+	/// transactions are dropped first.
+	#[structopt(long = "pool-sender-limit", value_name = "COUNT")]
+	pub pool_sender_limit: Option<usize>,
+	/// Length of the first tag provided by a transaction and of its leading bytes identifying
+	/// its sender, as `TAG_BYTES:PREFIX_BYTES`.
+	///
+	/// Transactions with a tag of any other length have no sender. The defaults match the
+	/// `(AccountId, Index)` tags of signed transactions and `(H160, U256)` tags of EVM
+	/// transactions.
+	#[structopt(
+		long = "pool-sender-tag-prefix",
+		value_name = "TAG_BYTES:PREFIX_BYTES",
+		parse(try_from_str = parse_tag_prefix),
+		use_delimiter = true,
+		default_value = "36:32,52:20",
+	)]
+	pub pool_sender_tag_prefix: Vec<(usize, usize)>,
+	/// Maximum number of transactions from external sources, i.e. the network and RPC,
+	/// in the transaction pool.
+	#[structopt(long = "pool-external-limit", value_name = "COUNT")]
+	pub pool_external_limit: Option<usize>,
+	/// Maximum number of kilobytes of transactions from external sources in the transaction pool.
+	#[structopt(long = "pool-external-kbytes", value_name = "COUNT")]
+	pub pool_external_kbytes: Option<usize>,
+	/// Persist the transactions submitted to the pool by the node and through RPC to a
+	/// journal in the chain directory, resubmitted to the pool when the node restarts.
+	///
+	/// Transactions received from the network are not journaled.
+	#[structopt(long = "pool-journal")]
+	pub pool_journal: bool,
+	/// Maximum number of transactions kept in the transaction pool journal.
+	#[structopt(long = "pool-journal-limit", value_name = "COUNT", default_value = "4096")]
+	pub pool_journal_limit: usize,
+	/// Number of blocks between rewrites of the transaction pool journal with the pool content.
+	#[structopt(long = "pool-journal-rotate", value_name = "BLOCKS", default_value = "16")]
+	pub pool_journal_rotate: u32,
 }
 
 impl TransactionPoolParams {
@@ -44,6 +88,39 @@ impl TransactionPoolParams {
 		config.transaction_pool.future.count = self.pool_limit / factor;
 		config.transaction_pool.future.total_bytes = self.pool_kbytes * 1024 / factor;
 
+		// quotas
+		config.transaction_pool.senders = self.pool_sender_limit.map(|count| TransactionPoolSenderLimit {
+			count,
+			tag_prefixes: self.pool_sender_tag_prefix.clone(),
+		});
+		if self.pool_external_limit.is_some() || self.pool_external_kbytes.is_some() {
+			config.transaction_pool.external = Some(TransactionPoolLimit {
+				count: self.pool_external_limit.unwrap_or(self.pool_limit),
+				total_bytes: self.pool_external_kbytes.unwrap_or(self.pool_kbytes) * 1024,
+			});
+		}
+
+		// journal
+		if self.pool_journal {
+			let path = config.in_chain_config_dir(DEFAULT_TRANSACTION_POOL_JOURNAL_PATH)
+				.ok_or_else(|| error::Error::Input(
+					"The transaction pool journal requires a base path".to_string()
+				))?;
+			config.transaction_pool.journal = Some(TransactionPoolJournalOptions {
+				path,
+				max_transactions: self.pool_journal_limit,
+				rotate_period: self.pool_journal_rotate,
+			});
+		}
+
 		Ok(())
 	}
 }
+
+fn parse_tag_prefix(s: &str) -> Result<(usize, usize), String> {
+	let mut lengths = s.splitn(2, ':').map(|length| length.trim().parse::<usize>());
+	match (lengths.next(), lengths.next()) {
+		(Some(Ok(tag)), Some(Ok(prefix))) if prefix <= tag => Ok((tag, prefix)),
+		_ => Err(format!("Invalid sender tag prefix `{}`, expected `TAG_BYTES:PREFIX_BYTES`", s)),
+	}
+}
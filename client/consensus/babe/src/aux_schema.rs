@@ -36,6 +36,16 @@ fn block_weight_key<H: Encode>(block_hash: H) -> Vec<u8> {
 	(b"block_weight", block_hash).encode()
 }
 
+/// Keys of the auxiliary data needed to continue from the block with the given hash without
+/// the history before it, e.g. when the database is seeded with a state snapshot.
+pub fn snapshot_aux_keys<H: Encode>(block_hash: H) -> Vec<Vec<u8>> {
+	vec![
+		BABE_EPOCH_CHANGES_VERSION.to_vec(),
+		BABE_EPOCH_CHANGES_KEY.to_vec(),
+		block_weight_key(block_hash),
+	]
+}
+
 fn load_decode<B, T>(backend: &B, key: &[u8]) -> ClientResult<Option<T>>
 	where
 		B: AuxStore,
@@ -30,6 +30,7 @@ mod codes {
 	pub const CONSENSUS_ERROR: i64 = 14_000;
 	pub const INHERENTS_ERROR: i64 = 15_000;
 	pub const BLOCKCHAIN_ERROR: i64 = 16_000;
+	pub const INVALID_TIMESTAMP: i64 = 17_000;
 	pub const UNKNOWN_ERROR: i64 = 20_000;
 }
 
@@ -56,6 +57,10 @@ pub enum Error {
 	#[display(fmt = "Supplied parent_hash: {} doesn't exist in chain", _0)]
 	#[from(ignore)]
 	BlockNotFound(String),
+	/// The requested block timestamp couldn't be resolved
+	#[display(fmt = "Invalid block timestamp: {:?}", _0)]
+	#[from(ignore)]
+	InvalidTimestamp(crate::rpc::SealTimestamp),
 	/// Some string error
 	#[display(fmt = "{}", _0)]
 	#[from(ignore)]
@@ -81,6 +86,7 @@ impl Error {
 			ConsensusError(_) => codes::CONSENSUS_ERROR,
 			InherentError(_) => codes::INHERENTS_ERROR,
 			BlockchainError(_) => codes::BLOCKCHAIN_ERROR,
+			InvalidTimestamp(_) => codes::INVALID_TIMESTAMP,
 			SendError(_) | Canceled(_) => codes::SERVER_SHUTTING_DOWN,
 			_ => codes::UNKNOWN_ERROR
 		}
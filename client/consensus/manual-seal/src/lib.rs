@@ -35,15 +35,18 @@ use futures::prelude::*;
 use sc_transaction_pool::txpool;
 use std::collections::HashMap;
 use std::sync::Arc;
+use std::time::Duration;
 
 pub mod rpc;
 mod error;
 mod finalize_block;
+mod revert_blocks;
 mod seal_new_block;
 use finalize_block::{finalize_block, FinalizeBlockParams};
+use revert_blocks::{revert_blocks, RevertBlocksParams};
 use seal_new_block::{seal_new_block, SealBlockParams};
 pub use error::Error;
-pub use rpc::{EngineCommand, CreatedBlock};
+pub use rpc::{EngineCommand, CreatedBlock, SealTimestamp};
 
 /// The synchronous block-import worker of the engine.
 pub struct ManualSealBlockImport<I> {
@@ -131,18 +134,22 @@ pub async fn run_manual_seal<B, CB, E, A, C, S, T>(
 		S: Stream<Item=EngineCommand<<B as BlockT>::Hash>> + Unpin + 'static,
 		C: SelectChain<B> + 'static,
 {
+	let mut timestamp_drift = 0;
 	while let Some(command) = seal_block_channel.next().await {
 		match command {
 			EngineCommand::SealNewBlock {
 				create_empty,
 				finalize,
 				parent_hash,
+				timestamp,
 				sender,
 			} => {
 				seal_new_block(
 					SealBlockParams {
 						sender,
 						parent_hash,
+						timestamp,
+						timestamp_drift: &mut timestamp_drift,
 						finalize,
 						create_empty,
 						env: &mut env,
@@ -164,6 +171,15 @@ pub async fn run_manual_seal<B, CB, E, A, C, S, T>(
 					}
 				).await
 			}
+			EngineCommand::RevertBlocks { count, sender } => {
+				revert_blocks::<B, _>(
+					RevertBlocksParams {
+						count,
+						sender,
+						backend: backend.clone(),
+					}
+				).await
+			}
 		}
 	}
 }
@@ -196,6 +212,7 @@ pub async fn run_instant_seal<B, CB, E, A, C, T>(
 				create_empty: false,
 				finalize: false,
 				parent_hash: None,
+				timestamp: None,
 				sender: None,
 			}
 		});
@@ -211,6 +228,55 @@ pub async fn run_instant_seal<B, CB, E, A, C, T>(
 	).await
 }
 
+/// runs the background authorship task for the instant seal engine, batching transactions.
+/// a new block is created `delay` after the first transaction imported into the
+/// transaction pool since the last block, including every transaction imported meanwhile.
+pub async fn run_delayed_instant_seal<B, CB, E, A, C, T>(
+	block_import: BoxBlockImport<B, T>,
+	env: E,
+	backend: Arc<CB>,
+	pool: Arc<txpool::Pool<A>>,
+	select_chain: C,
+	inherent_data_providers: InherentDataProviders,
+	delay: Duration,
+)
+	where
+		A: txpool::ChainApi<Block=B> + 'static,
+		B: BlockT + 'static,
+		CB: ClientBackend<B> + 'static,
+		E: Environment<B> + 'static,
+		E::Error: std::fmt::Display,
+		<E::Proposer as Proposer<B>>::Error: std::fmt::Display,
+		C: SelectChain<B> + 'static
+{
+	let import_notifications = pool.validated_pool().import_notification_stream();
+	let seal_block_channel = Box::pin(stream::unfold(import_notifications, move |mut notifications| async move {
+		notifications.next().await?;
+		futures_timer::Delay::new(delay).await;
+		// the transactions imported while waiting are sealed in the same block.
+		while let Some(Some(_)) = notifications.next().now_or_never() {}
+
+		let command = EngineCommand::SealNewBlock {
+			create_empty: false,
+			finalize: false,
+			parent_hash: None,
+			timestamp: None,
+			sender: None,
+		};
+		Some((command, notifications))
+	}));
+
+	run_manual_seal(
+		block_import,
+		env,
+		backend,
+		pool,
+		seal_block_channel,
+		select_chain,
+		inherent_data_providers,
+	).await
+}
+
 #[cfg(test)]
 mod tests {
 	use super::*;
@@ -267,6 +333,7 @@ mod tests {
 					create_empty: false,
 					finalize: true,
 					parent_hash: None,
+					timestamp: None,
 					sender
 				}
 			});
@@ -350,6 +417,7 @@ mod tests {
 			sender: Some(tx),
 			create_empty: false,
 			finalize: false,
+			timestamp: None,
 		}).await.unwrap();
 		let created_block = rx.await.unwrap().unwrap();
 
@@ -424,6 +492,7 @@ mod tests {
 			sender: Some(tx),
 			create_empty: false,
 			finalize: false,
+			timestamp: None,
 		}).await.unwrap();
 		let created_block = rx.await.unwrap().unwrap();
 		pool_api.increment_nonce(Alice.into());
@@ -461,6 +530,7 @@ mod tests {
 			sender: Some(tx1),
 			create_empty: false,
 			finalize: false,
+			timestamp: None,
 		}).await.is_ok());
 		assert_matches::assert_matches!(
 			rx1.await.expect("should be no error receiving"),
@@ -476,9 +546,130 @@ mod tests {
 			sender: Some(tx2),
 			create_empty: false,
 			finalize: false,
+			timestamp: None,
 		}).await.is_ok());
 		let imported = rx2.await.unwrap().unwrap();
 		// assert that fork block is in the db
 		assert!(client.header(&BlockId::Hash(imported.hash)).unwrap().is_some())
 	}
+
+	#[tokio::test]
+	async fn manual_seal_revert_blocks() {
+		let builder = TestClientBuilder::new();
+		let backend = builder.backend();
+		let client = Arc::new(builder.build());
+		let select_chain = LongestChain::new(backend.clone());
+		let inherent_data_providers = InherentDataProviders::new();
+		let pool = Arc::new(BasicPool::new(
+			Options::default(),
+			api(),
+			None,
+		).0);
+		let env = ProposerFactory::new(
+			client.clone(),
+			pool.clone()
+		);
+		// this test checks that unfinalized blocks are reverted, but finalized ones aren't.
+		let (mut sink, stream) = futures::channel::mpsc::channel(1024);
+		let future = run_manual_seal(
+			Box::new(client.clone()),
+			env,
+			backend.clone(),
+			pool.pool().clone(),
+			stream,
+			select_chain,
+			inherent_data_providers,
+		);
+		std::thread::spawn(|| {
+			let mut rt = tokio::runtime::Runtime::new().unwrap();
+			// spawn the background authorship task
+			rt.block_on(future);
+		});
+		for finalize in vec![true, false, false] {
+			let (tx, rx) = futures::channel::oneshot::channel();
+			sink.send(EngineCommand::SealNewBlock {
+				parent_hash: None,
+				sender: Some(tx),
+				create_empty: true,
+				finalize,
+				timestamp: None,
+			}).await.unwrap();
+			assert!(rx.await.unwrap().is_ok());
+		}
+		assert_eq!(client.info().best_number, 3);
+
+		let (tx, rx) = futures::channel::oneshot::channel();
+		sink.send(EngineCommand::RevertBlocks { count: 5, sender: Some(tx) }).await.unwrap();
+		// only the two unfinalized blocks are reverted.
+		assert_eq!(rx.await.unwrap().unwrap(), 2);
+		assert_eq!(client.info().best_number, 1);
+	}
+
+	#[test]
+	fn seal_timestamp_resolves() {
+		assert_eq!(SealTimestamp::Exact(42).resolve(None), Some(42));
+		assert_eq!(SealTimestamp::Exact(42).resolve(Some(1_000)), Some(42));
+		assert_eq!(SealTimestamp::Offset(500).resolve(Some(1_000)), Some(1_500));
+		assert_eq!(SealTimestamp::Offset(-500).resolve(Some(1_000)), Some(500));
+		assert_eq!(SealTimestamp::Offset(-1_500).resolve(Some(1_000)), None);
+		assert_eq!(SealTimestamp::Offset(500).resolve(None), None);
+	}
+
+	#[test]
+	fn sealed_timestamps_keep_their_drift() {
+		use seal_new_block::resolve_timestamp;
+
+		assert_eq!(resolve_timestamp(None, Some(1_000), 0).unwrap(), None);
+		assert_eq!(resolve_timestamp(None, Some(1_000), 500).unwrap(), Some(1_500));
+		// offsets add up with the drift of the previous blocks.
+		assert_eq!(resolve_timestamp(Some(SealTimestamp::Offset(500)), Some(1_000), 500).unwrap(), Some(2_000));
+		assert_eq!(resolve_timestamp(Some(SealTimestamp::Exact(42)), Some(1_000), 500).unwrap(), Some(42));
+		assert!(resolve_timestamp(Some(SealTimestamp::Offset(-2_000)), Some(1_000), 500).is_err());
+	}
+
+	#[tokio::test]
+	async fn delayed_instant_seal() {
+		use sp_blockchain::Backend as _;
+
+		let builder = TestClientBuilder::new();
+		let backend = builder.backend();
+		let client = Arc::new(builder.build());
+		let select_chain = LongestChain::new(backend.clone());
+		let inherent_data_providers = InherentDataProviders::new();
+		let pool = Arc::new(BasicPool::new(
+			Options::default(),
+			api(),
+			None,
+		).0);
+		let env = ProposerFactory::new(
+			client.clone(),
+			pool.clone()
+		);
+		// this test checks that the transactions imported within the delay are sealed in one block.
+		let future = run_delayed_instant_seal(
+			Box::new(client.clone()),
+			env,
+			backend.clone(),
+			pool.pool().clone(),
+			select_chain,
+			inherent_data_providers,
+			Duration::from_millis(200),
+		);
+		std::thread::spawn(|| {
+			let mut rt = tokio::runtime::Runtime::new().unwrap();
+			// spawn the background authorship task
+			rt.block_on(future);
+		});
+		assert!(pool.submit_one(&BlockId::Number(0), SOURCE, uxt(Alice, 0)).await.is_ok());
+		assert!(pool.submit_one(&BlockId::Number(0), SOURCE, uxt(Alice, 1)).await.is_ok());
+
+		let body = loop {
+			if let Some(body) = backend.blockchain().body(BlockId::Number(1)).unwrap() {
+				break body
+			}
+			futures_timer::Delay::new(Duration::from_millis(10)).await;
+		};
+		assert_eq!(body.len(), 2);
+		assert!(backend.blockchain().header(BlockId::Number(2)).unwrap().is_none());
+	}
 }
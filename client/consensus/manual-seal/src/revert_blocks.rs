@@ -0,0 +1,58 @@
+// Copyright 2020 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Block reversion utilities
+
+use crate::rpc;
+use sp_runtime::traits::{Block as BlockT, SaturatedConversion};
+use std::sync::Arc;
+use sc_client_api::backend::Backend as ClientBackend;
+
+/// params for block reversion.
+pub struct RevertBlocksParams<CB> {
+	/// number of blocks to revert
+	pub count: u32,
+	/// sender to report errors/success to the rpc.
+	pub sender: rpc::Sender<u32>,
+	/// client backend
+	pub backend: Arc<CB>,
+}
+
+/// reverts the last unfinalized blocks in the backend with the given params.
+pub async fn revert_blocks<B, CB>(params: RevertBlocksParams<CB>)
+	where
+		B: BlockT,
+		CB: ClientBackend<B>,
+{
+	let RevertBlocksParams {
+		count,
+		mut sender,
+		backend: back_end,
+	} = params;
+
+	// finalized blocks are never reverted.
+	match back_end.revert(count.into(), false) {
+		Err(e) => {
+			log::warn!("Failed to revert blocks {:?}", e);
+			rpc::send_result(&mut sender, Err(e.into()))
+		}
+		Ok(reverted) => {
+			let reverted = reverted.saturated_into::<u32>();
+			log::info!("⏪ Reverted {} blocks", reverted);
+			rpc::send_result(&mut sender, Ok(reverted))
+		}
+	}
+}
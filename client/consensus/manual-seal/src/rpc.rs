@@ -26,6 +26,7 @@ use futures::{
 };
 use serde::{Deserialize, Serialize};
 use sp_runtime::Justification;
+use std::convert::TryFrom;
 pub use self::gen_client::Client as ManualSealClient;
 
 /// Future's type for jsonrpc
@@ -49,6 +50,9 @@ pub enum EngineCommand<Hash> {
 		finalize: bool,
 		/// specify the parent hash of the about-to-created block
 		parent_hash: Option<Hash>,
+		/// timestamp to feed into the timestamp inherent of the block,
+		/// the inherent data providers' timestamp is used if `None`.
+		timestamp: Option<SealTimestamp>,
 		/// sender to report errors/success to the rpc.
 		sender: Sender<CreatedBlock<Hash>>,
 	},
@@ -60,6 +64,44 @@ pub enum EngineCommand<Hash> {
 		sender: Sender<()>,
 		/// finalization justification
 		justification: Option<Justification>,
+	},
+	/// Tells the engine to revert the last `count` unfinalized blocks
+	RevertBlocks {
+		/// number of blocks to revert
+		count: u32,
+		/// sender to report the number of reverted blocks to the rpc.
+		sender: Sender<u32>,
+	},
+}
+
+/// Timestamp (in milliseconds) for a block sealed by the engine.
+#[derive(Debug, Clone, Copy, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum SealTimestamp {
+	/// Use exactly this timestamp.
+	///
+	/// Must respect the minimum period of the runtime's timestamp module
+	/// relative to the parent block, otherwise the block will fail to build.
+	/// The following blocks keep the same drift from the inherent data providers'
+	/// timestamp.
+	Exact(u64),
+	/// Shift the timestamp of the block by this many milliseconds.
+	///
+	/// The shift adds up with the ones of the previous blocks, and is kept for the
+	/// following blocks, so that they don't go back in time.
+	Offset(i64),
+}
+
+impl SealTimestamp {
+	/// Resolve the timestamp of the block given the one it would have otherwise, i.e. the one
+	/// supplied by the inherent data providers shifted by the drift of the previous blocks.
+	pub fn resolve(self, provided: Option<u64>) -> Option<u64> {
+		match self {
+			SealTimestamp::Exact(timestamp) => Some(timestamp),
+			SealTimestamp::Offset(offset) => provided.and_then(|timestamp| {
+				u64::try_from(i128::from(timestamp) + i128::from(offset)).ok()
+			}),
+		}
 	}
 }
 
@@ -72,7 +114,8 @@ pub trait ManualSealApi<Hash> {
 		&self,
 		create_empty: bool,
 		finalize: bool,
-		parent_hash: Option<Hash>
+		parent_hash: Option<Hash>,
+		timestamp: Option<SealTimestamp>,
 	) -> FutureResult<CreatedBlock<Hash>>;
 
 	/// Instructs the manual-seal authorship task to finalize a block
@@ -82,6 +125,11 @@ pub trait ManualSealApi<Hash> {
 		hash: Hash,
 		justification: Option<Justification>
 	) -> FutureResult<bool>;
+
+	/// Instructs the manual-seal authorship task to revert the last `count` unfinalized blocks,
+	/// returns the number of blocks actually reverted.
+	#[rpc(name = "engine_revertBlocks")]
+	fn revert_blocks(&self, count: u32) -> FutureResult<u32>;
 }
 
 /// A struct that implements the [`ManualSealApi`].
@@ -110,7 +158,8 @@ impl<Hash: Send + 'static> ManualSealApi<Hash> for ManualSeal<Hash> {
 		&self,
 		create_empty: bool,
 		finalize: bool,
-		parent_hash: Option<Hash>
+		parent_hash: Option<Hash>,
+		timestamp: Option<SealTimestamp>,
 	) -> FutureResult<CreatedBlock<Hash>> {
 		let mut sink = self.import_block_channel.clone();
 		let future = async move {
@@ -119,6 +168,7 @@ impl<Hash: Send + 'static> ManualSealApi<Hash> for ManualSeal<Hash> {
 				create_empty,
 				finalize,
 				parent_hash,
+				timestamp,
 				sender: Some(sender),
 			};
 			sink.send(command).await?;
@@ -141,6 +191,18 @@ impl<Hash: Send + 'static> ManualSealApi<Hash> for ManualSeal<Hash> {
 
 		Box::new(future.boxed().map_err(Error::from).compat())
 	}
+
+	fn revert_blocks(&self, count: u32) -> FutureResult<u32> {
+		let mut sink = self.import_block_channel.clone();
+		let future = async move {
+			let (sender, receiver) = oneshot::channel();
+			sink.send(EngineCommand::RevertBlocks { count, sender: Some(sender) }).await?;
+
+			receiver.await?
+		};
+
+		Box::new(future.boxed().map_err(Error::from).compat())
+	}
 }
 
 /// report any errors or successes encountered by the authorship task back
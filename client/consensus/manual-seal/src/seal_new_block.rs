@@ -24,7 +24,7 @@ use sp_runtime::{
 };
 use futures::prelude::*;
 use sc_transaction_pool::txpool;
-use rpc::CreatedBlock;
+use rpc::{CreatedBlock, SealTimestamp};
 
 use sp_consensus::{
 	self, BlockImport, Environment, Proposer,
@@ -37,6 +37,7 @@ use sc_client_api::backend::Backend as ClientBackend;
 use std::collections::HashMap;
 use std::time::Duration;
 use sp_inherents::InherentDataProviders;
+use sp_timestamp::TimestampInherentData;
 
 /// max duration for creating a proposal in secs
 const MAX_PROPOSAL_DURATION: u64 = 10;
@@ -50,6 +51,11 @@ pub struct SealBlockParams<'a, B: BlockT, C, CB, E, T, P: txpool::ChainApi> {
 	pub finalize: bool,
 	/// specify the parent hash of the about-to-created block
 	pub parent_hash: Option<<B as BlockT>::Hash>,
+	/// timestamp to feed into the timestamp inherent
+	pub timestamp: Option<SealTimestamp>,
+	/// drift of the timestamps of the sealed blocks from the ones supplied by the inherent
+	/// data providers, in milliseconds, kept across blocks.
+	pub timestamp_drift: &'a mut i64,
 	/// sender to report errors/success to the rpc.
 	pub sender: rpc::Sender<CreatedBlock<<B as BlockT>::Hash>>,
 	/// transaction pool
@@ -73,6 +79,8 @@ pub async fn seal_new_block<B, SC, CB, E, T, P>(
 		finalize,
 		pool,
 		parent_hash,
+		timestamp,
+		timestamp_drift,
 		backend: back_end,
 		select_chain,
 		block_import,
@@ -111,7 +119,12 @@ pub async fn seal_new_block<B, SC, CB, E, T, P>(
 
 		let mut proposer = env.init(&header)
 			.map_err(|err| Error::StringError(format!("{}", err))).await?;
-		let id = inherent_data_provider.create_inherent_data()?;
+		let mut id = inherent_data_provider.create_inherent_data()?;
+		let provided = id.timestamp_inherent_data().ok();
+		let resolved = resolve_timestamp(timestamp, provided, *timestamp_drift)?;
+		if let Some(resolved) = resolved {
+			id.replace_data(sp_timestamp::INHERENT_IDENTIFIER, &resolved);
+		}
 		let inherents_len = id.len();
 		let proposal = proposer.propose(id, Default::default(), Duration::from_secs(MAX_PROPOSAL_DURATION), false.into())
 			.map_err(|err| Error::StringError(format!("{}", err))).await?;
@@ -128,6 +141,9 @@ pub async fn seal_new_block<B, SC, CB, E, T, P>(
 
 		match block_import.import_block(params, HashMap::new())? {
 			ImportResult::Imported(aux) => {
+				if let (Some(provided), Some(resolved)) = (provided, resolved) {
+					*timestamp_drift = (resolved as i64).saturating_sub(provided as i64);
+				}
 				Ok(CreatedBlock { hash: <B as BlockT>::Header::hash(&header), aux })
 			},
 			other => Err(other.into()),
@@ -136,3 +152,22 @@ pub async fn seal_new_block<B, SC, CB, E, T, P>(
 
 	rpc::send_result(&mut sender, future.await)
 }
+
+/// Resolves the timestamp of the block to seal given the one supplied by the inherent data
+/// providers and the drift of the previously sealed blocks from them.
+///
+/// Returns `None` if the supplied timestamp is kept as is.
+pub(crate) fn resolve_timestamp(
+	timestamp: Option<SealTimestamp>,
+	provided: Option<u64>,
+	drift: i64,
+) -> Result<Option<u64>, Error> {
+	let drifted = SealTimestamp::Offset(drift).resolve(provided);
+	match timestamp {
+		Some(timestamp) => timestamp.resolve(drifted)
+			.map(Some)
+			.ok_or(Error::InvalidTimestamp(timestamp)),
+		None if drift == 0 => Ok(None),
+		None => Ok(drifted),
+	}
+}
@@ -28,6 +28,7 @@
 
 pub mod light;
 pub mod offchain;
+pub mod snapshot;
 
 #[cfg(any(feature = "kvdb-rocksdb", test))]
 pub mod bench;
@@ -266,10 +267,36 @@ pub struct DatabaseSettings {
 	pub state_cache_child_ratio: Option<(usize, usize)>,
 	/// Pruning mode.
 	pub pruning: PruningMode,
+	/// Pruning mode of block bodies and justifications.
+	pub blocks_pruning: BlocksPruning,
 	/// Where to find the database.
 	pub source: DatabaseSettingsSrc,
 }
 
+/// Pruning of block bodies and justifications, independent of the state pruning mode.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BlocksPruning {
+	/// Keep the bodies and justifications of all blocks.
+	ArchiveAll,
+	/// Keep the bodies of the given number of most recent finalized blocks only.
+	///
+	/// The bodies of blocks finalized before switching to this mode and of blocks of
+	/// abandoned forks are not deleted.
+	KeepFinalized {
+		/// Number of finalized blocks to keep the body of.
+		blocks: u32,
+		/// Delete the justifications along with the bodies, except those marked as required
+		/// by the finality gadget, such as the ones of blocks enacting authority set changes.
+		justifications: bool,
+	},
+}
+
+impl Default for BlocksPruning {
+	fn default() -> Self {
+		BlocksPruning::ArchiveAll
+	}
+}
+
 /// Where to find the database..
 pub enum DatabaseSettingsSrc {
 	/// Load a database from a given path. Recommended for most uses.
@@ -757,6 +784,7 @@ pub struct Backend<Block: BlockT> {
 	shared_cache: SharedCache<Block>,
 	import_lock: Arc<RwLock<()>>,
 	is_archive: bool,
+	blocks_pruning: BlocksPruning,
 	io_stats: FrozenForDuration<(kvdb::IoStats, StateUsageInfo)>,
 	state_usage: Arc<StateUsageStats>,
 }
@@ -778,6 +806,7 @@ impl<Block: BlockT> Backend<Block> {
 			state_cache_size: 16777216,
 			state_cache_child_ratio: Some((50, 100)),
 			pruning: PruningMode::keep_blocks(keep_blocks),
+			blocks_pruning: BlocksPruning::ArchiveAll,
 			source: DatabaseSettingsSrc::Custom(db),
 		};
 
@@ -829,6 +858,7 @@ impl<Block: BlockT> Backend<Block> {
 			),
 			import_lock: Default::default(),
 			is_archive: is_archive_pruning,
+			blocks_pruning: config.blocks_pruning,
 			io_stats: FrozenForDuration::new(std::time::Duration::from_secs(1)),
 			state_usage: Arc::new(StateUsageStats::new()),
 		})
@@ -1049,6 +1079,9 @@ impl<Block: BlockT> Backend<Block> {
 
 		let mut meta_updates = Vec::with_capacity(operation.finalized_blocks.len());
 		let mut last_finalized_hash = self.blockchain.meta.read().finalized_hash;
+		// the first block of an empty database, either the genesis block or the block of a
+		// state snapshot, has no finalized parent in the database.
+		let is_first_block = last_finalized_hash == Default::default();
 
 		let mut changes_trie_cache_ops = None;
 		for (block, justification) in operation.finalized_blocks {
@@ -1156,10 +1189,11 @@ impl<Block: BlockT> Backend<Block> {
 			changes_trie_cache_ops = Some(self.changes_tries_storage.commit(
 				&mut transaction,
 				changes_trie_updates,
-				cache::ComplexBlockId::new(
-					*header.parent_hash(),
-					if number.is_zero() { Zero::zero() } else { number - One::one() },
-				),
+				if is_first_block {
+					cache::ComplexBlockId::new(Default::default(), Zero::zero())
+				} else {
+					cache::ComplexBlockId::new(*header.parent_hash(), number - One::one())
+				},
 				cache::ComplexBlockId::new(hash, number),
 				header,
 				finalized,
@@ -1172,7 +1206,9 @@ impl<Block: BlockT> Backend<Block> {
 
 			if finalized {
 				// TODO: ensure best chain contains this block.
-				self.ensure_sequential_finalization(header, Some(last_finalized_hash))?;
+				if !is_first_block {
+					self.ensure_sequential_finalization(header, Some(last_finalized_hash))?;
+				}
 				self.note_finalized(
 					&mut transaction,
 					true,
@@ -1324,6 +1360,10 @@ impl<Block: BlockT> Backend<Block> {
 			}
 		}
 
+		if let BlocksPruning::KeepFinalized { blocks, justifications } = self.blocks_pruning {
+			self.prune_block(transaction, f_header, blocks, justifications)?;
+		}
+
 		let new_displaced = self.blockchain.leaves.write().finalize_height(f_num);
 		match displaced {
 			x @ &mut None => *x = Some(new_displaced),
@@ -1332,6 +1372,48 @@ impl<Block: BlockT> Backend<Block> {
 
 		Ok(())
 	}
+
+	// deletes the body, and optionally the justification, of the finalized block that
+	// is `keep` blocks older than the newly finalized one.
+	fn prune_block(
+		&self,
+		transaction: &mut DBTransaction,
+		f_header: &Block::Header,
+		keep: u32,
+		justifications: bool,
+	) -> ClientResult<()> {
+		let keep: NumberFor<Block> = keep.saturated_into();
+		let f_num = *f_header.number();
+		if f_num < keep {
+			return Ok(())
+		}
+
+		let header = if keep.is_zero() {
+			f_header.clone()
+		} else {
+			// blocks older than an imported state snapshot are unknown
+			match self.blockchain.header(BlockId::Number(f_num - keep))? {
+				Some(header) => header,
+				None => return Ok(()),
+			}
+		};
+		let number = *header.number();
+		let hash = header.hash();
+
+		trace!(target: "db", "Prune body of block #{} ({:?})", number, hash);
+		let lookup_key = utils::number_and_hash_to_lookup_key(number, hash)?;
+		transaction.delete(columns::BODY, &lookup_key);
+
+		// justifications of authority set changes are needed to prove finality to syncing nodes
+		if justifications {
+			let keep_key = sc_client_api::backend::keep_justification_aux_key::<Block>(&hash);
+			if self.storage.db.get(columns::AUX, &keep_key).map_err(db_err)?.is_none() {
+				transaction.delete(columns::JUSTIFICATION, &lookup_key);
+			}
+		}
+
+		Ok(())
+	}
 }
 
 fn apply_state_commit(transaction: &mut DBTransaction, commit: sc_state_db::CommitSet<Vec<u8>>) {
@@ -1797,6 +1879,7 @@ pub(crate) mod tests {
 			state_cache_size: 16777216,
 			state_cache_child_ratio: Some((50, 100)),
 			pruning: PruningMode::keep_blocks(1),
+			blocks_pruning: BlocksPruning::ArchiveAll,
 			source: DatabaseSettingsSrc::Custom(backing),
 		}, 0).unwrap();
 		assert_eq!(backend.blockchain().info().best_number, 9);
@@ -2321,4 +2404,58 @@ pub(crate) mod tests {
 			backend.commit_operation(op).unwrap_err();
 		}
 	}
+
+	#[test]
+	fn prunes_block_bodies_and_justifications() {
+		use sc_client::blockchain::{Backend as BlockChainBackend};
+
+		let backend = Backend::<Block>::new(DatabaseSettings {
+			state_cache_size: 16777216,
+			state_cache_child_ratio: Some((50, 100)),
+			pruning: PruningMode::keep_blocks(1),
+			blocks_pruning: BlocksPruning::KeepFinalized { blocks: 2, justifications: true },
+			source: DatabaseSettingsSrc::Custom(Arc::new(kvdb_memorydb::create(crate::utils::NUM_COLUMNS))),
+		}, 0).unwrap();
+
+		let mut parent_hash = Default::default();
+		for number in 0..6 {
+			let header = Header {
+				number,
+				parent_hash,
+				state_root: BlakeTwo256::trie_root(Vec::new()),
+				digest: Default::default(),
+				extrinsics_root: Default::default(),
+			};
+			parent_hash = header.hash();
+
+			let block_id = if number == 0 {
+				BlockId::Hash(Default::default())
+			} else {
+				BlockId::Number(number - 1)
+			};
+			let mut op = backend.begin_operation().unwrap();
+			backend.begin_state_operation(&mut op, block_id).unwrap();
+			op.set_block_data(header, Some(Vec::new()), None, NewBlockState::Best).unwrap();
+			// block 2 enacts an authority set change
+			if number == 2 {
+				let key = sc_client_api::backend::keep_justification_aux_key::<Block>(&parent_hash);
+				op.insert_aux(vec![(key, Some(Vec::new()))]).unwrap();
+			}
+			backend.commit_operation(op).unwrap();
+
+			if number > 0 {
+				backend.finalize_block(BlockId::Number(number), Some(vec![number as u8])).unwrap();
+			}
+		}
+
+		for number in 0..6 {
+			let id = BlockId::Number(number);
+			assert!(backend.blockchain().header(id).unwrap().is_some());
+			assert_eq!(backend.blockchain().body(id).unwrap().is_some(), number >= 4);
+			assert_eq!(
+				backend.blockchain().justification(id).unwrap().is_some(),
+				number == 2 || number >= 4,
+			);
+		}
+	}
 }
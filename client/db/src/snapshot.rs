@@ -0,0 +1,577 @@
+// Copyright 2020 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! State snapshots.
+//!
+//! A snapshot holds the state of a finalized block, child tries included, together with the
+//! header and justification of the block, the genesis header and the auxiliary data the
+//! consensus engines need to continue from the block (e.g. the GRANDPA authority set).
+//! Importing a snapshot seeds an empty database, from which the node then syncs.
+//!
+//! A snapshot starts with a magic number, the format version and the [`SnapshotHeader`].
+//! The state follows as a sequence of length-prefixed chunks of storage entries, each one
+//! followed by its BLAKE2-256 hash, and an empty chunk ends the snapshot. The entries of the
+//! child tries come first and the entries of the main trie last, each trie in key order, so
+//! that the tries are built and written to the database chunk by chunk on import.
+
+use std::{io::{self, Read, Write}, iter, mem, vec};
+
+use codec::{Decode, Encode, IoReader};
+use hash_db::{Hasher, Prefix};
+use kvdb::{DBTransaction, KeyValueDB};
+use sc_client_api::backend::{Backend as _, BlockImportOperation as _, NewBlockState};
+use sp_blockchain::{Error as ClientError, Result as ClientResult};
+use sp_core::{
+	ChangesTrieConfiguration,
+	hashing::blake2_256,
+	hexdisplay::HexDisplay,
+	storage::{well_known_keys, OwnedChildInfo},
+};
+use sp_runtime::{
+	Justification,
+	generic::BlockId,
+	traits::{Block as BlockT, Header as HeaderT, HashFor, Zero},
+};
+use sp_trie::{ChildReference, Layout, ProcessEncodedNode, prefixed_key, trie_visit};
+
+use crate::{Backend, DatabaseSettings, CANONICALIZATION_DELAY, columns};
+use crate::utils::{self, db_err, meta_keys};
+
+/// Magic number a snapshot starts with.
+const MAGIC: &[u8; 4] = b"snap";
+
+/// Version of the snapshot format.
+pub const VERSION: u32 = 2;
+
+/// Size of the storage entries, or of the trie nodes, above which a chunk is written out.
+const CHUNK_SIZE: usize = 1024 * 1024;
+
+/// Everything a snapshot holds besides the state.
+#[derive(Encode, Decode)]
+pub struct SnapshotHeader<Block: BlockT> {
+	/// Header of the genesis block.
+	pub genesis: Block::Header,
+	/// Header of the block the state belongs to.
+	pub header: Block::Header,
+	/// Justification of the block, if any.
+	pub justification: Option<Justification>,
+	/// Auxiliary data to insert into the database.
+	pub aux: Vec<(Vec<u8>, Vec<u8>)>,
+}
+
+/// A chunk of storage entries.
+#[derive(Encode, Decode)]
+struct Chunk {
+	/// Storage key of the child trie the entries belong to, `None` for the main trie.
+	child: Option<Vec<u8>>,
+	entries: Vec<(Vec<u8>, Vec<u8>)>,
+}
+
+/// Writes a snapshot out, chunking the storage entries pushed to it.
+pub struct SnapshotWriter<W> {
+	output: W,
+	chunk: Chunk,
+	chunk_size: usize,
+}
+
+impl<W: Write> SnapshotWriter<W> {
+	/// Start a snapshot with the given header.
+	pub fn new<Block: BlockT>(mut output: W, header: &SnapshotHeader<Block>) -> io::Result<Self> {
+		output.write_all(MAGIC)?;
+		output.write_all(&VERSION.encode())?;
+		output.write_all(&header.encode())?;
+
+		Ok(SnapshotWriter {
+			output,
+			chunk: Chunk { child: None, entries: Vec::new() },
+			chunk_size: 0,
+		})
+	}
+
+	/// Add an entry of the main trie, or of the child trie with the given storage key.
+	///
+	/// The entries of every child trie must be pushed before the entries of the main trie,
+	/// the entries of each trie one after the other and in key order.
+	pub fn push(&mut self, child: Option<&[u8]>, key: Vec<u8>, value: Vec<u8>) -> io::Result<()> {
+		if self.chunk.child.as_ref().map(|c| &c[..]) != child {
+			self.flush_chunk()?;
+			self.chunk.child = child.map(|c| c.to_vec());
+		}
+
+		self.chunk_size += key.len() + value.len();
+		self.chunk.entries.push((key, value));
+		if self.chunk_size >= CHUNK_SIZE {
+			self.flush_chunk()?;
+		}
+		Ok(())
+	}
+
+	/// Write the remaining entries out and end the snapshot.
+	pub fn finish(mut self) -> io::Result<()> {
+		self.flush_chunk()?;
+		self.output.write_all(&Vec::<u8>::new().encode())?;
+		self.output.flush()
+	}
+
+	fn flush_chunk(&mut self) -> io::Result<()> {
+		if self.chunk.entries.is_empty() {
+			return Ok(())
+		}
+
+		let data = self.chunk.encode();
+		self.output.write_all(&data.encode())?;
+		self.output.write_all(&blake2_256(&data))?;
+		self.chunk.entries.clear();
+		self.chunk_size = 0;
+		Ok(())
+	}
+}
+
+/// Returns the child info of the child trie with the given storage key.
+///
+/// Only default child tries whose unique id is their storage key without the
+/// `:child_storage:default:` prefix, as used by contracts, are supported.
+pub fn child_info(storage_key: &[u8]) -> ClientResult<OwnedChildInfo> {
+	let prefix = [well_known_keys::CHILD_STORAGE_KEY_PREFIX, &b"default:"[..]].concat();
+	if !storage_key.starts_with(&prefix) {
+		return Err(ClientError::Backend(format!(
+			"Unsupported child trie 0x{} in state snapshot",
+			HexDisplay::from(&storage_key),
+		)))
+	}
+
+	Ok(OwnedChildInfo::new_default(storage_key[prefix.len()..].to_vec()))
+}
+
+fn invalid(e: codec::Error) -> ClientError {
+	ClientError::Backend(format!("Invalid state snapshot: {}", e.what()))
+}
+
+/// Reads a snapshot chunk by chunk, verifying the hash of the chunks.
+pub struct SnapshotReader<R> {
+	input: IoReader<R>,
+	/// Number of chunks read.
+	index: usize,
+	/// Child trie and remaining entries of the last chunk read, `None` once the snapshot ended.
+	chunk: Option<(Option<Vec<u8>>, vec::IntoIter<(Vec<u8>, Vec<u8>)>)>,
+	ended: bool,
+	/// Error met while iterating over entries.
+	error: Option<ClientError>,
+}
+
+impl<R: Read> SnapshotReader<R> {
+	/// Start reading a snapshot, returning its header.
+	pub fn new<Block: BlockT>(input: R) -> ClientResult<(SnapshotHeader<Block>, Self)> {
+		let mut input = IoReader(input);
+		if &<[u8; 4]>::decode(&mut input).map_err(invalid)? != MAGIC {
+			return Err(ClientError::Backend("Not a state snapshot".into()))
+		}
+		let version = u32::decode(&mut input).map_err(invalid)?;
+		if version != VERSION {
+			return Err(ClientError::Backend(format!("Unsupported state snapshot version {}", version)))
+		}
+		let header = SnapshotHeader::<Block>::decode(&mut input).map_err(invalid)?;
+
+		let reader = SnapshotReader { input, index: 0, chunk: None, ended: false, error: None };
+		Ok((header, reader))
+	}
+
+	/// Returns the storage key of the child trie the next entries belong to, `None` if they
+	/// belong to the main trie or if the snapshot ended.
+	pub fn next_child(&mut self) -> ClientResult<Option<Vec<u8>>> {
+		self.fill()?;
+		Ok(self.chunk.as_ref().and_then(|(child, _)| child.clone()))
+	}
+
+	/// Returns true if all the entries of the snapshot were read.
+	pub fn is_ended(&mut self) -> ClientResult<bool> {
+		self.fill()?;
+		Ok(self.chunk.is_none())
+	}
+
+	/// Returns an iterator over the next entries of the main trie, or of the child trie with
+	/// the given storage key, checking that they are in key order.
+	///
+	/// The iteration stops at the first error, returned by [`take_error`](Self::take_error).
+	pub fn entries<'a>(
+		&'a mut self,
+		child: Option<&'a [u8]>,
+	) -> impl Iterator<Item=(Vec<u8>, Vec<u8>)> + 'a {
+		let mut last_key: Option<Vec<u8>> = None;
+		iter::from_fn(move || {
+			if self.error.is_some() {
+				return None
+			}
+			let entry = match self.next_entry(child) {
+				Ok(entry) => entry?,
+				Err(e) => {
+					self.error = Some(e);
+					return None
+				},
+			};
+			if last_key.as_ref().map_or(false, |last_key| *last_key >= entry.0) {
+				self.error = Some(ClientError::Backend("State snapshot entries are not in key order".into()));
+				return None
+			}
+			last_key = Some(entry.0.clone());
+			Some(entry)
+		})
+	}
+
+	/// Returns the error met by the last iteration over entries, if any.
+	pub fn take_error(&mut self) -> ClientResult<()> {
+		self.error.take().map_or(Ok(()), Err)
+	}
+
+	fn next_entry(&mut self, child: Option<&[u8]>) -> ClientResult<Option<(Vec<u8>, Vec<u8>)>> {
+		self.fill()?;
+		Ok(match &mut self.chunk {
+			Some((chunk_child, entries)) if chunk_child.as_ref().map(|c| &c[..]) == child => entries.next(),
+			_ => None,
+		})
+	}
+
+	/// Read chunks until one has entries left or the snapshot ended.
+	fn fill(&mut self) -> ClientResult<()> {
+		while !self.ended && self.chunk.as_ref().map_or(true, |(_, entries)| entries.len() == 0) {
+			let data = Vec::<u8>::decode(&mut self.input).map_err(invalid)?;
+			if data.is_empty() {
+				self.ended = true;
+				self.chunk = None;
+				break
+			}
+			if <[u8; 32]>::decode(&mut self.input).map_err(invalid)? != blake2_256(&data) {
+				return Err(ClientError::Backend(format!(
+					"Chunk {} of the state snapshot is corrupted",
+					self.index,
+				)))
+			}
+
+			let chunk = Chunk::decode(&mut &data[..]).map_err(invalid)?;
+			self.index += 1;
+			self.chunk = Some((chunk.child, chunk.entries.into_iter()));
+		}
+		Ok(())
+	}
+}
+
+/// Builds a trie from its entries in key order, writing its nodes to the state column of the
+/// database in batches.
+struct TrieWriter<'a, H: Hasher> {
+	db: &'a dyn KeyValueDB,
+	/// Keyspace of the child trie, empty for the main trie.
+	keyspace: &'a [u8],
+	transaction: DBTransaction,
+	/// Size of the nodes in `transaction`.
+	pending: usize,
+	root: Option<H::Out>,
+	error: Option<ClientError>,
+}
+
+impl<'a, H: Hasher> TrieWriter<'a, H> {
+	/// Build the trie with the given entries, returning its root.
+	fn write(
+		db: &'a dyn KeyValueDB,
+		keyspace: &'a [u8],
+		entries: impl Iterator<Item=(Vec<u8>, Vec<u8>)>,
+	) -> ClientResult<H::Out> {
+		let mut writer = TrieWriter {
+			db,
+			keyspace,
+			transaction: DBTransaction::new(),
+			pending: 0,
+			root: None,
+			error: None,
+		};
+		trie_visit::<Layout<H>, _, _, _, _>(entries, &mut writer);
+		writer.flush();
+
+		match (writer.error, writer.root) {
+			(Some(e), _) => Err(e),
+			(None, Some(root)) => Ok(root),
+			(None, None) => Err(ClientError::Backend("Missing state snapshot trie root".into())),
+		}
+	}
+
+	fn flush(&mut self) {
+		if self.pending == 0 || self.error.is_some() {
+			return
+		}
+		let transaction = mem::replace(&mut self.transaction, DBTransaction::new());
+		self.pending = 0;
+		if let Err(e) = self.db.write(transaction) {
+			self.error = Some(db_err(e));
+		}
+	}
+}
+
+impl<'a, H: Hasher> ProcessEncodedNode<H::Out> for TrieWriter<'a, H> {
+	fn process(&mut self, prefix: Prefix, encoded_node: Vec<u8>, is_root: bool) -> ChildReference<H::Out> {
+		let len = encoded_node.len();
+		if !is_root && len < H::LENGTH {
+			let mut inline = H::Out::default();
+			inline.as_mut()[..len].copy_from_slice(&encoded_node);
+			return ChildReference::Inline(inline, len)
+		}
+
+		let hash = H::hash(&encoded_node);
+		let mut key_prefix = self.keyspace.to_vec();
+		key_prefix.extend_from_slice(prefix.0);
+		let key = prefixed_key::<H>(&hash, (&key_prefix, prefix.1));
+		self.pending += key.len() + len;
+		self.transaction.put_vec(columns::STATE, &key, encoded_node);
+		if self.pending >= CHUNK_SIZE {
+			self.flush();
+		}
+
+		if is_root {
+			self.root = Some(hash);
+		}
+		ChildReference::Hash(hash)
+	}
+}
+
+/// Seed an empty database with a snapshot, the snapshot block becoming its best and
+/// finalized block. Returns the header of the snapshot block.
+///
+/// The state is written to the database as it is read, a snapshot with an invalid state
+/// leaves its trie nodes behind and the database should then be purged.
+pub fn import_snapshot<Block: BlockT>(
+	settings: DatabaseSettings,
+	input: impl Read,
+) -> ClientResult<Block::Header> {
+	let (snapshot, reader) = SnapshotReader::new::<Block>(input)?;
+	let backend = Backend::<Block>::new(settings, CANONICALIZATION_DELAY)?;
+	let header = snapshot.header.clone();
+	backend.import_snapshot(snapshot, reader)?;
+	Ok(header)
+}
+
+impl<Block: BlockT> Backend<Block> {
+	fn import_snapshot<R: Read>(
+		&self,
+		snapshot: SnapshotHeader<Block>,
+		mut reader: SnapshotReader<R>,
+	) -> ClientResult<()> {
+		if self.blockchain.meta.read().genesis_hash != Default::default() {
+			return Err(ClientError::Backend(
+				"A state snapshot can only be imported into an empty database".into()
+			))
+		}
+
+		let SnapshotHeader { genesis, header, justification, aux } = snapshot;
+		if !genesis.number().is_zero() || header.number().is_zero() {
+			return Err(ClientError::Backend("Invalid state snapshot block".into()))
+		}
+
+		// child tries first, their roots being entries of the main trie.
+		let db = &*self.storage.db;
+		let mut child_roots: Vec<(Vec<u8>, Vec<u8>)> = Vec::new();
+		while let Some(storage_key) = reader.next_child()? {
+			if child_roots.last().map_or(false, |(last_key, _)| *last_key >= storage_key) {
+				return Err(ClientError::Backend("State snapshot child tries are not in key order".into()))
+			}
+			let child_info = child_info(&storage_key)?;
+			let root = TrieWriter::<HashFor<Block>>::write(
+				db,
+				child_info.as_ref().keyspace(),
+				reader.entries(Some(&storage_key[..])),
+			);
+			reader.take_error()?;
+			child_roots.push((storage_key, root?.as_ref().to_vec()));
+		}
+
+		let mut changes_trie_config = None;
+		let root = {
+			let mut entries = reader.entries(None).peekable();
+			let mut child_roots = child_roots.into_iter().peekable();
+			let merged = iter::from_fn(|| {
+				let next_is_child = match (entries.peek(), child_roots.peek()) {
+					(Some((key, _)), Some((storage_key, _))) => storage_key < key,
+					(Some(_), None) => false,
+					(None, _) => true,
+				};
+				if next_is_child { child_roots.next() } else { entries.next() }
+			}).inspect(|(key, value)| if &key[..] == well_known_keys::CHANGES_TRIE_CONFIG {
+				changes_trie_config = Some(value.clone());
+			});
+			TrieWriter::<HashFor<Block>>::write(db, &[], merged)
+		};
+		reader.take_error()?;
+		if !reader.is_ended()? {
+			return Err(ClientError::Backend("Unexpected child trie after the main trie in state snapshot".into()))
+		}
+		if root? != *header.state_root() {
+			return Err(ClientError::InvalidStateRoot)
+		}
+		let changes_trie_config = changes_trie_config
+			.map(|config| ChangesTrieConfiguration::decode(&mut &config[..]).map_err(invalid))
+			.transpose()?;
+
+		// the state is already written, so the block is committed with an empty state changeset.
+		let mut operation = self.begin_operation()?;
+		self.begin_state_operation(&mut operation, BlockId::Hash(Default::default()))?;
+		operation.commit_state = true;
+		operation.changes_trie_config_update = Some(changes_trie_config);
+		operation.set_block_data(header, None, justification, NewBlockState::Final)?;
+		operation.insert_aux(aux.into_iter().map(|(key, value)| (key, Some(value))))?;
+		self.commit_operation(operation)?;
+
+		// the client initializes the genesis block of databases without one, so insert the
+		// genesis header, which the snapshot block doesn't descend from in this database.
+		let hash = genesis.hash();
+		let mut transaction = DBTransaction::new();
+		let lookup_key = utils::number_and_hash_to_lookup_key(*genesis.number(), hash)?;
+		transaction.put(columns::HEADER, &lookup_key, &genesis.encode());
+		utils::insert_hash_to_key_mapping(&mut transaction, columns::KEY_LOOKUP, *genesis.number(), hash)?;
+		utils::insert_number_to_key_mapping(&mut transaction, columns::KEY_LOOKUP, *genesis.number(), hash)?;
+		transaction.put(columns::META, meta_keys::GENESIS_HASH, hash.as_ref());
+		self.storage.db.write(transaction).map_err(db_err)?;
+		self.blockchain.meta.write().genesis_hash = hash;
+
+		Ok(())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use std::sync::Arc;
+	use sc_client::blockchain::{Backend as _, HeaderBackend as _};
+	use sc_client_api::backend::{Backend as _, BlockImportOperation as _};
+	use sp_core::{H256, storage::{Storage, StorageChild}};
+	use sp_runtime::testing::Header;
+	use sp_state_machine::Backend as _;
+	use crate::{DatabaseSettingsSrc, PruningMode, tests::Block};
+
+	fn header(number: u64, state_root: H256) -> Header {
+		Header {
+			number,
+			parent_hash: Default::default(),
+			state_root,
+			digest: Default::default(),
+			extrinsics_root: Default::default(),
+		}
+	}
+
+	#[test]
+	fn imports_exported_snapshot() {
+		let child_key = b":child_storage:default:contract".to_vec();
+		let top = vec![
+			(b":code".to_vec(), b"code".to_vec()),
+			(b"key".to_vec(), b"value".to_vec()),
+			(b"z".to_vec(), b"last".to_vec()),
+		];
+		let mut storage = Storage::default();
+		storage.top.extend(top.clone());
+		storage.children.insert(child_key.clone(), StorageChild {
+			data: vec![(b"child".to_vec(), b"value".to_vec())].into_iter().collect(),
+			child_info: child_info(&child_key).unwrap(),
+		});
+
+		// compute the state root with a throwaway database.
+		let state_root = {
+			let backend = Backend::<Block>::new_test(0, 0);
+			let mut operation = backend.begin_operation().unwrap();
+			backend.begin_state_operation(&mut operation, BlockId::Hash(Default::default())).unwrap();
+			operation.reset_storage(storage.clone()).unwrap()
+		};
+		let snapshot = SnapshotHeader::<Block> {
+			genesis: header(0, Default::default()),
+			header: header(10, state_root),
+			justification: Some(vec![1, 2, 3]),
+			aux: vec![(b"aux".to_vec(), b"data".to_vec())],
+		};
+
+		let mut output = Vec::new();
+		let mut writer = SnapshotWriter::new(&mut output, &snapshot).unwrap();
+		writer.push(Some(&child_key), b"child".to_vec(), b"value".to_vec()).unwrap();
+		for (key, value) in top {
+			writer.push(None, key, value).unwrap();
+		}
+		writer.finish().unwrap();
+
+		let db = Arc::new(kvdb_memorydb::create(crate::utils::NUM_COLUMNS));
+		let settings = || DatabaseSettings {
+			state_cache_size: 16777216,
+			state_cache_child_ratio: Some((50, 100)),
+			pruning: PruningMode::keep_blocks(256),
+			blocks_pruning: crate::BlocksPruning::ArchiveAll,
+			source: DatabaseSettingsSrc::Custom(db.clone()),
+		};
+		let imported = import_snapshot::<Block>(settings(), &output[..]).unwrap();
+		assert_eq!(imported.hash(), snapshot.header.hash());
+
+		let backend = Backend::<Block>::new(settings(), 0).unwrap();
+		let info = backend.blockchain().info();
+		assert_eq!(info.best_number, 10);
+		assert_eq!(info.finalized_hash, snapshot.header.hash());
+		assert_eq!(info.genesis_hash, snapshot.genesis.hash());
+		assert_eq!(backend.blockchain().justification(BlockId::Number(10)).unwrap(), Some(vec![1, 2, 3]));
+		assert_eq!(backend.get_aux(b"aux").unwrap(), Some(b"data".to_vec()));
+
+		let state = backend.state_at(BlockId::Number(10)).unwrap();
+		assert_eq!(state.storage(b"key").unwrap(), Some(b"value".to_vec()));
+		assert_eq!(state.storage(b"z").unwrap(), Some(b"last".to_vec()));
+		let child_info = child_info(&child_key).unwrap();
+		assert_eq!(
+			state.child_storage(&child_key, child_info.as_ref(), b"child").unwrap(),
+			Some(b"value".to_vec()),
+		);
+
+		// a snapshot can't be imported twice.
+		assert!(import_snapshot::<Block>(settings(), &output[..]).is_err());
+	}
+
+	#[test]
+	fn rejects_corrupted_chunk() {
+		let snapshot = SnapshotHeader::<Block> {
+			genesis: header(0, Default::default()),
+			header: header(10, Default::default()),
+			justification: None,
+			aux: Vec::new(),
+		};
+		let mut output = Vec::new();
+		let mut writer = SnapshotWriter::new(&mut output, &snapshot).unwrap();
+		writer.push(None, b"key".to_vec(), b"value".to_vec()).unwrap();
+		writer.finish().unwrap();
+
+		let value = output.iter().rposition(|b| *b == b'e').unwrap();
+		output[value] = b'f';
+		let (_, mut reader) = SnapshotReader::new::<Block>(&output[..]).unwrap();
+		assert_eq!(reader.entries(None).count(), 0);
+		assert!(reader.take_error().is_err());
+	}
+
+	#[test]
+	fn rejects_unordered_entries() {
+		let snapshot = SnapshotHeader::<Block> {
+			genesis: header(0, Default::default()),
+			header: header(10, Default::default()),
+			justification: None,
+			aux: Vec::new(),
+		};
+		let mut output = Vec::new();
+		let mut writer = SnapshotWriter::new(&mut output, &snapshot).unwrap();
+		writer.push(None, b"b".to_vec(), b"value".to_vec()).unwrap();
+		writer.push(None, b"a".to_vec(), b"value".to_vec()).unwrap();
+		writer.finish().unwrap();
+
+		let (_, mut reader) = SnapshotReader::new::<Block>(&output[..]).unwrap();
+		assert_eq!(reader.entries(None).count(), 1);
+		assert!(reader.take_error().is_err());
+	}
+}
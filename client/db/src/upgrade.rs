@@ -166,6 +166,7 @@ mod tests {
 			state_cache_size: 0,
 			state_cache_child_ratio: None,
 			pruning: PruningMode::ArchiveAll,
+			blocks_pruning: crate::BlocksPruning::ArchiveAll,
 			source: DatabaseSettingsSrc::Path { path: db_path.to_owned(), cache_size: None },
 		}, DatabaseType::Full).map(|_| ())
 	}
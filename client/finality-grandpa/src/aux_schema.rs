@@ -406,6 +406,17 @@ pub(crate) fn write_voter_set_state<Block: BlockT, B: AuxStore>(
 	)
 }
 
+/// Keys of the auxiliary data needed to continue from a finalized block without the
+/// history before it, e.g. when the database is seeded with a state snapshot.
+pub fn snapshot_aux_keys() -> Vec<Vec<u8>> {
+	vec![
+		VERSION_KEY.to_vec(),
+		SET_STATE_KEY.to_vec(),
+		AUTHORITY_SET_KEY.to_vec(),
+		CONSENSUS_CHANGES_KEY.to_vec(),
+	]
+}
+
 /// Write concluded round.
 pub(crate) fn write_concluded_round<Block: BlockT, B: AuxStore>(
 	backend: &B,
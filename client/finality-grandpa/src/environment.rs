@@ -28,7 +28,7 @@ use parking_lot::RwLock;
 use sp_blockchain::{HeaderBackend, Error as ClientError, HeaderMetadata};
 use std::marker::PhantomData;
 
-use sc_client_api::{backend::Backend, utils::is_descendent_of};
+use sc_client_api::{backend::{Backend, keep_justification_aux_key}, utils::is_descendent_of};
 use sc_client::apply_aux;
 use finality_grandpa::{
 	BlockNumberOps, Equivocation, Error as GrandpaError, round::State as RoundState,
@@ -1085,6 +1085,12 @@ pub(crate) fn finalize_block<BE, Block, Client>(
 			"number" => ?number, "hash" => ?hash,
 		);
 
+		// blocks pruning must keep the justifications proving authority set changes
+		if status.new_set_block.is_some() {
+			let key = keep_justification_aux_key::<Block>(&hash);
+			apply_aux(import_op, &[(&key[..], &[][..])], &[])?;
+		}
+
 		let new_authorities = if let Some((canon_hash, canon_number)) = status.new_set_block {
 			// the authority set has changed.
 			let (new_id, set_ref) = authority_set.current();
@@ -21,7 +21,7 @@ use parity_scale_codec::Encode;
 use parking_lot::RwLockWriteGuard;
 
 use sp_blockchain::{BlockStatus, well_known_cache_keys};
-use sc_client_api::{backend::Backend, utils::is_descendent_of};
+use sc_client_api::{backend::{Backend, keep_justification_aux_key}, utils::is_descendent_of};
 use sp_api::{TransactionFor};
 
 use sp_consensus::{
@@ -387,6 +387,11 @@ where
 			);
 		}
 
+		// blocks pruning must keep the justification of the block enacting a forced change
+		if let AppliedChanges::Forced(_) = applied_changes {
+			block.auxiliary.push((keep_justification_aux_key::<Block>(&hash), Some(Vec::new())));
+		}
+
 		Ok(PendingSetChanges { just_in_case, applied_changes, do_pause })
 	}
 }
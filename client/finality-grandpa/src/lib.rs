@@ -96,6 +96,7 @@ mod observer;
 mod until_imported;
 mod voting_rule;
 
+pub use aux_schema::snapshot_aux_keys;
 pub use finality_proof::{FinalityProofProvider, StorageAndProofProvider};
 pub use justification::GrandpaJustification;
 pub use light_import::light_block_import;
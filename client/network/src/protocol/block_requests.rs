@@ -201,6 +201,19 @@ where
 			let hash = header.hash();
 			let parent_hash = header.parent_hash().clone();
 
+			let body = if get_body {
+				match self.chain.block_body(&BlockId::Hash(hash))? {
+					Some(body) => body.iter().map(|extrinsic| extrinsic.encode()).collect(),
+					// the body has been pruned, an empty one would be taken for the actual body
+					None => {
+						log::trace!("missing body of block {:?}, stopping block response", hash);
+						break
+					}
+				}
+			} else {
+				Vec::new()
+			};
+
 			let block_data = api::v1::BlockData {
 				hash: hash.encode(),
 				header: if get_header {
@@ -208,15 +221,7 @@ where
 				} else {
 					Vec::new()
 				},
-				body: if get_body {
-					self.chain.block_body(&BlockId::Hash(hash))?
-						.unwrap_or(Vec::new())
-						.iter_mut()
-						.map(|extrinsic| extrinsic.encode())
-						.collect()
-				} else {
-					Vec::new()
-				},
+				body,
 				receipt: Vec::new(),
 				message_queue: Vec::new(),
 				justification: if get_justification {
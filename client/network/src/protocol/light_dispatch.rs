@@ -164,6 +164,8 @@ enum Accept<Block: BlockT> {
 	Ok,
 	CheckFailed(ClientError, RequestData<Block>),
 	Unexpected(RequestData<Block>),
+	/// The peer does not have the requested data, e.g. because it has been pruned.
+	Missing(RequestData<Block>),
 }
 
 /// Dummy implementation of `FetchChecker` that always assumes that responses are bad.
@@ -303,6 +305,17 @@ impl<B: BlockT> LightDispatch<B> where
 
 				(retry_count, Some(retry_request_data))
 			},
+			Accept::Missing(retry_request_data) => {
+				trace!(target: "sync", "Peer {} does not have the data of remote {} request", peer, rtype);
+
+				if retry_count > 0 {
+					(retry_count - 1, Some(retry_request_data))
+				} else {
+					trace!(target: "sync", "Failed to get remote {} response for given number of retries", rtype);
+					retry_request_data.fail(ClientError::RemoteFetchFailed.into());
+					(0, None)
+				}
+			},
 		};
 
 		if let Some(request_data) = retry_request_data {
@@ -476,6 +489,11 @@ impl<B: BlockT> LightDispatch<B> where
 	) {
 		self.accept_response("body", network, peer, response.id, |request, checker| match request.data {
 			RequestData::RemoteBody(request, sender) => {
+				// full nodes stop the response at the first block whose body they don't have
+				if response.blocks.is_empty() {
+					return Accept::Missing(RequestData::RemoteBody(request, sender))
+				}
+
 				let mut bodies: Vec<_> = response
 					.blocks
 					.into_iter()
@@ -1310,4 +1328,31 @@ pub mod tests {
 		assert!(light_dispatch.active_peers.is_empty());
 		assert!(light_dispatch.idle_peers.is_empty(), "peer should be disconnected after bad response");
 	}
+
+	#[test]
+	fn remote_body_without_block_should_not_disconnect() {
+		let mut light_dispatch = dummy(true);
+		let mut network_interface = DummyNetwork::default();
+		let peer1 = PeerId::random();
+
+		let header = dummy_header();
+		light_dispatch.on_connect(&mut network_interface, peer1.clone(), Roles::FULL, 250);
+
+		light_dispatch.add_request(&mut network_interface, RequestData::RemoteBody(RemoteBodyRequest {
+			header: header.clone(),
+			retry_count: None,
+		}, oneshot::channel().0));
+
+		assert!(light_dispatch.pending_requests.is_empty());
+		assert_eq!(light_dispatch.active_peers.len(), 1);
+
+		let response = message::generic::BlockResponse {
+			id: 0,
+			blocks: Vec::new(),
+		};
+
+		light_dispatch.on_remote_body_response(&mut network_interface, peer1.clone(), response);
+		assert!(network_interface.disconnected_peers.is_empty(), "peer which pruned the body should stay connected");
+		assert_eq!(light_dispatch.idle_peers.len() + light_dispatch.active_peers.len(), 1);
+	}
 }
@@ -34,6 +34,10 @@ pub enum Error {
 	Client(Box<dyn std::error::Error + Send>),
 	/// Other error type.
 	Other(String),
+	/// The body of the requested block has been pruned.
+	#[display(fmt="Body of block {} has been pruned", _0)]
+	#[from(ignore)]
+	BodyPruned(String),
 }
 
 impl std::error::Error for Error {
@@ -56,6 +60,11 @@ impl From<Error> for rpc::Error {
 				message,
 				data: None,
 			},
+			Error::BodyPruned(_) => rpc::Error {
+				code: rpc::ErrorCode::ServerError(BASE_ERROR + 2),
+				message: format!("{}", e),
+				data: None,
+			},
 			e => errors::internal(e),
 		}
 	}
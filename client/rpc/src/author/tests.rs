@@ -29,6 +29,7 @@ use substrate_test_runtime_client::{
 	DefaultTestClientBuilderExt, TestClientBuilderExt, Backend, Client,
 };
 use sc_transaction_pool::{BasicPool, FullChainApi};
+use sp_transaction_pool::{ChainEvent, MaintainedTransactionPool};
 use tokio::runtime;
 
 fn uxt(sender: AccountKeyring, nonce: u64) -> Extrinsic {
@@ -55,6 +56,12 @@ struct TestSetup {
 
 impl Default for TestSetup {
 	fn default() -> Self {
+		Self::with_pool_options(Default::default())
+	}
+}
+
+impl TestSetup {
+	fn with_pool_options(options: sc_transaction_pool::txpool::Options) -> Self {
 		let keystore = KeyStore::new();
 		let client = Arc::new(
 			substrate_test_runtime_client::TestClientBuilder::new()
@@ -62,7 +69,7 @@ impl Default for TestSetup {
 				.build()
 		);
 		let pool = Arc::new(BasicPool::new(
-			Default::default(),
+			options,
 			Arc::new(FullChainApi::new(client.clone())),
 			None,
 		).0);
@@ -73,9 +80,7 @@ impl Default for TestSetup {
 			pool,
 		}
 	}
-}
 
-impl TestSetup {
 	fn author(&self) -> Author<FullTransactionPool, Client<Backend>> {
 		Author {
 			client: self.client.clone(),
@@ -101,6 +106,34 @@ fn submit_transaction_should_not_cause_error() {
 	);
 }
 
+#[test]
+fn submitted_transaction_should_be_resubmitted_from_journal() {
+	let dir = tempfile::tempdir().unwrap();
+	let options = sc_transaction_pool::txpool::Options {
+		journal: Some(sc_transaction_pool::txpool::JournalOptions {
+			path: dir.path().join("transactions.journal"),
+			max_transactions: 16,
+			rotate_period: 1,
+		}),
+		..Default::default()
+	};
+	let setup = TestSetup::with_pool_options(options.clone());
+	let xt = uxt(AccountKeyring::Alice, 0).encode();
+	assert_matches!(AuthorApi::submit_extrinsic(&setup.author(), xt.clone().into()).wait(), Ok(_));
+	// restart, letting the journal be written.
+	drop(setup);
+
+	let setup = TestSetup::with_pool_options(options);
+	let p = setup.author();
+	assert!(p.pending_extrinsics().unwrap().is_empty());
+	let hash = setup.client.info().genesis_hash;
+	let header = setup.client.header(&generic::BlockId::hash(hash)).unwrap().unwrap();
+	futures::executor::block_on(
+		setup.pool.maintain(ChainEvent::NewBlock { is_new_best: true, hash, header, tree_route: None })
+	);
+	assert_eq!(p.pending_extrinsics().unwrap(), vec![xt.into()]);
+}
+
 #[test]
 fn submit_rich_transaction_should_not_cause_error() {
 	let p = TestSetup::default().author();
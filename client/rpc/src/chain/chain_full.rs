@@ -23,7 +23,7 @@ use sc_rpc_api::Subscriptions;
 use sc_client_api::{BlockchainEvents, BlockBackend};
 use sp_runtime::{generic::{BlockId, SignedBlock}, traits::{Block as BlockT}};
 
-use super::{ChainBackend, client_err, error::FutureResult};
+use super::{ChainBackend, client_err, error::{Error, FutureResult}};
 use std::marker::PhantomData;
 use sp_blockchain::HeaderBackend;
 
@@ -70,9 +70,16 @@ impl<Block, Client> ChainBackend<Client, Block> for FullChain<Block, Client> whe
 	fn block(&self, hash: Option<Block::Hash>)
 		-> FutureResult<Option<SignedBlock<Block>>>
 	{
-		Box::new(result(self.client
-			.block(&BlockId::Hash(self.unwrap_or_best(hash)))
-			.map_err(client_err)
-		))
+		let hash = self.unwrap_or_best(hash);
+		let block = self.client.block(&BlockId::Hash(hash)).map_err(client_err);
+		Box::new(result(match block {
+			// a known header without body means that the body has been pruned
+			Ok(None) => match self.client.header(BlockId::Hash(hash)) {
+				Ok(Some(_)) => Err(Error::BodyPruned(format!("{:?}", hash))),
+				Ok(None) => Ok(None),
+				Err(e) => Err(client_err(e)),
+			},
+			block => block,
+		}))
 	}
 }
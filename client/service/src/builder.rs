@@ -43,6 +43,7 @@ use sp_runtime::traits::{
 	Block as BlockT, NumberFor, SaturatedConversion, HashFor, UniqueSaturatedInto,
 };
 use sp_api::ProvideRuntimeApi;
+use sp_core::storage::Storage;
 use sc_executor::{NativeExecutor, NativeExecutionDispatch};
 use std::{
 	io::{Read, Write, Seek},
@@ -122,7 +123,7 @@ pub type BackgroundTask = Pin<Box<dyn Future<Output=()> + Send>>;
 pub struct ServiceBuilder<TBl, TRtApi, TCl, TFchr, TSc, TImpQu, TFprb, TFpp,
 	TExPool, TRpc, Backend>
 {
-	config: Configuration,
+	pub(crate) config: Configuration,
 	pub (crate) client: Arc<TCl>,
 	backend: Arc<Backend>,
 	tasks_builder: TaskManagerBuilder,
@@ -235,21 +236,7 @@ fn new_full_parts<TBl, TRtApi, TExecDisp>(
 		.unwrap_or_default();
 
 	let (client, backend) = {
-		let db_config = sc_client_db::DatabaseSettings {
-			state_cache_size: config.state_cache_size,
-			state_cache_child_ratio:
-			config.state_cache_child_ratio.map(|v| (v, 100)),
-			pruning: config.pruning.clone(),
-			source: match config.expect_database() {
-				DatabaseConfig::Path { path, cache_size } =>
-					sc_client_db::DatabaseSettingsSrc::Path {
-						path: path.clone(),
-						cache_size: cache_size.clone().map(|u| u as usize),
-					},
-				DatabaseConfig::Custom(db) =>
-					sc_client_db::DatabaseSettingsSrc::Custom(db.clone()),
-			},
-		};
+		let db_config = database_settings(config);
 
 		let extensions = sc_client_api::execution_extensions::ExecutionExtensions::new(
 			config.execution_strategies.clone(),
@@ -271,6 +258,26 @@ fn new_full_parts<TBl, TRtApi, TExecDisp>(
 	Ok((client, backend, keystore, tasks_builder))
 }
 
+/// Database settings of the given configuration.
+pub(crate) fn database_settings(config: &Configuration) -> sc_client_db::DatabaseSettings {
+	sc_client_db::DatabaseSettings {
+		state_cache_size: config.state_cache_size,
+		state_cache_child_ratio:
+			config.state_cache_child_ratio.map(|v| (v, 100)),
+		pruning: config.pruning.clone(),
+		blocks_pruning: config.blocks_pruning,
+		source: match config.expect_database() {
+			DatabaseConfig::Path { path, cache_size } =>
+				sc_client_db::DatabaseSettingsSrc::Path {
+					path: path.clone(),
+					cache_size: cache_size.clone().map(|u| u as usize),
+				},
+			DatabaseConfig::Custom(db) =>
+				sc_client_db::DatabaseSettingsSrc::Custom(db.clone()),
+		},
+	}
+}
+
 impl ServiceBuilder<(), (), (), (), (), (), (), (), (), (), ()> {
 	/// Start the service builder with a configuration.
 	pub fn new_full<TBl: BlockT, TRtApi, TExecDisp: NativeExecutionDispatch + 'static>(
@@ -345,21 +352,7 @@ impl ServiceBuilder<(), (), (), (), (), (), (), (), (), (), ()> {
 		);
 
 		let db_storage = {
-			let db_settings = sc_client_db::DatabaseSettings {
-				state_cache_size: config.state_cache_size,
-				state_cache_child_ratio:
-					config.state_cache_child_ratio.map(|v| (v, 100)),
-				pruning: config.pruning.clone(),
-				source: match config.expect_database() {
-					DatabaseConfig::Path { path, cache_size } =>
-						sc_client_db::DatabaseSettingsSrc::Path {
-							path: path.clone(),
-							cache_size: cache_size.clone().map(|u| u as usize),
-						},
-					DatabaseConfig::Custom(db) =>
-						sc_client_db::DatabaseSettingsSrc::Custom(db.clone()),
-				},
-			};
+			let db_settings = database_settings(&config);
 			sc_client_db::light::LightStorage::new(db_settings)?
 		};
 		let light_blockchain = sc_client::light::new_light_blockchain(db_storage);
@@ -747,6 +740,23 @@ pub trait ServiceBuilderCommand {
 		self,
 		block: BlockId<Self::Block>
 	) -> Pin<Box<dyn Future<Output = Result<(), Error>> + Send>>;
+
+	/// Export a snapshot of the state of a finalized block, along with the auxiliary data
+	/// under the keys returned by `aux_keys` for the block hash.
+	fn export_state(
+		&self,
+		output: impl Write,
+		block: BlockId<Self::Block>,
+		aux_keys: impl FnOnce(&<Self::Block as BlockT>::Hash) -> Vec<Vec<u8>>,
+	) -> Result<(), Error>;
+
+	/// Build a raw chain spec whose genesis is the state of the given block, after `rewrite`
+	/// is applied to it.
+	fn export_chain_spec(
+		self,
+		block: BlockId<Self::Block>,
+		rewrite: impl FnOnce(&mut Storage) -> Result<(), Error>,
+	) -> Result<String, Error>;
 }
 
 impl<TBl, TRtApi, TBackend, TExec, TSc, TImpQu, TExPool, TRpc>
@@ -17,7 +17,8 @@
 //! Chain utilities.
 
 use crate::error;
-use crate::builder::{ServiceBuilderCommand, ServiceBuilder};
+use crate::builder::{ServiceBuilderCommand, ServiceBuilder, database_settings};
+use crate::config::Configuration;
 use crate::error::Error;
 use sc_chain_spec::ChainSpec;
 use log::{warn, info};
@@ -35,7 +36,9 @@ use sp_consensus::{
 use sc_executor::{NativeExecutor, NativeExecutionDispatch};
 
 use std::{io::{Read, Write, Seek}, pin::Pin};
-use sc_client_api::BlockBackend;
+use sc_client_api::{AuxStore, BlockBackend, backend::StateBackend};
+use sc_client_db::snapshot::{SnapshotHeader, SnapshotWriter};
+use sp_core::storage::{well_known_keys, Storage, StorageChild};
 
 /// Build a chain spec json
 pub fn build_spec(spec: &dyn ChainSpec, raw: bool) -> error::Result<String> {
@@ -296,4 +299,120 @@ impl<
 			Err(e) => Box::pin(future::err(format!("Error reading block: {:?}", e).into())),
 		}
 	}
+
+	fn export_state(
+		&self,
+		output: impl Write,
+		block: BlockId<TBl>,
+		aux_keys: impl FnOnce(&TBl::Hash) -> Vec<Vec<u8>>,
+	) -> Result<(), Error> {
+		let header = self.client.header(&block)?.ok_or("Unknown block")?;
+		let hash = header.hash();
+		let number = *header.number();
+		// the auxiliary data is the current one of the node, e.g. the current GRANDPA authority
+		// set, which only matches the state of the latest finalized block.
+		if hash != self.client.chain_info().finalized_hash {
+			return Err("Only the state of the latest finalized block can be exported".into())
+		}
+
+		let genesis = self.client.header(&BlockId::Number(Zero::zero()))?
+			.ok_or("Missing genesis header")?;
+		let justification = self.client.justification(&BlockId::Hash(hash))?;
+		let mut aux = Vec::new();
+		for key in aux_keys(&hash) {
+			if let Some(value) = self.client.get_aux(&key)? {
+				aux.push((key, value));
+			}
+		}
+
+		info!("Exporting state of block #{} ({})", number, hash);
+		let snapshot = SnapshotHeader::<TBl> { genesis, header, justification, aux };
+		let mut writer = SnapshotWriter::new(output, &snapshot)?;
+		let state = self.client.state_at(&BlockId::Hash(hash))?;
+		for_each_entry(&state, |child, key, value| Ok(writer.push(child, key, value)?))?;
+
+		writer.finish()?;
+		Ok(())
+	}
+
+	fn export_chain_spec(
+		mut self,
+		block: BlockId<TBl>,
+		rewrite: impl FnOnce(&mut Storage) -> Result<(), Error>,
+	) -> Result<String, Error> {
+		let header = self.client.header(&block)?.ok_or("Unknown block")?;
+		info!("Exporting state of block #{} ({}) as genesis", header.number(), header.hash());
+		let state = self.client.state_at(&BlockId::Hash(header.hash()))?;
+
+		let mut storage = Storage::default();
+		for_each_entry(&state, |child, key, value| {
+			match child {
+				None => { storage.top.insert(key, value); },
+				Some(storage_key) => {
+					let child_info = sc_client_db::snapshot::child_info(storage_key)?;
+					storage.children.entry(storage_key.to_vec())
+						.or_insert_with(|| StorageChild { data: Default::default(), child_info })
+						.data
+						.insert(key, value);
+				},
+			}
+			Ok(())
+		})?;
+		rewrite(&mut storage)?;
+
+		let spec = self.config.chain_spec.as_mut().ok_or("No chain spec specified")?;
+		spec.set_storage(storage);
+
+		// nodes of the new chain must neither connect to nor be mistaken for the original one
+		let id = format!("{}_{}", spec.id(), header.number());
+		spec.set_id(&id, Some(&id));
+		spec.clear_boot_nodes();
+		Ok(spec.as_json(true)?)
+	}
+}
+
+/// Call `f` with the storage key of the child trie, if any, the key and the value of every
+/// entry of the state, the entries of every child trie first and of the main trie last, each
+/// trie in key order.
+///
+/// The roots of child tries are left out, as they are recomputed from their content.
+fn for_each_entry<H, S>(
+	state: &S,
+	mut f: impl FnMut(Option<&[u8]>, Vec<u8>, Vec<u8>) -> Result<(), Error>,
+) -> Result<(), Error> where
+	H: sp_core::Hasher,
+	H::Out: Ord,
+	S: StateBackend<H>,
+{
+	let mut children = Vec::new();
+	state.for_keys_with_prefix(well_known_keys::CHILD_STORAGE_KEY_PREFIX, |key| children.push(key.to_vec()));
+	for storage_key in children {
+		let child_info = sc_client_db::snapshot::child_info(&storage_key)?;
+		let mut keys = Vec::new();
+		state.for_keys_in_child_storage(&storage_key, child_info.as_ref(), |key| keys.push(key.to_vec()));
+		for key in keys {
+			let value = state.child_storage(&storage_key, child_info.as_ref(), &key)
+				.map_err(|e| sp_blockchain::Error::from_state(Box::new(e)))?
+				.ok_or("Missing child storage value")?;
+			f(Some(&storage_key), key, value)?;
+		}
+	}
+
+	let mut result = Ok(());
+	state.for_key_values_with_prefix(&[], |key, value| {
+		if result.is_ok() && !well_known_keys::is_child_storage_key(key) {
+			result = f(None, key.to_vec(), value.to_vec());
+		}
+	});
+	result
+}
+
+/// Seed the empty database of the given configuration with a state snapshot produced by
+/// [`ServiceBuilderCommand::export_state`], from which the node then syncs.
+///
+/// The snapshot is expected to be of the chain the configuration is for.
+pub fn import_state<TBl: BlockT>(config: &Configuration, input: impl Read) -> Result<(), Error> {
+	let header = sc_client_db::snapshot::import_snapshot::<TBl>(database_settings(config), input)?;
+	info!("Imported state of block #{} ({})", header.number(), header.hash());
+	Ok(())
 }
@@ -17,12 +17,15 @@
 //! Service configuration.
 
 pub use sc_client::ExecutionStrategies;
-pub use sc_client_db::{kvdb::KeyValueDB, PruningMode};
+pub use sc_client_db::{kvdb::KeyValueDB, PruningMode, BlocksPruning};
 pub use sc_network::config::{ExtTransport, NetworkConfiguration, Roles};
 pub use sc_executor::WasmExecutionMethod;
 
 use std::{future::Future, path::{PathBuf, Path}, pin::Pin, net::SocketAddr, sync::Arc};
-pub use sc_transaction_pool::txpool::Options as TransactionPoolOptions;
+pub use sc_transaction_pool::txpool::{
+	Options as TransactionPoolOptions, JournalOptions as TransactionPoolJournalOptions,
+	base_pool::{Limit as TransactionPoolLimit, SenderLimit as TransactionPoolSenderLimit},
+};
 use sc_chain_spec::ChainSpec;
 use sp_core::crypto::Protected;
 use target_info::Target;
@@ -78,6 +81,8 @@ pub struct Configuration {
 	pub state_cache_child_ratio: Option<usize>,
 	/// Pruning settings.
 	pub pruning: PruningMode,
+	/// Pruning settings of block bodies and justifications.
+	pub blocks_pruning: BlocksPruning,
 	/// Chain configuration.
 	pub chain_spec: Option<Box<dyn ChainSpec>>,
 	/// Node name.
@@ -211,6 +216,7 @@ impl Default for Configuration {
 			state_cache_size: Default::default(),
 			state_cache_child_ratio: Default::default(),
 			pruning: PruningMode::default(),
+			blocks_pruning: BlocksPruning::default(),
 			wasm_method: WasmExecutionMethod::Interpreted,
 			execution_strategies: Default::default(),
 			rpc_http: None,
@@ -21,7 +21,7 @@
 //! For a more full-featured pool, have a look at the `pool` module.
 
 use std::{
-	collections::HashSet,
+	collections::{BTreeMap, HashMap, HashSet},
 	fmt,
 	hash,
 	sync::Arc,
@@ -228,6 +228,8 @@ pub struct BasePool<Hash: hash::Hash + Eq, Ex> {
 	/// transactions to future in case they were just stuck in verification.
 	recently_pruned: [HashSet<Tag>; RECENTLY_PRUNED_TAGS],
 	recently_pruned_index: usize,
+	/// Transactions counted against the per-sender and external quotas.
+	quotas: Quotas<Hash>,
 }
 
 impl<Hash: hash::Hash + Member + Serialize, Ex: std::fmt::Debug> Default for BasePool<Hash, Ex> {
@@ -239,12 +241,18 @@ impl<Hash: hash::Hash + Member + Serialize, Ex: std::fmt::Debug> Default for Bas
 impl<Hash: hash::Hash + Member + Serialize, Ex: std::fmt::Debug> BasePool<Hash, Ex> {
 	/// Create new pool given reject_future_transactions flag.
 	pub fn new(reject_future_transactions: bool) -> Self {
+		Self::with_sender_limit(reject_future_transactions, None)
+	}
+
+	/// Create new pool given reject_future_transactions flag and per-sender limit.
+	pub fn with_sender_limit(reject_future_transactions: bool, senders: Option<SenderLimit>) -> Self {
 		BasePool {
 			reject_future_transactions,
 			future: Default::default(),
 			ready: Default::default(),
 			recently_pruned: Default::default(),
 			recently_pruned_index: 0,
+			quotas: Quotas::new(senders),
 		}
 	}
 
@@ -296,6 +304,7 @@ impl<Hash: hash::Hash + Member + Serialize, Ex: std::fmt::Debug> BasePool<Hash,
 			}
 
 			let hash = tx.transaction.hash.clone();
+			self.quotas.add(&tx.transaction, false);
 			self.future.import(tx);
 			return Ok(Imported::Future { hash });
 		}
@@ -327,8 +336,13 @@ impl<Hash: hash::Hash + Member + Serialize, Ex: std::fmt::Debug> BasePool<Hash,
 
 			// import this transaction
 			let current_hash = tx.transaction.hash.clone();
+			let transaction = tx.transaction.clone();
 			match self.ready.import(tx) {
 				Ok(mut replaced) => {
+					self.quotas.add(&transaction, true);
+					for tx in &replaced {
+						self.quotas.remove(&tx.hash);
+					}
 					if !first {
 						promoted.push(current_hash);
 					}
@@ -337,9 +351,11 @@ impl<Hash: hash::Hash + Member + Serialize, Ex: std::fmt::Debug> BasePool<Hash,
 				},
 				// transaction failed to be imported.
 				Err(e) => if first {
+					self.quotas.remove(&current_hash);
 					debug!(target: "txpool", "[{:?}] Error importing: {:?}", current_hash, e);
 					return Err(e)
 				} else {
+					self.quotas.remove(&current_hash);
 					failed.push(current_hash);
 				},
 			}
@@ -354,7 +370,9 @@ impl<Hash: hash::Hash + Member + Serialize, Ex: std::fmt::Debug> BasePool<Hash,
 		if removed.iter().any(|tx| tx.hash == hash) {
 			// We still need to remove all transactions that we promoted
 			// since they depend on each other and will never get to the best iterator.
-			self.ready.remove_subtree(&promoted);
+			for tx in self.ready.remove_subtree(&promoted) {
+				self.quotas.remove(&tx.hash);
+			}
 
 			debug!(target: "txpool", "[{:?}] Cycle detected, bailing.", hash);
 			return Err(error::Error::CycleDetected)
@@ -378,6 +396,12 @@ impl<Hash: hash::Hash + Member + Serialize, Ex: std::fmt::Debug> BasePool<Hash,
 		self.future.all()
 	}
 
+	/// Returns an iterator over future transactions in the pool and the tags they are missing
+	/// to become ready.
+	pub fn futures_missing_tags(&self) -> impl Iterator<Item=(&Arc<Transaction<Hash, Ex>>, &HashSet<Tag>)> {
+		self.future.all_missing_tags()
+	}
+
 	/// Returns pool transactions given list of hashes.
 	///
 	/// Includes both ready and future pool. For every hash in the `hashes`
@@ -402,11 +426,24 @@ impl<Hash: hash::Hash + Member + Serialize, Ex: std::fmt::Debug> BasePool<Hash,
 	///
 	/// Removes and returns worst transactions from the queues and all transactions that depend on them.
 	/// Technically the worst transaction should be evaluated by computing the entire pending set.
-	/// We use a simplified approach to remove the transaction that occupies the pool for the longest time.
+	/// We use a simplified approach to remove the lowest priority transaction of the sender with most
+	/// transactions in the queue if senders are limited, or else the transaction that occupies the
+	/// pool for the longest time.
 	pub fn enforce_limits(&mut self, ready: &Limit, future: &Limit) -> Vec<Arc<Transaction<Hash, Ex>>> {
 		let mut removed = vec![];
+		let senders = self.quotas.sender_limit.clone();
+		let senders = senders.as_ref();
 
 		while ready.is_exceeded(self.ready.len(), self.ready.bytes()) {
+			let heaviest_sender_worst = senders.and_then(|senders| heaviest_sender_worst(
+				senders,
+				self.transactions_by_age().into_iter().filter(|(_, (future, _))| !future),
+			));
+			if let Some(worst) = heaviest_sender_worst {
+				removed.append(&mut self.remove_subtree(&[worst]));
+				continue
+			}
+
 			// find the worst transaction
 			let minimal = self.ready
 				.fold(|minimal, current| {
@@ -428,6 +465,15 @@ impl<Hash: hash::Hash + Member + Serialize, Ex: std::fmt::Debug> BasePool<Hash,
 		}
 
 		while future.is_exceeded(self.future.len(), self.future.bytes()) {
+			let heaviest_sender_worst = senders.and_then(|senders| heaviest_sender_worst(
+				senders,
+				self.transactions_by_age().into_iter().filter(|(_, (future, _))| *future),
+			));
+			if let Some(worst) = heaviest_sender_worst {
+				removed.append(&mut self.remove_subtree(&[worst]));
+				continue
+			}
+
 			// find the worst transaction
 			let minimal = self.future
 				.fold(|minimal, current| {
@@ -450,6 +496,80 @@ impl<Hash: hash::Hash + Member + Serialize, Ex: std::fmt::Debug> BasePool<Hash,
 		removed
 	}
 
+	/// Returns true if a sender exceeds the per-sender limit or if the transactions from
+	/// external sources exceed the `external` limit.
+	pub fn quotas_exceeded(&self, external: Option<&Limit>) -> bool {
+		let (count, bytes) = self.quotas.external;
+		!self.quotas.exceeding.is_empty()
+			|| external.map_or(false, |external| external.is_exceeded(count, bytes))
+	}
+
+	/// Makes sure that no sender exceeds the per-sender limit and that the transactions from
+	/// external sources stay within the `external` limit.
+	///
+	/// Removes and returns the lowest priority, most recent transactions of the offending sender,
+	/// or of external sources, and all transactions that depend on them.
+	/// Future transactions are removed before ready ones.
+	pub fn enforce_quotas(&mut self, external: Option<&Limit>) -> Vec<Arc<Transaction<Hash, Ex>>> {
+		let mut removed = vec![];
+
+		while let Some(sender) = self.quotas.exceeding.iter().next().cloned() {
+			match self.quotas.sender_worst(&sender) {
+				Some(worst) => removed.append(&mut self.remove_quota_subtree(worst)),
+				None => {
+					self.quotas.exceeding.remove(&sender);
+				},
+			}
+		}
+
+		if let Some(external) = external {
+			while external.is_exceeded(self.quotas.external.0, self.quotas.external.1) {
+				match self.quotas.external_worst() {
+					Some(worst) => removed.append(&mut self.remove_quota_subtree(worst)),
+					None => break,
+				}
+			}
+		}
+
+		removed
+	}
+
+	/// Removes the given counted transaction and all transactions that depend on it.
+	fn remove_quota_subtree(&mut self, hash: Hash) -> Vec<Arc<Transaction<Hash, Ex>>> {
+		let removed = self.remove_subtree(&[hash.clone()]);
+		// Make sure quota enforcement progresses even if the transaction left the pool uncounted.
+		self.quotas.remove(&hash);
+		removed
+	}
+
+	/// Returns all transactions in the pool with their age.
+	///
+	/// Greater is younger, and future transactions are younger than ready ones.
+	fn transactions_by_age(&mut self) -> Vec<(Arc<Transaction<Hash, Ex>>, (bool, u64))> {
+		let mut transactions = self.ready
+			.fold(|transactions, current| {
+				let mut transactions = transactions.unwrap_or_else(Vec::new);
+				let transaction = &current.transaction;
+				transactions.push((transaction.transaction.clone(), (false, transaction.insertion_id)));
+				Some(transactions)
+			})
+			.unwrap_or_default();
+
+		let mut future = self.future
+			.fold(|future, current| {
+				let mut future = future.unwrap_or_else(Vec::new);
+				future.push((current.transaction.clone(), current.imported_at));
+				Some(future)
+			})
+			.unwrap_or_default();
+		future.sort_by_key(|(_, imported_at)| *imported_at);
+		transactions.extend(
+			future.into_iter().enumerate().map(|(index, (tx, _))| (tx, (true, index as u64)))
+		);
+
+		transactions
+	}
+
 	/// Removes all transactions represented by the hashes and all other transactions
 	/// that depend on them.
 	///
@@ -461,12 +581,19 @@ impl<Hash: hash::Hash + Member + Serialize, Ex: std::fmt::Debug> BasePool<Hash,
 	pub fn remove_subtree(&mut self, hashes: &[Hash]) -> Vec<Arc<Transaction<Hash, Ex>>> {
 		let mut removed = self.ready.remove_subtree(hashes);
 		removed.extend(self.future.remove(hashes));
+		for tx in &removed {
+			self.quotas.remove(&tx.hash);
+		}
 		removed
 	}
 
 	/// Removes and returns all transactions from the future queue.
 	pub fn clear_future(&mut self) -> Vec<Arc<Transaction<Hash, Ex>>> {
-		self.future.clear()
+		let removed = self.future.clear();
+		for tx in &removed {
+			self.quotas.remove(&tx.hash);
+		}
+		removed
 	}
 
 	/// Prunes transactions that provide given list of tags.
@@ -486,7 +613,11 @@ impl<Hash: hash::Hash + Member + Serialize, Ex: std::fmt::Debug> BasePool<Hash,
 			// make sure to promote any future transactions that could be unlocked
 			to_import.append(&mut self.future.satisfy_tags(std::iter::once(&tag)));
 			// and actually prune transactions in ready queue
-			pruned.append(&mut self.ready.prune_tags(tag.clone()));
+			let mut pruned_by_tag = self.ready.prune_tags(tag.clone());
+			for tx in &pruned_by_tag {
+				self.quotas.remove(&tx.hash);
+			}
+			pruned.append(&mut pruned_by_tag);
 			// store the tags for next submission
 			recently_pruned.insert(tag);
 		}
@@ -522,6 +653,218 @@ impl<Hash: hash::Hash + Member + Serialize, Ex: std::fmt::Debug> BasePool<Hash,
 	}
 }
 
+/// Returns the hash of the lowest priority, youngest of the given transactions.
+fn worst<'a, Hash: Clone + 'a, Ex: 'a, Age: Ord>(
+	transactions: impl Iterator<Item=(&'a Arc<Transaction<Hash, Ex>>, Age)>,
+) -> Option<Hash> {
+	transactions
+		.min_by(|(a, a_age), (b, b_age)| a.priority.cmp(&b.priority).then_with(|| b_age.cmp(a_age)))
+		.map(|(tx, _)| tx.hash.clone())
+}
+
+/// Returns the hash of the worst transaction of the sender with most of the given transactions.
+fn heaviest_sender_worst<Hash: Clone, Ex, Age: Ord>(
+	senders: &SenderLimit,
+	transactions: impl Iterator<Item=(Arc<Transaction<Hash, Ex>>, Age)>,
+) -> Option<Hash> {
+	let transactions = transactions.collect::<Vec<_>>();
+	let mut by_sender = HashMap::<&[u8], Vec<_>>::new();
+	for (tx, age) in &transactions {
+		if let Some(sender) = senders.sender_of(tx) {
+			by_sender.entry(sender).or_default().push((tx, age));
+		}
+	}
+
+	by_sender.into_iter()
+		.max_by_key(|(_, transactions)| transactions.len())
+		.and_then(|(_, transactions)| worst(transactions.into_iter()))
+}
+
+/// Per-sender limit
+#[derive(Debug, Clone)]
+#[cfg_attr(not(target_os = "unknown"), derive(parity_util_mem::MallocSizeOf))]
+pub struct SenderLimit {
+	/// Maximal number of transactions of a single sender in the ready and future queues.
+	pub count: usize,
+	/// Lengths of the first `provides` tag and of its leading bytes identifying the sender.
+	///
+	/// FRAME's `CheckNonce` provides `(AccountId, Index)` tags and the EVM module `(H160, U256)`
+	/// tags, so with 32 bytes account ids and `u32` indices these are `(36, 32)` and `(52, 20)`.
+	/// Transactions with a tag of any other length have no sender.
+	pub tag_prefixes: Vec<(usize, usize)>,
+}
+
+impl SenderLimit {
+	/// Returns the sender of the given transaction.
+	pub fn sender_of<'a, Hash, Ex>(&self, tx: &'a Transaction<Hash, Ex>) -> Option<&'a [u8]> {
+		let tag = tx.provides.first()?;
+		self.tag_prefixes.iter()
+			.find(|(tag_length, _)| *tag_length == tag.len())
+			.map(|(_, prefix)| &tag[..(*prefix).min(tag.len())])
+	}
+}
+
+/// Orders counted transactions by eviction, the first one being evicted first.
+///
+/// That is the lowest priority, future before ready and most recently counted one, the last
+/// element being `u64::max_value()` minus a counter.
+type EvictionKey = (Priority, bool, u64);
+
+/// Counted transactions, by eviction order.
+type EvictionIndex<Hash> = BTreeMap<EvictionKey, Hash>;
+
+/// A transaction counted against the quotas.
+#[derive(Debug)]
+#[cfg_attr(not(target_os = "unknown"), derive(parity_util_mem::MallocSizeOf))]
+struct Counted {
+	sender: Option<Vec<u8>>,
+	key: EvictionKey,
+	/// Size of the transaction, if from an external source.
+	external_bytes: Option<usize>,
+}
+
+/// Transactions of the pool counted against the per-sender and external quotas.
+///
+/// Kept up to date as transactions enter and leave the pool, so that the quotas are only
+/// enforced when exceeded, and indexed by eviction order, so that enforcing them does not
+/// scan the pool.
+#[derive(Debug)]
+#[cfg_attr(not(target_os = "unknown"), derive(parity_util_mem::MallocSizeOf))]
+struct Quotas<Hash: hash::Hash + Eq> {
+	sender_limit: Option<SenderLimit>,
+	/// Number of transactions counted so far, ordering the most recent ones first.
+	counter: u64,
+	transactions: HashMap<Hash, Counted>,
+	/// Counted transactions by sender.
+	senders: HashMap<Vec<u8>, EvictionIndex<Hash>>,
+	/// Senders with more transactions than the per-sender limit.
+	exceeding: HashSet<Vec<u8>>,
+	/// Number and total size of the counted transactions from external sources.
+	external: (usize, usize),
+	/// Counted transactions from external sources.
+	external_transactions: EvictionIndex<Hash>,
+	/// Counted transactions from external sources by sender.
+	external_senders: HashMap<Vec<u8>, EvictionIndex<Hash>>,
+}
+
+impl<Hash: hash::Hash + Eq + Clone> Quotas<Hash> {
+	fn new(sender_limit: Option<SenderLimit>) -> Self {
+		Quotas {
+			sender_limit,
+			counter: 0,
+			transactions: Default::default(),
+			senders: Default::default(),
+			exceeding: Default::default(),
+			external: (0, 0),
+			external_transactions: Default::default(),
+			external_senders: Default::default(),
+		}
+	}
+
+	/// Count the given transaction, unless already counted, in the ready or future queue.
+	///
+	/// A counted future transaction is counted again when promoted to the ready queue.
+	fn add<Ex>(&mut self, tx: &Transaction<Hash, Ex>, ready: bool) {
+		if let Some(counted) = self.transactions.get(&tx.hash) {
+			if !ready || counted.key.1 {
+				return
+			}
+			self.remove(&tx.hash);
+		}
+
+		let key = (tx.priority, ready, u64::max_value() - self.counter);
+		self.counter += 1;
+
+		let Quotas {
+			sender_limit,
+			transactions,
+			senders,
+			exceeding,
+			external,
+			external_transactions,
+			external_senders,
+			..
+		} = self;
+		let sender = sender_limit.as_ref().and_then(|limit| {
+			let sender = limit.sender_of(tx)?.to_vec();
+			let sender_transactions = senders.entry(sender.clone()).or_default();
+			sender_transactions.insert(key, tx.hash.clone());
+			if sender_transactions.len() > limit.count {
+				exceeding.insert(sender.clone());
+			}
+			Some(sender)
+		});
+		let external_bytes = if tx.source == Source::External {
+			external.0 += 1;
+			external.1 += tx.bytes;
+			external_transactions.insert(key, tx.hash.clone());
+			if let Some(sender) = &sender {
+				external_senders.entry(sender.clone()).or_default().insert(key, tx.hash.clone());
+			}
+			Some(tx.bytes)
+		} else {
+			None
+		};
+		transactions.insert(tx.hash.clone(), Counted { sender, key, external_bytes });
+	}
+
+	/// Stop counting the transaction with the given hash, if counted.
+	fn remove(&mut self, hash: &Hash) {
+		let Counted { sender, key, external_bytes } = match self.transactions.remove(hash) {
+			Some(counted) => counted,
+			None => return,
+		};
+
+		if let Some(sender) = &sender {
+			let count = remove_indexed(&mut self.senders, sender, &key);
+			if self.sender_limit.as_ref().map_or(true, |limit| count <= limit.count) {
+				self.exceeding.remove(sender);
+			}
+		}
+		if let Some(bytes) = external_bytes {
+			self.external.0 -= 1;
+			self.external.1 -= bytes;
+			self.external_transactions.remove(&key);
+			if let Some(sender) = &sender {
+				remove_indexed(&mut self.external_senders, sender, &key);
+			}
+		}
+	}
+
+	/// Returns the hash of the worst transaction of the given sender.
+	fn sender_worst(&self, sender: &[u8]) -> Option<Hash> {
+		self.senders.get(sender)?.values().next().cloned()
+	}
+
+	/// Returns the hash of the worst transaction from external sources, of the sender with most
+	/// of them if any.
+	fn external_worst(&self) -> Option<Hash> {
+		self.external_senders.values()
+			.max_by_key(|transactions| transactions.len())
+			.and_then(|transactions| transactions.values().next())
+			.or_else(|| self.external_transactions.values().next())
+			.cloned()
+	}
+}
+
+/// Removes the transaction with the given key from the index of the sender.
+///
+/// Returns the number of transactions of the sender left in the index.
+fn remove_indexed<Hash>(
+	index: &mut HashMap<Vec<u8>, EvictionIndex<Hash>>,
+	sender: &[u8],
+	key: &EvictionKey,
+) -> usize {
+	let count = index.get_mut(sender).map(|transactions| {
+		transactions.remove(key);
+		transactions.len()
+	}).unwrap_or_default();
+	if count == 0 {
+		index.remove(sender);
+	}
+	count
+}
+
 /// Queue limits
 #[derive(Debug, Clone)]
 pub struct Limit {
@@ -1196,4 +1539,159 @@ source: TransactionSource::External, requires: [03,02], provides: [04], data: [4
 		assert_eq!(pool.reject_future_transactions, true);
 		assert_eq!(pool.future.len(), 1);
 	}
+
+	fn sender_transaction(
+		sender: u8,
+		nonce: u8,
+		priority: Priority,
+		source: Source,
+	) -> Transaction<Hash, Vec<u8>> {
+		Transaction {
+			data: vec![sender, nonce],
+			bytes: 1,
+			hash: (sender as u64) << 8 | nonce as u64,
+			priority,
+			valid_till: 64u64,
+			requires: if nonce == 0 { vec![] } else { vec![vec![sender, nonce - 1]] },
+			provides: vec![vec![sender, nonce]],
+			propagate: true,
+			source,
+		}
+	}
+
+	fn sender_limited_pool(count: usize) -> BasePool<Hash, Vec<u8>> {
+		BasePool::with_sender_limit(false, Some(SenderLimit { count, tag_prefixes: vec![(2, 1)] }))
+	}
+
+	#[test]
+	fn should_enforce_sender_limits() {
+		// given
+		let mut pool = sender_limited_pool(2);
+		pool.import(sender_transaction(1, 0, 5, Source::External)).unwrap();
+		pool.import(sender_transaction(1, 1, 1, Source::External)).unwrap();
+		pool.import(sender_transaction(1, 2, 5, Source::External)).unwrap();
+		pool.import(sender_transaction(2, 0, 5, Source::External)).unwrap();
+		assert_eq!(pool.ready().count(), 4);
+		assert!(pool.quotas_exceeded(None));
+
+		// when
+		let removed = pool.enforce_quotas(None);
+
+		// then
+		// removing the lowest priority transaction removes the one depending on it.
+		let mut removed = removed.into_iter().map(|tx| tx.hash).collect::<Vec<_>>();
+		removed.sort();
+		assert_eq!(removed, vec![257, 258]);
+		let mut ready = pool.ready().map(|tx| tx.hash).collect::<Vec<_>>();
+		ready.sort();
+		assert_eq!(ready, vec![256, 512]);
+		assert!(!pool.quotas_exceeded(None));
+	}
+
+	#[test]
+	fn should_enforce_sender_limits_on_future_transactions_first() {
+		// given
+		let mut pool = sender_limited_pool(2);
+		pool.import(sender_transaction(1, 0, 5, Source::External)).unwrap();
+		pool.import(sender_transaction(1, 1, 5, Source::External)).unwrap();
+		pool.import(sender_transaction(1, 3, 5, Source::External)).unwrap();
+		assert_eq!(pool.future.len(), 1);
+
+		// when
+		let removed = pool.enforce_quotas(None);
+
+		// then
+		assert_eq!(removed.into_iter().map(|tx| tx.hash).collect::<Vec<_>>(), vec![259]);
+		assert_eq!(pool.ready().count(), 2);
+		assert_eq!(pool.future.len(), 0);
+	}
+
+	#[test]
+	fn should_enforce_sender_limits_on_promoted_transactions_as_ready() {
+		// given
+		let mut pool = sender_limited_pool(2);
+		pool.import(sender_transaction(1, 3, 5, Source::External)).unwrap();
+		pool.import(sender_transaction(1, 1, 5, Source::External)).unwrap();
+		pool.import(sender_transaction(1, 0, 5, Source::External)).unwrap();
+		assert_eq!(pool.ready().count(), 2);
+		assert_eq!(pool.future.len(), 1);
+
+		// when
+		let removed = pool.enforce_quotas(None);
+
+		// then
+		assert_eq!(removed.into_iter().map(|tx| tx.hash).collect::<Vec<_>>(), vec![259]);
+		assert_eq!(pool.ready().count(), 2);
+		assert!(!pool.quotas_exceeded(None));
+	}
+
+	#[test]
+	fn should_enforce_external_limits() {
+		// given
+		let mut pool = pool();
+		let external = Limit { count: 1, total_bytes: 10 };
+		pool.import(sender_transaction(1, 0, 5, Source::Local)).unwrap();
+		pool.import(sender_transaction(2, 0, 5, Source::Local)).unwrap();
+		pool.import(sender_transaction(3, 0, 5, Source::External)).unwrap();
+		pool.import(sender_transaction(4, 0, 5, Source::External)).unwrap();
+
+		assert!(pool.quotas_exceeded(Some(&external)));
+
+		// when
+		let removed = pool.enforce_quotas(Some(&external));
+
+		// then
+		assert_eq!(removed.into_iter().map(|tx| tx.hash).collect::<Vec<_>>(), vec![1024]);
+		assert_eq!(pool.ready().count(), 3);
+	}
+
+	#[test]
+	fn should_evict_transactions_of_heaviest_sender() {
+		// given
+		let mut pool = sender_limited_pool(10);
+		let limit = Limit { count: 3, total_bytes: 10 };
+		pool.import(sender_transaction(1, 0, 1, Source::External)).unwrap();
+		pool.import(sender_transaction(2, 0, 5, Source::External)).unwrap();
+		pool.import(sender_transaction(2, 1, 5, Source::External)).unwrap();
+		pool.import(sender_transaction(2, 2, 5, Source::External)).unwrap();
+
+		// when
+		let removed = pool.enforce_limits(&limit, &limit);
+
+		// then
+		// the oldest and lowest priority transaction is kept, since its sender has a single one.
+		assert_eq!(removed.into_iter().map(|tx| tx.hash).collect::<Vec<_>>(), vec![514]);
+		assert_eq!(pool.ready().count(), 3);
+	}
+
+	#[test]
+	fn should_count_quotas_as_transactions_leave_the_pool() {
+		// given
+		let mut pool = sender_limited_pool(1);
+		let external = Limit { count: 2, total_bytes: 10 };
+		pool.import(sender_transaction(1, 0, 5, Source::External)).unwrap();
+		pool.import(sender_transaction(1, 1, 5, Source::External)).unwrap();
+		pool.import(sender_transaction(2, 0, 5, Source::External)).unwrap();
+		assert!(pool.quotas_exceeded(None));
+		assert!(pool.quotas_exceeded(Some(&external)));
+
+		// when
+		pool.prune_tags(vec![vec![1, 0]]);
+
+		// then
+		assert!(!pool.quotas_exceeded(Some(&external)));
+		assert!(pool.enforce_quotas(Some(&external)).is_empty());
+	}
+
+	#[test]
+	fn should_identify_senders_by_tag_length() {
+		let senders = SenderLimit { count: 1, tag_prefixes: vec![(36, 32), (52, 20)] };
+		let mut tx = sender_transaction(1, 0, 5, Source::External);
+		tx.provides = vec![vec![1; 36]];
+		assert_eq!(senders.sender_of(&tx), Some(&[1; 32][..]));
+		tx.provides = vec![vec![2; 52]];
+		assert_eq!(senders.sender_of(&tx), Some(&[2; 20][..]));
+		tx.provides = vec![vec![3; 40]];
+		assert_eq!(senders.sender_of(&tx), None);
+	}
 }
@@ -231,6 +231,11 @@ impl<Hash: hash::Hash + Eq + Clone, Ex> FutureTransactions<Hash, Ex> {
 		self.waiting.values().map(|waiting| &*waiting.transaction)
 	}
 
+	/// Returns iterator over all future transactions and the tags they are missing
+	pub fn all_missing_tags(&self) -> impl Iterator<Item=(&Arc<Transaction<Hash, Ex>>, &HashSet<Tag>)> {
+		self.waiting.values().map(|waiting| (&waiting.transaction, &waiting.missing_tags))
+	}
+
 	/// Removes and returns all future transactions.
 	pub fn clear(&mut self) -> Vec<Arc<Transaction<Hash, Ex>>> {
 		self.wanted_tags.clear();
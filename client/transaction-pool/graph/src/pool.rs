@@ -105,8 +105,25 @@ pub struct Options {
 	pub ready: base::Limit,
 	/// Future queue limits.
 	pub future: base::Limit,
+	/// Per-sender limits, senders are not limited if `None`.
+	pub senders: Option<base::SenderLimit>,
+	/// Limits of transactions from external sources, not limited apart from the queue limits if `None`.
+	pub external: Option<base::Limit>,
 	/// Reject future transactions.
 	pub reject_future_transactions: bool,
+	/// Persist the pool transactions to a journal replayed on restart.
+	pub journal: Option<JournalOptions>,
+}
+
+/// Transaction pool journal options.
+#[derive(Debug, Clone)]
+pub struct JournalOptions {
+	/// Path of the journal file.
+	pub path: std::path::PathBuf,
+	/// Maximum number of transactions kept in the journal.
+	pub max_transactions: usize,
+	/// Number of blocks between rewrites of the journal with the pool content.
+	pub rotate_period: u32,
 }
 
 impl Default for Options {
@@ -120,7 +137,10 @@ impl Default for Options {
 				count: 512,
 				total_bytes: 1 * 1024 * 1024,
 			},
+			senders: None,
+			external: None,
 			reject_future_transactions: false,
+			journal: None,
 		}
 	}
 }
@@ -116,7 +116,10 @@ where
 impl<B: ChainApi> ValidatedPool<B> {
 	/// Create a new transaction pool.
 	pub fn new(options: Options, api: Arc<B>) -> Self {
-		let base_pool = base::BasePool::new(options.reject_future_transactions);
+		let base_pool = base::BasePool::with_sender_limit(
+			options.reject_future_transactions,
+			options.senders.clone(),
+		);
 		ValidatedPool {
 			options,
 			listener: Default::default(),
@@ -185,13 +188,18 @@ impl<B: ChainApi> ValidatedPool<B> {
 	}
 
 	fn enforce_limits(&self) -> HashSet<ExtrinsicHash<B>> {
-		let status = self.pool.read().status();
 		let ready_limit = &self.options.ready;
 		let future_limit = &self.options.future;
+		let external = self.options.external.as_ref();
+		let (status, quotas_exceeded) = {
+			let pool = self.pool.read();
+			(pool.status(), pool.quotas_exceeded(external))
+		};
 
 		log::debug!(target: "txpool", "Pool Status: {:?}", status);
 		if ready_limit.is_exceeded(status.ready, status.ready_bytes)
 			|| future_limit.is_exceeded(status.future, status.future_bytes)
+			|| quotas_exceeded
 		{
 			log::debug!(
 				target: "txpool",
@@ -203,8 +211,9 @@ impl<B: ChainApi> ValidatedPool<B> {
 			// clean up the pool
 			let removed = {
 				let mut pool = self.pool.write();
-				let removed = pool.enforce_limits(ready_limit, future_limit)
-					.into_iter().map(|x| x.hash.clone()).collect::<HashSet<_>>();
+				let mut removed = pool.enforce_quotas(external);
+				removed.append(&mut pool.enforce_limits(ready_limit, future_limit));
+				let removed = removed.into_iter().map(|x| x.hash.clone()).collect::<HashSet<_>>();
 				// ban all removed transactions
 				self.rotator.ban(&Instant::now(), removed.iter().map(|x| x.clone()));
 				removed
@@ -556,6 +565,19 @@ impl<B: ChainApi> ValidatedPool<B> {
 		self.pool.read().ready()
 	}
 
+	/// Returns hashes and extrinsics of the transactions in the future queue.
+	pub fn futures(&self) -> Vec<(ExtrinsicHash<B>, ExtrinsicFor<B>)> {
+		self.pool.read().futures().map(|tx| (tx.hash.clone(), tx.data.clone())).collect()
+	}
+
+	/// Returns the transactions in the future queue and the tags they are missing to become ready.
+	pub fn futures_missing_tags(&self) -> Vec<(TransactionFor<B>, Vec<Tag>)> {
+		self.pool.read()
+			.futures_missing_tags()
+			.map(|(tx, missing_tags)| (tx.clone(), missing_tags.iter().cloned().collect()))
+			.collect()
+	}
+
 	/// Returns pool status.
 	pub fn status(&self) -> PoolStatus {
 		self.pool.read().status()
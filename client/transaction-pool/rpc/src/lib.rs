@@ -0,0 +1,374 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2020 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! RPC interface inspecting the content of the transaction pool.
+//!
+//! Unlike `author_pendingExtrinsics`, which only returns the encoded ready
+//! transactions, these calls expose the pool internals: the ready and future
+//! queues, the tags transactions require and provide, and the tags a future
+//! transaction is still waiting for.
+
+#![warn(missing_docs)]
+
+use std::sync::Arc;
+use codec::Encode;
+use jsonrpc_core::Result;
+use jsonrpc_derive::rpc;
+use serde::{Deserialize, Serialize};
+use sc_rpc_api::DenyUnsafe;
+use sc_transaction_graph::{ChainApi, ExtrinsicHash, Pool, TransactionFor};
+use sp_core::Bytes;
+use sp_runtime::transaction_validity::{
+	TransactionLongevity, TransactionPriority, TransactionSource, TransactionTag,
+};
+
+pub use self::gen_client::Client as TxPoolClient;
+
+/// Transaction pool inspection RPC API.
+#[rpc]
+pub trait TxPoolApi<Hash> {
+	/// Returns the number of transactions and their total size in both queues of the pool.
+	#[rpc(name = "txpool_status")]
+	fn status(&self) -> Result<Status>;
+
+	/// Returns all transactions in the pool.
+	///
+	/// Ready transactions are listed in the order they would be included in a block.
+	///
+	/// Unsafe, as the whole pool can be large.
+	#[rpc(name = "txpool_content")]
+	fn content(&self) -> Result<Content<Hash>>;
+
+	/// Returns the state of the transaction with given hash in the pool.
+	///
+	/// For a transaction in the future queue this lists the tags it is waiting for.
+	#[rpc(name = "txpool_inspect")]
+	fn inspect(&self, hash: Hash) -> Result<Inspection<Hash>>;
+}
+
+/// Number of transactions and their total size in both queues of the pool.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Status {
+	/// Number of transactions in the ready queue.
+	pub ready: usize,
+	/// Sum of encoded sizes of the transactions in the ready queue.
+	pub ready_bytes: usize,
+	/// Number of transactions in the future queue.
+	pub future: usize,
+	/// Sum of encoded sizes of the transactions in the future queue.
+	pub future_bytes: usize,
+}
+
+/// Where a transaction was submitted from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Source {
+	/// Submitted by the node itself.
+	Local,
+	/// Received over RPC or from the network.
+	External,
+	/// Resubmitted from a retracted block.
+	InBlock,
+}
+
+impl From<TransactionSource> for Source {
+	fn from(source: TransactionSource) -> Self {
+		match source {
+			TransactionSource::Local => Source::Local,
+			TransactionSource::External => Source::External,
+			TransactionSource::InBlock => Source::InBlock,
+		}
+	}
+}
+
+/// A transaction in the pool.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PoolTransaction<Hash> {
+	/// Hash of the transaction.
+	pub hash: Hash,
+	/// SCALE encoded extrinsic.
+	pub extrinsic: Bytes,
+	/// Encoded size of the extrinsic.
+	pub bytes: usize,
+	/// Priority of the transaction.
+	pub priority: TransactionPriority,
+	/// Block number at which the transaction becomes invalid.
+	pub valid_till: TransactionLongevity,
+	/// Tags required by the transaction.
+	pub requires: Vec<Bytes>,
+	/// Tags provided by the transaction.
+	pub provides: Vec<Bytes>,
+	/// Whether the transaction is propagated to other peers.
+	pub propagate: bool,
+	/// Where the transaction was submitted from.
+	pub source: Source,
+}
+
+impl<Hash: Clone, Ex: Encode> From<&sc_transaction_graph::Transaction<Hash, Ex>> for PoolTransaction<Hash> {
+	fn from(tx: &sc_transaction_graph::Transaction<Hash, Ex>) -> Self {
+		PoolTransaction {
+			hash: tx.hash.clone(),
+			extrinsic: tx.data.encode().into(),
+			bytes: tx.bytes,
+			priority: tx.priority,
+			valid_till: tx.valid_till,
+			requires: tx.requires.iter().cloned().map(Into::into).collect(),
+			provides: tx.provides.iter().cloned().map(Into::into).collect(),
+			propagate: tx.propagate,
+			source: tx.source.into(),
+		}
+	}
+}
+
+/// A transaction in the future queue.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FutureTransaction<Hash> {
+	/// The transaction.
+	#[serde(flatten)]
+	pub transaction: PoolTransaction<Hash>,
+	/// Required tags that no transaction in the pool or on chain provides yet.
+	pub missing_tags: Vec<Bytes>,
+}
+
+/// All transactions in the pool.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Content<Hash> {
+	/// Transactions in the ready queue, in the order they would be included in a block.
+	pub ready: Vec<PoolTransaction<Hash>>,
+	/// Transactions in the future queue.
+	pub future: Vec<FutureTransaction<Hash>>,
+}
+
+/// A tag a future transaction is waiting for.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MissingTag<Hash> {
+	/// The missing tag.
+	pub tag: Bytes,
+	/// Another future transaction providing the tag, if any.
+	///
+	/// The tag becomes available once that transaction is itself ready.
+	pub provided_by: Option<Hash>,
+}
+
+/// State of a transaction in the pool.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Inspection<Hash> {
+	/// The transaction is in the ready queue.
+	Ready(PoolTransaction<Hash>),
+	/// The transaction is in the future queue.
+	#[serde(rename_all = "camelCase")]
+	Future {
+		/// The transaction.
+		transaction: PoolTransaction<Hash>,
+		/// The tags the transaction is waiting for.
+		waiting_for: Vec<MissingTag<Hash>>,
+	},
+	/// The transaction is not in the pool and is temporarily banned from it.
+	Banned,
+	/// The transaction is not in the pool.
+	Unknown,
+}
+
+/// Implementation of the [`TxPoolApi`] on top of a transaction pool.
+pub struct TxPool<A: ChainApi> {
+	pool: Arc<Pool<A>>,
+	deny_unsafe: DenyUnsafe,
+}
+
+impl<A: ChainApi> TxPool<A> {
+	/// Create new `TxPool` inspecting the given pool.
+	pub fn new(pool: Arc<Pool<A>>, deny_unsafe: DenyUnsafe) -> Self {
+		TxPool { pool, deny_unsafe }
+	}
+
+	fn futures(&self) -> Vec<(TransactionFor<A>, Vec<TransactionTag>)> {
+		self.pool.validated_pool().futures_missing_tags()
+	}
+}
+
+impl<A: ChainApi + 'static> TxPoolApi<ExtrinsicHash<A>> for TxPool<A> {
+	fn status(&self) -> Result<Status> {
+		let status = self.pool.validated_pool().status();
+		Ok(Status {
+			ready: status.ready,
+			ready_bytes: status.ready_bytes,
+			future: status.future,
+			future_bytes: status.future_bytes,
+		})
+	}
+
+	fn content(&self) -> Result<Content<ExtrinsicHash<A>>> {
+		self.deny_unsafe.check_if_safe()?;
+
+		let ready = self.pool.validated_pool().ready()
+			.map(|tx| PoolTransaction::from(&*tx))
+			.collect();
+		let future = self.futures()
+			.into_iter()
+			.map(|(tx, missing_tags)| FutureTransaction {
+				transaction: PoolTransaction::from(&*tx),
+				missing_tags: missing_tags.into_iter().map(Into::into).collect(),
+			})
+			.collect();
+
+		Ok(Content { ready, future })
+	}
+
+	fn inspect(&self, hash: ExtrinsicHash<A>) -> Result<Inspection<ExtrinsicHash<A>>> {
+		let validated_pool = self.pool.validated_pool();
+		if let Some(tx) = validated_pool.ready_by_hash(&hash) {
+			return Ok(Inspection::Ready(PoolTransaction::from(&*tx)))
+		}
+
+		let futures = self.futures();
+		if let Some((tx, missing_tags)) = futures.iter().find(|(tx, _)| tx.hash == hash) {
+			let waiting_for = missing_tags.iter()
+				.map(|tag| MissingTag {
+					tag: tag.clone().into(),
+					provided_by: futures.iter()
+						.find(|(other, _)| other.provides.contains(tag))
+						.map(|(other, _)| other.hash.clone()),
+				})
+				.collect();
+
+			return Ok(Inspection::Future {
+				transaction: PoolTransaction::from(&**tx),
+				waiting_for,
+			})
+		}
+
+		if validated_pool.is_banned(&hash) {
+			Ok(Inspection::Banned)
+		} else {
+			Ok(Inspection::Unknown)
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use assert_matches::assert_matches;
+	use futures::executor::block_on;
+	use sc_transaction_graph::Options;
+	use sp_runtime::generic::BlockId;
+	use substrate_test_runtime_client::AccountKeyring::Alice;
+	use substrate_test_runtime_transaction_pool::{TestApi, uxt};
+
+	fn txpool() -> TxPool<TestApi> {
+		txpool_with(DenyUnsafe::No)
+	}
+
+	fn txpool_with(deny_unsafe: DenyUnsafe) -> TxPool<TestApi> {
+		let api = Arc::new(TestApi::with_alice_nonce(209));
+		// make every transaction require the one with the previous nonce
+		api.set_valid_modifier(Box::new(|validity| {
+			let nonce = validity.provides[0][0];
+			if nonce > 209 {
+				validity.requires = vec![vec![nonce - 1]];
+			}
+		}));
+		TxPool::new(Arc::new(Pool::new(Options::default(), api)), deny_unsafe)
+	}
+
+	fn submit(txpool: &TxPool<TestApi>, nonce: u64) -> ExtrinsicHash<TestApi> {
+		block_on(txpool.pool.submit_one(&BlockId::number(0), TransactionSource::External, uxt(Alice, nonce)))
+			.unwrap()
+	}
+
+	#[test]
+	fn should_return_status_and_content() {
+		let txpool = txpool();
+		let ready = submit(&txpool, 209);
+		let future = submit(&txpool, 211);
+
+		let status = txpool.status().unwrap();
+		assert_eq!((status.ready, status.future), (1, 1));
+
+		let content = txpool.content().unwrap();
+		assert_eq!(content.ready.iter().map(|tx| tx.hash).collect::<Vec<_>>(), vec![ready]);
+		assert_eq!(content.ready[0].provides, vec![Bytes(vec![209])]);
+		assert_eq!(content.ready[0].source, Source::External);
+		assert_eq!(content.future.len(), 1);
+		assert_eq!(content.future[0].transaction.hash, future);
+		assert_eq!(content.future[0].missing_tags, vec![Bytes(vec![210])]);
+	}
+
+	#[test]
+	fn should_deny_content_if_unsafe() {
+		let txpool = txpool_with(DenyUnsafe::Yes);
+		submit(&txpool, 209);
+
+		assert_eq!(txpool.status().unwrap().ready, 1);
+		assert_matches!(txpool.content(), Err(_));
+	}
+
+	#[test]
+	fn should_explain_why_transaction_is_future() {
+		let txpool = txpool();
+		let ready = submit(&txpool, 209);
+		let waiting = submit(&txpool, 212);
+		let provider = submit(&txpool, 211);
+
+		assert_matches!(txpool.inspect(ready).unwrap(), Inspection::Ready(_));
+		match txpool.inspect(waiting).unwrap() {
+			Inspection::Future { waiting_for, .. } => assert_eq!(waiting_for, vec![MissingTag {
+				tag: Bytes(vec![211]),
+				provided_by: Some(provider),
+			}]),
+			other => panic!("Unexpected inspection: {:?}", other),
+		}
+		match txpool.inspect(provider).unwrap() {
+			Inspection::Future { waiting_for, .. } => assert_eq!(waiting_for, vec![MissingTag {
+				tag: Bytes(vec![210]),
+				provided_by: None,
+			}]),
+			other => panic!("Unexpected inspection: {:?}", other),
+		}
+		assert_eq!(txpool.inspect(Default::default()).unwrap(), Inspection::Unknown);
+	}
+
+	#[test]
+	fn should_serialize_inspection() {
+		let inspection = Inspection::<u64>::Future {
+			transaction: PoolTransaction {
+				hash: 5,
+				extrinsic: Bytes(vec![1, 2]),
+				bytes: 2,
+				priority: 1,
+				valid_till: 64,
+				requires: vec![Bytes(vec![3])],
+				provides: vec![Bytes(vec![4])],
+				propagate: true,
+				source: Source::External,
+			},
+			waiting_for: vec![MissingTag { tag: Bytes(vec![3]), provided_by: None }],
+		};
+
+		assert_eq!(
+			serde_json::to_string(&inspection).unwrap(),
+			r#"{"future":{"transaction":{"hash":5,"extrinsic":"0x0102","bytes":2,"priority":1,"validTill":64,"requires":["0x03"],"provides":["0x04"],"propagate":true,"source":"external"},"waitingFor":[{"tag":"0x03","providedBy":null}]}}"#,
+		);
+	}
+}
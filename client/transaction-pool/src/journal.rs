@@ -0,0 +1,374 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2020 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! On-disk journal of the transactions submitted to the pool by the node and its RPC
+//! clients.
+//!
+//! Transactions are appended to the journal as they are submitted, and the whole
+//! journal is periodically rewritten ("rotated") with the journaled transactions
+//! still in the pool, so that included and invalid transactions are dropped from it. The journal is read
+//! back on startup and its transactions resubmitted to the pool with the source they
+//! were submitted from.
+//!
+//! Files are written by a dedicated thread, so that journaling never blocks the
+//! futures submitting transactions or maintaining the pool.
+
+use std::{
+	collections::HashSet,
+	fs::{self, File, OpenOptions},
+	hash,
+	io::{self, Read, Write},
+	sync::mpsc,
+	thread,
+};
+use codec::{Decode, Encode};
+use parking_lot::Mutex;
+use sc_transaction_graph::JournalOptions;
+use sp_runtime::transaction_validity::TransactionSource;
+
+/// Work of the journal thread.
+enum Command {
+	/// Append the given encoded entries.
+	Append(Vec<Vec<u8>>),
+	/// Rewrite the journal with the given encoded entries.
+	Rewrite(Vec<Vec<u8>>),
+	/// Notify once the previous commands are done.
+	#[cfg(test)]
+	Sync(mpsc::Sender<()>),
+}
+
+/// Journal file, owned by the journal thread.
+struct Writer {
+	options: JournalOptions,
+	/// Journal file opened for appending, opened on first write.
+	file: Option<File>,
+	/// Number of transactions in the journal.
+	count: usize,
+}
+
+impl Writer {
+	fn run(mut self, commands: mpsc::Receiver<Command>) {
+		for command in commands {
+			match command {
+				Command::Append(entries) => self.append(entries),
+				Command::Rewrite(entries) => self.rewrite(entries),
+				#[cfg(test)]
+				Command::Sync(done) => { let _ = done.send(()); },
+			}
+		}
+	}
+
+	/// Append entries, up to `max_transactions` in the journal.
+	fn append(&mut self, entries: Vec<Vec<u8>>) {
+		let Writer { options, file, count } = self;
+		let result = (|| -> io::Result<()> {
+			if file.is_none() {
+				*file = Some(OpenOptions::new().create(true).append(true).open(&options.path)?);
+			}
+			let file = file.as_mut().expect("opened above; qed");
+			for entry in entries {
+				if *count >= options.max_transactions {
+					break
+				}
+				file.write_all(&entry)?;
+				*count += 1;
+			}
+			file.flush()
+		})();
+
+		if let Err(e) = result {
+			log::warn!(target: "txpool", "Failed to write to transaction pool journal: {:?}", e);
+			*file = None;
+		}
+	}
+
+	/// Replace the journal with the given entries.
+	fn rewrite(&mut self, entries: Vec<Vec<u8>>) {
+		let result = (|| -> io::Result<usize> {
+			let mut tmp_path = self.options.path.clone();
+			tmp_path.set_extension("new");
+
+			let mut file = File::create(&tmp_path)?;
+			let count = entries.len().min(self.options.max_transactions);
+			for entry in entries.iter().take(count) {
+				file.write_all(entry)?;
+			}
+			file.sync_all()?;
+			fs::rename(&tmp_path, &self.options.path)?;
+
+			self.file = Some(OpenOptions::new().append(true).open(&self.options.path)?);
+			self.count = count;
+			Ok(count)
+		})();
+
+		match result {
+			Ok(count) => log::debug!(
+				target: "txpool",
+				"Rotated transaction pool journal, {} transactions kept",
+				count,
+			),
+			Err(e) => {
+				log::warn!(target: "txpool", "Failed to rotate transaction pool journal: {:?}", e);
+				self.file = None;
+			}
+		}
+	}
+}
+
+/// Append-only file of SCALE encoded transactions along with their source.
+pub struct Journal<Hash> {
+	/// Commands to the journal thread, `None` if it couldn't be started.
+	commands: Mutex<Option<mpsc::Sender<Command>>>,
+	/// Hashes of the journaled transactions, the ones kept at rotation.
+	journaled: Mutex<HashSet<Hash>>,
+	thread: Option<thread::JoinHandle<()>>,
+	/// Number of blocks between rotations.
+	rotate_period: u32,
+	/// Block number the journal was last rotated at.
+	rotated_at: Mutex<Option<u64>>,
+}
+
+impl<Hash: hash::Hash + Eq> Journal<Hash> {
+	/// Create a journal with the given options, reading back the transactions it contains.
+	pub fn load<Ex: Decode>(options: JournalOptions) -> (Self, Vec<(TransactionSource, Ex)>) {
+		let transactions = match read_transactions(&options) {
+			Ok(transactions) => transactions,
+			Err(e) => {
+				log::warn!(
+					target: "txpool",
+					"Failed to read transaction pool journal {}: {:?}",
+					options.path.display(),
+					e,
+				);
+				Vec::new()
+			}
+		};
+
+		log::info!(
+			target: "txpool",
+			"Loaded {} transactions from the transaction pool journal",
+			transactions.len(),
+		);
+
+		let rotate_period = options.rotate_period;
+		let writer = Writer { options, file: None, count: transactions.len() };
+		let (sender, receiver) = mpsc::channel();
+		let (commands, thread) = match thread::Builder::new()
+			.name("txpool-journal".into())
+			.spawn(move || writer.run(receiver))
+		{
+			Ok(thread) => (Some(sender), Some(thread)),
+			Err(e) => {
+				log::warn!(target: "txpool", "Failed to start transaction pool journal: {:?}", e);
+				(None, None)
+			}
+		};
+
+		let journal = Journal {
+			commands: Mutex::new(commands),
+			journaled: Default::default(),
+			thread,
+			rotate_period,
+			rotated_at: Mutex::new(None),
+		};
+		(journal, transactions)
+	}
+
+	/// Append the given transactions submitted from `source` to the journal.
+	///
+	/// Transactions exceeding `max_transactions` are not journaled until the next rotation.
+	pub fn insert<'a, Ex: Encode + 'a>(
+		&self,
+		source: TransactionSource,
+		transactions: impl IntoIterator<Item = (Hash, &'a Ex)>,
+	) {
+		let mut journaled = self.journaled.lock();
+		let entries = transactions.into_iter()
+			.map(|(hash, xt)| {
+				journaled.insert(hash);
+				(source, xt).encode()
+			})
+			.collect();
+		self.send(Command::Append(entries));
+	}
+
+	/// Note transactions read back from the journal, so that they are kept at rotation.
+	pub fn note_journaled(&self, hashes: impl IntoIterator<Item = Hash>) {
+		self.journaled.lock().extend(hashes);
+	}
+
+	/// Rewrite the journal with the journaled ones of the given pool transactions if
+	/// `rotate_period` blocks passed since the last rotation.
+	pub fn maybe_rotate<Ex: Encode>(
+		&self,
+		block_number: u64,
+		transactions: impl FnOnce() -> Vec<(Hash, TransactionSource, Ex)>,
+	) {
+		{
+			let mut rotated_at = self.rotated_at.lock();
+			match *rotated_at {
+				Some(at) if block_number < at.saturating_add(self.rotate_period.into()) => return,
+				_ => *rotated_at = Some(block_number),
+			}
+		}
+
+		let mut journaled = self.journaled.lock();
+		let transactions: Vec<_> = transactions().into_iter()
+			.filter(|(hash, _, _)| journaled.contains(hash))
+			.collect();
+		*journaled = HashSet::new();
+		let entries = transactions.into_iter()
+			.map(|(hash, source, xt)| {
+				journaled.insert(hash);
+				(source, xt).encode()
+			})
+			.collect();
+		self.send(Command::Rewrite(entries));
+	}
+
+	fn send(&self, command: Command) {
+		if let Some(commands) = self.commands.lock().as_ref() {
+			// The thread only stops once the sender is dropped.
+			let _ = commands.send(command);
+		}
+	}
+
+	/// Wait until the journal thread is done with the previous commands.
+	#[cfg(test)]
+	fn sync(&self) {
+		let (done, wait) = mpsc::channel();
+		self.send(Command::Sync(done));
+		let _ = wait.recv();
+	}
+}
+
+impl<Hash> Drop for Journal<Hash> {
+	/// Let the journal thread finish writing.
+	fn drop(&mut self) {
+		self.commands.lock().take();
+		if let Some(thread) = self.thread.take() {
+			let _ = thread.join();
+		}
+	}
+}
+
+/// Read the transactions of the journal.
+///
+/// A transaction that can't be decoded ends the journal, as the node may have stopped
+/// in the middle of writing it.
+fn read_transactions<Ex: Decode>(
+	options: &JournalOptions,
+) -> io::Result<Vec<(TransactionSource, Ex)>> {
+	let mut content = Vec::new();
+	match File::open(&options.path) {
+		Ok(mut file) => { file.read_to_end(&mut content)?; },
+		Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+		Err(e) => return Err(e),
+	}
+
+	let mut input = &content[..];
+	let mut transactions = Vec::new();
+	while !input.is_empty() && transactions.len() < options.max_transactions {
+		match <(TransactionSource, Ex)>::decode(&mut input) {
+			Ok(transaction) => transactions.push(transaction),
+			Err(e) => {
+				log::warn!(
+					target: "txpool",
+					"Transaction pool journal is truncated, ignoring the rest of it: {:?}",
+					e,
+				);
+				break
+			}
+		}
+	}
+
+	Ok(transactions)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn options(dir: &tempfile::TempDir, max_transactions: usize) -> JournalOptions {
+		JournalOptions {
+			path: dir.path().join("transactions.journal"),
+			max_transactions,
+			rotate_period: 2,
+		}
+	}
+
+	const LOCAL: TransactionSource = TransactionSource::Local;
+	const EXTERNAL: TransactionSource = TransactionSource::External;
+
+	type TestJournal = Journal<u8>;
+
+	/// The given transactions along with their hash, their first byte.
+	fn hashed(xts: &[Vec<u8>]) -> Vec<(u8, &Vec<u8>)> {
+		xts.iter().map(|xt| (xt[0], xt)).collect()
+	}
+
+	#[test]
+	fn inserted_transactions_are_loaded_with_their_source() {
+		let dir = tempfile::tempdir().unwrap();
+		let (journal, loaded) = TestJournal::load::<Vec<u8>>(options(&dir, 3));
+		assert!(loaded.is_empty());
+
+		journal.insert(LOCAL, hashed(&[vec![1u8], vec![2, 2]]));
+		journal.insert(EXTERNAL, hashed(&[vec![3u8], vec![4]]));
+		drop(journal);
+
+		let (_, loaded) = TestJournal::load::<Vec<u8>>(options(&dir, 3));
+		assert_eq!(loaded, vec![(LOCAL, vec![1]), (LOCAL, vec![2, 2]), (EXTERNAL, vec![3])]);
+	}
+
+	#[test]
+	fn rotation_keeps_journaled_transactions_of_the_pool() {
+		let dir = tempfile::tempdir().unwrap();
+		let (journal, _) = TestJournal::load::<Vec<u8>>(options(&dir, 3));
+		journal.insert(LOCAL, hashed(&[vec![1u8], vec![2]]));
+
+		journal.maybe_rotate(1, || vec![(2, LOCAL, vec![2u8]), (6, EXTERNAL, vec![6u8])]);
+		// not rotated before `rotate_period` blocks passed.
+		journal.maybe_rotate(2, || vec![(5, LOCAL, vec![5u8])]);
+		journal.insert(EXTERNAL, hashed(&[vec![3u8]]));
+		journal.sync();
+		assert_eq!(
+			TestJournal::load::<Vec<u8>>(options(&dir, 3)).1,
+			vec![(LOCAL, vec![2]), (EXTERNAL, vec![3])],
+		);
+
+		journal.note_journaled(vec![4]);
+		journal.maybe_rotate(3, || vec![(3, EXTERNAL, vec![3u8]), (4, LOCAL, vec![4u8]), (6, EXTERNAL, vec![6u8])]);
+		journal.sync();
+		assert_eq!(
+			TestJournal::load::<Vec<u8>>(options(&dir, 3)).1,
+			vec![(EXTERNAL, vec![3]), (LOCAL, vec![4])],
+		);
+	}
+
+	#[test]
+	fn truncated_transaction_ends_journal() {
+		let dir = tempfile::tempdir().unwrap();
+		let options = options(&dir, 3);
+		let mut content = (LOCAL, vec![1u8]).encode();
+		content.extend(&(LOCAL, vec![2u8, 2, 2]).encode()[..3]);
+		fs::write(&options.path, content).unwrap();
+
+		assert_eq!(TestJournal::load::<Vec<u8>>(options).1, vec![(LOCAL, vec![1])]);
+	}
+}
@@ -23,6 +23,7 @@
 #![warn(unused_extern_crates)]
 
 mod api;
+mod journal;
 mod revalidation;
 mod metrics;
 
@@ -40,7 +41,7 @@ use parking_lot::Mutex;
 
 use sp_runtime::{
 	generic::BlockId,
-	traits::{Block as BlockT, NumberFor, AtLeast32Bit, Extrinsic, Zero},
+	traits::{Block as BlockT, NumberFor, AtLeast32Bit, Extrinsic, Zero, SaturatedConversion},
 };
 use sp_transaction_pool::{
 	TransactionPool, PoolStatus, ImportNotificationStream, TxHash, TransactionFor,
@@ -75,6 +76,10 @@ pub struct BasicPool<PoolApi, Block>
 	revalidation_queue: Arc<revalidation::RevalidationQueue<PoolApi>>,
 	ready_poll: Arc<Mutex<ReadyPoll<ReadyIteratorFor<PoolApi>, Block>>>,
 	metrics: PrometheusMetrics,
+	journal: Option<Arc<journal::Journal<ExtrinsicHash<PoolApi>>>>,
+	/// Journaled transactions and their source, waiting to be resubmitted at the first new
+	/// best block.
+	journal_replay: Mutex<Vec<(TransactionSource, sc_transaction_graph::ExtrinsicFor<PoolApi>)>>,
 }
 
 struct ReadyPoll<T, Block: BlockT> {
@@ -180,6 +185,8 @@ impl<PoolApi, Block> BasicPool<PoolApi, Block>
 				revalidation_strategy: Arc::new(Mutex::new(RevalidationStrategy::Always)),
 				ready_poll: Default::default(),
 				metrics: Default::default(),
+				journal: None,
+				journal_replay: Default::default(),
 			},
 			background_task,
 			notifier,
@@ -194,6 +201,13 @@ impl<PoolApi, Block> BasicPool<PoolApi, Block>
 		prometheus: Option<&PrometheusRegistry>,
 		revalidation_type: RevalidationType,
 	) -> (Self, Option<Pin<Box<dyn Future<Output=()> + Send>>>) {
+		let (journal, journal_replay) = match options.journal.clone() {
+			Some(journal_options) => {
+				let (journal, transactions) = journal::Journal::load(journal_options);
+				(Some(Arc::new(journal)), transactions)
+			},
+			None => (None, Vec::new()),
+		};
 		let pool = Arc::new(sc_transaction_graph::Pool::new(options, pool_api.clone()));
 		let (revalidation_queue, background_task) = match revalidation_type {
 			RevalidationType::Light => (revalidation::RevalidationQueue::new(pool_api.clone(), pool.clone()), None),
@@ -216,6 +230,8 @@ impl<PoolApi, Block> BasicPool<PoolApi, Block>
 				)),
 				ready_poll: Default::default(),
 				metrics: PrometheusMetrics::new(prometheus),
+				journal,
+				journal_replay: Mutex::new(journal_replay),
 			},
 			background_task,
 		)
@@ -225,6 +241,33 @@ impl<PoolApi, Block> BasicPool<PoolApi, Block>
 	pub fn pool(&self) -> &Arc<sc_transaction_graph::Pool<PoolApi>> {
 		&self.pool
 	}
+
+	/// Import one transaction to the pool, appending it to `journal` if given.
+	fn import_one(
+		&self,
+		at: &BlockId<Block>,
+		source: TransactionSource,
+		xt: sc_transaction_graph::ExtrinsicFor<PoolApi>,
+		journal: Option<Arc<journal::Journal<ExtrinsicHash<PoolApi>>>>,
+	) -> PoolFuture<ExtrinsicHash<PoolApi>, PoolApi::Error> {
+		let pool = self.pool.clone();
+		let at = *at;
+
+		self.metrics.report(|metrics| metrics.validations_scheduled.inc());
+
+		let metrics = self.metrics.clone();
+		async move {
+			let journaled = journal.as_ref().map(|_| xt.clone());
+			let res = pool.submit_one(&at, source, xt).await;
+
+			metrics.report(|metrics| metrics.validations_finished.inc());
+			if let (Some(journal), Some(xt), Ok(hash)) = (journal, journaled, &res) {
+				journal.insert(source, vec![(hash.clone(), &xt)]);
+			}
+			res
+
+		}.boxed()
+	}
 }
 
 impl<PoolApi, Block> TransactionPool for BasicPool<PoolApi, Block>
@@ -251,10 +294,18 @@ impl<PoolApi, Block> TransactionPool for BasicPool<PoolApi, Block>
 		self.metrics.report(|metrics| metrics.validations_scheduled.inc_by(xts.len() as u64));
 
 		let metrics = self.metrics.clone();
+		let journal = self.journal.clone();
 		async move {
 			let tx_count = xts.len();
+			let journaled = journal.as_ref().map(|_| xts.clone());
 			let res = pool.submit_at(&at, source, xts, false).await;
 			metrics.report(|metrics| metrics.validations_finished.inc_by(tx_count as u64));
+			if let (Some(journal), Some(xts), Ok(results)) = (journal, journaled, &res) {
+				journal.insert(
+					source,
+					xts.iter().zip(results).filter_map(|(xt, r)| r.as_ref().ok().map(|hash| (hash.clone(), xt))),
+				);
+			}
 			res
 		}.boxed()
 	}
@@ -265,19 +316,7 @@ impl<PoolApi, Block> TransactionPool for BasicPool<PoolApi, Block>
 		source: TransactionSource,
 		xt: TransactionFor<Self>,
 	) -> PoolFuture<TxHash<Self>, Self::Error> {
-		let pool = self.pool.clone();
-		let at = *at;
-
-		self.metrics.report(|metrics| metrics.validations_scheduled.inc());
-
-		let metrics = self.metrics.clone();
-		async move {
-			let res = pool.submit_one(&at, source, xt).await;
-
-			metrics.report(|metrics| metrics.validations_finished.inc());
-			res
-
-		}.boxed()
+		self.import_one(at, source, xt, self.journal.clone())
 	}
 
 	fn submit_and_watch(
@@ -292,12 +331,17 @@ impl<PoolApi, Block> TransactionPool for BasicPool<PoolApi, Block>
 		self.metrics.report(|metrics| metrics.validations_scheduled.inc());
 
 		let metrics = self.metrics.clone();
+		let journal = self.journal.clone();
 		async move {
+			let journaled = journal.as_ref().map(|_| (pool.hash_of(&xt), xt.clone()));
 			let result = pool.submit_and_watch(&at, source, xt)
 				.map(|result| result.map(|watcher| Box::new(watcher.into_stream()) as _))
 				.await;
 
 			metrics.report(|metrics| metrics.validations_finished.inc());
+			if let (Some(journal), Some((hash, xt)), Ok(_)) = (journal, journaled, &result) {
+				journal.insert(source, vec![(hash, &xt)]);
+			}
 
 			result
 		}.boxed()
@@ -325,6 +369,15 @@ impl<PoolApi, Block> TransactionPool for BasicPool<PoolApi, Block>
 		self.pool.validated_pool().on_broadcasted(propagations)
 	}
 
+	fn submit_from_network(
+		&self,
+		at: &BlockId<Self::Block>,
+		xt: TransactionFor<Self>,
+	) -> PoolFuture<TxHash<Self>, Self::Error> {
+		// not journaled, as it is gossiped again.
+		self.import_one(at, TransactionSource::External, xt, None)
+	}
+
 	fn ready_transaction(&self, hash: &TxHash<Self>) -> Option<Arc<Self::InPoolTransaction>> {
 		self.pool.validated_pool().ready_by_hash(hash)
 	}
@@ -494,6 +547,12 @@ impl<PoolApi, Block> MaintainedTransactionPool for BasicPool<PoolApi, Block>
 				let revalidation_queue = self.revalidation_queue.clone();
 				let ready_poll = self.ready_poll.clone();
 				let metrics = self.metrics.clone();
+				let journal = self.journal.clone();
+				let journal_replay = if is_new_best {
+					std::mem::replace(&mut *self.journal_replay.lock(), Vec::new())
+				} else {
+					Vec::new()
+				};
 
 				async move {
 					// We keep track of everything we prune so that later we won't add
@@ -596,6 +655,48 @@ impl<PoolApi, Block> MaintainedTransactionPool for BasicPool<PoolApi, Block>
 						}
 					}
 
+					if !journal_replay.is_empty() {
+						log::info!(
+							target: "txpool",
+							"Resubmitting {} transactions from the journal",
+							journal_replay.len(),
+						);
+						// Resubmit in journal order, batching transactions of the same source.
+						let mut batches: Vec<(TransactionSource, Vec<_>)> = Vec::new();
+						for (source, xt) in journal_replay {
+							match batches.last_mut() {
+								Some((batch_source, xts)) if *batch_source == source => xts.push(xt),
+								_ => batches.push((source, vec![xt])),
+							}
+						}
+						if let Some(journal) = &journal {
+							journal.note_journaled(
+								batches.iter().flat_map(|(_, xts)| xts).map(|xt| pool.hash_of(xt))
+							);
+						}
+						for (source, xts) in batches {
+							if let Err(e) = pool.submit_at(&id, source, xts, false).await {
+								log::debug!(
+									target: "txpool",
+									"[{:?}] Error re-submitting journaled transactions: {:?}",
+									id,
+									e,
+								)
+							}
+						}
+					}
+
+					// rotate only once the journal was replayed at the first new best block.
+					if let (true, Some(journal)) = (is_new_best, journal) {
+						journal.maybe_rotate(block_number.saturated_into::<u64>(), || {
+							let validated_pool = pool.validated_pool();
+							validated_pool.ready()
+								.chain(validated_pool.futures_missing_tags().into_iter().map(|(tx, _)| tx))
+								.map(|tx| (tx.hash.clone(), tx.source, tx.data.clone()))
+								.collect::<Vec<_>>()
+						});
+					}
+
 					let extra_pool = pool.clone();
 					// After #5200 lands, this arguably might be moved to the
 					// handler of "all blocks notification".
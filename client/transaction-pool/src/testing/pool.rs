@@ -1091,3 +1091,34 @@ fn pruning_a_transaction_should_remove_it_from_best_transaction() {
 	// returned a second time by the iterator.
 	assert!(iterator.next().is_none());
 }
+
+#[test]
+fn should_resubmit_journaled_transactions_after_restart() {
+	let dir = tempfile::tempdir().unwrap();
+	let options = txpool::Options {
+		journal: Some(txpool::JournalOptions {
+			path: dir.path().join("transactions.journal"),
+			max_transactions: 16,
+			rotate_period: 1,
+		}),
+		..Default::default()
+	};
+	let api = Arc::new(TestApi::with_alice_nonce(209));
+	let pool = BasicPool::new(options.clone(), api.clone(), None).0;
+	let local = TransactionSource::Local;
+	block_on(pool.submit_one(&BlockId::number(0), local, uxt(Alice, 209))).expect("1. Imported");
+	block_on(pool.submit_one(&BlockId::number(0), SOURCE, uxt(Alice, 211))).expect("2. Imported");
+	block_on(pool.submit_from_network(&BlockId::number(0), uxt(Alice, 212))).expect("3. Imported");
+	assert_eq!(pool.status().ready, 1);
+	assert_eq!(pool.status().future, 2);
+	drop(pool);
+
+	// the restarted pool resubmits the transactions not received from the network at the
+	// first new best block.
+	let pool = BasicPool::new(options, api.clone(), None).0;
+	assert_eq!(pool.status().ready, 0);
+	let header = api.push_block(1, vec![]);
+	block_on(pool.maintain(block_event(header)));
+	assert_eq!(pool.status().ready, 1);
+	assert_eq!(pool.status().future, 1);
+}
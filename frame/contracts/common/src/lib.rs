@@ -45,3 +45,12 @@ pub enum RentProjection<BlockNumber> {
 	/// E.g. because the contract accumulated enough funds to offset the rent storage costs.
 	NoEviction,
 }
+
+/// The code a contract is instantiated from.
+#[derive(Eq, PartialEq, codec::Encode, codec::Decode, sp_runtime::RuntimeDebug)]
+pub enum Code<Hash> {
+	/// A wasm blob which is uploaded before instantiating.
+	Upload(Vec<u8>),
+	/// The hash of a code that was already stored with `put_code`.
+	Existing(Hash),
+}
@@ -24,6 +24,8 @@
 
 use codec::{Codec, Decode, Encode};
 use pallet_contracts_primitives::{GetStorageResult, RentProjectionResult};
+
+pub use pallet_contracts_primitives::Code;
 use sp_runtime::RuntimeDebug;
 use sp_std::vec::Vec;
 
@@ -45,12 +47,43 @@ pub enum ContractExecResult {
 	Error,
 }
 
+/// A result of a dry-run instantiation of a contract.
+#[derive(Eq, PartialEq, Encode, Decode, RuntimeDebug)]
+pub struct ContractInstantiateResult<AccountId> {
+	/// The address the contract is (or would be) instantiated at.
+	pub address: AccountId,
+	/// The gas consumed by the instantiation, including storing the code if it was uploaded.
+	pub gas_consumed: u64,
+	/// The SCALE-encoded runtime events deposited during the instantiation.
+	pub events: Vec<Vec<u8>>,
+	/// The result of executing the constructor.
+	pub result: ContractExecResult,
+}
+
+/// A result of a dry-run upload of contract code.
+#[derive(Eq, PartialEq, Encode, Decode, RuntimeDebug)]
+pub enum CodeUploadResult<Hash> {
+	/// The code was successfully instrumented.
+	Success {
+		/// The hash the code is (or would be) stored under.
+		code_hash: Hash,
+		/// The gas consumed by storing the code.
+		gas_consumed: u64,
+	},
+	/// The code was rejected, e.g. by the instrumentation.
+	///
+	/// Contains the reason of the rejection.
+	Error(Vec<u8>),
+}
+
 sp_api::decl_runtime_apis! {
 	/// The API to interact with contracts without using executive.
-	pub trait ContractsApi<AccountId, Balance, BlockNumber> where
+	#[api_version(2)]
+	pub trait ContractsApi<AccountId, Balance, BlockNumber, Hash> where
 		AccountId: Codec,
 		Balance: Codec,
 		BlockNumber: Codec,
+		Hash: Codec,
 	{
 		/// Perform a call from a specified account to a given contract.
 		///
@@ -63,6 +96,20 @@ sp_api::decl_runtime_apis! {
 			input_data: Vec<u8>,
 		) -> ContractExecResult;
 
+		/// Instantiate a contract from either a wasm blob or the hash of already stored code.
+		///
+		/// See the contracts' `instantiate` dispatchable function for more details.
+		fn instantiate(
+			origin: AccountId,
+			endowment: Balance,
+			gas_limit: u64,
+			code: Code<Hash>,
+			data: Vec<u8>,
+		) -> ContractInstantiateResult<AccountId>;
+
+		/// Instrument the given wasm blob as `put_code` would, without storing it.
+		fn upload_code(code: Vec<u8>) -> CodeUploadResult<Hash>;
+
 		/// Query a given storage key in a given contract.
 		///
 		/// Returns `Ok(Some(Vec<u8>))` if the storage value exists under the given key in the
@@ -34,7 +34,8 @@ use sp_runtime::{
 
 pub use self::gen_client::Client as ContractsClient;
 pub use pallet_contracts_rpc_runtime_api::{
-	self as runtime_api, ContractExecResult, ContractsApi as ContractsRuntimeApi,
+	self as runtime_api, Code, CodeUploadResult, ContractExecResult, ContractInstantiateResult,
+	ContractsApi as ContractsRuntimeApi,
 };
 
 const RUNTIME_ERROR: i64 = 1;
@@ -82,6 +83,38 @@ pub struct CallRequest<AccountId, Balance> {
 	input_data: Bytes,
 }
 
+/// The code to instantiate a contract with, as passed to the RPC.
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+#[serde(deny_unknown_fields)]
+pub enum RpcCode<Hash> {
+	/// A wasm blob which is uploaded before instantiating.
+	Upload(Bytes),
+	/// The hash of a code that is already stored on chain.
+	Existing(Hash),
+}
+
+impl<Hash> From<RpcCode<Hash>> for Code<Hash> {
+	fn from(code: RpcCode<Hash>) -> Self {
+		match code {
+			RpcCode::Upload(binary) => Code::Upload(binary.to_vec()),
+			RpcCode::Existing(hash) => Code::Existing(hash),
+		}
+	}
+}
+
+/// A struct that encodes RPC parameters required to instantiate a new smart-contract.
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+#[serde(deny_unknown_fields)]
+pub struct InstantiateRequest<AccountId, Balance, Hash> {
+	origin: AccountId,
+	endowment: Balance,
+	gas_limit: number::NumberOrHex<u64>,
+	code: RpcCode<Hash>,
+	data: Bytes,
+}
+
 /// An RPC serializable result of contract execution
 #[derive(Serialize, Deserialize)]
 #[serde(deny_unknown_fields)]
@@ -110,9 +143,65 @@ impl From<ContractExecResult> for RpcContractExecResult {
 	}
 }
 
+/// An RPC serializable result of a contract instantiation.
+#[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+#[serde(rename_all = "camelCase")]
+pub struct RpcContractInstantiateResult<AccountId> {
+	/// The address the contract would be instantiated at.
+	address: AccountId,
+	/// Gas consumed by the instantiation
+	gas_consumed: u64,
+	/// SCALE-encoded runtime events deposited by the instantiation
+	events: Vec<Bytes>,
+	/// Result of the constructor execution
+	result: RpcContractExecResult,
+}
+
+impl<AccountId> From<ContractInstantiateResult<AccountId>> for RpcContractInstantiateResult<AccountId> {
+	fn from(r: ContractInstantiateResult<AccountId>) -> Self {
+		RpcContractInstantiateResult {
+			address: r.address,
+			gas_consumed: r.gas_consumed,
+			events: r.events.into_iter().map(Bytes).collect(),
+			result: r.result.into(),
+		}
+	}
+}
+
+/// An RPC serializable result of a code upload.
+#[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+#[serde(rename_all = "camelCase")]
+pub enum RpcCodeUploadResult<Hash> {
+	/// The code was successfully instrumented
+	#[serde(rename_all = "camelCase")]
+	Success {
+		/// Hash of the code
+		code_hash: Hash,
+		/// Gas consumed by storing the code
+		gas_consumed: u64,
+	},
+	/// The code was rejected, with the reason
+	Error(String),
+}
+
+impl<Hash> From<CodeUploadResult<Hash>> for RpcCodeUploadResult<Hash> {
+	fn from(r: CodeUploadResult<Hash>) -> Self {
+		match r {
+			CodeUploadResult::Success { code_hash, gas_consumed } => RpcCodeUploadResult::Success {
+				code_hash,
+				gas_consumed,
+			},
+			CodeUploadResult::Error(reason) =>
+				RpcCodeUploadResult::Error(String::from_utf8_lossy(&reason).into_owned()),
+		}
+	}
+}
+
 /// Contracts RPC methods.
 #[rpc]
-pub trait ContractsApi<BlockHash, BlockNumber, AccountId, Balance> {
+pub trait ContractsApi<BlockHash, BlockNumber, AccountId, Balance, Hash> {
 	/// Executes a call to a contract.
 	///
 	/// This call is performed locally without submitting any transactions. Thus executing this
@@ -126,6 +215,30 @@ pub trait ContractsApi<BlockHash, BlockNumber, AccountId, Balance> {
 		at: Option<BlockHash>,
 	) -> Result<RpcContractExecResult>;
 
+	/// Instantiates a new contract, either from a wasm blob or from the hash of stored code.
+	///
+	/// This call is performed locally without submitting any transactions. Thus executing this
+	/// won't change any state.
+	///
+	/// This method is useful for finding out the address, gas consumption and events of an
+	/// instantiation before submitting it.
+	#[rpc(name = "contracts_instantiate")]
+	fn instantiate(
+		&self,
+		instantiate_request: InstantiateRequest<AccountId, Balance, Hash>,
+		at: Option<BlockHash>,
+	) -> Result<RpcContractInstantiateResult<AccountId>>;
+
+	/// Instruments the given wasm blob as `put_code` would, without storing it.
+	///
+	/// Returns the hash of the code or the reason it was rejected by the instrumentation.
+	#[rpc(name = "contracts_uploadCode")]
+	fn upload_code(
+		&self,
+		code: Bytes,
+		at: Option<BlockHash>,
+	) -> Result<RpcCodeUploadResult<Hash>>;
+
 	/// Returns the value under a specified storage `key` in a contract given by `address` param,
 	/// or `None` if it is not set.
 	#[rpc(name = "contracts_getStorage")]
@@ -165,12 +278,13 @@ impl<C, B> Contracts<C, B> {
 		}
 	}
 }
-impl<C, Block, AccountId, Balance>
+impl<C, Block, AccountId, Balance, Hash>
 	ContractsApi<
 		<Block as BlockT>::Hash,
 		<<Block as BlockT>::Header as HeaderT>::Number,
 		AccountId,
 		Balance,
+		Hash,
 	> for Contracts<C, Block>
 where
 	Block: BlockT,
@@ -180,9 +294,11 @@ where
 		AccountId,
 		Balance,
 		<<Block as BlockT>::Header as HeaderT>::Number,
+		Hash,
 	>,
 	AccountId: Codec,
 	Balance: Codec,
+	Hash: Codec,
 {
 	fn call(
 		&self,
@@ -201,23 +317,7 @@ where
 			gas_limit,
 			input_data,
 		} = call_request;
-		let gas_limit = gas_limit.to_number().map_err(|e| Error {
-			code: ErrorCode::InvalidParams,
-			message: e,
-			data: None,
-		})?;
-
-		let max_gas_limit = 5 * GAS_PER_SECOND;
-		if gas_limit > max_gas_limit {
-			return Err(Error {
-				code: ErrorCode::InvalidParams,
-				message: format!(
-					"Requested gas limit is greater than maximum allowed: {} > {}",
-					gas_limit, max_gas_limit
-				),
-				data: None,
-			});
-		}
+		let gas_limit = check_gas_limit(gas_limit)?;
 
 		let exec_result = api
 			.call(&at, origin, dest, value, gas_limit, input_data.to_vec())
@@ -226,6 +326,49 @@ where
 		Ok(exec_result.into())
 	}
 
+	fn instantiate(
+		&self,
+		instantiate_request: InstantiateRequest<AccountId, Balance, Hash>,
+		at: Option<<Block as BlockT>::Hash>,
+	) -> Result<RpcContractInstantiateResult<AccountId>> {
+		let api = self.client.runtime_api();
+		let at = BlockId::hash(at.unwrap_or_else(||
+			// If the block hash is not supplied assume the best block.
+			self.client.info().best_hash));
+
+		let InstantiateRequest {
+			origin,
+			endowment,
+			gas_limit,
+			code,
+			data,
+		} = instantiate_request;
+		let gas_limit = check_gas_limit(gas_limit)?;
+
+		let instantiate_result = api
+			.instantiate(&at, origin, endowment, gas_limit, code.into(), data.to_vec())
+			.map_err(|e| runtime_error_into_rpc_err(e))?;
+
+		Ok(instantiate_result.into())
+	}
+
+	fn upload_code(
+		&self,
+		code: Bytes,
+		at: Option<<Block as BlockT>::Hash>,
+	) -> Result<RpcCodeUploadResult<Hash>> {
+		let api = self.client.runtime_api();
+		let at = BlockId::hash(at.unwrap_or_else(||
+			// If the block hash is not supplied assume the best block.
+			self.client.info().best_hash));
+
+		let upload_result = api
+			.upload_code(&at, code.to_vec())
+			.map_err(|e| runtime_error_into_rpc_err(e))?;
+
+		Ok(upload_result.into())
+	}
+
 	fn get_storage(
 		&self,
 		address: AccountId,
@@ -268,6 +411,29 @@ where
 	}
 }
 
+/// Converts the requested gas limit into a number, checking it against the maximum allowed.
+fn check_gas_limit(gas_limit: number::NumberOrHex<u64>) -> Result<u64> {
+	let gas_limit = gas_limit.to_number().map_err(|e| Error {
+		code: ErrorCode::InvalidParams,
+		message: e,
+		data: None,
+	})?;
+
+	let max_gas_limit = 5 * GAS_PER_SECOND;
+	if gas_limit > max_gas_limit {
+		return Err(Error {
+			code: ErrorCode::InvalidParams,
+			message: format!(
+				"Requested gas limit is greater than maximum allowed: {} > {}",
+				gas_limit, max_gas_limit
+			),
+			data: None,
+		});
+	}
+
+	Ok(gas_limit)
+}
+
 /// Converts a runtime trap into an RPC error.
 fn runtime_error_into_rpc_err(err: impl std::fmt::Debug) -> Error {
 	Error {
@@ -292,4 +458,34 @@ mod tests {
 		test(r#"{"success":{"status":5,"data":"0x1234"}}"#);
 		test(r#"{"error":null}"#);
 	}
+
+	#[test]
+	fn instantiate_should_serialize_deserialize_properly() {
+		let req: InstantiateRequest<String, u64, H256> = serde_json::from_str(r#"{
+			"origin": "5CiPPseXPECbkjWCa6MnjNokrgYjMqmKndv2rSnekmSK2DjL",
+			"endowment": 100,
+			"gasLimit": "0x3e8",
+			"code": { "upload": "0x0061736d" },
+			"data": "0x"
+		}"#).unwrap();
+		assert_eq!(req.gas_limit.to_number(), Ok(1000));
+		assert_eq!(Code::from(req.code), Code::Upload(vec![0x00, 0x61, 0x73, 0x6d]));
+
+		let res: RpcContractInstantiateResult<String> = serde_json::from_str(
+			r#"{"address":"5C","gasConsumed":10,"events":["0x01"],"result":{"error":null}}"#
+		).unwrap();
+		assert_eq!(
+			serde_json::to_string(&res).unwrap(),
+			r#"{"address":"5C","gasConsumed":10,"events":["0x01"],"result":{"error":null}}"#,
+		);
+
+		fn test(expected: &str) {
+			let res: RpcCodeUploadResult<H256> = serde_json::from_str(expected).unwrap();
+			let actual = serde_json::to_string(&res).unwrap();
+			assert_eq!(actual, expected);
+		}
+
+		test(r#"{"success":{"codeHash":"0x0000000000000000000000000000000000000000000000000000000000000001","gasConsumed":3}}"#);
+		test(r#"{"error":"Can't decode wasm code"}"#);
+	}
 }
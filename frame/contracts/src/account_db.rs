@@ -17,17 +17,19 @@
 //! Auxiliaries to help with managing partial changes to accounts state.
 
 use super::{
-	AliveContractInfo, BalanceOf, CodeHash, ContractInfo, ContractInfoOf, Trait, TrieId,
-	TrieIdGenerator,
+	AliveContractInfo, BalanceOf, CodeHash, ContractInfo, ContractInfoOf, IssuedDoughnuts, Trait,
+	TrieId, TrieIdGenerator,
 };
+use crate::exec::AssetIdOf;
 use crate::exec::StorageKey;
 use sp_std::cell::RefCell;
-use sp_std::collections::btree_map::{BTreeMap, Entry};
+use sp_std::collections::{btree_map::{BTreeMap, Entry}, btree_set::BTreeSet};
 use sp_std::prelude::*;
 use sp_io::hashing::blake2_256;
 use sp_runtime::traits::{Bounded, Zero};
+use frame_support::additional_traits::MultiCurrencyAccounting;
 use frame_support::traits::{Currency, Get, Imbalance, SignedImbalance};
-use frame_support::{storage::child, StorageMap};
+use frame_support::{storage::child, StorageDoubleMap, StorageMap};
 use frame_system;
 
 // Note: we don't provide Option<Contract> because we can't create
@@ -37,6 +39,9 @@ pub struct ChangeEntry<T: Trait> {
 	/// If Some(_), then the account balance is modified to the value. If None and `reset` is false,
 	/// the balance unmodified. If None and `reset` is true, the balance is reset to 0.
 	balance: Option<BalanceOf<T>>,
+	/// Balances of `MultiCurrency` assets modified to the given values. These are kept when the
+	/// contract is deleted.
+	asset_balances: BTreeMap<AssetIdOf<T>, BalanceOf<T>>,
 	/// If Some(_), then a contract is instantiated with the code hash. If None and `reset` is false,
 	/// then the contract code is unmodified. If None and `reset` is true, the contract is deleted.
 	code_hash: Option<CodeHash<T>>,
@@ -93,6 +98,7 @@ impl<T: Trait> Default for ChangeEntry<T> {
 		ChangeEntry {
 			rent_allowance: Default::default(),
 			balance: Default::default(),
+			asset_balances: Default::default(),
 			code_hash: Default::default(),
 			storage: Default::default(),
 			reset: false,
@@ -116,6 +122,10 @@ pub trait AccountDb<T: Trait> {
 	/// Returns false iff account has no alive contract nor tombstone.
 	fn contract_exists(&self, account: &T::AccountId) -> bool;
 	fn get_balance(&self, account: &T::AccountId) -> BalanceOf<T>;
+	/// Returns the free balance of the `asset_id` asset of `MultiCurrency`.
+	fn get_asset_balance(&self, account: &T::AccountId, asset_id: &AssetIdOf<T>) -> BalanceOf<T>;
+	/// Returns the `MultiCurrency` assets of which the account may have a non-zero free balance.
+	fn get_assets(&self, account: &T::AccountId) -> BTreeSet<AssetIdOf<T>>;
 
 	fn commit(&mut self, change_set: ChangeSet<T>);
 }
@@ -142,14 +152,31 @@ impl<T: Trait> AccountDb<T> for DirectAccountDb {
 	fn get_balance(&self, account: &T::AccountId) -> BalanceOf<T> {
 		T::Currency::free_balance(account)
 	}
+	fn get_asset_balance(&self, account: &T::AccountId, asset_id: &AssetIdOf<T>) -> BalanceOf<T> {
+		T::MultiCurrency::free_balance(account, Some(*asset_id))
+	}
+	fn get_assets(&self, account: &T::AccountId) -> BTreeSet<AssetIdOf<T>> {
+		T::MultiCurrency::currencies_held(account).into_iter().collect()
+	}
 	fn commit(&mut self, s: ChangeSet<T>) {
 		let mut total_imbalance = SignedImbalance::zero();
+		// Imbalances of different assets cannot be merged, so they are summed up per asset.
+		let mut asset_imbalances = BTreeMap::new();
 		for (address, changed) in s.into_iter() {
 			if let Some(balance) = changed.balance() {
 				let imbalance = T::Currency::make_free_balance_be(&address, balance);
 				total_imbalance = total_imbalance.merge(imbalance.0);
 			}
 
+			for (asset_id, balance) in changed.asset_balances.iter() {
+				let imbalance = T::MultiCurrency::make_free_balance_be(&address, Some(*asset_id), *balance).0;
+				let total = match asset_imbalances.remove(asset_id) {
+					Some(total) => SignedImbalance::merge(total, imbalance),
+					None => imbalance,
+				};
+				asset_imbalances.insert(*asset_id, total);
+			}
+
 			if changed.code_hash().is_some()
 				|| changed.rent_allowance().is_some()
 				|| !changed.storage.is_empty()
@@ -169,11 +196,13 @@ impl<T: Trait> AccountDb<T> for DirectAccountDb {
 					(true, Some(info), None) => {
 						child::kill_storage(&info.trie_id, info.child_trie_unique_id());
 						<ContractInfoOf<T>>::remove(&address);
+						<IssuedDoughnuts<T>>::remove_prefix(&address);
 						continue;
 					}
 					// Existing contract is being replaced by a new one.
 					(true, Some(info), Some(code_hash)) => {
 						child::kill_storage(&info.trie_id, info.child_trie_unique_id());
+						<IssuedDoughnuts<T>>::remove_prefix(&address);
 						AliveContractInfo::<T> {
 							code_hash,
 							storage_size: T::StorageSizeOffset::get(),
@@ -244,6 +273,13 @@ impl<T: Trait> AccountDb<T> for DirectAccountDb {
 				panic!("contract subsystem resulting in positive imbalance!"),
 			_ => {}
 		}
+		for (_, imbalance) in asset_imbalances {
+			match imbalance {
+				SignedImbalance::Positive(ref p) if !p.peek().is_zero() =>
+					panic!("contract subsystem resulting in positive imbalance!"),
+				_ => {}
+			}
+		}
 	}
 }
 pub struct OverlayAccountDb<'a, T: Trait + 'a> {
@@ -295,17 +331,35 @@ impl<'a, T: Trait> OverlayAccountDb<'a, T> {
 	}
 
 	/// Mark a contract as deleted.
+	///
+	/// Balances of `MultiCurrency` assets are kept by the account, `terminate` sweeps them
+	/// beforehand.
 	pub fn destroy_contract(&mut self, account: &T::AccountId) {
 		let mut local = self.local.borrow_mut();
+		let asset_balances = local.remove(account)
+			.map(|changes| changes.asset_balances)
+			.unwrap_or_default();
 		local.insert(
 			account.clone(),
 			ChangeEntry {
 				reset: true,
+				asset_balances,
 				..Default::default()
 			}
 		);
 	}
 
+	/// Assume contract exists
+	///
+	/// The contract keeps its `trie_id` and therefore its storage.
+	pub fn set_code_hash(&mut self, account: &T::AccountId, code_hash: CodeHash<T>) {
+		self.local
+			.borrow_mut()
+			.entry(account.clone())
+			.or_insert(Default::default())
+			.code_hash = Some(code_hash);
+	}
+
 	/// Assume contract exists
 	pub fn set_rent_allowance(&mut self, account: &T::AccountId, rent_allowance: BalanceOf<T>) {
 		self.local
@@ -321,6 +375,19 @@ impl<'a, T: Trait> OverlayAccountDb<'a, T> {
 			.or_insert(Default::default())
 			.balance = Some(balance);
 	}
+	pub fn set_asset_balance(
+		&mut self,
+		account: &T::AccountId,
+		asset_id: AssetIdOf<T>,
+		balance: BalanceOf<T>,
+	) {
+		self.local
+			.borrow_mut()
+			.entry(account.clone())
+			.or_insert(Default::default())
+			.asset_balances
+			.insert(asset_id, balance);
+	}
 }
 
 impl<'a, T: Trait> AccountDb<T> for OverlayAccountDb<'a, T> {
@@ -364,6 +431,20 @@ impl<'a, T: Trait> AccountDb<T> for OverlayAccountDb<'a, T> {
 			.and_then(|changes| changes.balance())
 			.unwrap_or_else(|| self.underlying.get_balance(account))
 	}
+	fn get_asset_balance(&self, account: &T::AccountId, asset_id: &AssetIdOf<T>) -> BalanceOf<T> {
+		self.local
+			.borrow()
+			.get(account)
+			.and_then(|changes| changes.asset_balances.get(asset_id).cloned())
+			.unwrap_or_else(|| self.underlying.get_asset_balance(account, asset_id))
+	}
+	fn get_assets(&self, account: &T::AccountId) -> BTreeSet<AssetIdOf<T>> {
+		let mut assets = self.underlying.get_assets(account);
+		if let Some(changes) = self.local.borrow().get(account) {
+			assets.extend(changes.asset_balances.keys().cloned());
+		}
+		assets
+	}
 	fn commit(&mut self, s: ChangeSet<T>) {
 		let mut local = self.local.borrow_mut();
 
@@ -372,9 +453,14 @@ impl<'a, T: Trait> AccountDb<T> for OverlayAccountDb<'a, T> {
 				Entry::Occupied(e) => {
 					let mut value = e.into_mut();
 					if changed.reset {
+						let asset_balances = sp_std::mem::replace(&mut value.asset_balances, Default::default());
 						*value = changed;
+						for (asset_id, balance) in asset_balances {
+							value.asset_balances.entry(asset_id).or_insert(balance);
+						}
 					} else {
 						value.balance = changed.balance.or(value.balance);
+						value.asset_balances.extend(changed.asset_balances.into_iter());
 						value.code_hash = changed.code_hash.or(value.code_hash);
 						value.rent_allowance = changed.rent_allowance.or(value.rent_allowance);
 						value.storage.extend(changed.storage.into_iter());
@@ -0,0 +1,123 @@
+// Copyright 2020 Plug New Zealand Limited
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Runtime functions which contracts call synchronously through `ext_chain_extension`.
+//!
+//! The runtime configures a single [`ChainExtension`] in [`Trait`](crate::Trait), which
+//! dispatches on a function ID chosen by the contract. Input and output are opaque byte buffers,
+//! usually SCALE encoded.
+//!
+//! Functions must not write to runtime storage directly, as those writes would survive a failing
+//! contract. They [`dispatch`](Environment::dispatch) runtime calls instead, which are executed
+//! like `ext_dispatch_call` only if the calling contract succeeds.
+
+use crate::Trait;
+use crate::gas::{Gas, GasMeter, Token};
+use sp_std::prelude::*;
+use frame_support::dispatch::DispatchError;
+
+/// Gas charged by a chain extension function for the work it does.
+#[cfg_attr(test, derive(Debug, PartialEq, Eq))]
+#[derive(Copy, Clone)]
+pub struct ChainExtensionGas(pub Gas);
+
+impl<T: Trait> Token<T> for ChainExtensionGas {
+	type Metadata = ();
+
+	fn calculate_amount(&self, _metadata: &()) -> Gas {
+		self.0
+	}
+}
+
+/// The environment a chain extension function is called in.
+pub struct Environment<'a, T: Trait> {
+	address: &'a T::AccountId,
+	caller: &'a T::AccountId,
+	gas_meter: &'a mut GasMeter<T>,
+	out_of_gas: bool,
+	dispatched: Vec<<T as Trait>::Call>,
+}
+
+impl<'a, T: Trait> Environment<'a, T> {
+	pub(crate) fn new(
+		address: &'a T::AccountId,
+		caller: &'a T::AccountId,
+		gas_meter: &'a mut GasMeter<T>,
+	) -> Self {
+		Environment { address, caller, gas_meter, out_of_gas: false, dispatched: Vec::new() }
+	}
+
+	/// The address of the calling contract.
+	pub fn address(&self) -> &T::AccountId {
+		self.address
+	}
+
+	/// The caller of the calling contract.
+	pub fn caller(&self) -> &T::AccountId {
+		self.caller
+	}
+
+	/// The gas left to the calling contract.
+	pub fn gas_left(&self) -> Gas {
+		self.gas_meter.gas_left()
+	}
+
+	/// Charge `amount` of gas from the calling contract.
+	///
+	/// Returns an error if there is not enough gas, which should be returned from
+	/// `ChainExtension::call`. The calling contract then traps.
+	pub fn charge_gas(&mut self, amount: Gas) -> Result<(), DispatchError> {
+		if self.gas_meter.charge(&(), ChainExtensionGas(amount)).is_out_of_gas() {
+			self.out_of_gas = true;
+			Err("ran out of gas in chain extension".into())
+		} else {
+			Ok(())
+		}
+	}
+
+	/// Dispatch `call` with the calling contract as the origin.
+	///
+	/// The call is executed after the calling contract returns, and only if it and the chain
+	/// extension function succeed.
+	pub fn dispatch(&mut self, call: <T as Trait>::Call) {
+		self.dispatched.push(call);
+	}
+
+	/// Whether a `charge_gas` ran out of gas.
+	pub(crate) fn is_out_of_gas(&self) -> bool {
+		self.out_of_gas
+	}
+
+	/// The calls dispatched by the function.
+	pub(crate) fn into_dispatched(self) -> Vec<<T as Trait>::Call> {
+		self.dispatched
+	}
+}
+
+/// Runtime functions which contracts can call with `ext_chain_extension`.
+pub trait ChainExtension<T: Trait> {
+	/// Call the function `func_id` with `input`, returning its output.
+	///
+	/// The work done must be paid for with `Environment::charge_gas` before doing it. Functions
+	/// only read runtime storage and change it with `Environment::dispatch`, whose calls are
+	/// dropped if the calling contract fails.
+	fn call(func_id: u32, input: Vec<u8>, env: &mut Environment<T>) -> Result<Vec<u8>, DispatchError>;
+}
+
+/// No chain extension, every call fails.
+impl<T: Trait> ChainExtension<T> for () {
+	fn call(_func_id: u32, _input: Vec<u8>, _env: &mut Environment<T>) -> Result<Vec<u8>, DispatchError> {
+		Err("no chain extension is configured".into())
+	}
+}
@@ -15,21 +15,22 @@
 #![allow(unused_must_use)]
 
 use crate::{
-	ComputeDispatchFee, ContractAddressFor, GenesisConfig, Module, RawEvent, Trait,
-	TrieId, Schedule, TrieIdGenerator,
+	ChainExtension, ComputeDispatchFee, ContractAddressFor, Environment, GenesisConfig, Module,
+	RawEvent, Trait, TrieId, Schedule, TrieIdGenerator, IssuedDoughnuts, ContractInfoOf,
 };
 use sp_std::convert::TryInto;
 use codec::{Encode, Decode};
 use hex_literal::*;
 use sp_core::storage::well_known_keys;
 use sp_runtime::{
-	Perbill, traits::{BlakeTwo256, Hash, IdentityLookup, PlugDoughnutApi, ValidationError},
+	DispatchError, Perbill, traits::{BlakeTwo256, Hash, IdentityLookup, PlugDoughnutApi, ValidationError},
 	testing::{Header, H256},
 };
 use frame_support::{
 	assert_ok, assert_err, impl_outer_dispatch, impl_outer_event, impl_outer_origin,
-	parameter_types, StorageValue, traits::{Currency, Get}, weights::Weight,
-	additional_traits::DelegatedDispatchVerifier,
+	parameter_types, StorageDoubleMap, StorageMap, StorageValue, traits::{Currency, Get},
+	weights::Weight,
+	additional_traits::{DelegatedDispatchVerifier, DoughnutAuthorisations},
 };
 use std::{cell::RefCell, any::Any};
 use frame_system::{self as system, EventRecord, Phase, RawOrigin};
@@ -43,7 +44,7 @@ mod contract {
 }
 impl_outer_event! {
 	pub enum MetaEvent for Test {
-		system, pallet_balances<T>, contract<T>,
+		system, pallet_balances<T>, pallet_generic_asset<T>, contract<T>,
 	}
 }
 impl_outer_origin! {
@@ -89,6 +90,13 @@ pub struct MockDoughnut {
 	/// A mock branching point for verify_contract_to_contract_call, as a doughnut is verified at different level.
 	/// A doughnut is then verified at contract execution via ext_call().
 	contract_verifiable: bool,
+	/// A mock branching point for verify_signature, as a doughnut supplied to a contract is verified
+	/// via ext_verify_doughnut() and ext_forward_doughnut().
+	signature_verifiable: bool,
+	/// The issuer account
+	issuer: u64,
+	/// The holder account
+	holder: u64,
 }
 impl MockDoughnut {
 	pub fn set_runtime_verifiable(mut self, verifiable: bool) -> Self {
@@ -99,23 +107,46 @@ impl MockDoughnut {
 		self.contract_verifiable = verifiable;
 		self
 	}
+	pub fn set_signature_verifiable(mut self, verifiable: bool) -> Self {
+		self.signature_verifiable = verifiable;
+		self
+	}
+	pub fn set_issuer(mut self, issuer: u64) -> Self {
+		self.issuer = issuer;
+		self
+	}
+	pub fn set_holder(mut self, holder: u64) -> Self {
+		self.holder = holder;
+		self
+	}
+}
+
+/// Pad an account id into a doughnut public key
+fn to_public_key(account: u64) -> [u8; 32] {
+	let mut public_key = [0u8; 32];
+	public_key[..8].copy_from_slice(&account.encode());
+	public_key
 }
 
 impl PlugDoughnutApi for MockDoughnut {
 	type PublicKey = [u8; 32];
 	type Timestamp = u32;
 	type Signature = ();
-	fn holder(&self) -> Self::PublicKey { Default::default() }
-	fn issuer(&self) -> Self::PublicKey { Default::default() }
+	fn holder(&self) -> Self::PublicKey { to_public_key(self.holder) }
+	fn issuer(&self) -> Self::PublicKey { to_public_key(self.issuer) }
 	fn expiry(&self) -> Self::Timestamp { 0 }
 	fn not_before(&self) -> Self::Timestamp { 0 }
 	fn payload(&self) -> Vec<u8> { Vec::default() }
 	fn signature(&self) -> Self::Signature {}
 	fn signature_version(&self) -> u8 { 0 }
 	fn get_domain(&self, _domain: &str) -> Option<&[u8]> { None }
-	fn validate<Q: AsRef<[u8]>, R: TryInto<u32>>(&self, _who: Q, _now: R) -> Result<(), ValidationError> {
-		// Assume it is valid (not under test here)
-		Ok(())
+	fn validate<Q: AsRef<[u8]>, R: TryInto<u32>>(&self, who: Q, _now: R) -> Result<(), ValidationError> {
+		// Timestamps are not under test here
+		if who.as_ref() == &self.holder.encode()[..] {
+			Ok(())
+		} else {
+			Err(ValidationError::HolderIdentityMismatched)
+		}
 	}
 }
 
@@ -154,6 +185,13 @@ impl DelegatedDispatchVerifier for MockDispatchVerifier {
 			Err("Doughnut contract to contract call verification is not implemented for this domain")
 		}
 	}
+	fn verify_signature(doughnut: &Self::Doughnut) -> Result<(), &'static str> {
+		if doughnut.signature_verifiable {
+			Ok(())
+		} else {
+			Err("Doughnut signature verification failed")
+		}
+	}
 }
 
 parameter_types! {
@@ -192,6 +230,11 @@ impl pallet_balances::Trait for Test {
 	type ExistentialDeposit = ExistentialDeposit;
 	type CreationFee = CreationFee;
 }
+impl pallet_generic_asset::Trait for Test {
+	type Balance = u64;
+	type AssetId = u32;
+	type Event = MetaEvent;
+}
 parameter_types! {
 	pub const MinimumPeriod: u64 = 1;
 }
@@ -217,6 +260,8 @@ parameter_types! {
 }
 impl Trait for Test {
 	type Currency = Balances;
+	type MultiCurrency = GenericAsset;
+	type AssetId = u32;
 	type Time = Timestamp;
 	type Randomness = Randomness;
 	type Call = Call;
@@ -242,9 +287,41 @@ impl Trait for Test {
 	type MaxDepth = MaxDepth;
 	type MaxValueSize = MaxValueSize;
 	type BlockGasLimit = BlockGasLimit;
+	type ChainExtension = MockChainExtension;
+}
+
+/// Chain extension for the wasm host function tests.
+pub struct MockChainExtension;
+impl ChainExtension<Test> for MockChainExtension {
+	fn call(func_id: u32, input: Vec<u8>, env: &mut Environment<Test>) -> Result<Vec<u8>, DispatchError> {
+		match func_id {
+			// Reverse the input.
+			1 => {
+				env.charge_gas(100)?;
+				Ok(input.into_iter().rev().collect())
+			},
+			// Charge more gas than any test supplies.
+			2 => {
+				env.charge_gas(u64::max_value())?;
+				Ok(Vec::new())
+			},
+			// Dispatch a call and succeed.
+			4 => {
+				env.dispatch(Call::Balances(pallet_balances::Call::set_balance(42, 1337, 0)));
+				Ok(Vec::new())
+			},
+			// Dispatch a call and fail.
+			5 => {
+				env.dispatch(Call::Balances(pallet_balances::Call::set_balance(42, 1337, 0)));
+				Err("chain extension function failed".into())
+			},
+			_ => Err("unknown chain extension function".into()),
+		}
+	}
 }
 
 type Balances = pallet_balances::Module<Test>;
+type GenericAsset = pallet_generic_asset::Module<Test>;
 type Timestamp = pallet_timestamp::Module<Test>;
 type Contract = Module<Test>;
 type System = frame_system::Module<Test>;
@@ -737,3 +814,236 @@ fn contract_call_charges_gas_to_issuer() {
 		);
 	});
 }
+
+const CODE_ISSUE_DOUGHNUT: &str = r#"
+(module
+	(import "env" "ext_scratch_size" (func $ext_scratch_size (result i32)))
+	(import "env" "ext_scratch_read" (func $ext_scratch_read (param i32 i32 i32)))
+	(import "env" "ext_issue_doughnut" (func $ext_issue_doughnut (param i32 i32) (result i32)))
+	(import "env" "ext_revoke_doughnut" (func $ext_revoke_doughnut (param i32 i32) (result i32)))
+	(import "env" "ext_terminate" (func $ext_terminate (param i32 i32)))
+	(import "env" "memory" (memory 1 1))
+
+	(func $assert (param i32)
+		(block $ok
+			(br_if $ok
+				(get_local 0)
+			)
+			(unreachable)
+		)
+	)
+
+	;; Issues the doughnut given as input if the first byte of the input is 0, revokes it if it
+	;; is 1, terminates the contract otherwise.
+	(func (export "call")
+		(local $buf_size i32)
+
+		(set_local $buf_size (call $ext_scratch_size))
+		(call $ext_scratch_read
+			(i32.const 0)
+			(i32.const 0)
+			(get_local $buf_size)
+		)
+
+		(if (i32.gt_u (i32.load8_u (i32.const 0)) (i32.const 1))
+			(then
+				(call $ext_terminate
+					(i32.const 4096) ;; Pointer to the beneficiary, ALICE
+					(i32.const 8) ;; Length of the beneficiary
+				)
+			)
+		)
+
+		(if (i32.load8_u (i32.const 0))
+			(then
+				(call $assert
+					(i32.eqz
+						(call $ext_revoke_doughnut
+							(i32.const 1)
+							(i32.sub (get_local $buf_size) (i32.const 1))
+						)
+					)
+				)
+			)
+			(else
+				(call $assert
+					(i32.eqz
+						(call $ext_issue_doughnut
+							(i32.const 1)
+							(i32.sub (get_local $buf_size) (i32.const 1))
+						)
+					)
+				)
+			)
+		)
+	)
+	(func (export "deploy"))
+
+	(data (i32.const 4096) "\01\00\00\00\00\00\00\00")
+)
+"#;
+
+#[test]
+fn contract_issues_and_revokes_doughnut() {
+	let (wasm, code_hash) = compile_module::<Test>(CODE_ISSUE_DOUGHNUT).unwrap();
+	// The doughnut is issued by the contract account BOB.
+	let doughnut = MockDoughnut::default().set_issuer(BOB).set_holder(CHARLIE);
+	let doughnut_hash = BlakeTwo256::hash_of(&doughnut);
+	let foreign_doughnut = MockDoughnut::default().set_issuer(DJANGO).set_holder(CHARLIE);
+
+	ExtBuilder::default().existential_deposit(50).build().execute_with(|| {
+		Balances::deposit_creating(&ALICE, 1_000_000);
+
+		assert_ok!(Contract::put_code(Origin::signed(ALICE), 100_000, wasm));
+		assert_ok!(Contract::instantiate(
+			Origin::signed(ALICE),
+			100,
+			100_000,
+			code_hash.into(),
+			vec![],
+		));
+
+		let mut issue = vec![0];
+		issue.extend(doughnut.encode());
+		assert_ok!(Contract::call(Origin::signed(ALICE), BOB, 0, 100_000, issue));
+		assert!(IssuedDoughnuts::<Test>::get(BOB, doughnut_hash));
+		assert!(<Contract as DoughnutAuthorisations<u64>>::is_authorised(&BOB, &doughnut));
+		assert_eq!(
+			System::events().pop().map(|r| r.event),
+			Some(MetaEvent::contract(RawEvent::DoughnutIssued(BOB, doughnut_hash))),
+		);
+
+		// A contract can't authorise doughnuts issued by others.
+		let mut issue_foreign = vec![0];
+		issue_foreign.extend(foreign_doughnut.encode());
+		assert_err!(
+			Contract::call(Origin::signed(ALICE), BOB, 0, 100_000, issue_foreign),
+			"contract trapped during execution",
+		);
+		assert!(!<Contract as DoughnutAuthorisations<u64>>::is_authorised(&DJANGO, &foreign_doughnut));
+
+		let mut revoke = vec![1];
+		revoke.extend(doughnut.encode());
+		assert_ok!(Contract::call(Origin::signed(ALICE), BOB, 0, 100_000, revoke));
+		assert!(!<Contract as DoughnutAuthorisations<u64>>::is_authorised(&BOB, &doughnut));
+		assert_eq!(
+			System::events().pop().map(|r| r.event),
+			Some(MetaEvent::contract(RawEvent::DoughnutRevoked(BOB, doughnut_hash))),
+		);
+	});
+}
+
+#[test]
+fn contract_termination_removes_issued_doughnuts() {
+	let (wasm, code_hash) = compile_module::<Test>(CODE_ISSUE_DOUGHNUT).unwrap();
+	let doughnut = MockDoughnut::default().set_issuer(BOB).set_holder(CHARLIE);
+	let doughnut_hash = BlakeTwo256::hash_of(&doughnut);
+
+	ExtBuilder::default().existential_deposit(50).build().execute_with(|| {
+		Balances::deposit_creating(&ALICE, 1_000_000);
+
+		assert_ok!(Contract::put_code(Origin::signed(ALICE), 100_000, wasm));
+		assert_ok!(Contract::instantiate(
+			Origin::signed(ALICE),
+			100,
+			100_000,
+			code_hash.into(),
+			vec![],
+		));
+
+		let mut issue = vec![0];
+		issue.extend(doughnut.encode());
+		assert_ok!(Contract::call(Origin::signed(ALICE), BOB, 0, 100_000, issue));
+		assert!(IssuedDoughnuts::<Test>::contains_key(BOB, doughnut_hash));
+
+		assert_ok!(Contract::call(Origin::signed(ALICE), BOB, 0, 100_000, vec![2]));
+		assert!(ContractInfoOf::<Test>::get(BOB).is_none());
+		assert!(!IssuedDoughnuts::<Test>::contains_key(BOB, doughnut_hash));
+	});
+}
+
+const CODE_FORWARD_DOUGHNUT: &str = r#"
+(module
+	(import "env" "ext_scratch_size" (func $ext_scratch_size (result i32)))
+	(import "env" "ext_scratch_read" (func $ext_scratch_read (param i32 i32 i32)))
+	(import "env" "ext_forward_doughnut" (func $ext_forward_doughnut (param i32 i32 i32 i32)))
+	(import "env" "memory" (memory 1 1))
+
+	;; Forwards the doughnut given as input to dispatch the call below.
+	(func (export "call")
+		(local $buf_size i32)
+
+		(set_local $buf_size (call $ext_scratch_size))
+		(call $ext_scratch_read
+			(i32.const 16)
+			(i32.const 0)
+			(get_local $buf_size)
+		)
+		(call $ext_forward_doughnut
+			(i32.const 16) ;; Pointer to the doughnut
+			(get_local $buf_size) ;; Length of the doughnut
+			(i32.const 0) ;; Pointer to the start of encoded call buffer
+			(i32.const 11) ;; Length of the call buffer
+		)
+	)
+	(func (export "deploy"))
+
+	;; Balances::transfer(CHARLIE, 50)
+	(data (i32.const 0) "\00\00\03\00\00\00\00\00\00\00\C8")
+)
+"#;
+
+#[test]
+fn contract_forwards_doughnut_on_behalf_of_issuer() {
+	let (wasm, code_hash) = compile_module::<Test>(CODE_FORWARD_DOUGHNUT).unwrap();
+	// DJANGO delegates to the contract account BOB.
+	let doughnut = MockDoughnut::default()
+		.set_signature_verifiable(true)
+		.set_issuer(DJANGO)
+		.set_holder(BOB);
+
+	ExtBuilder::default().existential_deposit(50).build().execute_with(|| {
+		Balances::deposit_creating(&ALICE, 1_000_000);
+		Balances::deposit_creating(&DJANGO, 1_000_000);
+
+		assert_ok!(Contract::put_code(Origin::signed(ALICE), 100_000, wasm));
+		assert_ok!(Contract::instantiate(
+			Origin::signed(ALICE),
+			100,
+			100_000,
+			code_hash.into(),
+			vec![],
+		));
+
+		// The doughnut doesn't verify without the issuer signature.
+		assert_err!(
+			Contract::call(
+				Origin::signed(ALICE),
+				BOB,
+				0,
+				100_000,
+				doughnut.clone().set_signature_verifiable(false).encode(),
+			),
+			"contract trapped during execution",
+		);
+		// The contract is not the holder of the doughnut.
+		assert_err!(
+			Contract::call(
+				Origin::signed(ALICE),
+				BOB,
+				0,
+				100_000,
+				doughnut.clone().set_holder(CHARLIE).encode(),
+			),
+			"contract trapped during execution",
+		);
+		assert_eq!(Balances::free_balance(&CHARLIE), 0);
+
+		assert_ok!(Contract::call(Origin::signed(ALICE), BOB, 0, 100_000, doughnut.encode()));
+		assert_eq!(Balances::free_balance(&CHARLIE), 50);
+		assert_eq!(
+			System::events().pop().map(|r| r.event),
+			Some(MetaEvent::contract(RawEvent::DelegatedDispatched(DJANGO, doughnut, true))),
+		);
+	});
+}
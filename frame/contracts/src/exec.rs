@@ -15,15 +15,17 @@
 // along with Substrate. If not, see <http://www.gnu.org/licenses/>.
 
 use super::{CodeHash, Config, ContractAddressFor, Event, RawEvent, Trait,
-	TrieId, BalanceOf, ContractInfo};
+	TrieId, BalanceOf, ContractInfo, ContractInfoOf, Module};
 use crate::account_db::{AccountDb, DirectAccountDb, OverlayAccountDb};
 use crate::gas::{Gas, GasMeter, Token, approx_gas_for_balance};
 use crate::rent;
 
+use codec::{Decode, Encode};
 use sp_std::prelude::*;
-use sp_runtime::traits::{Bounded, CheckedAdd, CheckedSub, Zero};
+use sp_runtime::traits::{Bounded, CheckedAdd, CheckedSub, Hash, PlugDoughnutApi, Zero};
 use frame_support::{
 	storage::unhashed, dispatch::DispatchError,
+	additional_traits::{DelegatedDispatchVerifier, DoughnutAuthorisations, MultiCurrencyAccounting},
 	traits::{WithdrawReason, Currency, Time, Randomness},
 };
 
@@ -33,6 +35,7 @@ pub type CallOf<T> = <T as Trait>::Call;
 pub type MomentOf<T> = <<T as Trait>::Time as Time>::Moment;
 pub type SeedOf<T> = <T as frame_system::Trait>::Hash;
 pub type BlockNumberOf<T> = <T as frame_system::Trait>::BlockNumber;
+pub type AssetIdOf<T> = <T as Trait>::AssetId;
 pub type StorageKey = [u8; 32];
 
 /// A type that represents a topic of an event. At the moment a hash is used.
@@ -129,6 +132,15 @@ pub trait Ext {
 		gas_meter: &mut GasMeter<Self::T>,
 	) -> Result<(), DispatchError>;
 
+	/// Transfer some amount of the `asset_id` asset into the specified account.
+	fn transfer_asset(
+		&mut self,
+		asset_id: &AssetIdOf<Self::T>,
+		to: &AccountIdOf<Self::T>,
+		value: BalanceOf<Self::T>,
+		gas_meter: &mut GasMeter<Self::T>,
+	) -> Result<(), DispatchError>;
+
 	/// Transfer all funds to `beneficiary` and delete the contract.
 	fn terminate(
 		&mut self,
@@ -145,9 +157,44 @@ pub trait Ext {
 		input_data: Vec<u8>,
 	) -> ExecResult;
 
+	/// Switch the current contract to the code specified by `code_hash`, keeping its storage.
+	///
+	/// The `migrate` entrypoint of the new code, if it has one, is called with `input_data`.
+	/// On failure the contract keeps its current code.
+	fn set_code_hash(
+		&mut self,
+		code_hash: &CodeHash<Self::T>,
+		gas_meter: &mut GasMeter<Self::T>,
+		input_data: Vec<u8>,
+	) -> ExecResult;
+
 	/// Notes a call dispatch.
 	fn note_delegated_dispatch_call(&mut self, doughnut: DoughnutOf<Self::T>, call: CallOf<Self::T>);
 
+	/// Notes a call dispatch with a doughnut held by the current contract, on behalf of `issuer`.
+	fn note_forwarded_dispatch_call(
+		&mut self,
+		issuer: AccountIdOf<Self::T>,
+		doughnut: DoughnutOf<Self::T>,
+		call: CallOf<Self::T>,
+	);
+
+	/// Notes the authorisation (or its revocation) of a doughnut issued by the current contract.
+	///
+	/// Returns an error if the current contract is not the issuer of the doughnut.
+	fn note_doughnut_authorisation(
+		&mut self,
+		doughnut: &DoughnutOf<Self::T>,
+		authorised: bool,
+	) -> Result<(), &'static str>;
+
+	/// Verifies the current contract may use the given doughnut, i.e. that it is the holder, the
+	/// doughnut is valid at the current block timestamp and the issuer either signed it or
+	/// authorised it on-chain.
+	///
+	/// Returns the account id of the issuer.
+	fn verify_doughnut(&self, doughnut: &DoughnutOf<Self::T>) -> Result<AccountIdOf<Self::T>, &'static str>;
+
 	/// Notes a call dispatch.
 	fn note_dispatch_call(&mut self, call: CallOf<Self::T>);
 
@@ -177,9 +224,16 @@ pub trait Ext {
 	/// The `value_transferred` is already added.
 	fn balance(&self) -> BalanceOf<Self::T>;
 
+	/// Returns the balance of the `asset_id` asset of the current contract.
+	fn asset_balance(&self, asset_id: &AssetIdOf<Self::T>) -> BalanceOf<Self::T>;
+
 	/// Returns the value transferred along with this call or as endowment.
 	fn value_transferred(&self) -> BalanceOf<Self::T>;
 
+	/// Returns the asset of the value transferred along with this call, `None` if it was
+	/// transferred in `Currency`.
+	fn value_transferred_asset(&self) -> Option<AssetIdOf<Self::T>>;
+
 	/// Returns a reference to the timestamp of the current block
 	fn now(&self) -> &MomentOf<Self::T>;
 
@@ -226,6 +280,9 @@ pub trait Loader<T: Trait> {
 	/// Load the main portion of the code specified by the `code_hash`. This executable
 	/// is called for each call to a contract.
 	fn load_main(&self, code_hash: &CodeHash<T>) -> Result<Self::Executable, &'static str>;
+	/// Load the migration portion of the code specified by the `code_hash`, if the code has one.
+	/// This executable is called once when a contract switches to this code.
+	fn load_migrate(&self, code_hash: &CodeHash<T>) -> Result<Option<Self::Executable>, &'static str>;
 }
 
 /// A trait that represent a virtual machine.
@@ -283,6 +340,14 @@ pub enum DeferredAction<T: Trait> {
 		/// The call to dispatch.
 		call: <T as Trait>::Call,
 	},
+	ForwardedRuntimeCall {
+		/// The account id of the issuer of the doughnut, the call is dispatched on behalf of.
+		issuer: T::AccountId,
+		/// The doughnut held by the contract who dispatched this call.
+		doughnut: T::Doughnut,
+		/// The call to dispatch.
+		call: <T as Trait>::Call,
+	},
 	DispatchRuntimeCall {
 		/// The account id of the contract who dispatched this call.
 		origin: T::AccountId,
@@ -302,6 +367,14 @@ pub enum DeferredAction<T: Trait> {
 		/// The keys to delete upon restoration.
 		delta: Vec<StorageKey>,
 	},
+	AuthoriseDoughnut {
+		/// The account id of the contract which issued the doughnut.
+		issuer: T::AccountId,
+		/// The hash of the doughnut.
+		doughnut_hash: T::Hash,
+		/// Whether the doughnut is authorised or the authorisation is revoked.
+		authorised: bool,
+	},
 }
 
 pub struct ExecutionContext<'a, T: Trait + 'a, V, L> {
@@ -318,6 +391,8 @@ pub struct ExecutionContext<'a, T: Trait + 'a, V, L> {
 	pub block_number: T::BlockNumber,
 	pub origin: T::AccountId,
 	pub doughnut: Option<&'a T::Doughnut>,
+	/// The asset of the value endowed into this context, `None` for `Currency`.
+	pub value_asset: Option<AssetIdOf<T>>,
 }
 
 impl<'a, T, E, V, L> ExecutionContext<'a, T, V, L>
@@ -345,6 +420,7 @@ where
 			block_number: <frame_system::Module<T>>::block_number(),
 			origin: origin.clone(),
 			doughnut: doughnut,
+			value_asset: None,
 		}
 	}
 
@@ -365,6 +441,7 @@ where
 			block_number: self.block_number.clone(),
 			origin: self.origin.clone(),
 			doughnut: self.doughnut,
+			value_asset: None,
 		}
 	}
 
@@ -385,6 +462,25 @@ where
 		)
 	}
 
+	/// Transfer balance of the `asset_id` asset to `dest` without calling any contract code.
+	pub fn transfer_asset(
+		&mut self,
+		asset_id: AssetIdOf<T>,
+		dest: T::AccountId,
+		value: BalanceOf<T>,
+		gas_meter: &mut GasMeter<T>
+	) -> Result<(), DispatchError> {
+		transfer_asset(
+			gas_meter,
+			TransferCause::Call,
+			&self.self_account.clone(),
+			&dest,
+			asset_id,
+			value,
+			self,
+		)
+	}
+
 	/// Make a call to the specified address, optionally transferring some funds.
 	pub fn call(
 		&mut self,
@@ -392,6 +488,30 @@ where
 		value: BalanceOf<T>,
 		gas_meter: &mut GasMeter<T>,
 		input_data: Vec<u8>,
+	) -> ExecResult {
+		self.call_inner(dest, None, value, gas_meter, input_data)
+	}
+
+	/// Make a call to the specified address, optionally transferring some funds of the `asset_id`
+	/// asset.
+	pub fn call_with_asset(
+		&mut self,
+		dest: T::AccountId,
+		asset_id: AssetIdOf<T>,
+		value: BalanceOf<T>,
+		gas_meter: &mut GasMeter<T>,
+		input_data: Vec<u8>,
+	) -> ExecResult {
+		self.call_inner(dest, Some(asset_id), value, gas_meter, input_data)
+	}
+
+	fn call_inner(
+		&mut self,
+		dest: T::AccountId,
+		value_asset: Option<AssetIdOf<T>>,
+		value: BalanceOf<T>,
+		gas_meter: &mut GasMeter<T>,
+		input_data: Vec<u8>,
 	) -> ExecResult {
 		if self.depth == self.config.max_depth as usize {
 			return Err(ExecError {
@@ -427,18 +547,21 @@ where
 		let dest_trie_id = contract_info.and_then(|i| i.as_alive().map(|i| i.trie_id.clone()));
 
 		self.with_nested_context(dest.clone(), dest_trie_id, |nested| {
+			nested.value_asset = value_asset;
 			if value > BalanceOf::<T>::zero() {
-				try_or_exec_error!(
-					transfer(
+				let result = match value_asset {
+					Some(asset_id) => transfer_asset(
 						gas_meter,
 						TransferCause::Call,
 						&caller,
 						&dest,
+						asset_id,
 						value,
 						nested,
 					),
-					input_data
-				);
+					None => transfer(gas_meter, TransferCause::Call, &caller, &dest, value, nested),
+				};
+				try_or_exec_error!(result, input_data);
 			}
 
 			// If code_hash is not none, then the destination account is a live contract, otherwise
@@ -550,6 +673,91 @@ where
 		Ok((dest, output))
 	}
 
+	/// Switch the contract at `dest` to the code specified by `code_hash`, keeping its storage.
+	///
+	/// The `migrate` entrypoint of the new code, if it has one, is executed once in the context
+	/// of `dest` right after the switch. The switch is reverted if the migration fails.
+	pub fn set_code(
+		&mut self,
+		dest: T::AccountId,
+		code_hash: &CodeHash<T>,
+		gas_meter: &mut GasMeter<T>,
+		input_data: Vec<u8>,
+	) -> ExecResult {
+		let dest_trie_id = match <ContractInfoOf<T>>::get(&dest) {
+			Some(ContractInfo::Alive(info)) => Some(info.trie_id),
+			_ => return Err(ExecError {
+				reason: "contract is not alive".into(),
+				buffer: input_data,
+			}),
+		};
+
+		self.set_code_inner(dest, dest_trie_id, code_hash, gas_meter, input_data)
+	}
+
+	fn set_code_inner(
+		&mut self,
+		dest: T::AccountId,
+		dest_trie_id: Option<TrieId>,
+		code_hash: &CodeHash<T>,
+		gas_meter: &mut GasMeter<T>,
+		input_data: Vec<u8>,
+	) -> ExecResult {
+		if self.depth == self.config.max_depth as usize {
+			return Err(ExecError {
+				reason: "reached maximum depth, cannot set code".into(),
+				buffer: input_data,
+			});
+		}
+
+		if gas_meter
+			.charge(self.config, ExecFeeToken::Call)
+			.is_out_of_gas()
+		{
+			return Err(ExecError {
+				reason: "not enough gas to pay base call fee".into(),
+				buffer: input_data,
+			});
+		}
+
+		let old_code_hash = match self.overlay.get_code_hash(&dest) {
+			Some(old_code_hash) => old_code_hash,
+			None => return Err(ExecError {
+				reason: "contract is not alive".into(),
+				buffer: input_data,
+			}),
+		};
+		let caller = self.self_account.clone();
+
+		self.with_nested_context(dest.clone(), dest_trie_id, |nested| {
+			// Loading fails if no code is stored under `code_hash`.
+			let migration = try_or_exec_error!(
+				nested.loader.load_migrate(code_hash),
+				input_data
+			);
+
+			nested.overlay.set_code_hash(&dest, code_hash.clone());
+
+			let output = match migration {
+				Some(executable) => nested.vm
+					.execute(
+						&executable,
+						nested.new_call_context(caller, Zero::zero()),
+						input_data,
+						gas_meter,
+					)?,
+				None => ExecReturnValue { status: STATUS_SUCCESS, data: Vec::new() },
+			};
+
+			nested.deferred.push(DeferredAction::DepositEvent {
+				event: RawEvent::CodeUpdated(dest.clone(), old_code_hash, code_hash.clone()),
+				topics: Vec::new(),
+			});
+
+			Ok(output)
+		})
+	}
+
 	pub fn terminate(
 		&mut self,
 		beneficiary: &T::AccountId,
@@ -572,6 +780,20 @@ where
 			value,
 			self,
 		)?;
+		for asset_id in self.overlay.get_assets(&self_id) {
+			let asset_value = self.overlay.get_asset_balance(&self_id, &asset_id);
+			if !asset_value.is_zero() {
+				transfer_asset(
+					gas_meter,
+					TransferCause::Terminate,
+					&self_id,
+					beneficiary,
+					asset_id,
+					asset_value,
+					self,
+				)?;
+			}
+		}
 		self.overlay.destroy_contract(&self_id);
 		Ok(())
 	}
@@ -750,6 +972,72 @@ fn transfer<'a, T: Trait, V: Vm<T>, L: Loader<T>>(
 	Ok(())
 }
 
+/// Transfer some funds of the `asset_id` asset from `transactor` to `dest`.
+///
+/// As with `transfer`, all balance changes are performed in the `overlay` and the transfer fee is
+/// charged from the `gas_meter`. The minimum balance of `MultiCurrency` takes the place of the
+/// existential deposit. `cause` must not be `Instantiate`, contracts are endowed in `Currency`.
+fn transfer_asset<'a, T: Trait, V: Vm<T>, L: Loader<T>>(
+	gas_meter: &mut GasMeter<T>,
+	cause: TransferCause,
+	transactor: &T::AccountId,
+	dest: &T::AccountId,
+	asset_id: AssetIdOf<T>,
+	value: BalanceOf<T>,
+	ctx: &mut ExecutionContext<'a, T, V, L>,
+) -> Result<(), DispatchError> {
+	let token = TransferFeeToken {
+		kind: TransferFeeKind::Transfer,
+		gas_price: gas_meter.gas_price(),
+	};
+	if gas_meter.charge(ctx.config, token).is_out_of_gas() {
+		Err("not enough gas to pay transfer fee")?
+	}
+
+	let minimum_balance = T::MultiCurrency::minimum_balance();
+	let to_balance = ctx.overlay.get_asset_balance(dest, &asset_id);
+	if to_balance.is_zero() && value < minimum_balance {
+		Err("value too low to create account")?
+	}
+
+	let from_balance = ctx.overlay.get_asset_balance(transactor, &asset_id);
+	let new_from_balance = match from_balance.checked_sub(&value) {
+		Some(b) => b,
+		None => Err("balance too low to send value")?,
+	};
+
+	// Only ext_terminate is allowed to bring the sender below the minimum balance
+	let required_balance = match cause {
+		TransferCause::Terminate => Zero::zero(),
+		_ => minimum_balance,
+	};
+
+	T::MultiCurrency::ensure_can_withdraw(
+		transactor,
+		Some(asset_id),
+		value,
+		WithdrawReason::Transfer.into(),
+		new_from_balance.checked_sub(&required_balance)
+			.ok_or("brings sender below minimum balance")?,
+	)?;
+
+	let new_to_balance = match to_balance.checked_add(&value) {
+		Some(b) => b,
+		None => Err("destination balance too high to receive value")?,
+	};
+
+	if transactor != dest {
+		ctx.overlay.set_asset_balance(transactor, asset_id, new_from_balance);
+		ctx.overlay.set_asset_balance(dest, asset_id, new_to_balance);
+		ctx.deferred.push(DeferredAction::DepositEvent {
+			event: RawEvent::AssetTransfer(transactor.clone(), dest.clone(), asset_id, value),
+			topics: Vec::new(),
+		});
+	}
+
+	Ok(())
+}
+
 struct CallContext<'a, 'b: 'a, T: Trait + 'b, V: Vm<T> + 'b, L: Loader<T>> {
 	ctx: &'a mut ExecutionContext<'b, T, V, L>,
 	caller: T::AccountId,
@@ -803,6 +1091,16 @@ where
 		self.ctx.transfer(to.clone(), value, gas_meter)
 	}
 
+	fn transfer_asset(
+		&mut self,
+		asset_id: &AssetIdOf<T>,
+		to: &T::AccountId,
+		value: BalanceOf<T>,
+		gas_meter: &mut GasMeter<T>,
+	) -> Result<(), DispatchError> {
+		self.ctx.transfer_asset(*asset_id, to.clone(), value, gas_meter)
+	}
+
 	fn terminate(
 		&mut self,
 		beneficiary: &AccountIdOf<Self::T>,
@@ -821,6 +1119,17 @@ where
 		self.ctx.call(to.clone(), value, gas_meter, input_data)
 	}
 
+	fn set_code_hash(
+		&mut self,
+		code_hash: &CodeHash<T>,
+		gas_meter: &mut GasMeter<T>,
+		input_data: Vec<u8>,
+	) -> ExecResult {
+		let dest = self.ctx.self_account.clone();
+		let dest_trie_id = self.ctx.self_trie_id.clone();
+		self.ctx.set_code_inner(dest, dest_trie_id, code_hash, gas_meter, input_data)
+	}
+
 	fn note_delegated_dispatch_call(&mut self, doughnut: DoughnutOf<Self::T>, call: CallOf<Self::T>) {
 		self.ctx.deferred.push(DeferredAction::DelegatedRuntimeCall {
 			doughnut,
@@ -828,6 +1137,49 @@ where
 		});
 	}
 
+	fn note_forwarded_dispatch_call(
+		&mut self,
+		issuer: AccountIdOf<Self::T>,
+		doughnut: DoughnutOf<Self::T>,
+		call: CallOf<Self::T>,
+	) {
+		self.ctx.deferred.push(DeferredAction::ForwardedRuntimeCall {
+			issuer,
+			doughnut,
+			call,
+		});
+	}
+
+	fn note_doughnut_authorisation(
+		&mut self,
+		doughnut: &DoughnutOf<Self::T>,
+		authorised: bool,
+	) -> Result<(), &'static str> {
+		if doughnut.issuer().as_ref() != &self.ctx.self_account.encode()[..] {
+			return Err("contract is not the issuer of the doughnut");
+		}
+		self.ctx.deferred.push(DeferredAction::AuthoriseDoughnut {
+			issuer: self.ctx.self_account.clone(),
+			doughnut_hash: T::Hashing::hash_of(doughnut),
+			authorised,
+		});
+		Ok(())
+	}
+
+	fn verify_doughnut(&self, doughnut: &DoughnutOf<Self::T>) -> Result<AccountIdOf<Self::T>, &'static str> {
+		// Doughnut timestamps are in seconds, the block timestamp is in milliseconds.
+		let now = self.ctx.timestamp / MomentOf::<T>::from(1000u32);
+		doughnut.validate(self.ctx.self_account.encode(), now)
+			.map_err(|_| "doughnut is not valid for the contract at this time")?;
+
+		let issuer = AccountIdOf::<T>::decode(&mut doughnut.issuer().as_ref())
+			.map_err(|_| "doughnut issuer is not an account id")?;
+		if !<Module<T> as DoughnutAuthorisations<_>>::is_authorised(&issuer, doughnut) {
+			<T as frame_system::Trait>::DelegatedDispatchVerifier::verify_signature(doughnut)?;
+		}
+		Ok(issuer)
+	}
+
 	fn note_dispatch_call(&mut self, call: CallOf<Self::T>) {
 		self.ctx.deferred.push(DeferredAction::DispatchRuntimeCall {
 			origin: self.ctx.self_account.clone(),
@@ -871,10 +1223,18 @@ where
 		self.ctx.overlay.get_balance(&self.ctx.self_account)
 	}
 
+	fn asset_balance(&self, asset_id: &AssetIdOf<T>) -> BalanceOf<T> {
+		self.ctx.overlay.get_asset_balance(&self.ctx.self_account, asset_id)
+	}
+
 	fn value_transferred(&self) -> BalanceOf<T> {
 		self.value_transferred
 	}
 
+	fn value_transferred_asset(&self) -> Option<AssetIdOf<T>> {
+		self.ctx.value_asset
+	}
+
 	fn random(&self, subject: &[u8]) -> SeedOf<T> {
 		T::Randomness::random(subject)
 	}
@@ -946,6 +1306,8 @@ mod tests {
 	const BOB: u64 = 2;
 	const CHARLIE: u64 = 3;
 
+	const ASSET_ID: u32 = 16_000;
+
 	impl<'a, T, V, L> ExecutionContext<'a, T, V, L>
 		where T: crate::Trait
 	{
@@ -978,6 +1340,7 @@ mod tests {
 
 	struct MockLoader<'a> {
 		map: HashMap<CodeHash<Test>, MockExecutable<'a>>,
+		migrations: HashMap<CodeHash<Test>, MockExecutable<'a>>,
 		counter: u64,
 	}
 
@@ -985,6 +1348,7 @@ mod tests {
 		fn empty() -> Self {
 			MockLoader {
 				map: HashMap::new(),
+				migrations: HashMap::new(),
 				counter: 0,
 			}
 		}
@@ -997,6 +1361,10 @@ mod tests {
 			self.map.insert(code_hash, MockExecutable::new(f));
 			code_hash
 		}
+
+		fn insert_migration(&mut self, code_hash: CodeHash<Test>, f: impl Fn(MockCtx) -> ExecResult + 'a) {
+			self.migrations.insert(code_hash, MockExecutable::new(f));
+		}
 	}
 
 	struct MockVm<'a> {
@@ -1024,6 +1392,12 @@ mod tests {
 				.cloned()
 				.ok_or_else(|| "code not found")
 		}
+		fn load_migrate(&self, code_hash: &CodeHash<Test>) -> Result<Option<Self::Executable>, &'static str> {
+			if !self.map.contains_key(code_hash) {
+				return Err("code not found");
+			}
+			Ok(self.migrations.get(code_hash).cloned())
+		}
 	}
 
 	impl<'a> Vm<Test> for MockVm<'a> {
@@ -1211,6 +1585,121 @@ mod tests {
 		});
 	}
 
+	#[test]
+	fn transfer_with_asset_works() {
+		// This test verifies that a call can endow value in an asset other than `Currency`,
+		// which is visible to the callee.
+		let origin = ALICE;
+		let dest = BOB;
+
+		let vm = MockVm::new();
+		let mut loader = MockLoader::empty();
+		let asset_ch = loader.insert(|ctx| {
+			assert_eq!(ctx.ext.value_transferred(), 55);
+			assert_eq!(ctx.ext.value_transferred_asset(), Some(ASSET_ID));
+			assert_eq!(ctx.ext.asset_balance(&ASSET_ID), 55);
+			exec_success()
+		});
+
+		ExtBuilder::default().build().execute_with(|| {
+			let cfg = Config::preload();
+			let mut ctx = ExecutionContext::top_level(origin, &cfg, &vm, &loader, None);
+			ctx.overlay.instantiate_contract(&dest, asset_ch).unwrap();
+			ctx.overlay.set_balance(&origin, 100);
+			ctx.overlay.set_asset_balance(&origin, ASSET_ID, 100);
+
+			let output = ctx.call_with_asset(
+				dest,
+				ASSET_ID,
+				55,
+				&mut GasMeter::<Test>::with_limit(1000, 1),
+				vec![],
+			).unwrap();
+
+			assert!(output.is_success());
+			assert_eq!(ctx.overlay.get_asset_balance(&origin, &ASSET_ID), 45);
+			assert_eq!(ctx.overlay.get_asset_balance(&dest, &ASSET_ID), 55);
+			assert_eq!(ctx.overlay.get_balance(&origin), 100);
+			assert_eq!(ctx.overlay.get_balance(&dest), 0);
+		});
+	}
+
+	#[test]
+	fn asset_changes_are_reverted_on_failing_call() {
+		// This test verifies that asset transfers made by a failing call are reverted, along with
+		// the value it was endowed with.
+		let origin = ALICE;
+		let dest = BOB;
+
+		let vm = MockVm::new();
+		let mut loader = MockLoader::empty();
+		let return_ch = loader.insert(|ctx| {
+			ctx.ext.transfer_asset(&ASSET_ID, &CHARLIE, 20, ctx.gas_meter).unwrap();
+			Ok(ExecReturnValue { status: 1, data: Vec::new() })
+		});
+
+		ExtBuilder::default().build().execute_with(|| {
+			let cfg = Config::preload();
+			let mut ctx = ExecutionContext::top_level(origin, &cfg, &vm, &loader, None);
+			ctx.overlay.instantiate_contract(&dest, return_ch).unwrap();
+			ctx.overlay.set_asset_balance(&origin, ASSET_ID, 100);
+
+			let output = ctx.call_with_asset(
+				dest,
+				ASSET_ID,
+				55,
+				&mut GasMeter::<Test>::with_limit(1000, 1),
+				vec![],
+			).unwrap();
+
+			assert!(!output.is_success());
+			assert_eq!(ctx.overlay.get_asset_balance(&origin, &ASSET_ID), 100);
+			assert_eq!(ctx.overlay.get_asset_balance(&dest, &ASSET_ID), 0);
+			assert_eq!(ctx.overlay.get_asset_balance(&CHARLIE, &ASSET_ID), 0);
+		});
+	}
+
+	#[test]
+	fn terminate_sweeps_asset_balances() {
+		// This test verifies that a terminated contract sends its balances of `MultiCurrency`
+		// assets to the beneficiary along with its `Currency` balance.
+		let origin = ALICE;
+		let dest = BOB;
+
+		let vm = MockVm::new();
+		let mut loader = MockLoader::empty();
+		let terminate_ch = loader.insert(|ctx| {
+			ctx.ext.terminate(&CHARLIE, ctx.gas_meter).unwrap();
+			exec_success()
+		});
+
+		ExtBuilder::default().build().execute_with(|| {
+			let cfg = Config::preload();
+			let mut ctx = ExecutionContext::top_level(origin, &cfg, &vm, &loader, None);
+			ctx.overlay.instantiate_contract(&dest, terminate_ch).unwrap();
+			ctx.overlay.set_balance(&dest, 100);
+			ctx.overlay.set_asset_balance(&origin, ASSET_ID, 100);
+
+			let output = ctx.call_with_asset(
+				dest,
+				ASSET_ID,
+				55,
+				&mut GasMeter::<Test>::with_limit(1000, 1),
+				vec![],
+			).unwrap();
+
+			assert!(output.is_success());
+			assert_eq!(ctx.overlay.get_balance(&dest), 0);
+			assert_eq!(ctx.overlay.get_balance(&CHARLIE), 100);
+			assert_eq!(ctx.overlay.get_asset_balance(&dest, &ASSET_ID), 0);
+			assert_eq!(ctx.overlay.get_asset_balance(&CHARLIE, &ASSET_ID), 55);
+			assert!(ctx.events().contains(&DeferredAction::DepositEvent {
+				event: RawEvent::AssetTransfer(dest, CHARLIE, ASSET_ID, 55),
+				topics: Vec::new(),
+			}));
+		});
+	}
+
 	#[test]
 	fn transfer_fees() {
 		let origin = ALICE;
@@ -1848,4 +2337,130 @@ mod tests {
 			assert_matches!(result, Ok(_));
 		});
 	}
+
+	#[test]
+	fn set_code_hash_runs_migration_and_keeps_storage() {
+		let vm = MockVm::new();
+		let migrated_with = Rc::new(RefCell::new(None));
+
+		let mut loader = MockLoader::empty();
+		let new_ch = loader.insert(|ctx| {
+			assert_eq!(ctx.ext.get_storage(&[1; 32]), Some(vec![42]));
+			exec_success()
+		});
+		loader.insert_migration(new_ch, |ctx| {
+			*migrated_with.borrow_mut() = Some(ctx.input_data.clone());
+			assert_eq!(ctx.ext.get_storage(&[1; 32]), Some(vec![42]));
+			exec_success()
+		});
+		let old_ch = loader.insert(move |ctx| {
+			ctx.ext.set_storage([1; 32], Some(vec![42])).unwrap();
+			assert_matches!(
+				ctx.ext.set_code_hash(&new_ch, ctx.gas_meter, vec![1, 2, 3]),
+				Ok(ref output) if output.is_success()
+			);
+			exec_success()
+		});
+
+		ExtBuilder::default().build().execute_with(|| {
+			let cfg = Config::preload();
+			let mut ctx = ExecutionContext::top_level(ALICE, &cfg, &vm, &loader, None);
+			ctx.overlay.instantiate_contract(&BOB, old_ch).unwrap();
+
+			assert_matches!(
+				ctx.call(BOB, 0, &mut GasMeter::<Test>::with_limit(10000, 1), vec![]),
+				Ok(ref output) if output.is_success()
+			);
+			assert_eq!(ctx.overlay.get_code_hash(&BOB), Some(new_ch));
+			assert_eq!(&ctx.events(), &[
+				DeferredAction::DepositEvent {
+					event: RawEvent::CodeUpdated(BOB, old_ch, new_ch),
+					topics: Vec::new(),
+				},
+			]);
+
+			// The new code sees the storage written under the old code.
+			assert_matches!(
+				ctx.call(BOB, 0, &mut GasMeter::<Test>::with_limit(10000, 1), vec![]),
+				Ok(ref output) if output.is_success()
+			);
+		});
+
+		assert_eq!(*migrated_with.borrow(), Some(vec![1, 2, 3]));
+	}
+
+	#[test]
+	fn set_code_hash_is_reverted_on_failing_migration() {
+		let vm = MockVm::new();
+
+		let mut loader = MockLoader::empty();
+		let new_ch = loader.insert(|_| exec_success());
+		loader.insert_migration(new_ch, |_| {
+			Ok(ExecReturnValue { status: 1, data: Vec::new() })
+		});
+		let old_ch = loader.insert(move |ctx| {
+			assert_matches!(
+				ctx.ext.set_code_hash(&new_ch, ctx.gas_meter, vec![]),
+				Ok(ref output) if !output.is_success()
+			);
+			exec_success()
+		});
+
+		ExtBuilder::default().build().execute_with(|| {
+			let cfg = Config::preload();
+			let mut ctx = ExecutionContext::top_level(ALICE, &cfg, &vm, &loader, None);
+			ctx.overlay.instantiate_contract(&BOB, old_ch).unwrap();
+
+			assert_matches!(
+				ctx.call(BOB, 0, &mut GasMeter::<Test>::with_limit(10000, 1), vec![]),
+				Ok(ref output) if output.is_success()
+			);
+			assert_eq!(ctx.overlay.get_code_hash(&BOB), Some(old_ch));
+			assert_eq!(&ctx.events(), &[]);
+		});
+	}
+
+	#[test]
+	fn set_code_hash_fails_for_unknown_code() {
+		let vm = MockVm::new();
+		let unknown_ch = <Test as frame_system::Trait>::Hash::repeat_byte(0xff);
+
+		let mut loader = MockLoader::empty();
+		let old_ch = loader.insert(move |ctx| {
+			assert_matches!(
+				ctx.ext.set_code_hash(&unknown_ch, ctx.gas_meter, vec![]),
+				Err(ExecError { reason: DispatchError::Other("code not found"), .. })
+			);
+			exec_success()
+		});
+
+		ExtBuilder::default().build().execute_with(|| {
+			let cfg = Config::preload();
+			let mut ctx = ExecutionContext::top_level(ALICE, &cfg, &vm, &loader, None);
+			ctx.overlay.instantiate_contract(&BOB, old_ch).unwrap();
+
+			assert_matches!(
+				ctx.call(BOB, 0, &mut GasMeter::<Test>::with_limit(10000, 1), vec![]),
+				Ok(_)
+			);
+			assert_eq!(ctx.overlay.get_code_hash(&BOB), Some(old_ch));
+		});
+	}
+
+	#[test]
+	fn set_code_requires_alive_contract() {
+		let vm = MockVm::new();
+		let mut loader = MockLoader::empty();
+		let code_ch = loader.insert(|_| exec_success());
+
+		ExtBuilder::default().build().execute_with(|| {
+			let cfg = Config::preload();
+			let mut ctx = ExecutionContext::top_level(ALICE, &cfg, &vm, &loader, None);
+
+			assert_matches!(
+				ctx.set_code(BOB, &code_ch, &mut GasMeter::<Test>::with_limit(10000, 1), vec![]),
+				Err(ExecError { reason: DispatchError::Other("contract is not alive"), .. })
+			);
+		});
+	}
 }
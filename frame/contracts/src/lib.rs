@@ -63,6 +63,10 @@
 //! This instantiates a new smart contract account and calls its contract deploy handler to
 //! initialize the contract.
 //! * `call` - Makes a call to an account, optionally transferring some balance.
+//! * `call_with_asset` - Makes a call to an account, optionally transferring some balance of a
+//! `MultiCurrency` asset.
+//! * `set_code` - Switches a contract to another stored `code_hash` while keeping its storage. Requires root.
+//! Contracts can switch their own code with `ext_set_code_hash`.
 //!
 //! ### Signed Extensions
 //!
@@ -92,6 +96,7 @@
 mod gas;
 
 mod account_db;
+pub mod chain_extension;
 mod exec;
 mod wasm;
 mod rent;
@@ -108,6 +113,7 @@ use crate::wasm::{WasmLoader, WasmVm};
 
 pub use crate::gas::{Gas, GasMeter, GasHandler};
 pub use crate::exec::{ExecResult, ExecReturnValue, ExecError, StatusCode};
+pub use crate::chain_extension::{ChainExtension, Environment};
 
 #[cfg(feature = "std")]
 use serde::{Serialize, Deserialize};
@@ -129,9 +135,10 @@ use frame_support::{
 	weights::DispatchInfo,
 };
 use frame_support::traits::{OnUnbalanced, Currency, Get, Time, Randomness};
+use frame_support::additional_traits::{DoughnutAuthorisations, MultiCurrencyAccounting};
 use frame_system::{self as system, ensure_signed, RawOrigin, ensure_root, ensure_verified_contract_call};
 use sp_core::storage::well_known_keys::CHILD_STORAGE_KEY_PREFIX;
-use pallet_contracts_primitives::{RentProjection, ContractAccessError};
+use pallet_contracts_primitives::{RentProjection, ContractAccessError, Code};
 
 pub type CodeHash<T> = <T as frame_system::Trait>::Hash;
 pub type TrieId = Vec<u8>;
@@ -348,6 +355,20 @@ parameter_types! {
 
 pub trait Trait: frame_system::Trait {
 	type Currency: Currency<Self::AccountId>;
+
+	/// Assets which contracts can hold and transfer in addition to `Currency`.
+	///
+	/// `Currency` must not be one of these assets, as the balances of the two are tracked
+	/// separately during contract execution.
+	type MultiCurrency: MultiCurrencyAccounting<
+		AccountId = Self::AccountId,
+		Balance = BalanceOf<Self>,
+		CurrencyId = Self::AssetId,
+	>;
+
+	/// The ID type of a `MultiCurrency` asset.
+	type AssetId: Parameter + Member + Ord + Copy + Default;
+
 	type Time: Time;
 	type Randomness: Randomness<Self::Hash>;
 
@@ -433,6 +454,9 @@ pub trait Trait: frame_system::Trait {
 
 	/// The maximum amount of gas that could be expended per block.
 	type BlockGasLimit: Get<Gas>;
+
+	/// Runtime functions which contracts can call with `ext_chain_extension`.
+	type ChainExtension: ChainExtension<Self>;
 }
 
 /// Simple contract address determiner.
@@ -620,6 +644,29 @@ decl_module! {
 				.map_err(|e| e.reason.into())
 		}
 
+		/// Makes a call to an account, optionally transferring some balance of the `asset_id`
+		/// asset of `MultiCurrency`.
+		///
+		/// Behaves as `call`, the called contract can query the asset with
+		/// `ext_value_transferred_asset`.
+		pub fn call_with_asset(
+			origin,
+			dest: <T::Lookup as StaticLookup>::Source,
+			asset_id: T::AssetId,
+			#[compact] value: BalanceOf<T>,
+			#[compact] gas_limit: Gas,
+			data: Vec<u8>
+		) -> DispatchResult {
+			let dest = T::Lookup::lookup(dest)?;
+			let (origin, doughnut) = ensure_verified_contract_call::<T>(origin, &dest)?;
+
+			Self::execute_wasm(origin, gas_limit, doughnut, |ctx, gas_meter| {
+				ctx.call_with_asset(dest, asset_id, value, gas_meter, data)
+			}).0
+				.map(|_| ())
+				.map_err(|e| e.reason.into())
+		}
+
 		/// Instantiates a new contract from the `codehash` generated by `put_code`, optionally transferring some balance.
 		///
 		/// Instantiation is executed as follows:
@@ -642,7 +689,35 @@ decl_module! {
 			Self::execute_wasm(origin, gas_limit, None, |ctx, gas_meter| {
 				ctx.instantiate(endowment, gas_meter, &code_hash, data)
 					.map(|(_address, output)| output)
-			})
+			}).0
+			.map(|_| ())
+			.map_err(|e| e.reason.into())
+		}
+
+		/// Switches the contract at `dest` to the code with the given `code_hash`, keeping its
+		/// storage and balance.
+		///
+		/// If the new code exports a `migrate` function, it is executed once with `data` in the
+		/// context of the contract, called by `payer`. The gas for the migration is bought from
+		/// the balance of `payer`, not the one of the contract. If the migration fails, the
+		/// contract keeps its current code.
+		///
+		/// The dispatch origin for this call must be _Root_.
+		pub fn set_code(
+			origin,
+			dest: <T::Lookup as StaticLookup>::Source,
+			code_hash: CodeHash<T>,
+			payer: <T::Lookup as StaticLookup>::Source,
+			#[compact] gas_limit: Gas,
+			data: Vec<u8>
+		) -> DispatchResult {
+			ensure_root(origin)?;
+			let dest = T::Lookup::lookup(dest)?;
+			let payer = T::Lookup::lookup(payer)?;
+
+			Self::execute_wasm(payer, gas_limit, None, |ctx, gas_meter| {
+				ctx.set_code(dest, &code_hash, gas_meter, data)
+			}).0
 			.map(|_| ())
 			.map_err(|e| e.reason.into())
 		}
@@ -701,7 +776,7 @@ impl<T: Trait> Module<T> {
 	) -> ExecResult {
 		Self::execute_wasm(origin, gas_limit, doughnut, |ctx, gas_meter| {
 			ctx.call(dest, value, gas_meter, input_data)
-		})
+		}).0
 	}
 
 	/// Query storage of a specified contract under a specified key.
@@ -728,6 +803,56 @@ impl<T: Trait> Module<T> {
 	) -> sp_std::result::Result<RentProjection<T::BlockNumber>, ContractAccessError> {
 		rent::compute_rent_projection::<T>(&address)
 	}
+
+	/// Instantiate a contract from either a wasm blob or the hash of already stored code.
+	///
+	/// This function is meant to be used as a dry run and doesn't perform any address lookups.
+	/// If `code` is a blob, it is stored first and the gas for doing so is included in the
+	/// returned gas. Returns the address the contract is (or would be) instantiated at, the
+	/// result of the execution, the gas consumed and the events deposited during the execution.
+	pub fn bare_instantiate(
+		origin: T::AccountId,
+		endowment: BalanceOf<T>,
+		gas_limit: Gas,
+		code: Code<CodeHash<T>>,
+		data: Vec<u8>,
+	) -> (T::AccountId, ExecResult, Gas, Vec<<T as frame_system::Trait>::Event>) {
+		let code_hash = match code {
+			Code::Upload(ref binary) => T::Hashing::hash(binary),
+			Code::Existing(hash) => hash,
+		};
+		let address = T::DetermineContractAddress::contract_address_for(&code_hash, &data, &origin);
+		let event_count = <frame_system::Module<T>>::events().len();
+
+		let (result, gas_consumed) = Self::execute_wasm(origin, gas_limit, None, |ctx, gas_meter| {
+			if let Code::Upload(binary) = code {
+				let schedule = <Module<T>>::current_schedule();
+				wasm::save_code::<T>(binary, gas_meter, &schedule)
+					.map_err(|reason| ExecError { reason: reason.into(), buffer: Vec::new() })?;
+			}
+			ctx.instantiate(endowment, gas_meter, &code_hash, data)
+				.map(|(_address, output)| output)
+		});
+
+		let events = <frame_system::Module<T>>::events()
+			.into_iter()
+			.skip(event_count)
+			.map(|record| record.event)
+			.collect();
+
+		(address, result, gas_consumed, events)
+	}
+
+	/// Instrument the given wasm blob and store it, as `put_code` would.
+	///
+	/// This function is meant to be used as a dry run: no gas is bought. Returns the hash of the
+	/// code and the gas storing it costs, or the reason the code was rejected.
+	pub fn bare_upload_code(code: Vec<u8>) -> sp_std::result::Result<(CodeHash<T>, Gas), &'static str> {
+		let mut gas_meter = GasMeter::<T>::with_limit(Gas::max_value(), Zero::zero());
+		let schedule = <Module<T>>::current_schedule();
+		let code_hash = wasm::save_code::<T>(code, &mut gas_meter, &schedule)?;
+		Ok((code_hash, gas_meter.spent()))
+	}
 }
 
 impl<T: Trait> Module<T> {
@@ -736,14 +861,14 @@ impl<T: Trait> Module<T> {
 		gas_limit: Gas,
 		doughnut: Option<T::Doughnut>,
 		func: impl FnOnce(&mut ExecutionContext<T, WasmVm, WasmLoader>, &mut GasMeter<T>) -> ExecResult
-	) -> ExecResult {
+	) -> (ExecResult, Gas) {
 
 		// Fill up the gas meter upfront. Default behaviour is to pay for the gas upfront.
-		let mut gas_meter = try_or_exec_error!(
-			T::GasHandler::fill_gas(&origin, gas_limit),
+		let mut gas_meter = match T::GasHandler::fill_gas(&origin, gas_limit) {
+			Ok(gas_meter) => gas_meter,
 			// We don't have a spare buffer here in the first place, so create a new empty one.
-			Vec::new()
-		 );
+			Err(reason) => return (Err(ExecError { reason: reason.into(), buffer: Vec::new() }), 0),
+		};
 
 		let cfg = Config::preload();
 		let vm = WasmVm::new(&cfg.schedule);
@@ -760,7 +885,8 @@ impl<T: Trait> Module<T> {
 		// Increase total spent gas.
 		// This cannot overflow, since `gas_spent` is never greater than `block_gas_limit`, which
 		// also has Gas type.
-		GasSpent::mutate(|block_gas_spent| *block_gas_spent += gas_meter.spent());
+		let gas_spent = gas_meter.spent();
+		GasSpent::mutate(|block_gas_spent| *block_gas_spent += gas_spent);
 
 		// Handle unused gas of the gas meter. Default behaviour is to refund cost of the unused gas.
 		//
@@ -786,6 +912,14 @@ impl<T: Trait> Module<T> {
 					let result = call.dispatch(RawOrigin::Delegated(origin.clone(), doughnut.clone()).into());
 					Self::deposit_event(RawEvent::DelegatedDispatched(origin.clone(), doughnut, result.is_ok()));
 				},
+				ForwardedRuntimeCall {
+					issuer,
+					doughnut,
+					call,
+				} => {
+					let result = call.dispatch(RawOrigin::Delegated(issuer.clone(), doughnut.clone()).into());
+					Self::deposit_event(RawEvent::DelegatedDispatched(issuer, doughnut, result.is_ok()));
+				},
 				DispatchRuntimeCall {
 					origin: who,
 					call,
@@ -793,6 +927,23 @@ impl<T: Trait> Module<T> {
 					let result = call.dispatch(RawOrigin::Signed(who.clone()).into());
 					Self::deposit_event(RawEvent::Dispatched(who, result.is_ok()));
 				}
+				AuthoriseDoughnut {
+					issuer,
+					doughnut_hash,
+					authorised,
+				} => {
+					if authorised {
+						// The authorisations of a contract terminated by this call are gone.
+						if !<ContractInfoOf<T>>::get(&issuer).map_or(false, |c| c.get_alive().is_some()) {
+							return;
+						}
+						IssuedDoughnuts::<T>::insert(&issuer, &doughnut_hash, true);
+						Self::deposit_event(RawEvent::DoughnutIssued(issuer, doughnut_hash));
+					} else {
+						IssuedDoughnuts::<T>::remove(&issuer, &doughnut_hash);
+						Self::deposit_event(RawEvent::DoughnutRevoked(issuer, doughnut_hash));
+					}
+				}
 				RestoreTo {
 					donor,
 					dest,
@@ -810,7 +961,7 @@ impl<T: Trait> Module<T> {
 			}
 		});
 
-		result
+		(result, gas_spent)
 	}
 
 	fn restore_to(
@@ -885,6 +1036,7 @@ impl<T: Trait> Module<T> {
 			.sum::<u32>();
 
 		<ContractInfoOf<T>>::remove(&origin);
+		IssuedDoughnuts::<T>::remove_prefix(&origin);
 		<ContractInfoOf<T>>::insert(&dest, ContractInfo::Alive(RawAliveContractInfo {
 			trie_id: origin_contract.trie_id,
 			storage_size: origin_contract.storage_size,
@@ -906,6 +1058,7 @@ decl_event! {
 	pub enum Event<T>
 	where
 		Balance = BalanceOf<T>,
+		AssetId = <T as Trait>::AssetId,
 		<T as frame_system::Trait>::AccountId,
 		<T as frame_system::Trait>::Hash,
 		<T as frame_system::Trait>::Doughnut,
@@ -913,6 +1066,10 @@ decl_event! {
 		/// Transfer happened `from` to `to` with given `value` as part of a `call` or `instantiate`.
 		Transfer(AccountId, AccountId, Balance),
 
+		/// Transfer of the `asset_id` asset happened `from` to `to` with given `value` as part of
+		/// a `call_with_asset` or an `ext_transfer_asset`.
+		AssetTransfer(AccountId, AccountId, AssetId, Balance),
+
 		/// Contract deployed by address at the specified address.
 		Instantiated(AccountId, AccountId),
 
@@ -951,6 +1108,21 @@ decl_event! {
 
 		/// An event deposited upon execution of a contract from the account.
 		ContractExecution(AccountId, Vec<u8>),
+
+		/// A contract authorised the doughnut with the given hash, which it is the issuer of.
+		DoughnutIssued(AccountId, Hash),
+
+		/// A contract revoked its authorisation of the doughnut with the given hash.
+		DoughnutRevoked(AccountId, Hash),
+
+		/// A contract has been switched to new code and kept its storage.
+		///
+		/// # Params
+		///
+		/// - `contract`: `AccountId`: Account ID of the contract
+		/// - `old_code_hash`: `Hash`: Code hash of the replaced code
+		/// - `new_code_hash`: `Hash`: Code hash of the new code
+		CodeUpdated(AccountId, Hash, Hash),
 	}
 }
 
@@ -972,6 +1144,19 @@ decl_storage! {
 		pub ContractInfoOf: map hasher(twox_64_concat) T::AccountId => Option<ContractInfo<T>>;
 		/// The price of one unit of gas.
 		GasPrice get(fn gas_price) config(): BalanceOf<T> = 1.into();
+		/// Hashes of the doughnuts authorised by the contract which issued them.
+		///
+		/// TWOX-NOTE: SAFE since `AccountId` is a secure hash.
+		pub IssuedDoughnuts: double_map hasher(twox_64_concat) T::AccountId, hasher(identity) T::Hash => bool;
+	}
+}
+
+/// A contract can't sign the doughnuts it issues, it authorises them on-chain through
+/// `ext_issue_doughnut` instead. The authorisations are removed when the contract is
+/// terminated or evicted.
+impl<T: Trait> DoughnutAuthorisations<T::AccountId> for Module<T> {
+	fn is_authorised<Doughnut: Encode>(issuer: &T::AccountId, doughnut: &Doughnut) -> bool {
+		IssuedDoughnuts::<T>::get(issuer, T::Hashing::hash_of(doughnut))
 	}
 }
 
@@ -1044,6 +1229,15 @@ pub struct Schedule {
 	/// Cost for a simple balance transfer.
 	pub transfer_cost: Gas,
 
+	/// Base gas cost to verify a doughnut.
+	pub verify_doughnut_base_cost: Gas,
+
+	/// Base gas cost to authorise a doughnut or to revoke its authorisation.
+	pub authorise_doughnut_base_cost: Gas,
+
+	/// Gas cost per one byte of a doughnut verified, authorised or revoked.
+	pub doughnut_per_byte_cost: Gas,
+
 	/// The maximum number of topics supported by an event.
 	pub max_event_topics: u32,
 
@@ -1083,6 +1277,9 @@ impl Default for Schedule {
 			sandbox_data_read_cost: 1,
 			sandbox_data_write_cost: 1,
 			transfer_cost: 100,
+			verify_doughnut_base_cost: 500,
+			authorise_doughnut_base_cost: 100,
+			doughnut_per_byte_cost: 1,
 			max_event_topics: 4,
 			max_stack_height: 64 * 1024,
 			max_memory_pages: 16,
@@ -1142,6 +1339,7 @@ impl<T: Trait + Send + Sync> SignedExtension for CheckBlockGasLimit<T> {
 				Ok(ValidTransaction::default()),
 			Call::put_code(gas_limit, _)
 				| Call::call(_, _, gas_limit, _)
+				| Call::call_with_asset(_, _, _, gas_limit, _)
 				| Call::instantiate(_, gas_limit, _, _)
 			=> {
 				// Check if the specified amount of gas is available in the current block.
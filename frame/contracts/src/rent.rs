@@ -17,12 +17,12 @@
 //! A module responsible for computing the right amount of weight and charging it.
 
 use crate::{
-	AliveContractInfo, BalanceOf, ContractInfo, ContractInfoOf, Module, RawEvent,
+	AliveContractInfo, BalanceOf, ContractInfo, ContractInfoOf, IssuedDoughnuts, Module, RawEvent,
 	TombstoneContractInfo, Trait,
 };
 use frame_support::storage::child;
 use frame_support::traits::{Currency, ExistenceRequirement, Get, OnUnbalanced, WithdrawReason};
-use frame_support::StorageMap;
+use frame_support::{StorageDoubleMap, StorageMap};
 use pallet_contracts_primitives::{ContractAccessError, RentProjection, RentProjectionResult};
 use sp_runtime::traits::{Bounded, CheckedDiv, CheckedMul, SaturatedConversion, Saturating, Zero};
 
@@ -222,6 +222,7 @@ fn enact_verdict<T: Trait>(
 		Verdict::Exempt => return Some(ContractInfo::Alive(alive_contract_info)),
 		Verdict::Kill => {
 			<ContractInfoOf<T>>::remove(account);
+			<IssuedDoughnuts<T>>::remove_prefix(account);
 			child::kill_storage(
 				&alive_contract_info.trie_id,
 				alive_contract_info.child_trie_unique_id(),
@@ -243,6 +244,7 @@ fn enact_verdict<T: Trait>(
 			);
 			let tombstone_info = ContractInfo::Tombstone(tombstone);
 			<ContractInfoOf<T>>::insert(account, &tombstone_info);
+			<IssuedDoughnuts<T>>::remove_prefix(account);
 
 			child::kill_storage(
 				&alive_contract_info.trie_id,
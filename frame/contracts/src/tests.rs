@@ -22,8 +22,10 @@
 use crate::{
 	BalanceOf, ComputeDispatchFee, ContractAddressFor, ContractInfo, ContractInfoOf, GenesisConfig,
 	Module, RawAliveContractInfo, RawEvent, Trait, TrieId, TrieIdFromParentCounter, Schedule,
-	TrieIdGenerator, CheckBlockGasLimit, account_db::{AccountDb, DirectAccountDb, OverlayAccountDb},
+	TrieIdGenerator, CheckBlockGasLimit, PristineCode, exec::STATUS_SUCCESS,
+	account_db::{AccountDb, DirectAccountDb, OverlayAccountDb},
 };
+use pallet_contracts_primitives::Code;
 use assert_matches::assert_matches;
 use hex_literal::*;
 use codec::{Decode, Encode, KeyedVec};
@@ -50,10 +52,11 @@ mod contract {
 }
 
 use pallet_balances as balances;
+use pallet_generic_asset as generic_asset;
 
 impl_outer_event! {
 	pub enum MetaEvent for Test {
-		system, balances<T>, contract<T>,
+		system, balances<T>, generic_asset<T>, contract<T>,
 	}
 }
 impl_outer_origin! {
@@ -131,6 +134,11 @@ impl pallet_balances::Trait for Test {
 	type ExistentialDeposit = ExistentialDeposit;
 	type CreationFee = CreationFee;
 }
+impl pallet_generic_asset::Trait for Test {
+	type Balance = u64;
+	type AssetId = u32;
+	type Event = MetaEvent;
+}
 parameter_types! {
 	pub const MinimumPeriod: u64 = 1;
 }
@@ -156,6 +164,8 @@ parameter_types! {
 }
 impl Trait for Test {
 	type Currency = Balances;
+	type MultiCurrency = GenericAsset;
+	type AssetId = u32;
 	type Time = Timestamp;
 	type Randomness = Randomness;
 	type Call = Call;
@@ -181,9 +191,11 @@ impl Trait for Test {
 	type MaxDepth = MaxDepth;
 	type MaxValueSize = MaxValueSize;
 	type BlockGasLimit = BlockGasLimit;
+	type ChainExtension = ();
 }
 
 type Balances = pallet_balances::Module<Test>;
+type GenericAsset = pallet_generic_asset::Module<Test>;
 type Timestamp = pallet_timestamp::Module<Test>;
 type Contract = Module<Test>;
 type System = frame_system::Module<Test>;
@@ -490,6 +502,72 @@ fn instantiate_and_call_and_deposit_event() {
 	});
 }
 
+#[test]
+fn bare_instantiate_with_code_works() {
+	let (wasm, code_hash) = compile_module::<Test>(CODE_RETURN_FROM_START_FN).unwrap();
+
+	ExtBuilder::default().existential_deposit(100).build().execute_with(|| {
+		Balances::deposit_creating(&ALICE, 1_000_000);
+
+		let (address, result, gas_consumed, events) = Contract::bare_instantiate(
+			ALICE,
+			100,
+			100_000,
+			Code::Upload(wasm),
+			vec![],
+		);
+
+		assert_eq!(address, BOB);
+		assert_eq!(result.unwrap().status, STATUS_SUCCESS);
+		assert!(gas_consumed > 0);
+		assert_eq!(events, vec![
+			MetaEvent::balances(balances::RawEvent::NewAccount(BOB, 100)),
+			MetaEvent::contract(RawEvent::Transfer(ALICE, BOB, 100)),
+			MetaEvent::contract(RawEvent::ContractExecution(BOB, vec![1, 2, 3, 4])),
+			MetaEvent::contract(RawEvent::Instantiated(ALICE, BOB)),
+		]);
+		assert!(PristineCode::<Test>::contains_key(&code_hash));
+	});
+}
+
+#[test]
+fn bare_instantiate_with_missing_code_fails() {
+	let (_wasm, code_hash) = compile_module::<Test>(CODE_RETURN_FROM_START_FN).unwrap();
+
+	ExtBuilder::default().existential_deposit(100).build().execute_with(|| {
+		Balances::deposit_creating(&ALICE, 1_000_000);
+
+		let (address, result, _gas_consumed, events) = Contract::bare_instantiate(
+			ALICE,
+			100,
+			100_000,
+			Code::Existing(code_hash),
+			vec![],
+		);
+
+		assert_eq!(address, BOB);
+		assert!(result.is_err());
+		assert!(events.is_empty());
+		assert!(!ContractInfoOf::<Test>::contains_key(BOB));
+	});
+}
+
+#[test]
+fn bare_upload_code_reports_instrumentation_errors() {
+	let (wasm, code_hash) = compile_module::<Test>(CODE_RETURN_FROM_START_FN).unwrap();
+
+	ExtBuilder::default().build().execute_with(|| {
+		let (hash, gas_consumed) = Contract::bare_upload_code(wasm).unwrap();
+		assert_eq!(hash, code_hash);
+		assert!(gas_consumed > 0);
+
+		assert_eq!(
+			Contract::bare_upload_code(vec![0, 1, 2, 3]),
+			Err("Can't decode wasm code"),
+		);
+	});
+}
+
 const CODE_DISPATCH_CALL: &str = r#"
 (module
 	(import "env" "ext_dispatch_call" (func $ext_dispatch_call (param i32 i32)))
@@ -2829,3 +2907,136 @@ fn crypto_hashes() {
 		}
 	})
 }
+
+const CODE_UPGRADABLE_V1: &str = r#"
+(module
+	(import "env" "ext_set_storage" (func $ext_set_storage (param i32 i32 i32)))
+	(import "env" "memory" (memory 1 1))
+
+	(func (export "deploy")
+		(call $ext_set_storage
+			(i32.const 0)	;; Pointer to the storage key
+			(i32.const 32)	;; Pointer to the value
+			(i32.const 4)	;; Length of the value
+		)
+	)
+
+	(func (export "call"))
+
+	;; Storage key
+	(data (i32.const 0)
+		"\01\01\01\01\01\01\01\01\01\01\01\01\01\01\01\01"
+		"\01\01\01\01\01\01\01\01\01\01\01\01\01\01\01\01"
+	)
+	;; Value
+	(data (i32.const 32) "\2a\00\00\00")
+)
+"#;
+
+const CODE_UPGRADABLE_V2: &str = r#"
+(module
+	(import "env" "ext_get_storage" (func $ext_get_storage (param i32) (result i32)))
+	(import "env" "memory" (memory 1 1))
+
+	(func $assert (param i32)
+		(block $ok
+			(br_if $ok
+				(get_local 0)
+			)
+			(unreachable)
+		)
+	)
+
+	(func (export "deploy"))
+
+	(func (export "call"))
+
+	;; Only succeeds if the storage written by the previous code is still there.
+	(func (export "migrate")
+		(call $assert
+			(i32.eqz
+				(call $ext_get_storage
+					(i32.const 0)	;; Pointer to the storage key
+				)
+			)
+		)
+	)
+
+	;; Storage key
+	(data (i32.const 0)
+		"\01\01\01\01\01\01\01\01\01\01\01\01\01\01\01\01"
+		"\01\01\01\01\01\01\01\01\01\01\01\01\01\01\01\01"
+	)
+)
+"#;
+
+#[test]
+fn set_code_keeps_storage_and_runs_migration() {
+	let (v1_wasm, v1_code_hash) = compile_module::<Test>(CODE_UPGRADABLE_V1).unwrap();
+	let (v2_wasm, v2_code_hash) = compile_module::<Test>(CODE_UPGRADABLE_V2).unwrap();
+
+	ExtBuilder::default().existential_deposit(50).build().execute_with(|| {
+		Balances::deposit_creating(&ALICE, 1_000_000);
+		assert_ok!(Contract::put_code(Origin::signed(ALICE), 100_000, v1_wasm));
+		assert_ok!(Contract::put_code(Origin::signed(ALICE), 100_000, v2_wasm));
+		assert_ok!(Contract::instantiate(
+			Origin::signed(ALICE),
+			100_000,
+			100_000,
+			v1_code_hash.into(),
+			vec![],
+		));
+		let trie_id = ContractInfoOf::<Test>::get(BOB).unwrap().get_alive().unwrap().trie_id;
+
+		// Only root may switch the code of a contract.
+		assert_err!(
+			Contract::set_code(Origin::signed(ALICE), BOB, v2_code_hash.into(), ALICE, 10_000, vec![]),
+			sp_runtime::DispatchError::BadOrigin,
+		);
+
+		// The gas of the migration is paid by the given payer, not the contract.
+		let (alice_balance, bob_balance) = (Balances::free_balance(ALICE), Balances::free_balance(BOB));
+		assert_ok!(Contract::set_code(Origin::ROOT, BOB, v2_code_hash.into(), ALICE, 10_000, vec![]));
+		assert!(Balances::free_balance(ALICE) < alice_balance);
+		assert_eq!(Balances::free_balance(BOB), bob_balance);
+
+		let info = ContractInfoOf::<Test>::get(BOB).unwrap().get_alive().unwrap();
+		assert_eq!(info.code_hash, v2_code_hash);
+		assert_eq!(info.trie_id, trie_id);
+		assert!(System::events().iter().any(|record| record.event ==
+			MetaEvent::contract(RawEvent::CodeUpdated(BOB, v1_code_hash, v2_code_hash))
+		));
+		assert_ok!(Contract::call(Origin::signed(ALICE), BOB, 0, 100_000, vec![]));
+	});
+}
+
+#[test]
+fn set_code_is_reverted_on_failing_migration() {
+	let (v2_wasm, v2_code_hash) = compile_module::<Test>(CODE_UPGRADABLE_V2).unwrap();
+	let (wasm, code_hash) = compile_module::<Test>(CODE_RETURN_FROM_START_FN).unwrap();
+
+	ExtBuilder::default().existential_deposit(50).build().execute_with(|| {
+		Balances::deposit_creating(&ALICE, 1_000_000);
+		assert_ok!(Contract::put_code(Origin::signed(ALICE), 100_000, wasm));
+		assert_ok!(Contract::put_code(Origin::signed(ALICE), 100_000, v2_wasm));
+		assert_ok!(Contract::instantiate(
+			Origin::signed(ALICE),
+			100_000,
+			100_000,
+			code_hash.into(),
+			vec![],
+		));
+
+		// The migration traps since the storage it expects was never written.
+		assert!(
+			Contract::set_code(Origin::ROOT, BOB, v2_code_hash.into(), ALICE, 10_000, vec![]).is_err()
+		);
+
+		let info = ContractInfoOf::<Test>::get(BOB).unwrap().get_alive().unwrap();
+		assert_eq!(info.code_hash, code_hash);
+		assert!(!System::events().iter().any(|record| match record.event {
+			MetaEvent::contract(RawEvent::CodeUpdated(..)) => true,
+			_ => false,
+		}));
+	});
+}
@@ -90,6 +90,16 @@ impl<'a, T: Trait> crate::exec::Loader<T> for WasmLoader<'a> {
 			prefab_module,
 		})
 	}
+	fn load_migrate(&self, code_hash: &CodeHash<T>) -> Result<Option<WasmExecutable>, &'static str> {
+		let prefab_module = load_code::<T>(code_hash, self.schedule)?;
+		if !prepare::exports_function(&prefab_module.code, "migrate") {
+			return Ok(None);
+		}
+		Ok(Some(WasmExecutable {
+			entrypoint_name: "migrate",
+			prefab_module,
+		}))
+	}
 }
 
 /// Implementation of `Vm` that takes `WasmExecutable` and executes it.
@@ -161,7 +171,7 @@ mod tests {
 	use wabt;
 	use hex_literal::hex;
 	use assert_matches::assert_matches;
-	use sp_runtime::DispatchError;
+	use sp_runtime::{DispatchError, traits::PlugDoughnutApi};
 
 	#[derive(Debug, PartialEq, Eq)]
 	struct DispatchEntry(Call);
@@ -169,6 +179,12 @@ mod tests {
 	#[derive(Debug, PartialEq, Eq)]
 	struct DelegatedDispatchEntry(MockDoughnut, Call);
 
+	#[derive(Debug, PartialEq, Eq)]
+	struct ForwardedDispatchEntry(u64, MockDoughnut, Call);
+
+	#[derive(Debug, PartialEq, Eq)]
+	struct DoughnutAuthorisationEntry(MockDoughnut, bool);
+
 	#[derive(Debug, PartialEq, Eq)]
 	struct RestoreEntry {
 		dest: u64,
@@ -185,6 +201,12 @@ mod tests {
 		gas_left: u64,
 	}
 
+	#[derive(Debug, PartialEq, Eq)]
+	struct SetCodeHashEntry {
+		code_hash: H256,
+		data: Vec<u8>,
+	}
+
 	#[derive(Debug, PartialEq, Eq)]
 	struct TerminationEntry {
 		beneficiary: u64,
@@ -199,13 +221,22 @@ mod tests {
 		gas_left: u64,
 	}
 
+	#[derive(Debug, PartialEq, Eq)]
+	struct AssetTransferEntry {
+		asset_id: u32,
+		to: u64,
+		value: u64,
+	}
+
 	#[derive(Default)]
 	pub struct MockExt {
 		storage: HashMap<StorageKey, Vec<u8>>,
 		rent_allowance: u64,
 		instantiates: Vec<InstantiateEntry>,
+		code_hash_updates: Vec<SetCodeHashEntry>,
 		terminations: Vec<TerminationEntry>,
 		transfers: Vec<TransferEntry>,
+		asset_transfers: Vec<AssetTransferEntry>,
 		dispatches: Vec<DispatchEntry>,
 		restores: Vec<RestoreEntry>,
 		// (topics, data)
@@ -225,6 +256,8 @@ mod tests {
 
 		// Mock fields to test doughnut dispatch.
 		delegated_dispatches: Vec<DelegatedDispatchEntry>,
+		forwarded_dispatches: Vec<ForwardedDispatchEntry>,
+		doughnut_authorisations: Vec<DoughnutAuthorisationEntry>,
 		doughnut: Option<MockDoughnut>,
 	}
 
@@ -286,6 +319,20 @@ mod tests {
 			});
 			Ok(())
 		}
+		fn transfer_asset(
+			&mut self,
+			asset_id: &u32,
+			to: &u64,
+			value: u64,
+			_gas_meter: &mut GasMeter<Test>,
+		) -> Result<(), DispatchError> {
+			self.asset_transfers.push(AssetTransferEntry {
+				asset_id: *asset_id,
+				to: *to,
+				value,
+			});
+			Ok(())
+		}
 		fn call(
 			&mut self,
 			to: &u64,
@@ -303,11 +350,48 @@ mod tests {
 			// TODO: Add tests for different call outcomes.
 			Ok(ExecReturnValue { status: STATUS_SUCCESS, data: Vec::new() })
 		}
+		fn set_code_hash(
+			&mut self,
+			code_hash: &CodeHash<Test>,
+			_gas_meter: &mut GasMeter<Test>,
+			data: Vec<u8>,
+		) -> ExecResult {
+			self.code_hash_updates.push(SetCodeHashEntry {
+				code_hash: code_hash.clone(),
+				data,
+			});
+			Ok(ExecReturnValue { status: STATUS_SUCCESS, data: Vec::new() })
+		}
 
 		fn note_delegated_dispatch_call(&mut self, doughnut: MockDoughnut, call: Call) {
 			self.delegated_dispatches.push(DelegatedDispatchEntry(doughnut, call));
 		}
 
+		fn note_forwarded_dispatch_call(&mut self, issuer: u64, doughnut: MockDoughnut, call: Call) {
+			self.forwarded_dispatches.push(ForwardedDispatchEntry(issuer, doughnut, call));
+		}
+
+		fn note_doughnut_authorisation(
+			&mut self,
+			doughnut: &MockDoughnut,
+			authorised: bool,
+		) -> Result<(), &'static str> {
+			// Only doughnuts issued by the current contract may be authorised.
+			if doughnut.issuer()[0] != 69 {
+				return Err("contract is not the issuer of the doughnut");
+			}
+			self.doughnut_authorisations.push(DoughnutAuthorisationEntry(doughnut.clone(), authorised));
+			Ok(())
+		}
+
+		fn verify_doughnut(&self, doughnut: &MockDoughnut) -> Result<u64, &'static str> {
+			// Verifies doughnuts issued by account 7 only.
+			match doughnut.issuer()[0] {
+				7 => Ok(7),
+				_ => Err("doughnut doesn't verify"),
+			}
+		}
+
 		fn terminate(
 			&mut self,
 			beneficiary: &u64,
@@ -352,9 +436,15 @@ mod tests {
 		fn balance(&self) -> u64 {
 			228
 		}
+		fn asset_balance(&self, _asset_id: &u32) -> u64 {
+			229
+		}
 		fn value_transferred(&self) -> u64 {
 			1337
 		}
+		fn value_transferred_asset(&self) -> Option<u32> {
+			None
+		}
 
 		fn now(&self) -> &u64 {
 			&1111
@@ -430,6 +520,15 @@ mod tests {
 		) -> Result<(), DispatchError> {
 			(**self).transfer(to, value, gas_meter)
 		}
+		fn transfer_asset(
+			&mut self,
+			asset_id: &u32,
+			to: &u64,
+			value: u64,
+			gas_meter: &mut GasMeter<Test>,
+		) -> Result<(), DispatchError> {
+			(**self).transfer_asset(asset_id, to, value, gas_meter)
+		}
 		fn terminate(
 			&mut self,
 			beneficiary: &u64,
@@ -446,9 +545,30 @@ mod tests {
 		) -> ExecResult {
 			(**self).call(to, value, gas_meter, input_data)
 		}
+		fn set_code_hash(
+			&mut self,
+			code_hash: &CodeHash<Test>,
+			gas_meter: &mut GasMeter<Test>,
+			input_data: Vec<u8>,
+		) -> ExecResult {
+			(**self).set_code_hash(code_hash, gas_meter, input_data)
+		}
 		fn note_delegated_dispatch_call(&mut self, doughnut: MockDoughnut, call: Call) {
 			(**self).note_delegated_dispatch_call(doughnut, call)
 		}
+		fn note_forwarded_dispatch_call(&mut self, issuer: u64, doughnut: MockDoughnut, call: Call) {
+			(**self).note_forwarded_dispatch_call(issuer, doughnut, call)
+		}
+		fn note_doughnut_authorisation(
+			&mut self,
+			doughnut: &MockDoughnut,
+			authorised: bool,
+		) -> Result<(), &'static str> {
+			(**self).note_doughnut_authorisation(doughnut, authorised)
+		}
+		fn verify_doughnut(&self, doughnut: &MockDoughnut) -> Result<u64, &'static str> {
+			(**self).verify_doughnut(doughnut)
+		}
 		fn note_dispatch_call(&mut self, call: Call) {
 			(**self).note_dispatch_call(call)
 		}
@@ -481,9 +601,15 @@ mod tests {
 		fn balance(&self) -> u64 {
 			(**self).balance()
 		}
+		fn asset_balance(&self, asset_id: &u32) -> u64 {
+			(**self).asset_balance(asset_id)
+		}
 		fn value_transferred(&self) -> u64 {
 			(**self).value_transferred()
 		}
+		fn value_transferred_asset(&self) -> Option<u32> {
+			(**self).value_transferred_asset()
+		}
 		fn now(&self) -> &u64 {
 			(**self).now()
 		}
@@ -594,6 +720,66 @@ mod tests {
 		);
 	}
 
+	const CODE_TRANSFER_ASSET: &str = r#"
+(module
+	;; ext_transfer_asset(
+	;;    asset_id_ptr: u32,
+	;;    asset_id_len: u32,
+	;;    account_ptr: u32,
+	;;    account_len: u32,
+	;;    value_ptr: u32,
+	;;    value_len: u32,
+	;;) -> u32
+	(import "env" "ext_transfer_asset" (func $ext_transfer_asset (param i32 i32 i32 i32 i32 i32) (result i32)))
+	(import "env" "memory" (memory 1 1))
+	(func (export "call")
+		(drop
+			(call $ext_transfer_asset
+				(i32.const 20) ;; Pointer to the asset ID.
+				(i32.const 4)  ;; Length of the asset ID.
+				(i32.const 4)  ;; Pointer to "account" address.
+				(i32.const 8)  ;; Length of "account" address.
+				(i32.const 12) ;; Pointer to the buffer with value to transfer
+				(i32.const 8)  ;; Length of the buffer with value to transfer.
+			)
+		)
+	)
+	(func (export "deploy"))
+
+	;; Destination AccountId to transfer the funds.
+	;; Represented by u64 (8 bytes long) in little endian.
+	(data (i32.const 4) "\07\00\00\00\00\00\00\00")
+
+	;; Amount of value to transfer.
+	;; Represented by u64 (8 bytes long) in little endian.
+	(data (i32.const 12) "\99\00\00\00\00\00\00\00")
+
+	;; ID of the asset to transfer.
+	;; Represented by u32 (4 bytes long) in little endian.
+	(data (i32.const 20) "\80\3e\00\00")
+)
+"#;
+
+	#[test]
+	fn contract_transfer_asset() {
+		let mut mock_ext = MockExt::default();
+		let _ = execute(
+			CODE_TRANSFER_ASSET,
+			vec![],
+			&mut mock_ext,
+			&mut GasMeter::with_limit(50_000, 1),
+		).unwrap();
+
+		assert_eq!(
+			&mock_ext.asset_transfers,
+			&[AssetTransferEntry {
+				asset_id: 16_000,
+				to: 7,
+				value: 153,
+			}]
+		);
+	}
+
 	const CODE_CALL: &str = r#"
 (module
 	;; ext_call(
@@ -716,6 +902,69 @@ mod tests {
 		);
 	}
 
+	const CODE_SET_CODE_HASH: &str = r#"
+(module
+	;; ext_set_code_hash(
+	;;     code_hash_ptr: u32,
+	;;     code_hash_len: u32,
+	;;     gas: u64,
+	;;     input_data_ptr: u32,
+	;;     input_data_len: u32,
+	;; ) -> u32
+	(import "env" "ext_set_code_hash" (func $ext_set_code_hash (param i32 i32 i64 i32 i32) (result i32)))
+	(import "env" "memory" (memory 1 1))
+	(func $assert (param i32)
+		(block $ok
+			(br_if $ok
+				(get_local 0)
+			)
+			(unreachable)
+		)
+	)
+	(func (export "call")
+		(call $assert
+			(i32.eqz
+				(call $ext_set_code_hash
+					(i32.const 16)   ;; Pointer to `code_hash`
+					(i32.const 32)   ;; Length of `code_hash`
+					(i64.const 0)    ;; How much gas to devote for the migration. 0 = all.
+					(i32.const 12)   ;; Pointer to input data buffer address
+					(i32.const 4)    ;; Length of input data buffer
+				)
+			)
+		)
+	)
+	(func (export "deploy"))
+
+	;; Input data to pass to the migration.
+	(data (i32.const 12) "\01\02\03\04")
+	;; Hash of code.
+	(data (i32.const 16)
+		"\22\22\22\22\22\22\22\22\22\22\22\22\22\22\22\22"
+		"\22\22\22\22\22\22\22\22\22\22\22\22\22\22\22\22"
+	)
+)
+"#;
+
+	#[test]
+	fn contract_set_code_hash() {
+		let mut mock_ext = MockExt::default();
+		let _ = execute(
+			CODE_SET_CODE_HASH,
+			vec![],
+			&mut mock_ext,
+			&mut GasMeter::with_limit(50_000, 1),
+		).unwrap();
+
+		assert_eq!(
+			&mock_ext.code_hash_updates,
+			&[SetCodeHashEntry {
+				code_hash: [0x22; 32].into(),
+				data: vec![1, 2, 3, 4],
+			}]
+		);
+	}
+
 	const CODE_TERMINATE: &str = r#"
 (module
 	;; ext_terminate(
@@ -1148,6 +1397,73 @@ mod tests {
 		).unwrap();
 	}
 
+	const CODE_ASSET_BALANCE: &str = r#"
+(module
+	(import "env" "ext_asset_balance" (func $ext_asset_balance (param i32 i32)))
+	(import "env" "ext_scratch_size" (func $ext_scratch_size (result i32)))
+	(import "env" "ext_scratch_read" (func $ext_scratch_read (param i32 i32 i32)))
+	(import "env" "memory" (memory 1 1))
+
+	(func $assert (param i32)
+		(block $ok
+			(br_if $ok
+				(get_local 0)
+			)
+			(unreachable)
+		)
+	)
+
+	(func (export "call")
+		;; This stores the balance of the asset in the scratch buffer
+		(call $ext_asset_balance
+			(i32.const 16)		;; Pointer to the asset ID.
+			(i32.const 4)		;; Length of the asset ID.
+		)
+
+		;; assert $ext_scratch_size == 8
+		(call $assert
+			(i32.eq
+				(call $ext_scratch_size)
+				(i32.const 8)
+			)
+		)
+
+		;; copy contents of the scratch buffer into the contract's memory.
+		(call $ext_scratch_read
+			(i32.const 8)		;; Pointer in memory to the place where to copy.
+			(i32.const 0)		;; Offset from the start of the scratch buffer.
+			(i32.const 8)		;; Count of bytes to copy.
+		)
+
+		;; assert that contents of the buffer is equal to the i64 value of 229.
+		(call $assert
+			(i64.eq
+				(i64.load
+					(i32.const 8)
+				)
+				(i64.const 229)
+			)
+		)
+	)
+	(func (export "deploy"))
+
+	;; ID of the asset to query.
+	;; Represented by u32 (4 bytes long) in little endian.
+	(data (i32.const 16) "\80\3e\00\00")
+)
+"#;
+
+	#[test]
+	fn asset_balance() {
+		let mut gas_meter = GasMeter::with_limit(50_000, 1);
+		let _ = execute(
+			CODE_ASSET_BALANCE,
+			vec![],
+			MockExt::default(),
+			&mut gas_meter,
+		).unwrap();
+	}
+
 	const CODE_GAS_PRICE: &str = r#"
 (module
 	(import "env" "ext_gas_price" (func $ext_gas_price))
@@ -1423,6 +1739,174 @@ mod tests {
 		);
 	}
 
+	const CODE_VERIFY_DOUGHNUT: &str = r#"
+(module
+	(import "env" "ext_scratch_size" (func $ext_scratch_size (result i32)))
+	(import "env" "ext_scratch_read" (func $ext_scratch_read (param i32 i32 i32)))
+	(import "env" "ext_verify_doughnut" (func $ext_verify_doughnut (param i32 i32) (result i32)))
+	(import "env" "memory" (memory 1 1))
+
+	;; Verifies the doughnut given as input and returns the result as the status.
+	(func (export "call") (result i32)
+		(local $buf_size i32)
+
+		(set_local $buf_size (call $ext_scratch_size))
+		(call $ext_scratch_read
+			(i32.const 0)
+			(i32.const 0)
+			(get_local $buf_size)
+		)
+		(call $ext_verify_doughnut
+			(i32.const 0)
+			(get_local $buf_size)
+		)
+	)
+	(func (export "deploy"))
+)
+"#;
+
+	#[test]
+	fn verify_doughnut() {
+		let verifiable_doughnut = MockDoughnut::default().set_issuer(7).set_holder(69);
+		let output = execute(
+			CODE_VERIFY_DOUGHNUT,
+			verifiable_doughnut.encode(),
+			MockExt::default(),
+			&mut GasMeter::with_limit(50_000, 1),
+		).unwrap();
+		assert_eq!(output, ExecReturnValue { status: 0, data: Vec::new() });
+
+		let unverifiable_doughnut = MockDoughnut::default().set_issuer(8).set_holder(69);
+		let output = execute(
+			CODE_VERIFY_DOUGHNUT,
+			unverifiable_doughnut.encode(),
+			MockExt::default(),
+			&mut GasMeter::with_limit(50_000, 1),
+		).unwrap();
+		assert_eq!(output, ExecReturnValue { status: 1, data: Vec::new() });
+	}
+
+	const CODE_FORWARD_DOUGHNUT: &str = r#"
+(module
+	(import "env" "ext_scratch_size" (func $ext_scratch_size (result i32)))
+	(import "env" "ext_scratch_read" (func $ext_scratch_read (param i32 i32 i32)))
+	(import "env" "ext_forward_doughnut" (func $ext_forward_doughnut (param i32 i32 i32 i32)))
+	(import "env" "memory" (memory 1 1))
+
+	;; Forwards the doughnut given as input to dispatch the call below.
+	(func (export "call")
+		(local $buf_size i32)
+
+		(set_local $buf_size (call $ext_scratch_size))
+		(call $ext_scratch_read
+			(i32.const 16)
+			(i32.const 0)
+			(get_local $buf_size)
+		)
+		(call $ext_forward_doughnut
+			(i32.const 16) ;; Pointer to the doughnut
+			(get_local $buf_size) ;; Length of the doughnut
+			(i32.const 0) ;; Pointer to the start of encoded call buffer
+			(i32.const 13) ;; Length of the call buffer
+		)
+	)
+	(func (export "deploy"))
+
+	(data (i32.const 0) "\00\01\2B\00\00\00\00\00\00\00\E5\14\00")
+)
+"#;
+
+	#[test]
+	fn forward_doughnut() {
+		let doughnut = MockDoughnut::default().set_issuer(7).set_holder(69);
+		let mut mock_ext = MockExt::default();
+		execute(
+			CODE_FORWARD_DOUGHNUT,
+			doughnut.encode(),
+			&mut mock_ext,
+			&mut GasMeter::with_limit(50_000, 1),
+		).unwrap();
+
+		assert_eq!(
+			&mock_ext.forwarded_dispatches,
+			&[ForwardedDispatchEntry(
+				7,
+				doughnut,
+				Call::Balances(pallet_balances::Call::set_balance(43, 1337, 0)),
+			)]
+		);
+	}
+
+	#[test]
+	fn forward_doughnut_traps_if_it_does_not_verify() {
+		let doughnut = MockDoughnut::default().set_issuer(8).set_holder(69);
+		let mut mock_ext = MockExt::default();
+		assert_matches!(
+			execute(
+				CODE_FORWARD_DOUGHNUT,
+				doughnut.encode(),
+				&mut mock_ext,
+				&mut GasMeter::with_limit(50_000, 1),
+			),
+			Err(ExecError { reason: DispatchError::Other("contract trapped during execution"), buffer: _ })
+		);
+		assert!(mock_ext.forwarded_dispatches.is_empty());
+	}
+
+	const CODE_ISSUE_DOUGHNUT: &str = r#"
+(module
+	(import "env" "ext_scratch_size" (func $ext_scratch_size (result i32)))
+	(import "env" "ext_scratch_read" (func $ext_scratch_read (param i32 i32 i32)))
+	(import "env" "ext_issue_doughnut" (func $ext_issue_doughnut (param i32 i32) (result i32)))
+	(import "env" "memory" (memory 1 1))
+
+	;; Issues the doughnut given as input and returns the result as the status.
+	(func (export "call") (result i32)
+		(local $buf_size i32)
+
+		(set_local $buf_size (call $ext_scratch_size))
+		(call $ext_scratch_read
+			(i32.const 0)
+			(i32.const 0)
+			(get_local $buf_size)
+		)
+		(call $ext_issue_doughnut
+			(i32.const 0)
+			(get_local $buf_size)
+		)
+	)
+	(func (export "deploy"))
+)
+"#;
+
+	#[test]
+	fn issue_doughnut() {
+		let doughnut = MockDoughnut::default().set_issuer(69).set_holder(7);
+		let mut mock_ext = MockExt::default();
+		let output = execute(
+			CODE_ISSUE_DOUGHNUT,
+			doughnut.encode(),
+			&mut mock_ext,
+			&mut GasMeter::with_limit(50_000, 1),
+		).unwrap();
+		assert_eq!(output, ExecReturnValue { status: 0, data: Vec::new() });
+		assert_eq!(
+			&mock_ext.doughnut_authorisations,
+			&[DoughnutAuthorisationEntry(doughnut, true)],
+		);
+
+		// The contract is not the issuer.
+		let foreign_doughnut = MockDoughnut::default().set_issuer(7).set_holder(69);
+		let output = execute(
+			CODE_ISSUE_DOUGHNUT,
+			foreign_doughnut.encode(),
+			&mut mock_ext,
+			&mut GasMeter::with_limit(50_000, 1),
+		).unwrap();
+		assert_eq!(output, ExecReturnValue { status: 1, data: Vec::new() });
+		assert_eq!(mock_ext.doughnut_authorisations.len(), 1);
+	}
+
 	const CODE_RETURN_FROM_START_FN: &str = r#"
 (module
 	(import "env" "ext_return" (func $ext_return (param i32 i32)))
@@ -1458,6 +1942,96 @@ mod tests {
 		assert_eq!(output, ExecReturnValue { status: STATUS_SUCCESS, data: vec![1, 2, 3, 4] });
 	}
 
+	const CODE_CHAIN_EXTENSION: &str = r#"
+(module
+	;; ext_chain_extension(
+	;;    func_id: u32,
+	;;    input_ptr: u32,
+	;;    input_len: u32,
+	;;) -> u32
+	(import "env" "ext_chain_extension" (func $ext_chain_extension (param i32 i32 i32) (result i32)))
+	(import "env" "ext_scratch_read" (func $ext_scratch_read (param i32 i32 i32)))
+	(import "env" "memory" (memory 1 1))
+
+	;; Calls the chain extension function whose ID is given as input, returning its result as
+	;; the status code and its output as the output data.
+	(func (export "call") (result i32)
+		;; Copy the function ID from the scratch buffer.
+		(call $ext_scratch_read
+			(i32.const 0)		;; Pointer in memory to the place where to copy.
+			(i32.const 0)		;; Offset from the start of the scratch buffer.
+			(i32.const 4)		;; Count of bytes to copy.
+		)
+
+		(call $ext_chain_extension
+			(i32.load (i32.const 0))	;; The function ID.
+			(i32.const 8)		;; Pointer to the input.
+			(i32.const 4)		;; Length of the input.
+		)
+	)
+	(func (export "deploy"))
+
+	(data (i32.const 8) "\01\02\03\04")
+)
+"#;
+
+	#[test]
+	fn chain_extension() {
+		// The mock extension reverses its input.
+		let output = execute(
+			CODE_CHAIN_EXTENSION,
+			1u32.encode(),
+			MockExt::default(),
+			&mut GasMeter::with_limit(50_000, 1),
+		).unwrap();
+		assert_eq!(output, ExecReturnValue { status: 0, data: vec![4, 3, 2, 1] });
+
+		// Failing calls are reported to the contract.
+		let output = execute(
+			CODE_CHAIN_EXTENSION,
+			3u32.encode(),
+			MockExt::default(),
+			&mut GasMeter::with_limit(50_000, 1),
+		).unwrap();
+		assert_eq!(output, ExecReturnValue { status: 1, data: Vec::new() });
+
+		// Running out of gas in the extension traps.
+		assert_matches!(
+			execute(
+				CODE_CHAIN_EXTENSION,
+				2u32.encode(),
+				MockExt::default(),
+				&mut GasMeter::with_limit(50_000, 1),
+			),
+			Err(ExecError { reason: DispatchError::Other("ran out of gas during contract execution"), buffer: _ })
+		);
+
+		// Calls dispatched by the extension are noted only if it succeeds.
+		let mut mock_ext = MockExt::default();
+		execute(
+			CODE_CHAIN_EXTENSION,
+			4u32.encode(),
+			&mut mock_ext,
+			&mut GasMeter::with_limit(50_000, 1),
+		).unwrap();
+		assert_eq!(
+			&mock_ext.dispatches,
+			&[DispatchEntry(
+				Call::Balances(pallet_balances::Call::set_balance(42, 1337, 0)),
+			)]
+		);
+
+		let mut mock_ext = MockExt::default();
+		let output = execute(
+			CODE_CHAIN_EXTENSION,
+			5u32.encode(),
+			&mut mock_ext,
+			&mut GasMeter::with_limit(50_000, 1),
+		).unwrap();
+		assert_eq!(output, ExecReturnValue { status: 1, data: Vec::new() });
+		assert!(mock_ext.dispatches.is_empty());
+	}
+
 	const CODE_TIMESTAMP_NOW: &str = r#"
 (module
 	(import "env" "ext_now" (func $ext_now))
@@ -169,6 +169,7 @@ impl<'a> ContractModule<'a> {
 	///
 	/// - 'call'
 	/// - 'deploy'
+	/// - 'migrate' (optional)
 	///
 	/// Any other exports are not allowed.
 	fn scan_exports(&self) -> Result<(), &'static str> {
@@ -207,10 +208,11 @@ impl<'a> ContractModule<'a> {
 			match export.field() {
 				"call" => call_found = true,
 				"deploy" => deploy_found = true,
-				_ => return Err("unknown export: expecting only deploy and call functions"),
+				"migrate" => {},
+				_ => return Err("unknown export: expecting only deploy, call and migrate functions"),
 			}
 
-			// Then check the export kind. "call" and "deploy" are
+			// Then check the export kind. "call", "deploy" and "migrate" are
 			// functions.
 			let fn_idx = match export.internal() {
 				Internal::Function(ref fn_idx) => *fn_idx,
@@ -227,7 +229,7 @@ impl<'a> ContractModule<'a> {
 			};
 
 			// Then check the signature.
-			// Both "call" and "deploy" has a [] -> [] or [] -> [i32] function type.
+			// "call", "deploy" and "migrate" have a [] -> [] or [] -> [i32] function type.
 			//
 			// The [] -> [] signature predates the [] -> [i32] signature and is supported for
 			// backwards compatibility. This will likely be removed once ink! is updated to
@@ -390,6 +392,27 @@ pub fn prepare_contract<C: ImportSatisfyCheck>(
 	})
 }
 
+/// Returns whether the prepared module `code` exports a function with the given `name`.
+///
+/// Returns `false` if the code can't be decoded.
+pub fn exports_function(code: &[u8], name: &str) -> bool {
+	let module: elements::Module = match elements::deserialize_buffer(code) {
+		Ok(module) => module,
+		Err(_) => return false,
+	};
+	module
+		.export_section()
+		.map(|es| es.entries())
+		.unwrap_or(&[])
+		.iter()
+		.any(|export| {
+			export.field() == name && match export.internal() {
+				Internal::Function(_) => true,
+				_ => false,
+			}
+		})
+}
+
 #[cfg(test)]
 mod tests {
 	use super::*;
@@ -789,9 +812,57 @@ mod tests {
 				(func (export "whatevs"))
 			)
 			"#,
-			Err("unknown export: expecting only deploy and call functions")
+			Err("unknown export: expecting only deploy, call and migrate functions")
+		);
+
+		prepare_test!(migrate_export,
+			r#"
+			(module
+				(func (export "call"))
+				(func (export "deploy"))
+				(func (export "migrate"))
+			)
+			"#,
+			Ok(_)
+		);
+
+		prepare_test!(migrate_wrong_signature,
+			r#"
+			(module
+				(func (export "call"))
+				(func (export "deploy"))
+				(func (export "migrate") (param i32))
+			)
+			"#,
+			Err("entry point has wrong signature")
 		);
 
+		#[test]
+		fn migrate_export_is_kept_after_instrumentation() {
+			let schedule = Schedule::default();
+			let prepare = |wat: &str| {
+				let wasm = wabt::Wat2Wasm::new().validate(false).convert(wat).unwrap();
+				prepare_contract::<TestEnv>(wasm.as_ref(), &schedule).unwrap()
+			};
+
+			let with_migrate = prepare(r#"
+			(module
+				(func (export "call"))
+				(func (export "deploy"))
+				(func (export "migrate"))
+			)
+			"#);
+			let without_migrate = prepare(r#"
+			(module
+				(func (export "call"))
+				(func (export "deploy"))
+			)
+			"#);
+
+			assert!(exports_function(&with_migrate.code, "migrate"));
+			assert!(!exports_function(&without_migrate.code, "migrate"));
+		}
+
 		prepare_test!(global_float,
 			r#"
 			(module
@@ -18,9 +18,10 @@
 
 use crate::{Schedule, Trait, CodeHash, ComputeDispatchFee, BalanceOf};
 use crate::exec::{
-	Ext, ExecResult, ExecError, ExecReturnValue, StorageKey, TopicOf, STATUS_SUCCESS,
+	AssetIdOf, Ext, ExecResult, ExecError, ExecReturnValue, StorageKey, TopicOf, STATUS_SUCCESS,
 };
 use crate::gas::{Gas, GasMeter, Token, GasMeterResult, approx_gas_for_balance};
+use crate::chain_extension::{ChainExtension, Environment};
 use sp_sandbox;
 use frame_support::additional_traits::DelegatedDispatchVerifier;
 use frame_system;
@@ -161,6 +162,10 @@ pub enum RuntimeToken {
 	/// (topic_count, data_bytes): A buffer of the given size is posted as an event indexed with the
 	/// given number of topics.
 	DepositEvent(u32, u32),
+	/// A doughnut of the given number of bytes is verified.
+	VerifyDoughnut(u32),
+	/// A doughnut of the given number of bytes is authorised, or its authorisation revoked.
+	AuthoriseDoughnut(u32),
 }
 
 impl<T: Trait> Token<T> for RuntimeToken {
@@ -199,6 +204,14 @@ impl<T: Trait> Token<T> for RuntimeToken {
 					)
 			},
 			ComputedDispatchFee(gas) => Some(gas),
+			VerifyDoughnut(byte_count) => metadata
+				.doughnut_per_byte_cost
+				.checked_mul(byte_count.into())
+				.and_then(|cost| cost.checked_add(metadata.verify_doughnut_base_cost)),
+			AuthoriseDoughnut(byte_count) => metadata
+				.doughnut_per_byte_cost
+				.checked_mul(byte_count.into())
+				.and_then(|cost| cost.checked_add(metadata.authorise_doughnut_base_cost)),
 		};
 
 		value.unwrap_or_else(|| Bounded::max_value())
@@ -453,6 +466,43 @@ define_env!(Env, <E: Ext>,
 		}
 	},
 
+	// Transfer some value of a `MultiCurrency` asset to another account.
+	//
+	// If the value transfer was succesful zero is returned. Otherwise one is returned.
+	// The scratch buffer is not touched. The receiver can be a plain account or
+	// a contract.
+	//
+	// - asset_id_ptr: a pointer to the ID of the asset to transfer.
+	//   Should be decodable as a `T::AssetId`. Traps otherwise.
+	// - asset_id_len: length of the asset ID buffer.
+	// - account_ptr: a pointer to the address of the beneficiary account
+	//   Should be decodable as an `T::AccountId`. Traps otherwise.
+	// - account_len: length of the address buffer.
+	// - value_ptr: a pointer to the buffer with value, how much value to send.
+	//   Should be decodable as a `T::Balance`. Traps otherwise.
+	// - value_len: length of the value buffer.
+	ext_transfer_asset(
+		ctx,
+		asset_id_ptr: u32,
+		asset_id_len: u32,
+		account_ptr: u32,
+		account_len: u32,
+		value_ptr: u32,
+		value_len: u32
+	) -> u32 => {
+		let asset_id: AssetIdOf<<E as Ext>::T> =
+			read_sandbox_memory_as(ctx, asset_id_ptr, asset_id_len)?;
+		let callee: <<E as Ext>::T as frame_system::Trait>::AccountId =
+			read_sandbox_memory_as(ctx, account_ptr, account_len)?;
+		let value: BalanceOf<<E as Ext>::T> =
+			read_sandbox_memory_as(ctx, value_ptr, value_len)?;
+
+		match ctx.ext.transfer_asset(&asset_id, &callee, value, ctx.gas_meter) {
+			Ok(_) => Ok(0),
+			Err(_) => Ok(1),
+		}
+	},
+
 	// Make a call to another contract.
 	//
 	// If the called contract runs to completion, then this returns the status code the callee
@@ -633,7 +683,79 @@ define_env!(Env, <E: Ext>,
 		}
 	},
 
-	// Remove the calling account and transfer remaining balance.
+	// Switch the calling contract to the code with the specified code hash.
+	//
+	// The contract keeps its address, balance and storage. If the new code exports a `migrate`
+	// function it is executed once, in the context of the calling contract, right after the switch.
+	// The code of the currently running execution isn't affected, the new code is used by all
+	// subsequent calls into the contract.
+	//
+	// If the migration runs to completion, then this returns the status code it returns on exit in
+	// the bottom 8 bits of the return value. The top 24 bits are 0s. A status code of 0 indicates
+	// success, and any other code indicates a failure. On failure, the contract keeps its current
+	// code and any state changes made by the migration are reverted. The scratch buffer is filled
+	// with the output data returned by the migration, even in the case of a failure status.
+	//
+	// If the code hash is unknown or the migration traps or otherwise fails to complete
+	// successfully, then this function clears the scratch buffer and returns 0x0100. As with a
+	// failure status, the contract keeps its current code.
+	//
+	// - code_hash_ptr: a pointer to the buffer that contains the code hash of the new code.
+	// - code_hash_len: length of the code hash buffer.
+	// - gas: how much gas to devote to the execution of the migration.
+	// - input_data_ptr: a pointer to a buffer to be used as input data to the migration.
+	// - input_data_len: length of the input data buffer.
+	ext_set_code_hash(
+		ctx,
+		code_hash_ptr: u32,
+		code_hash_len: u32,
+		gas: u64,
+		input_data_ptr: u32,
+		input_data_len: u32
+	) -> u32 => {
+		let code_hash: CodeHash<<E as Ext>::T> =
+			read_sandbox_memory_as(ctx, code_hash_ptr, code_hash_len)?;
+
+		// Read input data into the scratch buffer, then take ownership of it.
+		read_sandbox_memory_into_scratch(ctx, input_data_ptr, input_data_len)?;
+		let input_data = mem::replace(&mut ctx.scratch_buf, Vec::new());
+
+		let nested_gas_limit = if gas == 0 {
+			ctx.gas_meter.gas_left()
+		} else {
+			gas.saturated_into()
+		};
+		let ext = &mut ctx.ext;
+		let set_code_outcome = ctx.gas_meter.with_nested(nested_gas_limit, |nested_meter| {
+			match nested_meter {
+				Some(nested_meter) => {
+					ext.set_code_hash(
+						&code_hash,
+						nested_meter,
+						input_data,
+					)
+					.map_err(|err| err.buffer)
+				}
+				// there is not enough gas to allocate for the migration.
+				None => Err(input_data),
+			}
+		});
+
+		match set_code_outcome {
+			Ok(output) => {
+				ctx.scratch_buf = output.data;
+				Ok(output.status.into())
+			},
+			Err(buffer) => {
+				ctx.scratch_buf = buffer;
+				ctx.scratch_buf.clear();
+				Ok(TRAP_RETURN_CODE)
+			},
+		}
+	},
+
+	// Remove the calling account and transfer remaining balance, including the balance of every
+	// `MultiCurrency` asset it holds.
 	//
 	// This function never returns. Either the termination was successful and the
 	// execution of the destroyed contract is halted. Or it failed during the termination
@@ -732,6 +854,21 @@ define_env!(Env, <E: Ext>,
 		Ok(())
 	},
 
+	// Stores the balance of a `MultiCurrency` asset of the current account into the scratch buffer.
+	//
+	// - asset_id_ptr: a pointer to the ID of the asset.
+	//   Should be decodable as a `T::AssetId`. Traps otherwise.
+	// - asset_id_len: length of the asset ID buffer.
+	//
+	// The data is encoded as T::Balance. The current contents of the scratch buffer are overwritten.
+	ext_asset_balance(ctx, asset_id_ptr: u32, asset_id_len: u32) => {
+		let asset_id: AssetIdOf<<E as Ext>::T> =
+			read_sandbox_memory_as(ctx, asset_id_ptr, asset_id_len)?;
+		ctx.scratch_buf.clear();
+		ctx.ext.asset_balance(&asset_id).encode_to(&mut ctx.scratch_buf);
+		Ok(())
+	},
+
 	// Stores the value transferred along with this call or as endowment into the scratch buffer.
 	//
 	// The data is encoded as T::Balance. The current contents of the scratch buffer are overwritten.
@@ -741,6 +878,51 @@ define_env!(Env, <E: Ext>,
 		Ok(())
 	},
 
+	// Stores the asset of the value transferred along with this call into the scratch buffer.
+	//
+	// The data is encoded as Option<T::AssetId>, `None` if the value was transferred in the
+	// native currency. The current contents of the scratch buffer are overwritten.
+	ext_value_transferred_asset(ctx) => {
+		ctx.scratch_buf.clear();
+		ctx.ext.value_transferred_asset().encode_to(&mut ctx.scratch_buf);
+		Ok(())
+	},
+
+	// Call the function `func_id` of the chain extension of the runtime.
+	//
+	// If the call succeeded, the scratch buffer is filled with its output and zero is returned.
+	// Otherwise the scratch buffer is cleared and one is returned. The call traps if the chain
+	// extension ran out of gas. The runtime calls dispatched by a successful function are
+	// executed as if dispatched with `ext_dispatch_call`.
+	//
+	// - func_id: the ID of the chain extension function to call.
+	// - input_ptr: a pointer to the input passed to the function.
+	// - input_len: length of the input buffer.
+	ext_chain_extension(ctx, func_id: u32, input_ptr: u32, input_len: u32) -> u32 => {
+		let input = read_sandbox_memory(ctx, input_ptr, input_len)?;
+
+		let mut env = Environment::new(ctx.ext.address(), ctx.ext.caller(), ctx.gas_meter);
+		let result = <<E as Ext>::T as Trait>::ChainExtension::call(func_id, input, &mut env);
+		if env.is_out_of_gas() {
+			ctx.special_trap = Some(SpecialTrap::OutOfGas);
+			return Err(sp_sandbox::HostError);
+		}
+
+		match result {
+			Ok(output) => {
+				for call in env.into_dispatched() {
+					ctx.ext.note_dispatch_call(call);
+				}
+				ctx.scratch_buf = output;
+				Ok(0)
+			},
+			Err(_) => {
+				ctx.scratch_buf.clear();
+				Ok(1)
+			},
+		}
+	},
+
 	// Stores the random number for the current block for the given subject into the scratch
 	// buffer.
 	//
@@ -847,6 +1029,123 @@ define_env!(Env, <E: Ext>,
 		Ok(())
 	},
 
+	// Verify a doughnut supplied to the contract, e.g. as part of its input data.
+	//
+	// The doughnut verifies if the current contract is its holder, it is valid at the current
+	// block timestamp and its issuer either signed it or authorised it on-chain.
+	//
+	// - doughnut_ptr: a pointer to a buffer with the encoded doughnut.
+	// - doughnut_len: length of the doughnut buffer.
+	//
+	// Returns 0 if the doughnut verifies, 1 otherwise.
+	ext_verify_doughnut(ctx, doughnut_ptr: u32, doughnut_len: u32) -> u32 => {
+		charge_gas(
+			&mut ctx.gas_meter,
+			ctx.schedule,
+			&mut ctx.special_trap,
+			RuntimeToken::VerifyDoughnut(doughnut_len)
+		)?;
+
+		let doughnut: <<E as Ext>::T as frame_system::Trait>::Doughnut =
+			read_sandbox_memory_as(ctx, doughnut_ptr, doughnut_len)?;
+
+		Ok(match ctx.ext.verify_doughnut(&doughnut) {
+			Ok(_) => 0,
+			Err(_) => 1,
+		})
+	},
+
+	// Dispatch a call with a doughnut held by the current contract, on behalf of its issuer.
+	//
+	// Similar to `ext_delegated_dispatch_call`, but the doughnut is supplied by the contract
+	// instead of being the one the contract was called with. The doughnut is verified as with
+	// `ext_verify_doughnut`, the contract traps if it doesn't verify.
+	//
+	// - doughnut_ptr: a pointer to a buffer with the encoded doughnut.
+	// - doughnut_len: length of the doughnut buffer.
+	// - call_ptr: a pointer to a buffer with the encoded call.
+	// - call_len: length of the call buffer.
+	ext_forward_doughnut(ctx, doughnut_ptr: u32, doughnut_len: u32, call_ptr: u32, call_len: u32) => {
+		let doughnut: <<E as Ext>::T as frame_system::Trait>::Doughnut =
+			read_sandbox_memory_as(ctx, doughnut_ptr, doughnut_len)?;
+		let call: <<E as Ext>::T as Trait>::Call =
+			read_sandbox_memory_as(ctx, call_ptr, call_len)?;
+
+		// Charge gas for dispatching this call.
+		let fee = {
+			let balance_fee = <<E as Ext>::T as Trait>::ComputeDispatchFee::compute_dispatch_fee(&call);
+			approx_gas_for_balance(ctx.gas_meter.gas_price(), balance_fee)
+		};
+		charge_gas(
+			&mut ctx.gas_meter,
+			ctx.schedule,
+			&mut ctx.special_trap,
+			RuntimeToken::ComputedDispatchFee(fee)
+		)?;
+
+		charge_gas(
+			&mut ctx.gas_meter,
+			ctx.schedule,
+			&mut ctx.special_trap,
+			RuntimeToken::VerifyDoughnut(doughnut_len)
+		)?;
+
+		let issuer = ctx.ext.verify_doughnut(&doughnut).map_err(|_| sp_sandbox::HostError)?;
+		ctx.ext.note_forwarded_dispatch_call(issuer, doughnut, call);
+
+		Ok(())
+	},
+
+	// Authorise a doughnut issued by the current contract.
+	//
+	// A contract can't sign the doughnuts it issues, the authorisation is recorded on-chain instead
+	// and accepted in place of the issuer signature. It takes effect once the current
+	// transaction succeeds.
+	//
+	// - doughnut_ptr: a pointer to a buffer with the encoded doughnut.
+	// - doughnut_len: length of the doughnut buffer.
+	//
+	// Returns 0 on success, 1 if the current contract is not the issuer of the doughnut.
+	ext_issue_doughnut(ctx, doughnut_ptr: u32, doughnut_len: u32) -> u32 => {
+		charge_gas(
+			&mut ctx.gas_meter,
+			ctx.schedule,
+			&mut ctx.special_trap,
+			RuntimeToken::AuthoriseDoughnut(doughnut_len)
+		)?;
+
+		let doughnut: <<E as Ext>::T as frame_system::Trait>::Doughnut =
+			read_sandbox_memory_as(ctx, doughnut_ptr, doughnut_len)?;
+
+		Ok(match ctx.ext.note_doughnut_authorisation(&doughnut, true) {
+			Ok(()) => 0,
+			Err(_) => 1,
+		})
+	},
+
+	// Revoke the authorisation of a doughnut issued by the current contract.
+	//
+	// - doughnut_ptr: a pointer to a buffer with the encoded doughnut.
+	// - doughnut_len: length of the doughnut buffer.
+	//
+	// Returns 0 on success, 1 if the current contract is not the issuer of the doughnut.
+	ext_revoke_doughnut(ctx, doughnut_ptr: u32, doughnut_len: u32) -> u32 => {
+		charge_gas(
+			&mut ctx.gas_meter,
+			ctx.schedule,
+			&mut ctx.special_trap,
+			RuntimeToken::AuthoriseDoughnut(doughnut_len)
+		)?;
+
+		let doughnut: <<E as Ext>::T as frame_system::Trait>::Doughnut =
+			read_sandbox_memory_as(ctx, doughnut_ptr, doughnut_len)?;
+
+		Ok(match ctx.ext.note_doughnut_authorisation(&doughnut, false) {
+			Ok(()) => 0,
+			Err(_) => 1,
+		})
+	},
+
 	// Record a request to restore the caller contract to the specified contract.
 	//
 	// At the finalization stage, i.e. when all changes from the extrinsic that invoked this
@@ -0,0 +1,148 @@
+// Copyright 2019-2020 Plug New Zealand Ltd.
+// This file is part of Plug.
+
+// Plug is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Plug is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Plug.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Minimal Solidity ABI helpers for precompile inputs and outputs.
+
+use sp_std::prelude::*;
+use sp_core::{H160, U256};
+use pallet_evm::ExitError;
+
+/// Size of an ABI word.
+const WORD: usize = 32;
+
+/// The 4 byte function selector of a call.
+pub fn selector(input: &[u8]) -> Result<[u8; 4], ExitError> {
+	if input.len() < 4 {
+		return Err(ExitError::Other("input is too short for a function selector".into()));
+	}
+	let mut selector = [0u8; 4];
+	selector.copy_from_slice(&input[0..4]);
+	Ok(selector)
+}
+
+/// The arguments of a call, i.e. the input following the function selector.
+fn arguments(input: &[u8]) -> &[u8] {
+	input.get(4..).unwrap_or_default()
+}
+
+/// The word starting at `offset` of `data`.
+fn word_at(data: &[u8], offset: usize) -> Result<&[u8], ExitError> {
+	offset.checked_add(WORD)
+		.and_then(|end| data.get(offset..end))
+		.ok_or(ExitError::Other("input is too short for the function arguments".into()))
+}
+
+/// Read the `index`-th argument of a call as a `uint256`.
+pub fn read_u256(input: &[u8], index: usize) -> Result<U256, ExitError> {
+	word_at(arguments(input), index * WORD).map(U256::from_big_endian)
+}
+
+/// Read the `index`-th argument of a call as an `address`.
+pub fn read_address(input: &[u8], index: usize) -> Result<H160, ExitError> {
+	word_at(arguments(input), index * WORD).map(|word| H160::from_slice(&word[12..]))
+}
+
+/// Read the `index`-th argument of a call as a `bytes` or `string`.
+pub fn read_bytes(input: &[u8], index: usize) -> Result<Vec<u8>, ExitError> {
+	let arguments = arguments(input);
+	let out_of_bounds = || ExitError::Other("dynamic argument is out of bounds".into());
+	let to_usize = |value: U256| if value > U256::from(arguments.len()) {
+		Err(out_of_bounds())
+	} else {
+		Ok(value.as_usize())
+	};
+
+	let offset = to_usize(U256::from_big_endian(word_at(arguments, index * WORD)?))?;
+	let len = to_usize(U256::from_big_endian(word_at(arguments, offset)?))?;
+	let start = offset + WORD;
+	start.checked_add(len)
+		.and_then(|end| arguments.get(start..end))
+		.map(|bytes| bytes.to_vec())
+		.ok_or_else(out_of_bounds)
+}
+
+/// Encode a `uint256` output.
+pub fn encode_u256(value: U256) -> Vec<u8> {
+	let mut output = vec![0u8; WORD];
+	value.to_big_endian(&mut output);
+	output
+}
+
+/// Encode a `bool` output.
+pub fn encode_bool(value: bool) -> Vec<u8> {
+	encode_u256(if value { U256::one() } else { U256::zero() })
+}
+
+/// Encode a `bytes` or `string` output.
+pub fn encode_bytes(value: &[u8]) -> Vec<u8> {
+	let padded_len = (value.len() + WORD - 1) / WORD * WORD;
+	let mut output = encode_u256(U256::from(WORD));
+	output.extend(encode_u256(U256::from(value.len())));
+	output.extend_from_slice(value);
+	output.resize(2 * WORD + padded_len, 0);
+	output
+}
+
+/// Check `cost` fits in `target_gas`, returning the gas used by the precompile.
+pub fn ensure_gas(target_gas: Option<usize>, cost: usize) -> Result<usize, ExitError> {
+	match target_gas {
+		Some(target_gas) if cost > target_gas => Err(ExitError::OutOfGas),
+		_ => Ok(cost),
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn static_arguments_are_read() {
+		let mut input = vec![0xa9, 0x05, 0x9c, 0xbb];
+		input.extend(vec![0u8; 12]);
+		input.extend(vec![1u8; 20]);
+		input.extend(encode_u256(U256::from(1_000)));
+
+		assert_eq!(selector(&input).unwrap(), [0xa9, 0x05, 0x9c, 0xbb]);
+		assert_eq!(read_address(&input, 0).unwrap(), H160::repeat_byte(1));
+		assert_eq!(read_u256(&input, 1).unwrap(), U256::from(1_000));
+		assert!(read_u256(&input, 2).is_err());
+	}
+
+	#[test]
+	fn bytes_round_trip() {
+		let mut input = vec![0u8; 4];
+		input.extend(encode_bytes(b"GenericAsset"));
+
+		assert_eq!(encode_bytes(b"GenericAsset").len(), 3 * WORD);
+		assert_eq!(read_bytes(&input, 0).unwrap(), b"GenericAsset".to_vec());
+	}
+
+	#[test]
+	fn out_of_bounds_bytes_are_rejected() {
+		let mut input = vec![0u8; 4];
+		input.extend(encode_u256(U256::from(WORD)));
+		input.extend(encode_u256(U256::from(64)));
+
+		assert!(read_bytes(&input, 0).is_err());
+	}
+
+	#[test]
+	fn gas_is_checked() {
+		assert_eq!(ensure_gas(None, 100), Ok(100));
+		assert_eq!(ensure_gas(Some(100), 100), Ok(100));
+		assert_eq!(ensure_gas(Some(99), 100), Err(ExitError::OutOfGas));
+	}
+}
@@ -0,0 +1,83 @@
+// Copyright 2019-2020 Plug New Zealand Ltd.
+// This file is part of Plug.
+
+// Plug is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Plug is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Plug.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Doughnut delegation precompile.
+
+use sp_std::{prelude::*, marker::PhantomData};
+use sp_core::H160;
+use frame_support::additional_traits::DelegatedDispatchVerifier;
+use pallet_evm::{Context, ExitError, ExitSucceed, PrecompileFrame, Precompiles};
+use crate::abi;
+
+/// Address of the `DoughnutPrecompile`.
+pub const DOUGHNUT_PRECOMPILE_ADDRESS: H160 = H160([
+	0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0x04, 0x00,
+]);
+
+/// Gas charged for a doughnut verification.
+const VERIFY_GAS: usize = 5_000;
+
+const IS_AUTHORISED: [u8; 4] = [0x63, 0x01, 0x6d, 0xc4];
+
+/// Check the doughnut of the current EVM operation.
+///
+/// `isAuthorised(string module, string method) returns (bool)` is `true` if the EVM operation was
+/// dispatched with a doughnut that `frame_system::Trait::DelegatedDispatchVerifier` accepts for
+/// `module` and `method`, and `false` otherwise. Every contract of the call stack sees the
+/// doughnut of the top-level operation, see `pallet_evm::Module::current_doughnut`.
+pub struct DoughnutPrecompile<T>(PhantomData<T>);
+
+impl<T: pallet_evm::Trait> Precompiles for DoughnutPrecompile<T> {
+	fn execute(
+		address: H160,
+		input: &[u8],
+		target_gas: Option<usize>,
+		_context: &Context,
+		_frame: &mut PrecompileFrame,
+	) -> Option<core::result::Result<(ExitSucceed, Vec<u8>, usize), ExitError>> {
+		if address != DOUGHNUT_PRECOMPILE_ADDRESS {
+			return None;
+		}
+		Some(Self::is_authorised(input, target_gas))
+	}
+}
+
+impl<T: pallet_evm::Trait> DoughnutPrecompile<T> {
+	fn is_authorised(
+		input: &[u8],
+		target_gas: Option<usize>,
+	) -> Result<(ExitSucceed, Vec<u8>, usize), ExitError> {
+		if abi::selector(input)? != IS_AUTHORISED {
+			return Err(ExitError::Other("unknown doughnut precompile function".into()));
+		}
+		let cost = abi::ensure_gas(target_gas, VERIFY_GAS)?;
+
+		let module = abi::read_bytes(input, 0)?;
+		let method = abi::read_bytes(input, 1)?;
+		let (module, method) = match (sp_std::str::from_utf8(&module), sp_std::str::from_utf8(&method)) {
+			(Ok(module), Ok(method)) => (module, method),
+			_ => return Err(ExitError::Other("module and method must be UTF-8".into())),
+		};
+
+		let authorised = pallet_evm::Module::<T>::current_doughnut()
+			.map(|doughnut| {
+				T::DelegatedDispatchVerifier::verify_dispatch(&doughnut, module, method, Vec::new()).is_ok()
+			})
+			.unwrap_or(false);
+
+		Ok((ExitSucceed::Returned, abi::encode_bool(authorised), cost))
+	}
+}
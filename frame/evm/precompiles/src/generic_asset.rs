@@ -0,0 +1,227 @@
+// Copyright 2019-2020 Plug New Zealand Ltd.
+// This file is part of Plug.
+
+// Plug is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Plug is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Plug.  If not, see <http://www.gnu.org/licenses/>.
+
+//! ERC20 precompiles of generic assets.
+
+use sp_std::{prelude::*, convert::TryFrom, marker::PhantomData};
+use codec::{Encode, Decode};
+use sp_core::{H160, U256};
+use sp_runtime::traits::{Saturating, UniqueSaturatedInto};
+use pallet_evm::{AddressMapping, Context, ExitError, ExitSucceed, PrecompileFrame, Precompiles};
+use pallet_generic_asset::{FreeBalance, Module as GenericAsset};
+use crate::abi;
+
+/// Prefix of the address of a generic asset ERC20 precompile, see `asset_address`.
+pub const ASSET_ADDRESS_PREFIX: [u8; 4] = [0xcc, 0xcc, 0xcc, 0xcc];
+
+/// Gas charged for reading an asset balance or metadata.
+const READ_GAS: usize = 1_000;
+/// Gas charged for an asset transfer.
+const TRANSFER_GAS: usize = 20_000;
+
+const TOTAL_SUPPLY: [u8; 4] = [0x18, 0x16, 0x0d, 0xdd];
+const BALANCE_OF: [u8; 4] = [0x70, 0xa0, 0x82, 0x31];
+const TRANSFER: [u8; 4] = [0xa9, 0x05, 0x9c, 0xbb];
+const APPROVE: [u8; 4] = [0x09, 0x5e, 0xa7, 0xb3];
+const DECIMALS: [u8; 4] = [0x31, 0x3c, 0xe5, 0x67];
+const SYMBOL: [u8; 4] = [0x95, 0xd8, 0x9b, 0x41];
+
+/// The address of the ERC20 precompile of `asset_id`: `ASSET_ADDRESS_PREFIX` followed by the
+/// asset ID as a big-endian `u128`.
+pub fn asset_address<AssetId: UniqueSaturatedInto<u128>>(asset_id: AssetId) -> H160 {
+	let mut address = H160::zero();
+	address[0..4].copy_from_slice(&ASSET_ADDRESS_PREFIX);
+	address[4..20].copy_from_slice(&UniqueSaturatedInto::<u128>::unique_saturated_into(asset_id).to_be_bytes());
+	address
+}
+
+/// A transfer made by an ERC20 precompile, reverted with the EVM call frame making it.
+#[derive(Encode, Decode)]
+struct AssetTransfer<AccountId, Balance> {
+	from: AccountId,
+	to: AccountId,
+	amount: Balance,
+}
+
+/// The asset ID of an ERC20 precompile address, if it is one.
+fn address_asset<AssetId: TryFrom<u128>>(address: &H160) -> Option<AssetId> {
+	if address[0..4] != ASSET_ADDRESS_PREFIX {
+		return None;
+	}
+	let mut asset_id = [0u8; 16];
+	asset_id.copy_from_slice(&address[4..20]);
+	AssetId::try_from(u128::from_be_bytes(asset_id)).ok()
+}
+
+/// Expose every generic asset as an ERC20 contract at `asset_address(asset_id)`.
+///
+/// Supports `totalSupply`, `balanceOf`, `transfer`, `decimals` and `symbol`, the latter two from
+/// the `AssetInfo` of the asset. Generic assets have no allowances, so `approve` and
+/// `transferFrom` are rejected.
+///
+/// The holder of an EVM address is the account given by the address mapping `A`, which should
+/// match the one of `pallet_evm::Trait::AccountBalances`.
+///
+/// Transfers are made right away, failing the precompile if they cannot be made, and recorded in
+/// the call frame, so that they are reverted with it. Their events are deposited once the EVM
+/// operation is executed. They are rejected in static frames and when the precompile is not
+/// called directly, e.g. by `DELEGATECALL`.
+///
+/// The asset holding the EVM balances through `pallet_evm::Trait::AccountBalances`, if any, must
+/// not be exposed, as the EVM applies its balances after the transfers are made.
+pub struct GenericAssetPrecompiles<T, A>(PhantomData<(T, A)>);
+
+impl<T, A> Precompiles for GenericAssetPrecompiles<T, A> where
+	T: pallet_generic_asset::Trait,
+	A: AddressMapping<T::AccountId>,
+{
+	fn execute(
+		address: H160,
+		input: &[u8],
+		target_gas: Option<usize>,
+		context: &Context,
+		frame: &mut PrecompileFrame,
+	) -> Option<core::result::Result<(ExitSucceed, Vec<u8>, usize), ExitError>> {
+		let asset_id = address_asset::<T::AssetId>(&address)?;
+		Some(Self::execute_erc20(address, asset_id, input, target_gas, context, frame))
+	}
+
+	fn revert_change(address: H160, change: &[u8]) -> bool {
+		let asset_id = match address_asset::<T::AssetId>(&address) {
+			Some(asset_id) => asset_id,
+			None => return false,
+		};
+		// Later transfers are reverted first, so `to` holds the amount again.
+		if let Ok(transfer) = AssetTransfer::<T::AccountId, T::Balance>::decode(&mut &change[..]) {
+			if transfer.from != transfer.to {
+				<FreeBalance<T>>::mutate(&asset_id, &transfer.to, |balance| {
+					*balance = balance.saturating_sub(transfer.amount)
+				});
+				<FreeBalance<T>>::mutate(&asset_id, &transfer.from, |balance| {
+					*balance = balance.saturating_add(transfer.amount)
+				});
+			}
+		}
+		true
+	}
+
+	fn commit_change(address: H160, change: &[u8]) -> bool {
+		let asset_id = match address_asset::<T::AssetId>(&address) {
+			Some(asset_id) => asset_id,
+			None => return false,
+		};
+		if let Ok(transfer) = AssetTransfer::<T::AccountId, T::Balance>::decode(&mut &change[..]) {
+			if transfer.from != transfer.to {
+				let event = pallet_generic_asset::Event::<T>::Transferred(
+					asset_id,
+					transfer.from,
+					transfer.to,
+					transfer.amount,
+				);
+				frame_system::Module::<T>::deposit_event(<T as pallet_generic_asset::Trait>::Event::from(event));
+			}
+		}
+		true
+	}
+}
+
+impl<T, A> GenericAssetPrecompiles<T, A> where
+	T: pallet_generic_asset::Trait,
+	A: AddressMapping<T::AccountId>,
+{
+	fn execute_erc20(
+		address: H160,
+		asset_id: T::AssetId,
+		input: &[u8],
+		target_gas: Option<usize>,
+		context: &Context,
+		frame: &mut PrecompileFrame,
+	) -> Result<(ExitSucceed, Vec<u8>, usize), ExitError> {
+		if context.address != address {
+			return Err(ExitError::Other("asset precompiles must be called directly".into()));
+		}
+		if !context.apparent_value.is_zero() {
+			return Err(ExitError::Other("asset precompiles do not accept value".into()));
+		}
+
+		let (output, cost) = match abi::selector(input)? {
+			TOTAL_SUPPLY => {
+				let cost = abi::ensure_gas(target_gas, READ_GAS)?;
+				let total_supply = GenericAsset::<T>::total_issuance(asset_id);
+				(abi::encode_u256(Self::balance_to_u256(total_supply)), cost)
+			},
+			BALANCE_OF => {
+				let cost = abi::ensure_gas(target_gas, READ_GAS)?;
+				let who = A::into_account_id(abi::read_address(input, 0)?);
+				let balance = GenericAsset::<T>::free_balance(&asset_id, &who);
+				(abi::encode_u256(Self::balance_to_u256(balance)), cost)
+			},
+			DECIMALS => {
+				let cost = abi::ensure_gas(target_gas, READ_GAS)?;
+				let decimals = GenericAsset::<T>::asset_meta(asset_id).decimal_places();
+				(abi::encode_u256(U256::from(decimals)), cost)
+			},
+			SYMBOL => {
+				let cost = abi::ensure_gas(target_gas, READ_GAS)?;
+				(abi::encode_bytes(GenericAsset::<T>::asset_meta(asset_id).symbol()), cost)
+			},
+			TRANSFER => {
+				if frame.is_static() {
+					return Err(ExitError::Other("asset transfers are not allowed in a static call".into()));
+				}
+				let cost = abi::ensure_gas(target_gas, TRANSFER_GAS)?;
+				let from = A::into_account_id(context.caller);
+				let to = A::into_account_id(abi::read_address(input, 0)?);
+				let amount = Self::u256_to_balance(abi::read_u256(input, 1)?)?;
+				GenericAsset::<T>::make_transfer(&asset_id, &from, &to, amount)
+					.map_err(|_| ExitError::Other("asset transfer failed".into()))?;
+				frame.record_change(address, AssetTransfer { from, to, amount }.encode());
+				(abi::encode_bool(true), cost)
+			},
+			APPROVE => return Err(ExitError::Other("generic assets do not support allowances".into())),
+			_ => return Err(ExitError::Other("unknown asset precompile function".into())),
+		};
+
+		Ok((ExitSucceed::Returned, output, cost))
+	}
+
+	fn balance_to_u256(balance: T::Balance) -> U256 {
+		U256::from(UniqueSaturatedInto::<u128>::unique_saturated_into(balance))
+	}
+
+	fn u256_to_balance(value: U256) -> Result<T::Balance, ExitError> {
+		let too_large = || ExitError::Other("amount is too large for an asset balance".into());
+		if value > U256::from(u128::max_value()) {
+			return Err(too_large());
+		}
+		T::Balance::try_from(value.low_u128()).map_err(|_| too_large())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn asset_address_round_trips() {
+		let address = asset_address(16_000u32);
+
+		assert_eq!(&address[0..4], &ASSET_ADDRESS_PREFIX);
+		assert_eq!(address_asset::<u32>(&address), Some(16_000));
+		assert_eq!(address_asset::<u32>(&asset_address(u64::max_value())), None);
+		assert_eq!(address_asset::<u32>(&H160::from_low_u64_be(1)), None);
+	}
+}
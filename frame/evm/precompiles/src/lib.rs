@@ -0,0 +1,38 @@
+// Copyright 2019-2020 Plug New Zealand Ltd.
+// This file is part of Plug.
+
+// Plug is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Plug is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Plug.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Precompiles exposing the native functionality of a Plug runtime to EVM contracts.
+//!
+//! - `GenericAssetPrecompiles` exposes every `pallet_generic_asset` asset as an ERC20 contract at
+//!   `asset_address(asset_id)`.
+//! - `DoughnutPrecompile` lets contracts check whether the current EVM operation was dispatched
+//!   with a doughnut authorising a given module and method.
+//!
+//! Both can be combined as `type Precompiles = (GenericAssetPrecompiles<..>, DoughnutPrecompile<..>)`
+//! in the `pallet_evm::Trait` implementation.
+//!
+//! Asset transfers are made right away and recorded in the EVM call frame. They are reverted
+//! with reverted frames and at the end of a dry run.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+mod abi;
+mod doughnut;
+mod generic_asset;
+mod tests;
+
+pub use crate::doughnut::{DoughnutPrecompile, DOUGHNUT_PRECOMPILE_ADDRESS};
+pub use crate::generic_asset::{GenericAssetPrecompiles, ASSET_ADDRESS_PREFIX, asset_address};
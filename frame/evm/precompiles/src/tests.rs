@@ -0,0 +1,332 @@
+// Copyright 2019-2020 Plug New Zealand Ltd.
+// This file is part of Plug.
+
+// Plug is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Plug is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Plug.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Tests of the precompiles called by EVM contracts.
+
+#![cfg(test)]
+
+use crate::*;
+use sp_core::{H160, H256, U256};
+use sp_runtime::{
+	Perbill,
+	traits::{BlakeTwo256, IdentityLookup},
+	testing::Header,
+};
+use frame_support::{assert_ok, assert_err, impl_outer_event, impl_outer_origin, parameter_types};
+use frame_support::weights::Weight;
+use frame_system::{self as system, EventRecord};
+use pallet_evm::{AddressMapping, ConvertAccountId, EVMAccountBalances, EVMCurrencyAdapter, LinearGasToWeight};
+
+mod evm {
+	pub use pallet_evm::Event;
+}
+
+use pallet_balances as balances;
+use pallet_generic_asset as generic_asset;
+
+impl_outer_event! {
+	pub enum TestEvent for Test {
+		system, balances<T>, generic_asset<T>, evm<T>,
+	}
+}
+impl_outer_origin! {
+	pub enum Origin for Test where system = frame_system { }
+}
+
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct Test;
+parameter_types! {
+	pub const BlockHashCount: u64 = 250;
+	pub const MaximumBlockWeight: Weight = 1024;
+	pub const MaximumBlockLength: u32 = 2 * 1024;
+	pub const AvailableBlockRatio: Perbill = Perbill::one();
+}
+impl frame_system::Trait for Test {
+	type Origin = Origin;
+	type Index = u64;
+	type BlockNumber = u64;
+	type Hash = H256;
+	type Call = ();
+	type Hashing = BlakeTwo256;
+	type AccountId = u64;
+	type Lookup = IdentityLookup<Self::AccountId>;
+	type Header = Header;
+	type Event = TestEvent;
+	type BlockHashCount = BlockHashCount;
+	type MaximumBlockWeight = MaximumBlockWeight;
+	type AvailableBlockRatio = AvailableBlockRatio;
+	type MaximumBlockLength = MaximumBlockLength;
+	type Version = ();
+	type ModuleToIndex = ();
+	type Doughnut = ();
+	type DelegatedDispatchVerifier = ();
+}
+parameter_types! {
+	pub const ExistentialDeposit: u64 = 1;
+	pub const CreationFee: u64 = 0;
+}
+impl pallet_balances::Trait for Test {
+	type Balance = u64;
+	type Event = TestEvent;
+	type DustRemoval = ();
+	type ExistentialDeposit = ExistentialDeposit;
+	type OnReapAccount = ();
+	type OnNewAccount = ();
+	type TransferPayment = ();
+	type CreationFee = CreationFee;
+}
+impl pallet_generic_asset::Trait for Test {
+	type Balance = u64;
+	type AssetId = u32;
+	type Event = TestEvent;
+}
+parameter_types! {
+	pub const MinimumPeriod: u64 = 1;
+}
+impl pallet_timestamp::Trait for Test {
+	type Moment = u64;
+	type OnTimestampSet = ();
+	type MinimumPeriod = MinimumPeriod;
+}
+
+/// Use the account id as the low bytes of the address, and back.
+pub struct LowBytesAddress;
+impl ConvertAccountId<u64> for LowBytesAddress {
+	fn convert_account_id(account_id: &u64) -> H160 {
+		H160::from_low_u64_be(*account_id)
+	}
+}
+impl AddressMapping<u64> for LowBytesAddress {
+	fn into_account_id(address: H160) -> u64 {
+		address.to_low_u64_be()
+	}
+}
+
+parameter_types! {
+	pub const ChainId: u64 = 42;
+	pub const GasWeight: Weight = 0;
+	pub const BlockGasLimit: u32 = 10_000_000;
+}
+impl pallet_evm::Trait for Test {
+	type FeeCalculator = ();
+	type ConvertAccountId = LowBytesAddress;
+	type Currency = Balances;
+	type AccountBalances = EVMAccountBalances<Self>;
+	type OnChargeEVMFee = EVMCurrencyAdapter<()>;
+	type FindAuthor = ();
+	type Event = TestEvent;
+	type Precompiles = GenericAssetPrecompiles<Self, LowBytesAddress>;
+	type ChainId = ChainId;
+	type GasToWeight = LinearGasToWeight<GasWeight>;
+	type BlockGasLimit = BlockGasLimit;
+}
+
+type System = frame_system::Module<Test>;
+type Balances = pallet_balances::Module<Test>;
+type GenericAsset = pallet_generic_asset::Module<Test>;
+type EVM = pallet_evm::Module<Test>;
+
+const ALICE: u64 = 1;
+const BOB: u64 = 2;
+const ASSET_ID: u32 = 16001;
+
+const CALL: u8 = 0xf1;
+const DELEGATECALL: u8 = 0xf4;
+const STATICCALL: u8 = 0xfa;
+
+fn new_test_ext() -> sp_io::TestExternalities {
+	let mut t = frame_system::GenesisConfig::default().build_storage::<Test>().unwrap();
+	pallet_generic_asset::GenesisConfig::<Test> {
+		assets: vec![ASSET_ID],
+		endowed_accounts: vec![ALICE],
+		initial_balance: 1000,
+		next_asset_id: 17000,
+		staking_asset_id: 16000,
+		spending_asset_id: ASSET_ID,
+		permissions: vec![],
+		asset_meta: vec![],
+	}.assimilate_storage(&mut t).unwrap();
+	let mut ext = sp_io::TestExternalities::new(t);
+	ext.execute_with(|| System::set_block_number(1));
+	ext
+}
+
+/// Runtime code forwarding its call data to the asset precompile with the call opcode `op`, then
+/// returning whether the call succeeded, or reverting if `revert` is set.
+fn forwarder(op: u8, revert: bool) -> Vec<u8> {
+	// CALLDATACOPY(0, 0, CALLDATASIZE)
+	let mut code = vec![0x36, 0x60, 0x00, 0x60, 0x00, 0x37];
+	// retSize, retOffset, argsSize, argsOffset
+	code.extend_from_slice(&[0x60, 0x20, 0x60, 0x00, 0x36, 0x60, 0x00]);
+	if op == CALL {
+		// value
+		code.extend_from_slice(&[0x60, 0x00]);
+	}
+	code.push(0x73);
+	code.extend_from_slice(asset_address(ASSET_ID).as_bytes());
+	// GAS, then the call itself
+	code.extend_from_slice(&[0x5a, op]);
+	if revert {
+		// REVERT(0, 0)
+		code.extend_from_slice(&[0x60, 0x00, 0x60, 0x00, 0xfd]);
+	} else {
+		// MSTORE(0, success), RETURN(0, 32)
+		code.extend_from_slice(&[0x60, 0x00, 0x52, 0x60, 0x20, 0x60, 0x00, 0xf3]);
+	}
+	code
+}
+
+/// Deploy `code` from `ALICE`, returning the address of the contract.
+fn deploy(code: Vec<u8>) -> H160 {
+	// CODECOPY(0, 11, len), RETURN(0, len)
+	let mut init = vec![0x60, code.len() as u8, 0x80, 0x60, 0x0b, 0x60, 0x00, 0x39, 0x60, 0x00, 0xf3];
+	init.extend(code);
+	assert_ok!(EVM::create(Origin::signed(ALICE), init, U256::zero(), 1_000_000, U256::zero(), None));
+
+	System::events().into_iter()
+		.filter_map(|EventRecord { event, .. }| match event {
+			TestEvent::evm(pallet_evm::RawEvent::Created(address)) => Some(address),
+			_ => None,
+		})
+		.last()
+		.expect("contract is created")
+}
+
+/// Call data of `transfer(to, amount)`.
+fn transfer_input(to: u64, amount: u64) -> Vec<u8> {
+	let mut input = vec![0xa9, 0x05, 0x9c, 0xbb];
+	input.extend_from_slice(H256::from(LowBytesAddress::convert_account_id(&to)).as_bytes());
+	input.extend_from_slice(H256::from_low_u64_be(amount).as_bytes());
+	input
+}
+
+fn call(contract: H160, input: Vec<u8>) -> frame_support::dispatch::DispatchResult {
+	EVM::call(Origin::signed(ALICE), contract, input, U256::zero(), 1_000_000, U256::zero(), None)
+}
+
+fn balance(who: u64) -> u64 {
+	GenericAsset::free_balance(&ASSET_ID, &who)
+}
+
+/// The `Transferred` events deposited by the asset precompile, as `(from, to, amount)`.
+fn transferred_events() -> Vec<(u64, u64, u64)> {
+	System::events().into_iter()
+		.filter_map(|EventRecord { event, .. }| match event {
+			TestEvent::generic_asset(pallet_generic_asset::RawEvent::Transferred(_, from, to, amount)) =>
+				Some((from, to, amount)),
+			_ => None,
+		})
+		.collect()
+}
+
+#[test]
+fn contract_transfers_its_own_assets() {
+	new_test_ext().execute_with(|| {
+		let contract = deploy(forwarder(CALL, false));
+		let holder = LowBytesAddress::into_account_id(contract);
+		assert_ok!(GenericAsset::make_transfer(&ASSET_ID, &ALICE, &holder, 300));
+
+		assert_ok!(call(contract, transfer_input(BOB, 100)));
+		assert_eq!(balance(holder), 200);
+		assert_eq!(balance(BOB), 100);
+		assert_eq!(transferred_events(), vec![(holder, BOB, 100)]);
+	});
+}
+
+#[test]
+fn transfer_above_the_balance_fails_the_precompile() {
+	new_test_ext().execute_with(|| {
+		let contract = deploy(forwarder(CALL, false));
+		let holder = LowBytesAddress::into_account_id(contract);
+		assert_ok!(GenericAsset::make_transfer(&ASSET_ID, &ALICE, &holder, 300));
+
+		let info = EVM::dry_run(
+			LowBytesAddress::convert_account_id(&ALICE),
+			Some(contract),
+			transfer_input(BOB, 301),
+			U256::zero(),
+			1_000_000,
+			U256::zero(),
+		).unwrap();
+		// The precompile fails, the contract returns `false`.
+		assert_eq!(info.output, H256::zero().as_bytes().to_vec());
+
+		assert_ok!(call(contract, transfer_input(BOB, 301)));
+		assert_eq!(balance(holder), 300);
+		assert_eq!(balance(BOB), 0);
+		assert!(transferred_events().is_empty());
+	});
+}
+
+#[test]
+fn delegate_call_cannot_transfer_assets_of_the_caller() {
+	new_test_ext().execute_with(|| {
+		let contract = deploy(forwarder(DELEGATECALL, false));
+
+		// The precompile fails, the contract returns `false`.
+		assert_ok!(call(contract, transfer_input(BOB, 100)));
+		assert_eq!(balance(ALICE), 1000);
+		assert_eq!(balance(BOB), 0);
+	});
+}
+
+#[test]
+fn static_call_cannot_transfer_assets() {
+	new_test_ext().execute_with(|| {
+		let contract = deploy(forwarder(STATICCALL, false));
+		let holder = LowBytesAddress::into_account_id(contract);
+		assert_ok!(GenericAsset::make_transfer(&ASSET_ID, &ALICE, &holder, 300));
+
+		assert_ok!(call(contract, transfer_input(BOB, 100)));
+		assert_eq!(balance(holder), 300);
+		assert_eq!(balance(BOB), 0);
+	});
+}
+
+#[test]
+fn reverted_call_drops_asset_transfer() {
+	new_test_ext().execute_with(|| {
+		let contract = deploy(forwarder(CALL, true));
+		let holder = LowBytesAddress::into_account_id(contract);
+		assert_ok!(GenericAsset::make_transfer(&ASSET_ID, &ALICE, &holder, 300));
+
+		assert_err!(call(contract, transfer_input(BOB, 100)), pallet_evm::Error::<Test>::ExitReasonRevert);
+		assert_eq!(balance(holder), 300);
+		assert_eq!(balance(BOB), 0);
+		assert!(transferred_events().is_empty());
+	});
+}
+
+#[test]
+fn dry_run_does_not_transfer_assets() {
+	new_test_ext().execute_with(|| {
+		let contract = deploy(forwarder(CALL, false));
+		let holder = LowBytesAddress::into_account_id(contract);
+		assert_ok!(GenericAsset::make_transfer(&ASSET_ID, &ALICE, &holder, 300));
+
+		let info = EVM::dry_run(
+			LowBytesAddress::convert_account_id(&ALICE),
+			Some(contract),
+			transfer_input(BOB, 100),
+			U256::zero(),
+			1_000_000,
+			U256::zero(),
+		).unwrap();
+		assert_eq!(info.output, H256::from_low_u64_be(1).as_bytes().to_vec());
+		assert_eq!(balance(holder), 300);
+		assert_eq!(balance(BOB), 0);
+	});
+}
@@ -0,0 +1,99 @@
+// Copyright 2019-2020
+//     by  Centrality Investments Ltd.
+//     and Parity Technologies (UK) Ltd.
+// This file is part of Plug-blockchain.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Runtime API definition required by the Ethereum-compatible EVM RPC extensions.
+//!
+//! This API should be imported and implemented by the runtime,
+//! of a node that wants to use the custom RPC extension
+//! adding `eth_*` access methods.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use pallet_evm::{Account, Bloom, ExecutionInfo, Receipt, Trace};
+use sp_core::{H160, H256, U256};
+use sp_runtime::{DispatchError, traits::Block as BlockT};
+use sp_std::vec::Vec;
+
+sp_api::decl_runtime_apis! {
+	/// The API to query and call into the EVM of the runtime.
+	pub trait EVMRuntimeApi {
+		/// Returns the EIP-155 chain ID of the EVM.
+		fn chain_id() -> u64;
+
+		/// Returns the minimum gas price accepted by the EVM.
+		fn gas_price() -> U256;
+
+		/// Returns the nonce and native balance of an EVM account.
+		fn account_basic(address: H160) -> Account;
+
+		/// Returns the code of an EVM account, empty if it is not a contract.
+		fn account_code_at(address: H160) -> Vec<u8>;
+
+		/// Returns the value of the storage slot `index` of an EVM account.
+		fn storage_at(address: H160, index: U256) -> H256;
+
+		/// Returns the maximum amount of gas the EVM operations of a block can use.
+		fn block_gas_limit() -> U256;
+
+		/// Returns the timestamp of the block, in seconds.
+		fn timestamp() -> u64;
+
+		/// Returns the receipts of the EVM operations executed in the block.
+		fn block_receipts() -> Vec<Receipt>;
+
+		/// Returns the bloom filter of the logs emitted in the block.
+		fn block_logs_bloom() -> Bloom;
+
+		/// Execute an EVM call from `from` to `to`, or a contract creation if `to` is `None`,
+		/// without applying its state changes.
+		///
+		/// Returns the output, exit status, logs and gas used of the operation. No fee is charged
+		/// and the nonce of `from` is not checked.
+		fn dry_run(
+			from: H160,
+			to: Option<H160>,
+			data: Vec<u8>,
+			value: U256,
+			gas_limit: U256,
+			gas_price: Option<U256>,
+		) -> Result<ExecutionInfo, DispatchError>;
+
+		/// Trace an EVM call from `from` to `to`, or a contract creation if `to` is `None`.
+		///
+		/// Tracing is only available when the runtime is executed natively, `Ok(None)` is
+		/// returned otherwise. The state changes are discarded together with the runtime API call.
+		fn trace_call(
+			from: H160,
+			to: Option<H160>,
+			data: Vec<u8>,
+			value: U256,
+			gas_limit: U256,
+			gas_price: Option<U256>,
+		) -> Result<Option<Trace>, DispatchError>;
+
+		/// Execute the block with the given `header` and `extrinsics`, returning the traces of its
+		/// EVM operations.
+		///
+		/// Must be called at the parent of the block. Tracing is only available when the runtime
+		/// is executed natively, `None` is returned otherwise.
+		fn trace_block(
+			header: <Block as BlockT>::Header,
+			extrinsics: Vec<<Block as BlockT>::Extrinsic>,
+		) -> Option<Vec<Trace>>;
+	}
+}
@@ -0,0 +1,169 @@
+// Copyright 2019-2020
+//     by  Centrality Investments Ltd.
+//     and Parity Technologies (UK) Ltd.
+// This file is part of Plug-blockchain.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! `debug_*` RPC methods tracing EVM operations.
+//!
+//! Tracing re-executes the EVM operations on top of historical state, and is only available when
+//! the runtime is executed natively, e.g. with `--execution native`.
+
+use std::{marker::PhantomData, sync::Arc};
+use jsonrpc_core::{Error as RpcError, ErrorCode, Result};
+use jsonrpc_derive::rpc;
+use sc_client_api::BlockBackend;
+use sp_api::ProvideRuntimeApi;
+use sp_blockchain::HeaderBackend;
+use sp_core::H256;
+use sp_runtime::{
+	generic::BlockId,
+	traits::{Block as BlockT, Header as HeaderT, NumberFor, UniqueSaturatedFrom,
+		UniqueSaturatedInto, Zero},
+};
+use pallet_evm_rpc_runtime_api::EVMRuntimeApi;
+use crate::{
+	Error, BlockNumber, CallRequest, TransactionTrace,
+	client_error_into_rpc_err, runtime_error_into_rpc_err, execution_error_into_rpc_err,
+};
+
+pub use self::gen_client::Client as DebugClient;
+
+/// The number of blocks searched back from the best block for the transaction of a
+/// `debug_traceTransaction` request.
+const MAX_TRACE_SEARCH_BLOCKS: u64 = 1_024;
+
+/// EVM tracing RPC methods.
+#[rpc]
+pub trait DebugApi {
+	/// Re-execute the block of an EVM transaction and return the trace of the transaction.
+	#[rpc(name = "debug_traceTransaction")]
+	fn trace_transaction(&self, transaction_hash: H256) -> Result<TransactionTrace>;
+
+	/// Trace a call, or a contract creation if `to` is missing, against the state of the given
+	/// block. The state changes are discarded.
+	#[rpc(name = "debug_traceCall")]
+	fn trace_call(&self, request: CallRequest, number: Option<BlockNumber>) -> Result<TransactionTrace>;
+}
+
+/// A struct that implements the [`DebugApi`].
+pub struct Debug<B, C> {
+	client: Arc<C>,
+	_marker: PhantomData<B>,
+}
+
+impl<B, C> Debug<B, C> {
+	/// Create new `Debug` with the given reference to the client.
+	pub fn new(client: Arc<C>) -> Self {
+		Debug { client, _marker: Default::default() }
+	}
+}
+
+impl<B, C> Debug<B, C> where
+	B: BlockT<Hash = H256>,
+	C: ProvideRuntimeApi<B> + HeaderBackend<B> + BlockBackend<B>,
+	C::Api: EVMRuntimeApi<B>,
+{
+	/// Find the hash of the block holding the EVM operation `transaction_hash`.
+	fn find_transaction_block(&self, transaction_hash: H256) -> Result<Option<H256>> {
+		let best = UniqueSaturatedInto::<u64>::unique_saturated_into(self.client.info().best_number);
+		for number in (best.saturating_sub(MAX_TRACE_SEARCH_BLOCKS - 1)..=best).rev() {
+			let number = NumberFor::<B>::unique_saturated_from(number);
+			let hash = match self.client.hash(number).map_err(client_error_into_rpc_err)? {
+				Some(hash) => hash,
+				None => continue,
+			};
+			let receipts = self.client.runtime_api().block_receipts(&BlockId::Hash(hash))
+				.map_err(runtime_error_into_rpc_err)?;
+			if receipts.iter().any(|receipt| receipt.transaction_hash == transaction_hash) {
+				return Ok(Some(hash));
+			}
+		}
+
+		Ok(None)
+	}
+}
+
+impl<B, C> DebugApi for Debug<B, C> where
+	B: BlockT<Hash = H256>,
+	C: Send + Sync + 'static + ProvideRuntimeApi<B> + HeaderBackend<B> + BlockBackend<B>,
+	C::Api: EVMRuntimeApi<B>,
+{
+	fn trace_transaction(&self, transaction_hash: H256) -> Result<TransactionTrace> {
+		let hash = self.find_transaction_block(transaction_hash)?
+			.ok_or_else(|| RpcError::invalid_params(format!(
+				"Transaction not found in the last {} blocks", MAX_TRACE_SEARCH_BLOCKS,
+			)))?;
+		let at = BlockId::Hash(hash);
+		let header = self.client.header(at)
+			.map_err(client_error_into_rpc_err)?
+			.ok_or_else(|| RpcError::invalid_params("Unknown block"))?;
+		let extrinsics = self.client.block_body(&at)
+			.map_err(client_error_into_rpc_err)?
+			.unwrap_or_default();
+		let parent = BlockId::Hash(*header.parent_hash());
+
+		let traces = self.client.runtime_api()
+			.trace_block(&parent, header, extrinsics)
+			.map_err(runtime_error_into_rpc_err)?
+			.ok_or_else(tracing_unavailable)?;
+
+		traces.into_iter()
+			.find(|trace| trace.transaction_hash == transaction_hash)
+			.map(Into::into)
+			.ok_or_else(|| RpcError {
+				code: ErrorCode::ServerError(Error::RuntimeError as i64),
+				message: "Transaction was not executed when re-executing its block".into(),
+				data: None,
+			})
+	}
+
+	fn trace_call(&self, request: CallRequest, number: Option<BlockNumber>) -> Result<TransactionTrace> {
+		let at = match number.unwrap_or_default() {
+			BlockNumber::Num(number) => BlockId::Number(NumberFor::<B>::unique_saturated_from(number)),
+			BlockNumber::Earliest => BlockId::Number(Zero::zero()),
+			BlockNumber::Latest | BlockNumber::Pending => BlockId::Hash(self.client.info().best_hash),
+		};
+		let CallRequest { from, to, gas_price, gas, value, data, .. } = request;
+		let api = self.client.runtime_api();
+		let gas_limit = match gas {
+			Some(gas) => gas,
+			None => api.block_gas_limit(&at).map_err(runtime_error_into_rpc_err)?,
+		};
+
+		api.trace_call(
+			&at,
+			from.unwrap_or_default(),
+			to,
+			data.map(|d| d.0).unwrap_or_default(),
+			value.unwrap_or_default(),
+			gas_limit,
+			gas_price,
+		)
+			.map_err(runtime_error_into_rpc_err)?
+			.map_err(execution_error_into_rpc_err)?
+			.map(Into::into)
+			.ok_or_else(tracing_unavailable)
+	}
+}
+
+/// The error returned when the runtime was not executed natively.
+fn tracing_unavailable() -> RpcError {
+	RpcError {
+		code: ErrorCode::ServerError(Error::TracingUnavailable as i64),
+		message: "EVM tracing requires native runtime execution".into(),
+		data: Some("Run the node with `--execution native`".into()),
+	}
+}
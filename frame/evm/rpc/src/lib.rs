@@ -0,0 +1,626 @@
+// Copyright 2019-2020
+//     by  Centrality Investments Ltd.
+//     and Parity Technologies (UK) Ltd.
+// This file is part of Plug-blockchain.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Ethereum-compatible RPC interface for the EVM module.
+//!
+//! Implements the subset of the `eth_*` JSON-RPC namespace needed by wallets and tooling such as
+//! web3 or ethers. Substrate blocks are presented as Ethereum-style blocks.
+
+mod debug;
+mod types;
+
+use std::{marker::PhantomData, sync::Arc};
+use codec::Encode;
+use futures::future::TryFutureExt;
+use jsonrpc_core::{BoxFuture, Error as RpcError, ErrorCode, Result, futures::{Future, future}};
+use jsonrpc_derive::rpc;
+use sc_client_api::BlockBackend;
+use sha3::{Digest, Keccak256};
+use sp_api::ProvideRuntimeApi;
+use sp_blockchain::HeaderBackend;
+use sp_core::{Bytes, H160, H256, U256};
+use sp_runtime::{
+	generic::BlockId,
+	traits::{Block as BlockT, Header as HeaderT, NumberFor, UniqueSaturatedFrom,
+		UniqueSaturatedInto, Zero},
+};
+use sp_transaction_pool::{TransactionPool, TransactionSource};
+
+use pallet_evm::{ExecutionInfo, ExitStatus};
+
+pub use pallet_evm_rpc_runtime_api::EVMRuntimeApi;
+pub use self::gen_client::Client as EthClient;
+pub use self::debug::{Debug, DebugApi, DebugClient};
+pub use self::types::{
+	Block, BlockNumber, CallFrame, CallRequest, Filter, Log, Receipt, TraceLog, TransactionTrace,
+	VariadicValue, decode_revert_reason,
+};
+
+/// The maximum number of blocks searched by a single `eth_getLogs` request.
+const MAX_LOGS_BLOCK_RANGE: u64 = 1_024;
+
+/// Converts a raw signed Ethereum transaction into an extrinsic of the runtime.
+pub trait ConvertTransaction<Extrinsic> {
+	/// Decode the RLP encoded `transaction` and wrap it into an extrinsic.
+	fn convert_transaction(&self, transaction: &[u8]) -> std::result::Result<Extrinsic, String>;
+}
+
+/// Rejects every transaction, for runtimes which do not accept Ethereum transactions.
+impl<Extrinsic> ConvertTransaction<Extrinsic> for () {
+	fn convert_transaction(&self, _: &[u8]) -> std::result::Result<Extrinsic, String> {
+		Err("Ethereum transactions are not supported by this runtime".into())
+	}
+}
+
+/// Ethereum-compatible RPC methods.
+#[rpc]
+pub trait EthApi {
+	/// Returns the EIP-155 chain ID of the EVM.
+	#[rpc(name = "eth_chainId")]
+	fn chain_id(&self) -> Result<U256>;
+
+	/// Returns the number of the best block.
+	#[rpc(name = "eth_blockNumber")]
+	fn block_number(&self) -> Result<U256>;
+
+	/// Returns the minimum gas price accepted by the EVM.
+	#[rpc(name = "eth_gasPrice")]
+	fn gas_price(&self) -> Result<U256>;
+
+	/// Returns the native balance of `address`.
+	#[rpc(name = "eth_getBalance")]
+	fn balance(&self, address: H160, number: Option<BlockNumber>) -> Result<U256>;
+
+	/// Returns the code of `address`, empty if it is not a contract.
+	#[rpc(name = "eth_getCode")]
+	fn code_at(&self, address: H160, number: Option<BlockNumber>) -> Result<Bytes>;
+
+	/// Returns the value of the storage slot `index` of `address`.
+	#[rpc(name = "eth_getStorageAt")]
+	fn storage_at(&self, address: H160, index: U256, number: Option<BlockNumber>) -> Result<H256>;
+
+	/// Returns the nonce of `address`.
+	#[rpc(name = "eth_getTransactionCount")]
+	fn transaction_count(&self, address: H160, number: Option<BlockNumber>) -> Result<U256>;
+
+	/// Returns the block with the given number.
+	///
+	/// Transactions are always returned as the hashes of the EVM operations of the block.
+	#[rpc(name = "eth_getBlockByNumber")]
+	fn block_by_number(&self, number: BlockNumber, full: bool) -> Result<Option<Block>>;
+
+	/// Returns the block with the given hash.
+	///
+	/// Transactions are always returned as the hashes of the EVM operations of the block.
+	#[rpc(name = "eth_getBlockByHash")]
+	fn block_by_hash(&self, hash: H256, full: bool) -> Result<Option<Block>>;
+
+	/// Returns the receipts of the EVM operations of the given block.
+	#[rpc(name = "eth_getBlockReceipts")]
+	fn block_receipts(&self, number: BlockNumber) -> Result<Option<Vec<Receipt>>>;
+
+	/// Returns the logs matching `filter`.
+	#[rpc(name = "eth_getLogs")]
+	fn logs(&self, filter: Filter) -> Result<Vec<Log>>;
+
+	/// Executes a call to a contract and returns its output.
+	///
+	/// This call is performed locally without submitting any transactions. Thus executing this
+	/// won't change any state.
+	#[rpc(name = "eth_call")]
+	fn call(&self, request: CallRequest, number: Option<BlockNumber>) -> Result<Bytes>;
+
+	/// Executes a call or contract creation locally and returns the lowest gas limit it succeeds
+	/// with.
+	///
+	/// The limit is found by binary search between the gas used and the requested gas, or the
+	/// block gas limit if none was requested.
+	#[rpc(name = "eth_estimateGas")]
+	fn estimate_gas(&self, request: CallRequest, number: Option<BlockNumber>) -> Result<U256>;
+
+	/// Submits a raw signed Ethereum transaction, returning its hash.
+	#[rpc(name = "eth_sendRawTransaction")]
+	fn send_raw_transaction(&self, transaction: Bytes) -> BoxFuture<H256>;
+}
+
+/// A struct that implements the [`EthApi`].
+pub struct Eth<B, C, P, CT> {
+	client: Arc<C>,
+	pool: Arc<P>,
+	convert_transaction: CT,
+	_marker: PhantomData<B>,
+}
+
+impl<B, C, P, CT> Eth<B, C, P, CT> {
+	/// Create new `Eth` with the given reference to the client and the transaction pool.
+	///
+	/// `convert_transaction` wraps raw Ethereum transactions into extrinsics of the runtime.
+	pub fn new(client: Arc<C>, pool: Arc<P>, convert_transaction: CT) -> Self {
+		Eth { client, pool, convert_transaction, _marker: Default::default() }
+	}
+}
+
+/// Error type of this RPC api.
+pub enum Error {
+	/// The call to runtime failed.
+	RuntimeError,
+	/// The EVM execution failed.
+	ExecutionError,
+	/// The transaction was rejected.
+	TransactionRejected,
+	/// Tracing is not available, the runtime was not executed natively.
+	TracingUnavailable,
+	/// The EVM execution reverted.
+	Reverted,
+}
+
+impl<B, C, P, CT> Eth<B, C, P, CT> where
+	B: BlockT<Hash = H256>,
+	C: ProvideRuntimeApi<B> + HeaderBackend<B> + BlockBackend<B>,
+	C::Api: EVMRuntimeApi<B>,
+{
+	/// The requested gas limit, defaulting to the block gas limit.
+	fn gas_limit(&self, at: &BlockId<B>, gas: Option<U256>) -> Result<U256> {
+		match gas {
+			Some(gas) => Ok(gas),
+			None => self.client.runtime_api().block_gas_limit(at).map_err(runtime_error_into_rpc_err),
+		}
+	}
+
+	/// Execute `request` with `gas_limit` at `at`, without applying its state changes.
+	fn dry_run(&self, at: &BlockId<B>, request: &CallRequest, gas_limit: U256) -> Result<ExecutionInfo> {
+		self.client.runtime_api()
+			.dry_run(
+				at,
+				request.from.unwrap_or_default(),
+				request.to,
+				request.data.clone().map(|d| d.0).unwrap_or_default(),
+				request.value.unwrap_or_default(),
+				gas_limit,
+				request.gas_price,
+			)
+			.map_err(runtime_error_into_rpc_err)?
+			.map_err(execution_error_into_rpc_err)
+	}
+
+	/// Resolve an Ethereum block selector, defaulting to the best block.
+	fn block_id(&self, number: Option<BlockNumber>) -> BlockId<B> {
+		match number.unwrap_or_default() {
+			BlockNumber::Num(number) => BlockId::Number(NumberFor::<B>::unique_saturated_from(number)),
+			BlockNumber::Earliest => BlockId::Number(Zero::zero()),
+			BlockNumber::Latest | BlockNumber::Pending => BlockId::Hash(self.client.info().best_hash),
+		}
+	}
+
+	/// Build the Ethereum-style view of the block `id`.
+	fn block(&self, id: BlockId<B>) -> Result<Option<Block>> {
+		let header = match self.client.header(id).map_err(client_error_into_rpc_err)? {
+			Some(header) => header,
+			None => return Ok(None),
+		};
+		let hash = header.hash();
+		let at = BlockId::Hash(hash);
+		let extrinsics = self.client.block_body(&at)
+			.map_err(client_error_into_rpc_err)?
+			.unwrap_or_default();
+		let api = self.client.runtime_api();
+		let timestamp = api.timestamp(&at).map_err(runtime_error_into_rpc_err)?;
+		let receipts = api.block_receipts(&at).map_err(runtime_error_into_rpc_err)?;
+		let logs_bloom = api.block_logs_bloom(&at).map_err(runtime_error_into_rpc_err)?;
+		let gas_limit = api.block_gas_limit(&at).map_err(runtime_error_into_rpc_err)?;
+
+		Ok(Some(Block {
+			hash: Some(hash),
+			parent_hash: *header.parent_hash(),
+			// Keccak-256 hash of the RLP encoded empty list.
+			uncles_hash: H256::from_slice(Keccak256::digest(&[0xc0]).as_slice()),
+			author: H160::zero(),
+			miner: H160::zero(),
+			state_root: *header.state_root(),
+			transactions_root: *header.extrinsics_root(),
+			receipts_root: H256::zero(),
+			number: Some(U256::from(UniqueSaturatedInto::<u64>::unique_saturated_into(*header.number()))),
+			gas_used: receipts.last().map(|r| r.cumulative_gas_used).unwrap_or_default(),
+			gas_limit,
+			extra_data: Bytes(Vec::new()),
+			logs_bloom,
+			timestamp: U256::from(timestamp),
+			difficulty: U256::zero(),
+			total_difficulty: Some(U256::zero()),
+			seal_fields: Vec::new(),
+			uncles: Vec::new(),
+			transactions: receipts.iter().map(|r| r.transaction_hash).collect(),
+			size: Some(U256::from(header.encoded_size() + extrinsics.encoded_size())),
+		}))
+	}
+
+	/// Build the receipts of the block `hash`, keeping only the logs accepted by `filter_log`.
+	fn receipts<F: Fn(&pallet_evm::Log) -> bool>(
+		&self,
+		hash: H256,
+		number: NumberFor<B>,
+		filter_log: F,
+	) -> Result<Vec<Receipt>> {
+		let receipts = self.client.runtime_api().block_receipts(&BlockId::Hash(hash))
+			.map_err(runtime_error_into_rpc_err)?;
+		let block_number = U256::from(UniqueSaturatedInto::<u64>::unique_saturated_into(number));
+
+		let mut log_index = 0u32;
+		Ok(receipts.into_iter().map(|receipt| {
+			let transaction_hash = receipt.transaction_hash;
+			let transaction_index = U256::from(receipt.transaction_index);
+			let logs = receipt.logs.into_iter().enumerate()
+				.map(|(transaction_log_index, log)| {
+					let index = log_index;
+					log_index += 1;
+					(index, transaction_log_index, log)
+				})
+				.filter(|(_, _, log)| filter_log(log))
+				.map(|(index, transaction_log_index, log)| Log {
+					address: log.address,
+					topics: log.topics,
+					data: Bytes(log.data),
+					block_hash: hash,
+					block_number,
+					transaction_hash,
+					transaction_index,
+					log_index: U256::from(index),
+					transaction_log_index: U256::from(transaction_log_index),
+					removed: false,
+				})
+				.collect();
+
+			Receipt {
+				transaction_hash,
+				transaction_index,
+				block_hash: hash,
+				block_number,
+				from: receipt.from,
+				to: receipt.to,
+				contract_address: receipt.contract_address,
+				cumulative_gas_used: receipt.cumulative_gas_used,
+				gas_used: receipt.gas_used,
+				logs,
+				logs_bloom: receipt.logs_bloom,
+				status: U256::from(receipt.status as u8),
+			}
+		}).collect())
+	}
+
+	/// Resolve the block number of an Ethereum block selector.
+	fn block_number_of(&self, number: Option<BlockNumber>) -> Result<NumberFor<B>> {
+		match self.block_id(number) {
+			BlockId::Number(number) => Ok(number),
+			BlockId::Hash(hash) => self.client.number(hash)
+				.map_err(client_error_into_rpc_err)?
+				.ok_or_else(|| RpcError::invalid_params("Unknown block")),
+		}
+	}
+}
+
+impl<B, C, P, CT> EthApi for Eth<B, C, P, CT> where
+	B: BlockT<Hash = H256>,
+	C: Send + Sync + 'static + ProvideRuntimeApi<B> + HeaderBackend<B> + BlockBackend<B>,
+	C::Api: EVMRuntimeApi<B>,
+	P: TransactionPool<Block = B> + 'static,
+	CT: ConvertTransaction<<B as BlockT>::Extrinsic> + Send + Sync + 'static,
+{
+	fn chain_id(&self) -> Result<U256> {
+		let at = self.block_id(None);
+		self.client.runtime_api().chain_id(&at)
+			.map(U256::from)
+			.map_err(runtime_error_into_rpc_err)
+	}
+
+	fn block_number(&self) -> Result<U256> {
+		let number = UniqueSaturatedInto::<u64>::unique_saturated_into(self.client.info().best_number);
+		Ok(U256::from(number))
+	}
+
+	fn gas_price(&self) -> Result<U256> {
+		let at = self.block_id(None);
+		self.client.runtime_api().gas_price(&at).map_err(runtime_error_into_rpc_err)
+	}
+
+	fn balance(&self, address: H160, number: Option<BlockNumber>) -> Result<U256> {
+		let at = self.block_id(number);
+		self.client.runtime_api().account_basic(&at, address)
+			.map(|account| account.balance)
+			.map_err(runtime_error_into_rpc_err)
+	}
+
+	fn code_at(&self, address: H160, number: Option<BlockNumber>) -> Result<Bytes> {
+		let at = self.block_id(number);
+		self.client.runtime_api().account_code_at(&at, address)
+			.map(Bytes)
+			.map_err(runtime_error_into_rpc_err)
+	}
+
+	fn storage_at(&self, address: H160, index: U256, number: Option<BlockNumber>) -> Result<H256> {
+		let at = self.block_id(number);
+		self.client.runtime_api().storage_at(&at, address, index)
+			.map_err(runtime_error_into_rpc_err)
+	}
+
+	fn transaction_count(&self, address: H160, number: Option<BlockNumber>) -> Result<U256> {
+		let at = self.block_id(number);
+		self.client.runtime_api().account_basic(&at, address)
+			.map(|account| account.nonce)
+			.map_err(runtime_error_into_rpc_err)
+	}
+
+	fn block_by_number(&self, number: BlockNumber, _full: bool) -> Result<Option<Block>> {
+		self.block(self.block_id(Some(number)))
+	}
+
+	fn block_by_hash(&self, hash: H256, _full: bool) -> Result<Option<Block>> {
+		self.block(BlockId::Hash(hash))
+	}
+
+	fn block_receipts(&self, number: BlockNumber) -> Result<Option<Vec<Receipt>>> {
+		let header = match self.client.header(self.block_id(Some(number)))
+			.map_err(client_error_into_rpc_err)?
+		{
+			Some(header) => header,
+			None => return Ok(None),
+		};
+
+		self.receipts(header.hash(), *header.number(), |_| true).map(Some)
+	}
+
+	fn logs(&self, filter: Filter) -> Result<Vec<Log>> {
+		let (from, to) = match filter.block_hash {
+			Some(hash) => {
+				let number = self.client.number(hash)
+					.map_err(client_error_into_rpc_err)?
+					.ok_or_else(|| RpcError::invalid_params("Unknown block"))?;
+				(number, number)
+			},
+			None => (
+				self.block_number_of(filter.from_block)?,
+				self.block_number_of(filter.to_block)?,
+			),
+		};
+
+		let from = UniqueSaturatedInto::<u64>::unique_saturated_into(from);
+		let to = UniqueSaturatedInto::<u64>::unique_saturated_into(to);
+		if to.saturating_sub(from) >= MAX_LOGS_BLOCK_RANGE {
+			return Err(RpcError::invalid_params(format!(
+				"Block range is greater than maximum allowed: {}", MAX_LOGS_BLOCK_RANGE,
+			)));
+		}
+
+		let mut logs = Vec::new();
+		for number in from..=to {
+			let number = NumberFor::<B>::unique_saturated_from(number);
+			let hash = match self.client.hash(number).map_err(client_error_into_rpc_err)? {
+				Some(hash) => hash,
+				None => break,
+			};
+
+			let bloom = self.client.runtime_api().block_logs_bloom(&BlockId::Hash(hash))
+				.map_err(runtime_error_into_rpc_err)?;
+			if !filter.matches_bloom(&bloom) {
+				continue;
+			}
+
+			let receipts = self.receipts(hash, number, |log| filter.matches(log))?;
+			logs.extend(receipts.into_iter().flat_map(|receipt| receipt.logs));
+		}
+
+		Ok(logs)
+	}
+
+	fn call(&self, request: CallRequest, number: Option<BlockNumber>) -> Result<Bytes> {
+		let at = self.block_id(number);
+		if request.to.is_none() {
+			return Err(RpcError {
+				code: ErrorCode::InvalidParams,
+				message: "A `to` address is required".into(),
+				data: None,
+			});
+		}
+
+		let gas_limit = self.gas_limit(&at, request.gas)?;
+		let info = self.dry_run(&at, &request, gas_limit)?;
+		match info.exit_status {
+			ExitStatus::Succeed => Ok(Bytes(info.output)),
+			_ => Err(exit_status_into_rpc_err(info)),
+		}
+	}
+
+	fn estimate_gas(&self, request: CallRequest, number: Option<BlockNumber>) -> Result<U256> {
+		let at = self.block_id(number);
+		let cap = self.gas_limit(&at, request.gas)?;
+
+		let info = self.dry_run(&at, &request, cap)?;
+		if info.exit_status != ExitStatus::Succeed {
+			return Err(exit_status_into_rpc_err(info));
+		}
+
+		// The gas used is a lower bound, calls forwarding gas or refunding storage may need more.
+		let used = info.gas_used.min(cap);
+		let succeeds = |gas_limit: U256| -> Result<bool> {
+			Ok(self.dry_run(&at, &request, gas_limit)?.exit_status == ExitStatus::Succeed)
+		};
+		if used == cap || succeeds(used)? {
+			return Ok(used);
+		}
+
+		// Invariant: `lo` fails and `hi` succeeds.
+		let (mut lo, mut hi) = (used, cap);
+		while hi - lo > U256::one() {
+			let mid = lo + (hi - lo) / 2;
+			if succeeds(mid)? {
+				hi = mid;
+			} else {
+				lo = mid;
+			}
+		}
+
+		Ok(hi)
+	}
+
+	fn send_raw_transaction(&self, transaction: Bytes) -> BoxFuture<H256> {
+		let transaction_hash = H256::from_slice(Keccak256::digest(&transaction.0).as_slice());
+		let xt = match self.convert_transaction.convert_transaction(&transaction.0) {
+			Ok(xt) => xt,
+			Err(e) => return Box::new(future::err(RpcError {
+				code: ErrorCode::InvalidParams,
+				message: "Invalid Ethereum transaction".into(),
+				data: Some(e.into()),
+			})),
+		};
+
+		let best_block_hash = self.client.info().best_hash;
+		Box::new(self.pool
+			.submit_one(&BlockId::hash(best_block_hash), TransactionSource::External, xt)
+			.compat()
+			.map(move |_| transaction_hash)
+			.map_err(|e| RpcError {
+				code: ErrorCode::ServerError(Error::TransactionRejected as i64),
+				message: "Transaction was rejected by the pool".into(),
+				data: Some(format!("{:?}", e).into()),
+			})
+		)
+	}
+}
+
+/// Converts a client error into an RPC error.
+fn client_error_into_rpc_err(err: sp_blockchain::Error) -> RpcError {
+	RpcError {
+		code: ErrorCode::ServerError(Error::RuntimeError as i64),
+		message: "Unable to query the block".into(),
+		data: Some(format!("{:?}", err).into()),
+	}
+}
+
+/// Converts a runtime trap into an RPC error.
+fn runtime_error_into_rpc_err(err: impl std::fmt::Debug) -> RpcError {
+	RpcError {
+		code: ErrorCode::ServerError(Error::RuntimeError as i64),
+		message: "Runtime trapped".into(),
+		data: Some(format!("{:?}", err).into()),
+	}
+}
+
+/// Converts an unsuccessful EVM execution into an RPC error, carrying the revert data if any.
+fn exit_status_into_rpc_err(info: ExecutionInfo) -> RpcError {
+	match info.exit_status {
+		ExitStatus::Revert => RpcError {
+			code: ErrorCode::ServerError(Error::Reverted as i64),
+			message: match decode_revert_reason(&info.output) {
+				Some(reason) => format!("Execution reverted: {}", reason),
+				None => "Execution reverted".into(),
+			},
+			data: Some(sp_core::bytes::to_hex(&info.output, false).into()),
+		},
+		status => RpcError {
+			code: ErrorCode::ServerError(Error::ExecutionError as i64),
+			message: "EVM execution failed".into(),
+			data: Some(format!("{:?}", status).into()),
+		},
+	}
+}
+
+/// Converts a failed EVM execution into an RPC error.
+fn execution_error_into_rpc_err(err: sp_runtime::DispatchError) -> RpcError {
+	RpcError {
+		code: ErrorCode::ServerError(Error::ExecutionError as i64),
+		message: "EVM execution failed".into(),
+		data: Some(format!("{:?}", err).into()),
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn should_deserialize_block_numbers() {
+		let parse = |s: &str| serde_json::from_str::<BlockNumber>(s);
+
+		assert_eq!(parse(r#""latest""#).unwrap(), BlockNumber::Latest);
+		assert_eq!(parse(r#""earliest""#).unwrap(), BlockNumber::Earliest);
+		assert_eq!(parse(r#""pending""#).unwrap(), BlockNumber::Pending);
+		assert_eq!(parse(r#""0x1f""#).unwrap(), BlockNumber::Num(31));
+		assert!(parse(r#""31""#).is_err());
+		assert_eq!(serde_json::to_string(&BlockNumber::Num(31)).unwrap(), r#""0x1f""#);
+	}
+
+	#[test]
+	fn should_deserialize_call_request() {
+		let request: CallRequest = serde_json::from_str(r#"{
+			"from": "0x0000000000000000000000000000000000000001",
+			"to": "0x0000000000000000000000000000000000000002",
+			"gas": "0x5208",
+			"data": "0x1234"
+		}"#).unwrap();
+
+		assert_eq!(request.from, Some(H160::from_low_u64_be(1)));
+		assert_eq!(request.to, Some(H160::from_low_u64_be(2)));
+		assert_eq!(request.gas, Some(U256::from(21_000)));
+		assert_eq!(request.data, Some(Bytes(vec![0x12, 0x34])));
+		assert_eq!(request.value, None);
+	}
+
+	#[test]
+	fn should_match_logs_by_address_and_topics() {
+		let log = pallet_evm::Log {
+			address: H160::from_low_u64_be(1),
+			topics: vec![H256::from_low_u64_be(10), H256::from_low_u64_be(20)],
+			data: vec![],
+		};
+		let bloom = pallet_evm::logs_bloom(&[log.clone()]);
+		let filter: Filter = serde_json::from_str(r#"{
+			"address": "0x0000000000000000000000000000000000000001",
+			"topics": [null, ["0x0000000000000000000000000000000000000000000000000000000000000014"]]
+		}"#).unwrap();
+
+		assert!(filter.matches_bloom(&bloom));
+		assert!(filter.matches(&log));
+
+		let filter = Filter {
+			topics: Some(vec![Some(VariadicValue::Single(H256::from_low_u64_be(20)))]),
+			..Default::default()
+		};
+		assert!(!filter.matches(&log));
+
+		let filter = Filter {
+			address: Some(VariadicValue::Multiple(vec![H160::from_low_u64_be(2)])),
+			..Default::default()
+		};
+		assert!(!filter.matches(&log));
+	}
+
+	#[test]
+	fn should_decode_revert_reasons() {
+		// `revert("Not enough Ether")`
+		let mut output = vec![0x08, 0xc3, 0x79, 0xa0];
+		output.extend(vec![0u8; 31]);
+		output.push(0x20);
+		output.extend(vec![0u8; 31]);
+		output.push(16);
+		output.extend(b"Not enough Ether");
+		output.extend(vec![0u8; 16]);
+
+		assert_eq!(decode_revert_reason(&output), Some("Not enough Ether".to_string()));
+		assert_eq!(decode_revert_reason(&output[..output.len() - 32]), None);
+		assert_eq!(decode_revert_reason(&[0x4e, 0x48, 0x7b, 0x71]), None);
+	}
+}
@@ -0,0 +1,402 @@
+// Copyright 2019-2020
+//     by  Centrality Investments Ltd.
+//     and Parity Technologies (UK) Ltd.
+// This file is part of Plug-blockchain.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Ethereum JSON-RPC types.
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer, de::Error as _};
+use sp_core::{Bytes, H160, H256, U256};
+use pallet_evm::Bloom;
+use ethbloom::Input as BloomInput;
+
+/// A block selector as accepted by `eth_*` methods.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BlockNumber {
+	/// A block by its number.
+	Num(u64),
+	/// The best block.
+	Latest,
+	/// The genesis block.
+	Earliest,
+	/// The block being built. Treated as the best block.
+	Pending,
+}
+
+impl Default for BlockNumber {
+	fn default() -> Self {
+		BlockNumber::Latest
+	}
+}
+
+impl<'de> Deserialize<'de> for BlockNumber {
+	fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+		let value = String::deserialize(deserializer)?;
+		match value.as_str() {
+			"latest" => Ok(BlockNumber::Latest),
+			"earliest" => Ok(BlockNumber::Earliest),
+			"pending" => Ok(BlockNumber::Pending),
+			number if number.starts_with("0x") => u64::from_str_radix(&number[2..], 16)
+				.map(BlockNumber::Num)
+				.map_err(|e| D::Error::custom(format!("Invalid block number: {}", e))),
+			_ => Err(D::Error::custom("Invalid block number: missing 0x prefix")),
+		}
+	}
+}
+
+impl Serialize for BlockNumber {
+	fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+		match *self {
+			BlockNumber::Num(number) => serializer.serialize_str(&format!("0x{:x}", number)),
+			BlockNumber::Latest => serializer.serialize_str("latest"),
+			BlockNumber::Earliest => serializer.serialize_str("earliest"),
+			BlockNumber::Pending => serializer.serialize_str("pending"),
+		}
+	}
+}
+
+/// The parameters of an `eth_call` or `eth_estimateGas` request.
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+#[serde(deny_unknown_fields)]
+pub struct CallRequest {
+	/// Sender, the zero address if not given.
+	pub from: Option<H160>,
+	/// Recipient, a contract creation if not given.
+	pub to: Option<H160>,
+	/// Gas price, the runtime minimum if not given.
+	pub gas_price: Option<U256>,
+	/// Gas limit.
+	pub gas: Option<U256>,
+	/// Value transferred.
+	pub value: Option<U256>,
+	/// Call data or contract init code.
+	pub data: Option<Bytes>,
+	/// Nonce, ignored.
+	pub nonce: Option<U256>,
+}
+
+/// An Ethereum-style view of a Substrate block.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Block {
+	/// Hash of the block.
+	pub hash: Option<H256>,
+	/// Hash of the parent block.
+	pub parent_hash: H256,
+	/// Hash of the uncles, always the hash of an empty list.
+	#[serde(rename = "sha3Uncles")]
+	pub uncles_hash: H256,
+	/// Block author.
+	pub author: H160,
+	/// Alias of `author`.
+	pub miner: H160,
+	/// State root of the block.
+	pub state_root: H256,
+	/// Extrinsics root of the block.
+	pub transactions_root: H256,
+	/// Receipts root of the block.
+	pub receipts_root: H256,
+	/// Block number.
+	pub number: Option<U256>,
+	/// Gas used by EVM transactions of the block.
+	pub gas_used: U256,
+	/// EVM gas limit of the block.
+	pub gas_limit: U256,
+	/// Extra data, always empty.
+	pub extra_data: Bytes,
+	/// Bloom filter of the logs emitted in the block.
+	pub logs_bloom: Bloom,
+	/// Block timestamp, in seconds.
+	pub timestamp: U256,
+	/// Difficulty, always zero.
+	pub difficulty: U256,
+	/// Total difficulty, always zero.
+	pub total_difficulty: Option<U256>,
+	/// Seal fields, always empty.
+	pub seal_fields: Vec<Bytes>,
+	/// Uncles, always empty.
+	pub uncles: Vec<H256>,
+	/// Hashes of the EVM operations of the block.
+	pub transactions: Vec<H256>,
+	/// Encoded size of the block.
+	pub size: Option<U256>,
+}
+
+/// A log emitted by an EVM operation.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Log {
+	/// Address of the contract which emitted the log.
+	pub address: H160,
+	/// Topics of the log.
+	pub topics: Vec<H256>,
+	/// Data of the log.
+	pub data: Bytes,
+	/// Hash of the block.
+	pub block_hash: H256,
+	/// Number of the block.
+	pub block_number: U256,
+	/// Hash of the operation which emitted the log.
+	pub transaction_hash: H256,
+	/// Index of the operation in the block.
+	pub transaction_index: U256,
+	/// Index of the log in the block.
+	pub log_index: U256,
+	/// Index of the log in the operation.
+	pub transaction_log_index: U256,
+	/// Whether the log was removed by a reorganisation, always false.
+	pub removed: bool,
+}
+
+/// The receipt of an EVM operation.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Receipt {
+	/// Hash of the operation.
+	pub transaction_hash: H256,
+	/// Index of the operation in the block.
+	pub transaction_index: U256,
+	/// Hash of the block.
+	pub block_hash: H256,
+	/// Number of the block.
+	pub block_number: U256,
+	/// Sender of the operation.
+	pub from: H160,
+	/// Target of a call, `None` for a contract creation.
+	pub to: Option<H160>,
+	/// Address of the created contract, `None` for a call.
+	pub contract_address: Option<H160>,
+	/// Gas used by the EVM operations of the block up to and including this one.
+	pub cumulative_gas_used: U256,
+	/// Gas used by the operation.
+	pub gas_used: U256,
+	/// Logs emitted by the operation.
+	pub logs: Vec<Log>,
+	/// Bloom filter of the logs.
+	pub logs_bloom: Bloom,
+	/// 1 if the operation succeeded, 0 otherwise.
+	pub status: U256,
+}
+
+/// One value or a list of values, any of which matches.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum VariadicValue<T> {
+	/// A single value.
+	Single(T),
+	/// A list of values.
+	Multiple(Vec<T>),
+}
+
+impl<T: PartialEq> VariadicValue<T> {
+	/// Whether `value` matches. An empty list matches anything.
+	pub fn matches(&self, value: &T) -> bool {
+		match self {
+			VariadicValue::Single(expected) => expected == value,
+			VariadicValue::Multiple(expected) => expected.is_empty() || expected.contains(value),
+		}
+	}
+
+	/// Whether any of the values may be in `bloom`.
+	fn matches_bloom(&self, bloom: &Bloom) -> bool where T: AsRef<[u8]> {
+		match self {
+			VariadicValue::Single(expected) =>
+				bloom.contains_input(BloomInput::Raw(expected.as_ref())),
+			VariadicValue::Multiple(expected) => expected.is_empty() ||
+				expected.iter().any(|value| bloom.contains_input(BloomInput::Raw(value.as_ref()))),
+		}
+	}
+}
+
+/// The parameters of an `eth_getLogs` request.
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+#[serde(deny_unknown_fields)]
+pub struct Filter {
+	/// First block of the range, the best block if not given.
+	pub from_block: Option<BlockNumber>,
+	/// Last block of the range, the best block if not given.
+	pub to_block: Option<BlockNumber>,
+	/// A single block to search, instead of a range.
+	pub block_hash: Option<H256>,
+	/// Addresses of the contracts which emitted the logs.
+	pub address: Option<VariadicValue<H160>>,
+	/// Topics of the logs, by position. `null` matches any topic.
+	pub topics: Option<Vec<Option<VariadicValue<H256>>>>,
+}
+
+impl Filter {
+	/// Whether a block with the given logs bloom may contain matching logs.
+	pub fn matches_bloom(&self, bloom: &Bloom) -> bool {
+		let address_matches = self.address.as_ref()
+			.map_or(true, |address| address.matches_bloom(bloom));
+		let topics_match = self.topics.iter().flatten().flatten()
+			.all(|topic| topic.matches_bloom(bloom));
+
+		address_matches && topics_match
+	}
+
+	/// Whether `log` matches the filter.
+	pub fn matches(&self, log: &pallet_evm::Log) -> bool {
+		let address_matches = self.address.as_ref()
+			.map_or(true, |address| address.matches(&log.address));
+		let topics_match = self.topics.iter().flatten().enumerate()
+			.all(|(i, topic)| match topic {
+				Some(topic) => log.topics.get(i).map_or(false, |t| topic.matches(t)),
+				None => true,
+			});
+
+		address_matches && topics_match
+	}
+}
+
+/// A call frame entered during a traced EVM operation.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CallFrame {
+	/// How the frame was entered: `CALL`, `CREATE` or `CREATE2`.
+	#[serde(rename = "type")]
+	pub kind: String,
+	/// Caller of the frame.
+	pub from: H160,
+	/// Called address, or address of the created contract.
+	pub to: H160,
+	/// Call data, or init code of the created contract.
+	pub input: Bytes,
+	/// Value transferred with the call.
+	pub value: U256,
+	/// Gas available to the frame.
+	pub gas: U256,
+	/// Gas used by the frame.
+	pub gas_used: U256,
+}
+
+/// A log emitted during a traced EVM operation.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TraceLog {
+	/// Address of the contract which emitted the log.
+	pub address: H160,
+	/// Topics of the log.
+	pub topics: Vec<H256>,
+	/// Data of the log.
+	pub data: Bytes,
+}
+
+/// Trace of an EVM operation, as returned by `debug_traceTransaction` and `debug_traceCall`.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TransactionTrace {
+	/// Hash of the operation.
+	pub transaction_hash: H256,
+	/// Sender of the operation.
+	pub from: H160,
+	/// Target of a call, `None` for a contract creation.
+	pub to: Option<H160>,
+	/// Address of the created contract, `None` for a call.
+	pub contract_address: Option<H160>,
+	/// Call data or contract init code.
+	pub input: Bytes,
+	/// Value transferred.
+	pub value: U256,
+	/// Gas limit of the operation.
+	pub gas: U256,
+	/// Gas used by the operation.
+	pub gas_used: U256,
+	/// Output of a call, holding the revert data if the call reverted.
+	pub output: Bytes,
+	/// Whether the operation failed.
+	pub failed: bool,
+	/// Why the operation failed: `revert`, `error` or `fatal`.
+	pub error: Option<String>,
+	/// Reason given to `revert` or `require`, decoded from the `Error(string)` revert data.
+	pub revert_reason: Option<String>,
+	/// Call frames entered by the operation, in execution order and at any depth.
+	pub calls: Vec<CallFrame>,
+	/// Logs emitted by the operation.
+	pub logs: Vec<TraceLog>,
+}
+
+/// Selector of `Error(string)`, the revert data of `revert` and `require` with a reason.
+const ERROR_SELECTOR: [u8; 4] = [0x08, 0xc3, 0x79, 0xa0];
+
+/// Decode the reason of `Error(string)` revert data.
+pub fn decode_revert_reason(output: &[u8]) -> Option<String> {
+	if output.len() < 4 || output[0..4] != ERROR_SELECTOR {
+		return None;
+	}
+	let data = &output[4..];
+	// Offsets and lengths larger than the data are out of bounds anyway.
+	let word = |offset: usize| data.get(offset..offset.checked_add(32)?)
+		.map(U256::from_big_endian)
+		.filter(|value| *value <= U256::from(data.len()))
+		.map(|value| value.as_usize());
+
+	let offset = word(0)?;
+	let len = word(offset)?;
+	let start = offset + 32;
+	let reason = data.get(start..start.checked_add(len)?)?;
+	String::from_utf8(reason.to_vec()).ok()
+}
+
+impl From<pallet_evm::Trace> for TransactionTrace {
+	fn from(trace: pallet_evm::Trace) -> Self {
+		let error = match trace.status {
+			pallet_evm::ExitStatus::Succeed => None,
+			pallet_evm::ExitStatus::Revert => Some("revert".to_string()),
+			pallet_evm::ExitStatus::Error => Some("error".to_string()),
+			pallet_evm::ExitStatus::Fatal => Some("fatal".to_string()),
+		};
+		let revert_reason = match trace.status {
+			pallet_evm::ExitStatus::Revert => decode_revert_reason(&trace.output),
+			_ => None,
+		};
+
+		TransactionTrace {
+			transaction_hash: trace.transaction_hash,
+			from: trace.from,
+			to: trace.to,
+			contract_address: trace.contract_address,
+			input: Bytes(trace.input),
+			value: trace.value,
+			gas: trace.gas_limit,
+			gas_used: trace.gas_used,
+			output: Bytes(trace.output),
+			failed: error.is_some(),
+			error,
+			revert_reason,
+			calls: trace.calls.into_iter().map(|call| CallFrame {
+				kind: match call.kind {
+					pallet_evm::CallKind::Call => "CALL",
+					pallet_evm::CallKind::Create => "CREATE",
+					pallet_evm::CallKind::Create2 => "CREATE2",
+				}.to_string(),
+				from: call.from,
+				to: call.to,
+				input: Bytes(call.input),
+				value: call.value,
+				gas: call.gas,
+				gas_used: call.gas_used,
+			}).collect(),
+			logs: trace.logs.into_iter().map(|log| TraceLog {
+				address: log.address,
+				topics: log.topics,
+				data: Bytes(log.data),
+			}).collect(),
+		}
+	}
+}
@@ -8,7 +8,8 @@ use sp_runtime::traits::UniqueSaturatedInto;
 use frame_support::storage::{StorageMap, StorageDoubleMap};
 use sha3::{Keccak256, Digest};
 use evm::backend::{Backend as BackendT, ApplyBackend, Apply};
-use crate::{Trait, Accounts, AccountStorages, AccountCodes, Module, Event};
+use frame_support::traits::{FindAuthor, Get};
+use crate::{Trait, Accounts, AccountStorages, AccountCodes, Module, Event, AccountBalances, ConvertAccountId};
 
 #[derive(Clone, Eq, PartialEq, Encode, Decode, Default)]
 #[cfg_attr(feature = "std", derive(Debug, Serialize, Deserialize))]
@@ -74,7 +75,12 @@ impl<'vicinity, T: Trait> BackendT for Backend<'vicinity, T> {
 	}
 
 	fn block_coinbase(&self) -> H160 {
-		H160::default()
+		let digest = frame_system::Module::<T>::digest();
+		let pre_runtime_digests = digest.logs.iter().filter_map(|d| d.as_pre_runtime());
+
+		T::FindAuthor::find_author(pre_runtime_digests)
+			.map(|author| T::ConvertAccountId::convert_account_id(&author))
+			.unwrap_or_default()
 	}
 
 	fn block_timestamp(&self) -> U256 {
@@ -87,11 +93,11 @@ impl<'vicinity, T: Trait> BackendT for Backend<'vicinity, T> {
 	}
 
 	fn block_gas_limit(&self) -> U256 {
-		U256::zero()
+		U256::from(T::BlockGasLimit::get())
 	}
 
 	fn chain_id(&self) -> U256 {
-		U256::from(sp_io::misc::chain_id())
+		U256::from(T::ChainId::get())
 	}
 
 	fn exists(&self, _address: H160) -> bool {
@@ -102,7 +108,7 @@ impl<'vicinity, T: Trait> BackendT for Backend<'vicinity, T> {
 		let account = Accounts::get(&address);
 
 		evm::backend::Basic {
-			balance: account.balance,
+			balance: T::AccountBalances::balance(&address),
 			nonce: account.nonce,
 		}
 	}
@@ -141,9 +147,9 @@ impl<'vicinity, T: Trait> ApplyBackend for Backend<'vicinity, T> {
 					address, basic, code, storage, reset_storage,
 				} => {
 					Accounts::mutate(&address, |account| {
-						account.balance = basic.balance;
 						account.nonce = basic.nonce;
 					});
+					T::AccountBalances::set_balance(&address, basic.balance);
 
 					if let Some(code) = code {
 						AccountCodes::insert(address, code);
@@ -166,6 +172,7 @@ impl<'vicinity, T: Trait> ApplyBackend for Backend<'vicinity, T> {
 					}
 				},
 				Apply::Delete { address } => {
+					T::AccountBalances::set_balance(&address, U256::zero());
 					Module::<T>::remove_account(&address)
 				},
 			}
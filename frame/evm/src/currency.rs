@@ -0,0 +1,201 @@
+// Copyright 2017-2020 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Ledgers for the native balance of EVM accounts, and the matching gas fee handlers.
+
+use sp_std::marker::PhantomData;
+use sp_core::{U256, H160};
+use sp_runtime::{DispatchResult, traits::{UniqueSaturatedFrom, UniqueSaturatedInto}};
+use frame_support::additional_traits::{AssetIdAuthority, MultiCurrencyAccounting};
+use frame_support::storage::StorageMap;
+use frame_support::traits::{Currency, ExistenceRequirement, Imbalance, OnUnbalanced, WithdrawReason};
+use crate::{
+	Trait, Module, Error, Accounts, AccountBalances, AddressMapping, OnChargeEVMFee, BalanceOf,
+	NegativeImbalanceOf,
+};
+
+/// Convert an EVM amount into `u128`, saturating on overflow.
+fn saturated_u128(value: U256) -> u128 {
+	if value > U256::from(u128::max_value()) {
+		u128::max_value()
+	} else {
+		value.low_u128()
+	}
+}
+
+/// Keep the native balance of EVM accounts in the module's `Accounts` storage.
+///
+/// The total of all EVM balances is backed by `Trait::Currency` held in the module account.
+pub struct EVMAccountBalances<T>(PhantomData<T>);
+
+impl<T: Trait> AccountBalances<T::AccountId> for EVMAccountBalances<T> {
+	fn balance(address: &H160) -> U256 {
+		Accounts::get(address).balance
+	}
+
+	fn set_balance(address: &H160, balance: U256) {
+		Accounts::mutate(address, |account| account.balance = balance);
+	}
+
+	fn deposit(who: &T::AccountId, address: &H160, value: U256) -> DispatchResult {
+		let imbalance = T::Currency::withdraw(
+			who,
+			BalanceOf::<T>::unique_saturated_from(saturated_u128(value)),
+			WithdrawReason::Reserve.into(),
+			ExistenceRequirement::AllowDeath,
+		)?;
+		T::Currency::resolve_creating(&Module::<T>::account_id(), imbalance);
+
+		Accounts::mutate(address, |account| {
+			account.balance = account.balance.saturating_add(value);
+		});
+		Ok(())
+	}
+
+	fn withdraw(who: &T::AccountId, address: &H160, value: U256) -> DispatchResult {
+		let mut account = Accounts::get(address);
+		account.balance = account.balance.checked_sub(value).ok_or(Error::<T>::BalanceLow)?;
+
+		let imbalance = T::Currency::withdraw(
+			&Module::<T>::account_id(),
+			BalanceOf::<T>::unique_saturated_from(saturated_u128(value)),
+			WithdrawReason::Reserve.into(),
+			ExistenceRequirement::AllowDeath,
+		)?;
+		Accounts::insert(address, account);
+
+		T::Currency::resolve_creating(who, imbalance);
+		Ok(())
+	}
+}
+
+/// Charge gas fees from `EVMAccountBalances`.
+///
+/// The consumed fee is withdrawn from the module account and handed to `OU`, e.g. to pay the block
+/// author or the treasury.
+pub struct EVMCurrencyAdapter<OU>(PhantomData<OU>);
+
+impl<T, OU> OnChargeEVMFee<T> for EVMCurrencyAdapter<OU> where
+	T: Trait,
+	OU: OnUnbalanced<NegativeImbalanceOf<T>>,
+{
+	type LiquidityInfo = U256;
+
+	fn withdraw_fee(who: &H160, fee: U256) -> Result<Self::LiquidityInfo, Error<T>> {
+		let mut account = Accounts::get(who);
+		account.balance = account.balance.checked_sub(fee).ok_or(Error::<T>::BalanceLow)?;
+		Accounts::insert(who, account);
+		Ok(fee)
+	}
+
+	fn correct_and_deposit_fee(who: &H160, corrected_fee: U256, already_withdrawn: Self::LiquidityInfo) {
+		let corrected_fee = corrected_fee.min(already_withdrawn);
+		Accounts::mutate(who, |account| {
+			account.balance = account.balance.saturating_add(already_withdrawn - corrected_fee);
+		});
+
+		let amount = BalanceOf::<T>::unique_saturated_from(saturated_u128(corrected_fee));
+		// The module account backs every EVM balance, including the fee just taken.
+		if let Ok(imbalance) = T::Currency::withdraw(
+			&Module::<T>::account_id(),
+			amount,
+			WithdrawReason::TransactionPayment.into(),
+			ExistenceRequirement::AllowDeath,
+		) {
+			OU::on_unbalanced(imbalance);
+		}
+	}
+}
+
+/// Keep the native balance of EVM accounts as the free balance of the `U` asset in the
+/// multi-currency system `M`, under the account given by the address mapping `A`.
+pub struct MultiCurrencyBalances<M, U, A>(PhantomData<(M, U, A)>);
+
+impl<M, U, A> AccountBalances<M::AccountId> for MultiCurrencyBalances<M, U, A> where
+	M: MultiCurrencyAccounting,
+	U: AssetIdAuthority<AssetId = M::CurrencyId>,
+	A: AddressMapping<M::AccountId>,
+{
+	fn balance(address: &H160) -> U256 {
+		let balance = M::free_balance(&A::into_account_id(*address), Some(U::asset_id()));
+		U256::from(UniqueSaturatedInto::<u128>::unique_saturated_into(balance))
+	}
+
+	fn set_balance(address: &H160, balance: U256) {
+		// EVM value transfers always net to zero, so the imbalances are simply dropped.
+		let _ = M::make_free_balance_be(
+			&A::into_account_id(*address),
+			Some(U::asset_id()),
+			M::Balance::unique_saturated_from(saturated_u128(balance)),
+		);
+	}
+
+	fn deposit(who: &M::AccountId, address: &H160, value: U256) -> DispatchResult {
+		M::transfer(
+			who,
+			&A::into_account_id(*address),
+			Some(U::asset_id()),
+			M::Balance::unique_saturated_from(saturated_u128(value)),
+			ExistenceRequirement::AllowDeath,
+		)
+	}
+
+	fn withdraw(who: &M::AccountId, address: &H160, value: U256) -> DispatchResult {
+		M::transfer(
+			&A::into_account_id(*address),
+			who,
+			Some(U::asset_id()),
+			M::Balance::unique_saturated_from(saturated_u128(value)),
+			ExistenceRequirement::AllowDeath,
+		)
+	}
+}
+
+/// Charge gas fees from `MultiCurrencyBalances`, handing the consumed fee to `OU`.
+pub struct MultiCurrencyAdapter<M, U, A, OU>(PhantomData<(M, U, A, OU)>);
+
+impl<T, M, U, A, OU> OnChargeEVMFee<T> for MultiCurrencyAdapter<M, U, A, OU> where
+	T: Trait,
+	M: MultiCurrencyAccounting,
+	U: AssetIdAuthority<AssetId = M::CurrencyId>,
+	A: AddressMapping<M::AccountId>,
+	OU: OnUnbalanced<M::NegativeImbalance>,
+{
+	type LiquidityInfo = M::NegativeImbalance;
+
+	fn withdraw_fee(who: &H160, fee: U256) -> Result<Self::LiquidityInfo, Error<T>> {
+		M::withdraw(
+			&A::into_account_id(*who),
+			Some(U::asset_id()),
+			M::Balance::unique_saturated_from(saturated_u128(fee)),
+			WithdrawReason::TransactionPayment.into(),
+			ExistenceRequirement::AllowDeath,
+		).map_err(|_| Error::<T>::WithdrawFailed)
+	}
+
+	fn correct_and_deposit_fee(who: &H160, corrected_fee: U256, already_withdrawn: Self::LiquidityInfo) {
+		let corrected_fee = M::Balance::unique_saturated_from(saturated_u128(corrected_fee));
+		let refund_amount = already_withdrawn.peek().saturating_sub(corrected_fee);
+		let refund_imbalance = M::deposit_creating(
+			&A::into_account_id(*who),
+			Some(U::asset_id()),
+			refund_amount,
+		);
+		let fee = already_withdrawn.offset(refund_imbalance)
+			.unwrap_or_else(|_| M::NegativeImbalance::zero());
+		OU::on_unbalanced(fee);
+	}
+}
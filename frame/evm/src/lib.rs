@@ -20,23 +20,44 @@
 #![cfg_attr(not(feature = "std"), no_std)]
 
 mod backend;
+mod currency;
+mod executor;
+mod receipt;
+mod transaction;
+mod tests;
+pub mod tracing;
 
 pub use crate::backend::{Account, Log, Vicinity, Backend};
+pub use crate::executor::PrecompileFrame;
+pub use crate::currency::{EVMAccountBalances, EVMCurrencyAdapter, MultiCurrencyBalances, MultiCurrencyAdapter};
+pub use crate::receipt::{Receipt, logs_bloom};
+pub use ethbloom::Bloom;
+pub use crate::transaction::{EthereumTransaction, TransactionAction, TransactionSignature};
+pub use crate::tracing::{Trace, CallFrame, CallKind, ExitStatus};
 
-use sp_std::{vec::Vec, marker::PhantomData};
-use frame_support::{ensure, decl_module, decl_storage, decl_event, decl_error};
-use frame_support::weights::{Weight, DispatchClass, FunctionOf};
-use frame_support::traits::{Currency, WithdrawReason, ExistenceRequirement};
-use frame_system::{self as system, ensure_signed};
+use sp_std::{prelude::*, marker::PhantomData};
+#[cfg(feature = "std")]
+use serde::{Serialize, Deserialize};
+use codec::{Encode, Decode};
+use frame_support::{ensure, decl_module, decl_storage, decl_event, decl_error, IsSubType};
+use frame_support::additional_traits::MaybeDoughnutRef;
+use frame_support::weights::{Weight, DispatchClass, DispatchInfo, FunctionOf};
+use frame_support::traits::{Currency, FindAuthor, Get};
+use frame_system::{self as system, ensure_signed, ensure_none};
 use sp_runtime::ModuleId;
+use sp_runtime::transaction_validity::{
+	TransactionValidity, TransactionValidityError, TransactionSource, InvalidTransaction,
+	ValidTransaction, TransactionPriority, TransactionLongevity,
+};
 use frame_support::weights::SimpleDispatchInfo;
 use sp_core::{U256, H256, H160, Hasher};
 use sp_runtime::{
-	DispatchResult, traits::{UniqueSaturatedInto, AccountIdConversion, SaturatedConversion},
+	DispatchResult,
+	traits::{UniqueSaturatedInto, AccountIdConversion, SaturatedConversion, Convert, SignedExtension},
 };
 use sha3::{Digest, Keccak256};
-use evm::{ExitReason, ExitSucceed, ExitError, Config};
-use evm::executor::StackExecutor;
+pub use evm::{ExitReason, ExitSucceed, ExitError, Config, Context};
+use crate::executor::StackExecutor;
 use evm::backend::ApplyBackend;
 
 const MODULE_ID: ModuleId = ModuleId(*b"py/ethvm");
@@ -44,6 +65,10 @@ const MODULE_ID: ModuleId = ModuleId(*b"py/ethvm");
 /// Type alias for currency balance.
 pub type BalanceOf<T> = <<T as Trait>::Currency as Currency<<T as frame_system::Trait>::AccountId>>::Balance;
 
+/// Type alias for negative imbalance of the currency.
+pub type NegativeImbalanceOf<T> =
+	<<T as Trait>::Currency as Currency<<T as frame_system::Trait>::AccountId>>::NegativeImbalance;
+
 /// Trait that outputs the current transaction gas price.
 pub trait FeeCalculator {
 	/// Return the minimal required gas price.
@@ -54,6 +79,22 @@ impl FeeCalculator for () {
 	fn min_gas_price() -> U256 { U256::zero() }
 }
 
+/// A fixed minimal gas price `P`.
+pub struct FixedGasPrice<P>(PhantomData<P>);
+
+impl<P: Get<u128>> FeeCalculator for FixedGasPrice<P> {
+	fn min_gas_price() -> U256 { U256::from(P::get()) }
+}
+
+/// Weigh EVM gas with a fixed weight `W` per unit of gas.
+pub struct LinearGasToWeight<W>(PhantomData<W>);
+
+impl<W: Get<Weight>> Convert<u32, Weight> for LinearGasToWeight<W> {
+	fn convert(gas: u32) -> Weight {
+		gas.saturating_mul(W::get())
+	}
+}
+
 /// Trait for converting account ids of `balances` module into
 /// `H160` for EVM module.
 ///
@@ -92,28 +133,155 @@ impl<H: Hasher, A: AsRef<[u8]>> ConvertAccountId<A> for HashTruncateConvertAccou
 	}
 }
 
+/// Trait for mapping an Ethereum address to the Substrate account holding its native balance.
+///
+/// This is the reverse direction of `ConvertAccountId`, and is only needed when the balance of
+/// EVM accounts is kept in a Substrate currency, see `MultiCurrencyBalances`.
+pub trait AddressMapping<A> {
+	/// Given an Ethereum address, return the corresponding Substrate account.
+	fn into_account_id(address: H160) -> A;
+}
+
+/// Hash the prefixed address, taking the 256-bit hash as the account id.
+pub struct HashedAddressMapping<H>(PhantomData<H>);
+
+impl<H: Hasher<Out = H256>, A: From<[u8; 32]>> AddressMapping<A> for HashedAddressMapping<H> {
+	fn into_account_id(address: H160) -> A {
+		let mut data = [0u8; 24];
+		data[0..4].copy_from_slice(b"evm:");
+		data[4..24].copy_from_slice(address.as_bytes());
+
+		A::from(H::hash(&data).to_fixed_bytes())
+	}
+}
+
+/// The ledger holding the native balance of EVM accounts.
+pub trait AccountBalances<AccountId> {
+	/// Return the balance of `address`.
+	fn balance(address: &H160) -> U256;
+	/// Set the balance of `address` as a result of EVM execution.
+	fn set_balance(address: &H160, balance: U256);
+	/// Move `value` from `who` into the balance of `address`.
+	fn deposit(who: &AccountId, address: &H160, value: U256) -> DispatchResult;
+	/// Move `value` from the balance of `address` back to `who`.
+	fn withdraw(who: &AccountId, address: &H160, value: U256) -> DispatchResult;
+}
+
+/// Handler for the gas fee of EVM operations.
+///
+/// The fee for the whole gas limit is withdrawn before execution. Once the gas actually used is
+/// known, the rest is refunded and the consumed fee is dealt with, e.g. paid to the block author.
+/// The handler must operate on the same ledger as `Trait::AccountBalances`.
+pub trait OnChargeEVMFee<T: Trait> {
+	/// The fee withdrawn before execution, as needed to correct it afterwards.
+	type LiquidityInfo;
+
+	/// Withdraw `fee` from the balance of `who` before execution.
+	fn withdraw_fee(who: &H160, fee: U256) -> Result<Self::LiquidityInfo, Error<T>>;
+
+	/// Refund `who` the part of `already_withdrawn` above `corrected_fee`, and handle the
+	/// `corrected_fee` consumed by execution.
+	fn correct_and_deposit_fee(who: &H160, corrected_fee: U256, already_withdrawn: Self::LiquidityInfo);
+}
+
+/// Target of an EVM operation.
+enum ExecutionAction {
+	/// A call of the given address.
+	Call(H160),
+	/// A contract creation with the given scheme.
+	Create(evm::CreateScheme),
+}
+
+#[derive(Clone, Eq, PartialEq, Encode, Decode)]
+#[cfg_attr(feature = "std", derive(Debug, Serialize, Deserialize))]
+/// Outcome of an EVM operation executed without applying its state changes, see `dry_run`.
+pub struct ExecutionInfo {
+	/// Outcome of the operation.
+	pub exit_status: ExitStatus,
+	/// Output of a call, holding the revert data if the call reverted.
+	pub output: Vec<u8>,
+	/// Address of the contract a contract creation would create, `None` for a call.
+	pub contract_address: Option<H160>,
+	/// Logs the operation would emit.
+	pub logs: Vec<Log>,
+	/// Gas used by the operation.
+	pub gas_used: U256,
+}
+
 /// Custom precompiles to be used by EVM engine.
+///
+/// Precompile sets can be combined as a tuple, the first one handling the code address wins.
 pub trait Precompiles {
 	/// Try to execute the code address as precompile. If the code address is not
 	/// a precompile or the precompile is not yet available, return `None`.
 	/// Otherwise, calculate the amount of gas needed with given `input` and
 	/// `target_gas`. Return `Some(Ok(status, output, gas_used))` if the execution
 	/// is successful. Otherwise return `Some(Err(_))`.
+	///
+	/// `context` holds the caller of the precompile and the value it was called with. A
+	/// precompile changing state must record its changes in `frame`, so that they are reverted
+	/// with the call frame making them. A change that cannot be made fails the precompile.
 	fn execute(
 		address: H160,
 		input: &[u8],
-		target_gas: Option<usize>
+		target_gas: Option<usize>,
+		context: &Context,
+		frame: &mut PrecompileFrame,
 	) -> Option<core::result::Result<(ExitSucceed, Vec<u8>, usize), ExitError>>;
+
+	/// Revert a change the precompile at `address` recorded with `PrecompileFrame::record_change`,
+	/// when the call frame making it reverts. Changes are reverted most recent first, so this
+	/// restores the state before the change. Return `false` if the address is not one of these
+	/// precompiles.
+	fn revert_change(_address: H160, _change: &[u8]) -> bool {
+		false
+	}
+
+	/// Commit a change the precompile at `address` recorded with `PrecompileFrame::record_change`,
+	/// e.g. deposit its events, once the EVM operation making it is executed. Return `false` if
+	/// the address is not one of these precompiles.
+	fn commit_change(_address: H160, _change: &[u8]) -> bool {
+		false
+	}
 }
 
-impl Precompiles for () {
+#[impl_trait_for_tuples::impl_for_tuples(16)]
+impl Precompiles for Tuple {
 	fn execute(
-		_address: H160,
-		_input: &[u8],
-		_target_gas: Option<usize>
+		address: H160,
+		input: &[u8],
+		target_gas: Option<usize>,
+		context: &Context,
+		frame: &mut PrecompileFrame,
 	) -> Option<core::result::Result<(ExitSucceed, Vec<u8>, usize), ExitError>> {
+		for_tuples!( #(
+			if let Some(result) = Tuple::execute(address, input, target_gas, context, frame) {
+				return Some(result);
+			}
+		)* );
+
 		None
 	}
+
+	fn revert_change(address: H160, change: &[u8]) -> bool {
+		for_tuples!( #(
+			if Tuple::revert_change(address, change) {
+				return true;
+			}
+		)* );
+
+		false
+	}
+
+	fn commit_change(address: H160, change: &[u8]) -> bool {
+		for_tuples!( #(
+			if Tuple::commit_change(address, change) {
+				return true;
+			}
+		)* );
+
+		false
+	}
 }
 
 static ISTANBUL_CONFIG: Config = Config::istanbul();
@@ -126,10 +294,31 @@ pub trait Trait: frame_system::Trait + pallet_timestamp::Trait {
 	type ConvertAccountId: ConvertAccountId<Self::AccountId>;
 	/// Currency type for deposit and withdraw.
 	type Currency: Currency<Self::AccountId>;
+	/// Ledger of the native balance of EVM accounts.
+	///
+	/// `EVMAccountBalances` keeps it in this module's `Accounts`, backed by `Currency` held in the
+	/// module account. `MultiCurrencyBalances` uses an asset of a multi-currency system instead.
+	type AccountBalances: AccountBalances<Self::AccountId>;
+	/// Handler for the gas fee of EVM operations, matching `AccountBalances`.
+	type OnChargeEVMFee: OnChargeEVMFee<Self>;
+	/// Find the author of the current block, used as the EVM block coinbase.
+	type FindAuthor: FindAuthor<Self::AccountId>;
 	/// The overarching event type.
 	type Event: From<Event<Self>> + Into<<Self as frame_system::Trait>::Event>;
 	/// Precompiles associated with this EVM engine.
 	type Precompiles: Precompiles;
+	/// Chain ID of the EVM, as reported to Ethereum tooling and used for replay protection.
+	type ChainId: Get<u64>;
+	/// Convert an amount of gas into the weight of executing it.
+	///
+	/// EVM operations are weighed by their gas limit, the weight of the unused gas is refunded
+	/// once they are executed.
+	type GasToWeight: Convert<u32, Weight>;
+	/// The maximum amount of gas the EVM operations of a block can use, returned by the
+	/// `GASLIMIT` opcode.
+	///
+	/// Use `CheckBlockGasLimit` to keep EVM operations exceeding it out of blocks.
+	type BlockGasLimit: Get<u32>;
 
 	/// EVM config used in the module.
 	fn config() -> &'static Config {
@@ -142,6 +331,14 @@ decl_storage! {
 		Accounts get(fn accounts) config(): map hasher(blake2_128_concat) H160 => Account;
 		AccountCodes: map hasher(blake2_128_concat) H160 => Vec<u8>;
 		AccountStorages: double_map hasher(blake2_128_concat) H160, hasher(blake2_128_concat) H256 => H256;
+		/// Receipts of the EVM operations executed in the current block.
+		CurrentReceipts get(fn current_receipts): Vec<Receipt>;
+		/// Bloom filter of the logs emitted in the current block.
+		CurrentLogsBloom get(fn current_logs_bloom): Bloom;
+		/// Gas used by the EVM operations of the current block.
+		BlockGasUsed get(fn block_gas_used): u32;
+		/// Doughnut of the EVM operation being executed, if it was dispatched with one.
+		CurrentDoughnut get(fn current_doughnut): Option<T::Doughnut>;
 	}
 }
 
@@ -181,6 +378,10 @@ decl_error! {
 		ExitReasonFatal,
 		/// Nonce is invalid
 		InvalidNonce,
+		/// Ethereum transaction signature is invalid or for another chain
+		InvalidSignature,
+		/// Gas limit is higher than the block gas limit
+		GasLimitTooHigh,
 	}
 }
 
@@ -190,24 +391,25 @@ decl_module! {
 
 		fn deposit_event() = default;
 
+		fn on_initialize() -> Weight {
+			CurrentReceipts::kill();
+			CurrentLogsBloom::kill();
+
+			SimpleDispatchInfo::default().weigh_data(())
+		}
+
+		fn on_finalize() {
+			BlockGasUsed::kill();
+		}
+
 		/// Deposit balance from currency/balances module into EVM.
 		#[weight = SimpleDispatchInfo::FixedNormal(10_000)]
 		fn deposit_balance(origin, value: BalanceOf<T>) {
 			let sender = ensure_signed(origin)?;
-
-			let imbalance = T::Currency::withdraw(
-				&sender,
-				value,
-				WithdrawReason::Reserve.into(),
-				ExistenceRequirement::AllowDeath,
-			)?;
-			T::Currency::resolve_creating(&Self::account_id(), imbalance);
-
-			let bvalue = U256::from(UniqueSaturatedInto::<u128>::unique_saturated_into(value));
 			let address = T::ConvertAccountId::convert_account_id(&sender);
-			Accounts::mutate(&address, |account| {
-				account.balance += bvalue;
-			});
+			let bvalue = U256::from(UniqueSaturatedInto::<u128>::unique_saturated_into(value));
+
+			T::AccountBalances::deposit(&sender, &address, bvalue)?;
 			Module::<T>::deposit_event(Event::<T>::BalanceDeposit(sender, address, bvalue));
 		}
 
@@ -218,25 +420,12 @@ decl_module! {
 			let address = T::ConvertAccountId::convert_account_id(&sender);
 			let bvalue = U256::from(UniqueSaturatedInto::<u128>::unique_saturated_into(value));
 
-			let mut account = Accounts::get(&address);
-			account.balance = account.balance.checked_sub(bvalue)
-				.ok_or(Error::<T>::BalanceLow)?;
-
-			let imbalance = T::Currency::withdraw(
-				&Self::account_id(),
-				value,
-				WithdrawReason::Reserve.into(),
-				ExistenceRequirement::AllowDeath
-			)?;
-
-			Accounts::insert(&address, account);
-
-			T::Currency::resolve_creating(&sender, imbalance);
+			T::AccountBalances::withdraw(&sender, &address, bvalue)?;
 			Module::<T>::deposit_event(Event::<T>::BalanceWithdraw(sender, address, bvalue));
 		}
 
 		/// Issue an EVM call operation. This is similar to a message call transaction in Ethereum.
-		#[weight = FunctionOf(|(_, _, _, gas_limit, gas_price, _): (&H160, &Vec<u8>, &U256, &u32, &U256, &Option<U256>)| (*gas_price).saturated_into::<Weight>().saturating_mul(*gas_limit), DispatchClass::Normal, true)]
+		#[weight = FunctionOf(|(_, _, _, gas_limit, _, _): (&H160, &Vec<u8>, &U256, &u32, &U256, &Option<U256>)| T::GasToWeight::convert(*gas_limit), DispatchClass::Normal, true)]
 		fn call(
 			origin,
 			target: H160,
@@ -246,28 +435,19 @@ decl_module! {
 			gas_price: U256,
 			nonce: Option<U256>,
 		) -> DispatchResult {
+			let doughnut = origin.doughnut().cloned();
 			let sender = ensure_signed(origin)?;
 			let source = T::ConvertAccountId::convert_account_id(&sender);
 
-			Self::execute_evm(
-				source,
-				value,
-				gas_limit,
-				gas_price,
-				nonce,
-				|executor| ((), executor.transact_call(
-					source,
-					target,
-					value,
-					input,
-					gas_limit as usize,
-				)),
-			).map_err(Into::into)
+			Self::with_doughnut(doughnut, || {
+				Self::execute_call(source, target, input, value, gas_limit, gas_price, nonce, None)
+			})?;
+			Ok(())
 		}
 
 		/// Issue an EVM create operation. This is similar to a contract creation transaction in
 		/// Ethereum.
-		#[weight = FunctionOf(|(_, _, gas_limit, gas_price, _): (&Vec<u8>, &U256, &u32, &U256, &Option<U256>)| (*gas_price).saturated_into::<Weight>().saturating_mul(*gas_limit), DispatchClass::Normal, true)]
+		#[weight = FunctionOf(|(_, _, gas_limit, _, _): (&Vec<u8>, &U256, &u32, &U256, &Option<U256>)| T::GasToWeight::convert(*gas_limit), DispatchClass::Normal, true)]
 		fn create(
 			origin,
 			init: Vec<u8>,
@@ -276,33 +456,68 @@ decl_module! {
 			gas_price: U256,
 			nonce: Option<U256>,
 		) -> DispatchResult {
+			let doughnut = origin.doughnut().cloned();
 			let sender = ensure_signed(origin)?;
 			let source = T::ConvertAccountId::convert_account_id(&sender);
 
-			let create_address = Self::execute_evm(
+			let (create_address, _) = Self::with_doughnut(doughnut, || Self::execute_create(
 				source,
+				init,
 				value,
 				gas_limit,
 				gas_price,
 				nonce,
-				|executor| {
-					(executor.create_address(
-						evm::CreateScheme::Legacy { caller: source },
-					), executor.transact_create(
+				None,
+			))?;
+
+			Module::<T>::deposit_event(Event::<T>::Created(create_address));
+			Ok(())
+		}
+
+		/// Issue an EVM call or create operation from a raw Ethereum transaction.
+		///
+		/// The transaction is unsigned from Substrate's point of view, the sender is recovered from
+		/// its EIP-155 signature instead.
+		#[weight = FunctionOf(|(transaction,): (&EthereumTransaction,)| T::GasToWeight::convert(transaction.gas_limit.saturated_into::<u32>()), DispatchClass::Normal, true)]
+		fn transact(origin, transaction: EthereumTransaction) -> DispatchResult {
+			ensure_none(origin)?;
+			let source = transaction.recover_signer(T::ChainId::get())
+				.ok_or(Error::<T>::InvalidSignature)?;
+			let gas_limit = transaction.gas_limit_u32().ok_or(Error::<T>::GasLimitTooHigh)?;
+			let transaction_hash = transaction.hash();
+
+			match transaction.action {
+				TransactionAction::Call(target) => {
+					Self::execute_call(
 						source,
-						value,
-						init,
-						gas_limit as usize,
-					))
+						target,
+						transaction.input,
+						transaction.value,
+						gas_limit,
+						transaction.gas_price,
+						Some(transaction.nonce),
+						Some(transaction_hash),
+					)?;
 				},
-			)?;
+				TransactionAction::Create => {
+					let (create_address, _) = Self::execute_create(
+						source,
+						transaction.input,
+						transaction.value,
+						gas_limit,
+						transaction.gas_price,
+						Some(transaction.nonce),
+						Some(transaction_hash),
+					)?;
+					Module::<T>::deposit_event(Event::<T>::Created(create_address));
+				},
+			}
 
-			Module::<T>::deposit_event(Event::<T>::Created(create_address));
 			Ok(())
 		}
 
 		/// Issue an EVM create2 operation.
-		#[weight = FunctionOf(|(_, _, _, gas_limit, gas_price, _): (&Vec<u8>, &H256, &U256, &u32, &U256, &Option<U256>)| (*gas_price).saturated_into::<Weight>().saturating_mul(*gas_limit), DispatchClass::Normal, true)]
+		#[weight = FunctionOf(|(_, _, _, gas_limit, _, _): (&Vec<u8>, &H256, &U256, &u32, &U256, &Option<U256>)| T::GasToWeight::convert(*gas_limit), DispatchClass::Normal, true)]
 		fn create2(
 			origin,
 			init: Vec<u8>,
@@ -312,17 +527,21 @@ decl_module! {
 			gas_price: U256,
 			nonce: Option<U256>,
 		) -> DispatchResult {
+			let doughnut = origin.doughnut().cloned();
 			let sender = ensure_signed(origin)?;
 			let source = T::ConvertAccountId::convert_account_id(&sender);
 
 			let code_hash = H256::from_slice(Keccak256::digest(&init).as_slice());
-			let create_address = Self::execute_evm(
+			let (create_address, _) = Self::with_doughnut(doughnut, || Self::execute_evm(
 				source,
+				ExecutionAction::Create(evm::CreateScheme::Create2 { caller: source, code_hash, salt }),
+				init,
 				value,
 				gas_limit,
 				gas_price,
 				nonce,
-				|executor| {
+				None,
+				|executor, init| {
 					(executor.create_address(
 						evm::CreateScheme::Create2 { caller: source, code_hash, salt },
 					), executor.transact_create2(
@@ -331,9 +550,9 @@ decl_module! {
 						init,
 						salt,
 						gas_limit as usize,
-					))
+					), Vec::new())
 				},
-			)?;
+			))?;
 
 			Module::<T>::deposit_event(Event::<T>::Created(create_address));
 			Ok(())
@@ -350,13 +569,146 @@ impl<T: Trait> Module<T> {
 		MODULE_ID.into_account()
 	}
 
+	/// Get the nonce and native balance of an EVM account.
+	pub fn account_basic(address: &H160) -> Account {
+		Account {
+			nonce: Accounts::get(address).nonce,
+			balance: T::AccountBalances::balance(address),
+		}
+	}
+
+	/// Get the code of an EVM account, empty if it is not a contract.
+	pub fn account_code_at(address: &H160) -> Vec<u8> {
+		AccountCodes::get(address)
+	}
+
+	/// Get the value of the storage slot `index` of an EVM account.
+	pub fn account_storage_at(address: &H160, index: H256) -> H256 {
+		AccountStorages::get(address, index)
+	}
+
+	/// Execute an EVM call operation from `source`, returning the output and the gas used.
+	///
+	/// `transaction_hash` is the hash of the raw Ethereum transaction, if any, as recorded in the
+	/// receipt of the operation.
+	pub fn execute_call(
+		source: H160,
+		target: H160,
+		input: Vec<u8>,
+		value: U256,
+		gas_limit: u32,
+		gas_price: U256,
+		nonce: Option<U256>,
+		transaction_hash: Option<H256>,
+	) -> Result<(Vec<u8>, U256), Error<T>> {
+		Self::execute_evm(
+			source,
+			ExecutionAction::Call(target),
+			input,
+			value,
+			gas_limit,
+			gas_price,
+			nonce,
+			transaction_hash,
+			|executor, input| {
+				let (reason, output) = executor.transact_call(
+					source,
+					target,
+					value,
+					input,
+					gas_limit as usize,
+				);
+				(output.clone(), reason, output)
+			},
+		)
+	}
+
+	/// Execute an EVM create operation from `source`, returning the created address and the gas
+	/// used.
+	///
+	/// `transaction_hash` is the hash of the raw Ethereum transaction, if any, as recorded in the
+	/// receipt of the operation.
+	pub fn execute_create(
+		source: H160,
+		init: Vec<u8>,
+		value: U256,
+		gas_limit: u32,
+		gas_price: U256,
+		nonce: Option<U256>,
+		transaction_hash: Option<H256>,
+	) -> Result<(H160, U256), Error<T>> {
+		Self::execute_evm(
+			source,
+			ExecutionAction::Create(evm::CreateScheme::Legacy { caller: source }),
+			init,
+			value,
+			gas_limit,
+			gas_price,
+			nonce,
+			transaction_hash,
+			|executor, init| {
+				(executor.create_address(
+					evm::CreateScheme::Legacy { caller: source },
+				), executor.transact_create(
+					source,
+					value,
+					init,
+					gas_limit as usize,
+				), Vec::new())
+			},
+		)
+	}
+
+	/// Check a raw Ethereum transaction against the current state, returning its sender and the
+	/// sender's account.
+	///
+	/// Future nonces are accepted, so that the transaction pool can queue them.
+	fn validate_transaction(
+		transaction: &EthereumTransaction,
+	) -> Result<(H160, Account), TransactionValidityError> {
+		// Unsigned transactions are only paid for through gas, so they must not be free.
+		if transaction.gas_price.is_zero() || transaction.gas_price < T::FeeCalculator::min_gas_price() {
+			return Err(InvalidTransaction::Payment.into());
+		}
+
+		let source = transaction.recover_signer(T::ChainId::get())
+			.ok_or(InvalidTransaction::BadProof)?;
+		let account = Self::account_basic(&source);
+
+		if transaction.nonce < account.nonce {
+			return Err(InvalidTransaction::Stale.into());
+		}
+		let gas_limit = transaction.gas_limit_u32().ok_or(InvalidTransaction::ExhaustsResources)?;
+		Self::check_block_gas(gas_limit)?;
+
+		let total_payment = transaction.gas_price.checked_mul(transaction.gas_limit)
+			.and_then(|fee| fee.checked_add(transaction.value))
+			.ok_or(InvalidTransaction::Payment)?;
+		if account.balance < total_payment {
+			return Err(InvalidTransaction::Payment.into());
+		}
+
+		Ok((source, account))
+	}
+
+	/// Check that an EVM operation with the given gas limit fits in the gas left in the block.
+	fn check_block_gas(gas_limit: u32) -> Result<(), TransactionValidityError> {
+		let gas_available = T::BlockGasLimit::get().saturating_sub(Self::block_gas_used());
+		if gas_limit > gas_available {
+			// gas limit reached, revert the transaction and retry again in the future
+			Err(InvalidTransaction::ExhaustsResources.into())
+		} else {
+			Ok(())
+		}
+	}
+
 	/// Check whether an account is empty.
 	pub fn is_account_empty(address: &H160) -> bool {
 		let account = Accounts::get(address);
 		let code_len = AccountCodes::decode_len(address).unwrap_or(0);
 
 		account.nonce == U256::zero() &&
-			account.balance == U256::zero() &&
+			T::AccountBalances::balance(address) == U256::zero() &&
 			code_len == 0
 	}
 
@@ -374,58 +726,365 @@ impl<T: Trait> Module<T> {
 		AccountStorages::remove_prefix(address);
 	}
 
-	/// Execute an EVM operation.
-	fn execute_evm<F, R>(
+	/// Execute an EVM call from `source`, or a contract creation if `target` is `None`, without
+	/// applying its state changes.
+	///
+	/// No fee is charged and no receipt is recorded, the nonce is not checked. The changes
+	/// made by precompiles are reverted as well.
+	pub fn dry_run(
 		source: H160,
+		target: Option<H160>,
+		input: Vec<u8>,
 		value: U256,
 		gas_limit: u32,
 		gas_price: U256,
-		nonce: Option<U256>,
-		f: F,
-	) -> Result<R, Error<T>> where
-		F: FnOnce(&mut StackExecutor<Backend<T>>) -> (R, ExitReason),
-	{
-		ensure!(gas_price >= T::FeeCalculator::min_gas_price(), Error::<T>::GasPriceTooLow);
+	) -> Result<ExecutionInfo, Error<T>> {
+		ensure!(gas_limit <= T::BlockGasLimit::get(), Error::<T>::GasLimitTooHigh);
 
 		let vicinity = Vicinity {
 			gas_price,
 			origin: source,
 		};
 
-		let mut backend = Backend::<T>::new(&vicinity);
+		let backend = Backend::<T>::new(&vicinity);
 		let mut executor = StackExecutor::new_with_precompile(
 			&backend,
 			gas_limit as usize,
 			T::config(),
-			T::Precompiles::execute,
+			Self::execute_precompile,
+			Self::revert_precompile_change,
 		);
 
+		let (reason, output, contract_address) = match target {
+			Some(target) => {
+				let (reason, output) = executor.transact_call(
+					source,
+					target,
+					value,
+					input,
+					gas_limit as usize,
+				);
+				(reason, output, None)
+			},
+			None => {
+				let contract_address = executor.create_address(
+					evm::CreateScheme::Legacy { caller: source },
+				);
+				let reason = executor.transact_create(source, value, input, gas_limit as usize);
+				(reason, Vec::new(), Some(contract_address))
+			},
+		};
+
+		let gas_used = U256::from(executor.used_gas());
+		executor.revert_changes(0);
+		// The state changes are dropped, only the logs are kept.
+		let (_, logs, _) = executor.deconstruct();
+
+		Ok(ExecutionInfo {
+			exit_status: ExitStatus::from(&reason),
+			output,
+			contract_address,
+			logs: logs.into_iter()
+				.map(|log| Log { address: log.address, topics: log.topics, data: log.data })
+				.collect(),
+			gas_used,
+		})
+	}
+
+	/// Trace an EVM call from `source`, or a contract creation if `target` is `None`.
+	///
+	/// The state changes are kept, so this is meant for runtime API calls. Returns `Ok(None)` when
+	/// the runtime is not executed natively, see `tracing::trace`.
+	pub fn trace_call(
+		source: H160,
+		target: Option<H160>,
+		input: Vec<u8>,
+		value: U256,
+		gas_limit: u32,
+		gas_price: U256,
+	) -> Result<Option<Trace>, Error<T>> {
+		let (result, traces) = tracing::trace(|| match target {
+			Some(target) => Self::execute_call(source, target, input, value, gas_limit, gas_price, None, None)
+				.map(|_| ()),
+			None => Self::execute_create(source, input, value, gas_limit, gas_price, None, None)
+				.map(|_| ()),
+		});
+
+		match traces.map(|mut traces| traces.pop()) {
+			Some(Some(trace)) => Ok(Some(trace)),
+			// The operation was rejected before being executed.
+			Some(None) => result.map(|_| None),
+			None => Ok(None),
+		}
+	}
+
+	/// Execute an EVM operation, returning the result of `f` and the gas used.
+	///
+	/// `f` is given the call data or init code `input`, and returns its result, the exit reason
+	/// and the output of the operation.
+	///
+	/// Once executed, a receipt of the operation is recorded whether it succeeded or not, and the
+	/// weight of the unused gas is refunded to the block. The operation is traced if `trace` is
+	/// in progress.
+	fn execute_evm<F, R>(
+		source: H160,
+		action: ExecutionAction,
+		input: Vec<u8>,
+		value: U256,
+		gas_limit: u32,
+		gas_price: U256,
+		nonce: Option<U256>,
+		transaction_hash: Option<H256>,
+		f: F,
+	) -> Result<(R, U256), Error<T>> where
+		F: FnOnce(&mut StackExecutor<Backend<T>>, Vec<u8>) -> (R, ExitReason, Vec<u8>),
+	{
+		ensure!(gas_price >= T::FeeCalculator::min_gas_price(), Error::<T>::GasPriceTooLow);
+		ensure!(gas_limit <= T::BlockGasLimit::get(), Error::<T>::GasLimitTooHigh);
+
 		let total_fee = gas_price.checked_mul(U256::from(gas_limit))
 			.ok_or(Error::<T>::FeeOverflow)?;
 		let total_payment = value.checked_add(total_fee).ok_or(Error::<T>::PaymentOverflow)?;
-		let source_account = Accounts::get(&source);
-		ensure!(source_account.balance >= total_payment, Error::<T>::BalanceLow);
-		executor.withdraw(source, total_fee).map_err(|_| Error::<T>::WithdrawFailed)?;
+		ensure!(T::AccountBalances::balance(&source) >= total_payment, Error::<T>::BalanceLow);
 
+		let source_nonce = Accounts::get(&source).nonce;
 		if let Some(nonce) = nonce {
-			ensure!(source_account.nonce == nonce, Error::<T>::InvalidNonce);
+			ensure!(source_nonce == nonce, Error::<T>::InvalidNonce);
 		}
+		let transaction_hash = transaction_hash.unwrap_or_else(||
+			H256::from_slice(Keccak256::digest(&(source, source_nonce).encode()).as_slice())
+		);
 
-		let (retv, reason) = f(&mut executor);
+		// The fee is taken out of the EVM state before execution, so that the executor only
+		// moves `value` between accounts.
+		let fee = T::OnChargeEVMFee::withdraw_fee(&source, total_fee)?;
 
+		let vicinity = Vicinity {
+			gas_price,
+			origin: source,
+		};
+
+		let mut backend = Backend::<T>::new(&vicinity);
+		let mut executor = StackExecutor::new_with_precompile(
+			&backend,
+			gas_limit as usize,
+			T::config(),
+			Self::execute_precompile,
+			Self::revert_precompile_change,
+		);
+
+		let (to, contract_address) = match action {
+			ExecutionAction::Call(target) => (Some(target), None),
+			ExecutionAction::Create(scheme) => (None, Some(executor.create_address(scheme))),
+		};
+
+		let traced_input = if tracing::is_tracing() { Some(input.clone()) } else { None };
+		let (retv, reason, output) = f(&mut executor, input);
+
+		let used_gas = U256::from(executor.used_gas());
+		let unused_gas = gas_limit.saturating_sub(used_gas.saturated_into::<u32>());
+		frame_system::Module::<T>::refund_unused_weight(T::GasToWeight::convert(unused_gas));
+		let status = match reason {
+			ExitReason::Succeed(_) => true,
+			_ => false,
+		};
 		let ret = match reason {
-			ExitReason::Succeed(_) => Ok(retv),
+			ExitReason::Succeed(_) => Ok((retv, used_gas)),
 			ExitReason::Error(_) => Err(Error::<T>::ExitReasonFailed),
 			ExitReason::Revert(_) => Err(Error::<T>::ExitReasonRevert),
 			ExitReason::Fatal(_) => Err(Error::<T>::ExitReasonFatal),
 		};
 
 		let actual_fee = executor.fee(gas_price);
-		executor.deposit(source, total_fee.saturating_sub(actual_fee));
 
-		let (values, logs) = executor.deconstruct();
-		backend.apply(values, logs, true);
+		let (values, logs, changes) = executor.deconstruct();
+		let logs: Vec<evm::backend::Log> = logs.into_iter().collect();
+		backend.apply(values, logs.clone(), true);
+		// Changes of reverted frames have been reverted already.
+		for (address, change) in changes {
+			T::Precompiles::commit_change(address, &change);
+		}
+		let logs: Vec<Log> = logs.into_iter()
+			.map(|log| Log { address: log.address, topics: log.topics, data: log.data })
+			.collect();
+
+		T::OnChargeEVMFee::correct_and_deposit_fee(&source, actual_fee, fee);
+
+		tracing::note_trace(|calls| Trace {
+			transaction_hash,
+			from: source,
+			to,
+			contract_address,
+			input: traced_input.unwrap_or_default(),
+			value,
+			gas_limit: U256::from(gas_limit),
+			gas_used: used_gas,
+			output,
+			status: ExitStatus::from(&reason),
+			calls,
+			logs: logs.clone(),
+		});
+
+		Self::note_receipt(Receipt {
+			transaction_hash,
+			transaction_index: frame_system::Module::<T>::extrinsic_index().unwrap_or_default(),
+			from: source,
+			to,
+			contract_address,
+			status,
+			gas_used: used_gas,
+			cumulative_gas_used: U256::zero(),
+			logs_bloom: logs_bloom(&logs),
+			logs,
+		});
 
 		ret
 	}
+
+	/// Run `T::Precompiles`.
+	fn execute_precompile(
+		address: H160,
+		input: &[u8],
+		target_gas: Option<usize>,
+		context: &Context,
+		frame: &mut PrecompileFrame,
+	) -> Option<core::result::Result<(ExitSucceed, Vec<u8>, usize), ExitError>> {
+		T::Precompiles::execute(address, input, target_gas, context, frame)
+	}
+
+	/// Revert a change made by `T::Precompiles`.
+	fn revert_precompile_change(address: H160, change: &[u8]) {
+		T::Precompiles::revert_change(address, change);
+	}
+
+	/// Execute `f` with `doughnut` as the `current_doughnut`, so that precompiles can check the
+	/// delegation the EVM operation was dispatched with.
+	///
+	/// Storage is only touched when there is a doughnut.
+	fn with_doughnut<R>(doughnut: Option<T::Doughnut>, f: impl FnOnce() -> R) -> R {
+		match doughnut {
+			Some(doughnut) => {
+				<CurrentDoughnut<T>>::put(doughnut);
+				let result = f();
+				<CurrentDoughnut<T>>::kill();
+				result
+			},
+			None => f(),
+		}
+	}
+
+	/// Record the receipt of an EVM operation executed in the current block.
+	fn note_receipt(mut receipt: Receipt) {
+		BlockGasUsed::mutate(|gas_used| {
+			*gas_used = gas_used.saturating_add(receipt.gas_used.saturated_into::<u32>())
+		});
+		CurrentLogsBloom::mutate(|bloom| bloom.accrue_bloom(&receipt.logs_bloom));
+		CurrentReceipts::mutate(|receipts| {
+			let cumulative_gas_used = receipts.last()
+				.map(|last| last.cumulative_gas_used)
+				.unwrap_or_default();
+			receipt.cumulative_gas_used = cumulative_gas_used.saturating_add(receipt.gas_used);
+			receipts.push(receipt);
+		});
+	}
+}
+
+impl<T: Trait> frame_support::unsigned::ValidateUnsigned for Module<T> {
+	type Call = Call<T>;
+
+	fn validate_unsigned(
+		_source: TransactionSource,
+		call: &Self::Call,
+	) -> TransactionValidity {
+		if let Call::transact(transaction) = call {
+			let (source, account) = Self::validate_transaction(transaction)?;
+
+			let requires = if transaction.nonce > account.nonce {
+				vec![(source, transaction.nonce - 1).encode()]
+			} else {
+				vec![]
+			};
+
+			Ok(ValidTransaction {
+				priority: transaction.gas_price.saturated_into::<TransactionPriority>(),
+				requires,
+				provides: vec![(source, transaction.nonce).encode()],
+				longevity: TransactionLongevity::max_value(),
+				propagate: true,
+			})
+		} else {
+			InvalidTransaction::Call.into()
+		}
+	}
+
+	fn pre_dispatch(call: &Self::Call) -> Result<(), TransactionValidityError> {
+		if let Call::transact(transaction) = call {
+			let (_, account) = Self::validate_transaction(transaction)?;
+
+			// Only the next nonce of the sender can be executed in this block.
+			if transaction.nonce > account.nonce {
+				return Err(InvalidTransaction::Future.into());
+			}
+
+			Ok(())
+		} else {
+			Err(InvalidTransaction::Call.into())
+		}
+	}
+}
+
+/// `SignedExtension` that checks if an EVM operation would exhaust the block gas limit.
+///
+/// Raw Ethereum transactions are checked when they are validated as unsigned transactions.
+#[derive(Encode, Decode, Clone, Eq, PartialEq)]
+pub struct CheckBlockGasLimit<T: Trait + Send + Sync>(PhantomData<T>);
+
+impl<T: Trait + Send + Sync> Default for CheckBlockGasLimit<T> {
+	fn default() -> Self {
+		Self(PhantomData)
+	}
+}
+
+impl<T: Trait + Send + Sync> sp_std::fmt::Debug for CheckBlockGasLimit<T> {
+	#[cfg(feature = "std")]
+	fn fmt(&self, f: &mut sp_std::fmt::Formatter) -> sp_std::fmt::Result {
+		write!(f, "CheckBlockGasLimit")
+	}
+
+	#[cfg(not(feature = "std"))]
+	fn fmt(&self, _: &mut sp_std::fmt::Formatter) -> sp_std::fmt::Result {
+		Ok(())
+	}
+}
+
+impl<T: Trait + Send + Sync> SignedExtension for CheckBlockGasLimit<T> where
+	<T as frame_system::Trait>::Call: IsSubType<Module<T>, T>,
+{
+	const IDENTIFIER: &'static str = "CheckEVMBlockGasLimit";
+	type AccountId = T::AccountId;
+	type Call = <T as frame_system::Trait>::Call;
+	type AdditionalSigned = ();
+	type DispatchInfo = DispatchInfo;
+	type Pre = ();
+
+	fn additional_signed(&self) -> sp_std::result::Result<(), TransactionValidityError> { Ok(()) }
+
+	fn validate(
+		&self,
+		_: &Self::AccountId,
+		call: &Self::Call,
+		_: Self::DispatchInfo,
+		_: usize,
+	) -> TransactionValidity {
+		match call.is_sub_type() {
+			Some(Call::call(_, _, _, gas_limit, _, _))
+				| Some(Call::create(_, _, gas_limit, _, _))
+				| Some(Call::create2(_, _, _, gas_limit, _, _))
+			=> {
+				Module::<T>::check_block_gas(*gas_limit)?;
+				Ok(ValidTransaction::default())
+			},
+			_ => Ok(ValidTransaction::default()),
+		}
+	}
 }
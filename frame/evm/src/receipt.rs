@@ -0,0 +1,66 @@
+// Copyright 2017-2020 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Receipts of EVM operations and their logs bloom.
+
+use sp_std::vec::Vec;
+#[cfg(feature = "std")]
+use serde::{Serialize, Deserialize};
+use codec::{Encode, Decode};
+use sp_core::{U256, H256, H160};
+use ethbloom::{Bloom, Input as BloomInput};
+use crate::backend::Log;
+
+#[derive(Clone, Eq, PartialEq, Encode, Decode)]
+#[cfg_attr(feature = "std", derive(Debug, Serialize, Deserialize))]
+/// Receipt of an EVM operation executed in a block.
+pub struct Receipt {
+	/// Hash of the operation.
+	///
+	/// The Ethereum transaction hash for `transact`, otherwise the Keccak-256 hash of the SCALE
+	/// encoded sender and nonce.
+	pub transaction_hash: H256,
+	/// Index of the extrinsic in the block.
+	pub transaction_index: u32,
+	/// Sender of the operation.
+	pub from: H160,
+	/// Target of a call, `None` for a contract creation.
+	pub to: Option<H160>,
+	/// Address of the created contract, `None` for a call.
+	pub contract_address: Option<H160>,
+	/// Whether the operation succeeded.
+	pub status: bool,
+	/// Gas used by the operation.
+	pub gas_used: U256,
+	/// Gas used by the EVM operations of the block up to and including this one.
+	pub cumulative_gas_used: U256,
+	/// Logs emitted by the operation.
+	pub logs: Vec<Log>,
+	/// Bloom filter of the logs.
+	pub logs_bloom: Bloom,
+}
+
+/// Compute the bloom filter of `logs`, over their addresses and topics.
+pub fn logs_bloom(logs: &[Log]) -> Bloom {
+	let mut bloom = Bloom::default();
+	for log in logs {
+		bloom.accrue(BloomInput::Raw(log.address.as_bytes()));
+		for topic in &log.topics {
+			bloom.accrue(BloomInput::Raw(topic.as_bytes()));
+		}
+	}
+	bloom
+}
@@ -0,0 +1,289 @@
+// Copyright 2017-2020 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Tests for the EVM module.
+
+#![cfg(test)]
+
+use crate::*;
+use frame_support::{assert_ok, assert_noop, impl_outer_event, impl_outer_origin, parameter_types};
+use sp_core::H256;
+use sp_runtime::{
+	Perbill,
+	traits::{BlakeTwo256, IdentityLookup},
+	testing::Header,
+};
+
+mod evm {
+	pub use crate::Event;
+}
+
+use pallet_balances as balances;
+use pallet_generic_asset as generic_asset;
+
+impl_outer_event! {
+	pub enum TestEvent for Test {
+		system, balances<T>, generic_asset<T>, evm<T>,
+	}
+}
+impl_outer_origin! {
+	pub enum Origin for Test where system = frame_system { }
+}
+
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct Test;
+parameter_types! {
+	pub const BlockHashCount: u64 = 250;
+	pub const MaximumBlockWeight: Weight = 1024;
+	pub const MaximumBlockLength: u32 = 2 * 1024;
+	pub const AvailableBlockRatio: Perbill = Perbill::one();
+}
+impl frame_system::Trait for Test {
+	type Origin = Origin;
+	type Index = u64;
+	type BlockNumber = u64;
+	type Hash = H256;
+	type Call = ();
+	type Hashing = BlakeTwo256;
+	type AccountId = u64;
+	type Lookup = IdentityLookup<Self::AccountId>;
+	type Header = Header;
+	type Event = TestEvent;
+	type BlockHashCount = BlockHashCount;
+	type MaximumBlockWeight = MaximumBlockWeight;
+	type AvailableBlockRatio = AvailableBlockRatio;
+	type MaximumBlockLength = MaximumBlockLength;
+	type Version = ();
+	type ModuleToIndex = ();
+	type Doughnut = ();
+	type DelegatedDispatchVerifier = ();
+}
+parameter_types! {
+	pub const ExistentialDeposit: u64 = 1;
+	pub const CreationFee: u64 = 0;
+}
+impl pallet_balances::Trait for Test {
+	type Balance = u64;
+	type Event = TestEvent;
+	type DustRemoval = ();
+	type ExistentialDeposit = ExistentialDeposit;
+	type OnReapAccount = ();
+	type OnNewAccount = ();
+	type TransferPayment = ();
+	type CreationFee = CreationFee;
+}
+impl pallet_generic_asset::Trait for Test {
+	type Balance = u64;
+	type AssetId = u32;
+	type Event = TestEvent;
+}
+parameter_types! {
+	pub const MinimumPeriod: u64 = 1;
+}
+impl pallet_timestamp::Trait for Test {
+	type Moment = u64;
+	type OnTimestampSet = ();
+	type MinimumPeriod = MinimumPeriod;
+}
+
+/// Use the account id as the low bytes of the address, and back.
+pub struct LowBytesAddress;
+impl ConvertAccountId<u64> for LowBytesAddress {
+	fn convert_account_id(account_id: &u64) -> H160 {
+		H160::from_low_u64_be(*account_id)
+	}
+}
+impl AddressMapping<u64> for LowBytesAddress {
+	fn into_account_id(address: H160) -> u64 {
+		address.to_low_u64_be()
+	}
+}
+
+parameter_types! {
+	pub const ChainId: u64 = 42;
+	pub const GasWeight: Weight = 1;
+	pub const BlockGasLimit: u32 = 10_000_000;
+}
+impl Trait for Test {
+	type FeeCalculator = ();
+	type ConvertAccountId = LowBytesAddress;
+	type Currency = Balances;
+	type AccountBalances = EVMAccountBalances<Self>;
+	type OnChargeEVMFee = EVMCurrencyAdapter<()>;
+	type FindAuthor = ();
+	type Event = TestEvent;
+	type Precompiles = ();
+	type ChainId = ChainId;
+	type GasToWeight = LinearGasToWeight<GasWeight>;
+	type BlockGasLimit = BlockGasLimit;
+}
+
+type Balances = pallet_balances::Module<Test>;
+type GenericAsset = pallet_generic_asset::Module<Test>;
+type EVM = Module<Test>;
+type SpendingAsset = pallet_generic_asset::SpendingAssetIdAuthority<Test>;
+type AssetBalances = MultiCurrencyBalances<GenericAsset, SpendingAsset, LowBytesAddress>;
+
+const ALICE: u64 = 1;
+const SPENDING_ASSET_ID: u32 = 16001;
+
+/// Build storage with `ALICE` holding 1000 of both the native currency and the spending asset.
+fn new_test_ext() -> sp_io::TestExternalities {
+	let mut t = frame_system::GenesisConfig::default().build_storage::<Test>().unwrap();
+	pallet_balances::GenesisConfig::<Test> {
+		balances: vec![(ALICE, 1000)],
+	}.assimilate_storage(&mut t).unwrap();
+	pallet_generic_asset::GenesisConfig::<Test> {
+		assets: vec![SPENDING_ASSET_ID],
+		endowed_accounts: vec![ALICE],
+		initial_balance: 1000,
+		next_asset_id: 17000,
+		staking_asset_id: 16000,
+		spending_asset_id: SPENDING_ASSET_ID,
+		permissions: vec![],
+		asset_meta: vec![],
+	}.assimilate_storage(&mut t).unwrap();
+	t.into()
+}
+
+#[test]
+fn deposit_and_withdraw_balance_move_currency_into_the_module_account() {
+	new_test_ext().execute_with(|| {
+		let address = LowBytesAddress::convert_account_id(&ALICE);
+
+		assert_ok!(EVM::deposit_balance(Origin::signed(ALICE), 300));
+		assert_eq!(Balances::free_balance(ALICE), 700);
+		assert_eq!(Balances::free_balance(EVM::account_id()), 300);
+		assert_eq!(EVM::accounts(address).balance, U256::from(300));
+
+		assert_ok!(EVM::withdraw_balance(Origin::signed(ALICE), 100));
+		assert_eq!(Balances::free_balance(ALICE), 800);
+		assert_eq!(Balances::free_balance(EVM::account_id()), 200);
+		assert_eq!(EVM::accounts(address).balance, U256::from(200));
+
+		assert_noop!(EVM::withdraw_balance(Origin::signed(ALICE), 201), Error::<Test>::BalanceLow);
+	});
+}
+
+#[test]
+fn multi_currency_balances_deposit_and_withdraw_move_the_asset() {
+	new_test_ext().execute_with(|| {
+		let address = H160::from_low_u64_be(2);
+
+		assert_ok!(AssetBalances::deposit(&ALICE, &address, U256::from(300)));
+		assert_eq!(GenericAsset::free_balance(&SPENDING_ASSET_ID, &ALICE), 700);
+		assert_eq!(AssetBalances::balance(&address), U256::from(300));
+		// The native currency and the module's own ledger are left alone.
+		assert_eq!(Balances::free_balance(ALICE), 1000);
+		assert_eq!(EVM::accounts(address).balance, U256::zero());
+
+		assert_ok!(AssetBalances::withdraw(&ALICE, &address, U256::from(100)));
+		assert_eq!(GenericAsset::free_balance(&SPENDING_ASSET_ID, &ALICE), 800);
+		assert_eq!(AssetBalances::balance(&address), U256::from(200));
+
+		assert_noop!(
+			AssetBalances::withdraw(&ALICE, &address, U256::from(201)),
+			pallet_generic_asset::Error::<Test>::InsufficientBalance,
+		);
+	});
+}
+
+#[test]
+fn trace_records_create_frames_and_the_gas_they_use() {
+	new_test_ext().execute_with(|| {
+		let gas_limit = 1_000_000;
+		// CREATE(0, 0, 0): an empty contract, created from the init code of the traced creation.
+		let init = vec![0x60, 0x00, 0x60, 0x00, 0x60, 0x00, 0xf0, 0x00];
+
+		let trace = EVM::trace_call(
+			LowBytesAddress::convert_account_id(&ALICE),
+			None,
+			init.clone(),
+			U256::zero(),
+			gas_limit,
+			U256::zero(),
+		).unwrap().expect("tracing is available natively");
+
+		assert_eq!(trace.status, ExitStatus::Succeed);
+		assert_eq!(trace.calls.len(), 2);
+		let (top, inner) = (&trace.calls[0], &trace.calls[1]);
+
+		assert_eq!(top.kind, CallKind::Create);
+		assert_eq!(Some(top.to), trace.contract_address);
+		assert_eq!(top.input, init);
+		// The gas left by the top-level frame is what is left of the operation, without refunds.
+		assert!(!top.gas_used.is_zero());
+		assert_eq!(top.gas - top.gas_used, U256::from(gas_limit) - trace.gas_used);
+
+		assert_eq!(inner.kind, CallKind::Create);
+		assert_eq!(inner.from, top.to);
+		assert!(inner.input.is_empty());
+		assert!(inner.gas < top.gas);
+		// Creating a contract without code uses no gas in its own frame.
+		assert_eq!(inner.gas_used, U256::zero());
+	});
+}
+
+#[test]
+fn logs_of_reverted_call_frames_are_dropped() {
+	new_test_ext().execute_with(|| {
+		// LOG0(0, 0) then REVERT(0, 0).
+		let reverting = [0x60, 0x00, 0x60, 0x00, 0xa0, 0x60, 0x00, 0x60, 0x00, 0xfd];
+		// Store the reverting init code in memory, CREATE(0, 22, 10) with it, then LOG0(0, 0).
+		let mut init = vec![0x69];
+		init.extend_from_slice(&reverting);
+		init.extend_from_slice(&[
+			0x60, 0x00, 0x52,
+			0x60, 0x0a, 0x60, 0x16, 0x60, 0x00, 0xf0,
+			0x60, 0x00, 0x60, 0x00, 0xa0,
+			0x00,
+		]);
+
+		let info = EVM::dry_run(
+			LowBytesAddress::convert_account_id(&ALICE),
+			None,
+			init,
+			U256::zero(),
+			1_000_000,
+			U256::zero(),
+		).unwrap();
+
+		assert_eq!(info.exit_status, ExitStatus::Succeed);
+		// Only the log of the succeeding outer frame is kept.
+		assert_eq!(info.logs.len(), 1);
+		assert_eq!(Some(info.logs[0].address), info.contract_address);
+	});
+}
+
+#[test]
+fn unsigned_transactions_must_pay_for_gas() {
+	new_test_ext().execute_with(|| {
+		let transaction = EthereumTransaction {
+			nonce: U256::zero(),
+			gas_price: U256::zero(),
+			gas_limit: U256::from(21_000),
+			action: TransactionAction::Call(H160::repeat_byte(0x35)),
+			value: U256::zero(),
+			input: Vec::new(),
+			signature: TransactionSignature { v: 119, r: H256::repeat_byte(1), s: H256::repeat_byte(1) },
+		};
+
+		assert_eq!(
+			<EVM as frame_support::unsigned::ValidateUnsigned>::validate_unsigned(TransactionSource::External, &Call::transact(transaction)),
+			InvalidTransaction::Payment.into(),
+		);
+	});
+}
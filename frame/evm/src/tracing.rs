@@ -0,0 +1,200 @@
+// Copyright 2017-2020 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Call-level tracing of EVM operations.
+//!
+//! Tracing is only available when the runtime is executed natively, see `trace`.
+
+use sp_std::vec::Vec;
+#[cfg(feature = "std")]
+use serde::{Serialize, Deserialize};
+use codec::{Encode, Decode};
+use sp_core::{U256, H256, H160};
+use evm::ExitReason;
+use crate::backend::Log;
+
+#[derive(Clone, Copy, Eq, PartialEq, Encode, Decode)]
+#[cfg_attr(feature = "std", derive(Debug, Serialize, Deserialize))]
+/// Outcome of an EVM operation.
+pub enum ExitStatus {
+	/// The operation succeeded.
+	Succeed,
+	/// The operation reverted, its output holds the revert data.
+	Revert,
+	/// The operation failed with an error, e.g. running out of gas.
+	Error,
+	/// The EVM failed with a fatal error.
+	Fatal,
+}
+
+impl From<&ExitReason> for ExitStatus {
+	fn from(reason: &ExitReason) -> Self {
+		match reason {
+			ExitReason::Succeed(_) => ExitStatus::Succeed,
+			ExitReason::Revert(_) => ExitStatus::Revert,
+			ExitReason::Error(_) => ExitStatus::Error,
+			ExitReason::Fatal(_) => ExitStatus::Fatal,
+		}
+	}
+}
+
+#[derive(Clone, Copy, Eq, PartialEq, Encode, Decode)]
+#[cfg_attr(feature = "std", derive(Debug, Serialize, Deserialize))]
+/// How a call frame was entered.
+pub enum CallKind {
+	/// A message call, including to a precompile.
+	Call,
+	/// A contract creation with `CREATE`, or by the EVM operation itself.
+	Create,
+	/// A contract creation with `CREATE2`.
+	Create2,
+}
+
+#[derive(Clone, Eq, PartialEq, Encode, Decode)]
+#[cfg_attr(feature = "std", derive(Debug, Serialize, Deserialize))]
+/// A call frame entered during a traced EVM operation.
+pub struct CallFrame {
+	/// How the frame was entered.
+	pub kind: CallKind,
+	/// Caller of the frame.
+	pub from: H160,
+	/// Called address, or address of the created contract.
+	pub to: H160,
+	/// Call data, or init code of the created contract.
+	pub input: Vec<u8>,
+	/// Value transferred with the call.
+	pub value: U256,
+	/// Gas available to the frame.
+	pub gas: U256,
+	/// Gas used by the frame, the gas available minus the gas remaining when it exited.
+	pub gas_used: U256,
+}
+
+#[derive(Clone, Eq, PartialEq, Encode, Decode)]
+#[cfg_attr(feature = "std", derive(Debug, Serialize, Deserialize))]
+/// Trace of an EVM operation.
+pub struct Trace {
+	/// Hash of the operation, see `Receipt::transaction_hash`.
+	pub transaction_hash: H256,
+	/// Sender of the operation.
+	pub from: H160,
+	/// Target of a call, `None` for a contract creation.
+	pub to: Option<H160>,
+	/// Address of the created contract, `None` for a call.
+	pub contract_address: Option<H160>,
+	/// Call data or contract init code.
+	pub input: Vec<u8>,
+	/// Value transferred.
+	pub value: U256,
+	/// Gas limit of the operation.
+	pub gas_limit: U256,
+	/// Gas used by the operation.
+	pub gas_used: U256,
+	/// Output of a call, holding the revert data if the call reverted.
+	pub output: Vec<u8>,
+	/// Outcome of the operation.
+	pub status: ExitStatus,
+	/// Call frames entered by the operation, in execution order and at any depth, starting with
+	/// its top-level frame.
+	pub calls: Vec<CallFrame>,
+	/// Logs emitted by the operation.
+	pub logs: Vec<Log>,
+}
+
+#[cfg(feature = "std")]
+#[derive(Default)]
+struct Tracer {
+	traces: Vec<Trace>,
+	calls: Vec<CallFrame>,
+	/// Indices in `calls` of the frames not exited yet, innermost last.
+	entered: Vec<usize>,
+}
+
+#[cfg(feature = "std")]
+environmental::environmental!(tracer: Tracer);
+
+/// Execute `f`, tracing the EVM operations it executes.
+///
+/// Returns the result of `f` and the traces, in execution order. The traces are `None` when the
+/// runtime is not executed natively.
+pub fn trace<R, F: FnOnce() -> R>(f: F) -> (R, Option<Vec<Trace>>) {
+	#[cfg(feature = "std")]
+	{
+		let mut new_tracer = Tracer::default();
+		let result = tracer::using(&mut new_tracer, f);
+		(result, Some(new_tracer.traces))
+	}
+
+	#[cfg(not(feature = "std"))]
+	{
+		(f(), None)
+	}
+}
+
+/// Whether the EVM operations being executed are traced.
+pub(crate) fn is_tracing() -> bool {
+	#[cfg(feature = "std")]
+	{
+		tracer::with(|_| ()).is_some()
+	}
+
+	#[cfg(not(feature = "std"))]
+	{
+		false
+	}
+}
+
+/// Note a call frame entered by the EVM operation being traced, if any.
+///
+/// The gas used by the frame is noted when it exits, see `note_call_frame_exit`.
+pub(crate) fn note_call_frame(make_frame: impl FnOnce() -> CallFrame) {
+	#[cfg(feature = "std")]
+	tracer::with(|tracer| {
+		tracer.entered.push(tracer.calls.len());
+		tracer.calls.push(make_frame());
+	});
+
+	#[cfg(not(feature = "std"))]
+	let _ = make_frame;
+}
+
+/// Note the gas used by the innermost call frame not exited yet, which is exiting.
+pub(crate) fn note_call_frame_exit(gas_used: usize) {
+	#[cfg(feature = "std")]
+	tracer::with(|tracer| {
+		if let Some(index) = tracer.entered.pop() {
+			tracer.calls[index].gas_used = U256::from(gas_used);
+		}
+	});
+
+	#[cfg(not(feature = "std"))]
+	let _ = gas_used;
+}
+
+/// Note the outcome of the EVM operation being traced, if any.
+///
+/// `make_trace` is given the call frames entered by the operation.
+pub(crate) fn note_trace(make_trace: impl FnOnce(Vec<CallFrame>) -> Trace) {
+	#[cfg(feature = "std")]
+	tracer::with(|tracer| {
+		tracer.entered.clear();
+		let calls = sp_std::mem::replace(&mut tracer.calls, Vec::new());
+		tracer.traces.push(make_trace(calls));
+	});
+
+	#[cfg(not(feature = "std"))]
+	let _ = make_trace;
+}
@@ -0,0 +1,253 @@
+// Copyright 2017-2020 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Raw Ethereum transactions, as signed by Ethereum wallets.
+
+use sp_std::vec::Vec;
+use codec::{Encode, Decode};
+use sp_core::{U256, H256, H160};
+use sp_runtime::RuntimeDebug;
+use sha3::{Digest, Keccak256};
+use rlp::{Rlp, RlpStream, DecoderError};
+
+/// Half of the secp256k1 curve order, the highest `s` value accepted in signatures (EIP-2).
+const SECP256K1N_HALF: [u8; 32] = [
+	0x7f, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
+	0x5d, 0x57, 0x6e, 0x73, 0x57, 0xa4, 0x50, 0x1d, 0xdf, 0xe9, 0x2f, 0x46, 0x68, 0x1b, 0x20, 0xa0,
+];
+
+/// The action of an Ethereum transaction.
+#[derive(Clone, Eq, PartialEq, Encode, Decode, RuntimeDebug)]
+pub enum TransactionAction {
+	/// Call the given address.
+	Call(H160),
+	/// Create a contract.
+	Create,
+}
+
+/// The signature of an Ethereum transaction.
+#[derive(Clone, Eq, PartialEq, Encode, Decode, RuntimeDebug)]
+pub struct TransactionSignature {
+	/// EIP-155 `v` value, encoding the chain ID and the recovery ID.
+	pub v: u64,
+	/// `r` value of the signature.
+	pub r: H256,
+	/// `s` value of the signature.
+	pub s: H256,
+}
+
+impl TransactionSignature {
+	/// The chain ID the transaction was signed for, `None` for pre-EIP-155 signatures.
+	pub fn chain_id(&self) -> Option<u64> {
+		if self.v >= 35 {
+			Some((self.v - 35) / 2)
+		} else {
+			None
+		}
+	}
+
+	/// The recovery ID of the signature.
+	pub fn recovery_id(&self) -> u8 {
+		if self.v >= 35 {
+			((self.v - 35) % 2) as u8
+		} else {
+			self.v.saturating_sub(27) as u8
+		}
+	}
+}
+
+/// An EIP-155 signed Ethereum transaction.
+#[derive(Clone, Eq, PartialEq, Encode, Decode, RuntimeDebug)]
+pub struct EthereumTransaction {
+	/// Nonce of the sender.
+	pub nonce: U256,
+	/// Gas price.
+	pub gas_price: U256,
+	/// Gas limit.
+	pub gas_limit: U256,
+	/// Call or contract creation.
+	pub action: TransactionAction,
+	/// Value transferred.
+	pub value: U256,
+	/// Call data or contract init code.
+	pub input: Vec<u8>,
+	/// Signature of the sender.
+	pub signature: TransactionSignature,
+}
+
+impl EthereumTransaction {
+	/// Decode a transaction from its RLP encoding.
+	pub fn decode_rlp(bytes: &[u8]) -> Result<Self, DecoderError> {
+		rlp::decode(bytes)
+	}
+
+	/// The Ethereum transaction hash, i.e. the Keccak-256 hash of the RLP encoding.
+	pub fn hash(&self) -> H256 {
+		H256::from_slice(Keccak256::digest(&rlp::encode(self)).as_slice())
+	}
+
+	/// The hash signed by the sender, as defined by EIP-155.
+	pub fn message_hash(&self, chain_id: u64) -> H256 {
+		let mut stream = RlpStream::new_list(9);
+		self.append_unsigned(&mut stream);
+		stream.append(&chain_id);
+		stream.append(&0u8);
+		stream.append(&0u8);
+		H256::from_slice(Keccak256::digest(&stream.out()).as_slice())
+	}
+
+	/// The gas limit, `None` if it does not fit the `u32` gas of EVM operations.
+	pub fn gas_limit_u32(&self) -> Option<u32> {
+		if self.gas_limit > U256::from(u32::max_value()) {
+			None
+		} else {
+			Some(self.gas_limit.low_u32())
+		}
+	}
+
+	/// Recover the sender of the transaction, checking that it was signed for `chain_id`.
+	///
+	/// Signatures with a high `s` value are malleable and rejected, as in EIP-2.
+	pub fn recover_signer(&self, chain_id: u64) -> Option<H160> {
+		if self.signature.chain_id() != Some(chain_id) {
+			return None;
+		}
+		if self.signature.s.as_bytes() > &SECP256K1N_HALF[..] {
+			return None;
+		}
+
+		let mut sig = [0u8; 65];
+		sig[0..32].copy_from_slice(self.signature.r.as_bytes());
+		sig[32..64].copy_from_slice(self.signature.s.as_bytes());
+		sig[64] = self.signature.recovery_id();
+		let msg = self.message_hash(chain_id);
+
+		let pubkey = sp_io::crypto::secp256k1_ecdsa_recover(&sig, msg.as_fixed_bytes()).ok()?;
+		Some(H160::from_slice(&Keccak256::digest(&pubkey)[12..]))
+	}
+
+	fn append_unsigned(&self, stream: &mut RlpStream) {
+		stream.append(&self.nonce);
+		stream.append(&self.gas_price);
+		stream.append(&self.gas_limit);
+		match self.action {
+			TransactionAction::Call(ref address) => stream.append(address),
+			TransactionAction::Create => stream.append_empty_data(),
+		};
+		stream.append(&self.value);
+		stream.append(&self.input);
+	}
+}
+
+impl rlp::Encodable for EthereumTransaction {
+	fn rlp_append(&self, stream: &mut RlpStream) {
+		stream.begin_list(9);
+		self.append_unsigned(stream);
+		stream.append(&self.signature.v);
+		stream.append(&U256::from_big_endian(self.signature.r.as_bytes()));
+		stream.append(&U256::from_big_endian(self.signature.s.as_bytes()));
+	}
+}
+
+impl rlp::Decodable for EthereumTransaction {
+	fn decode(rlp: &Rlp) -> Result<Self, DecoderError> {
+		if rlp.item_count()? != 9 {
+			return Err(DecoderError::RlpIncorrectListLen);
+		}
+
+		let to = rlp.at(3)?;
+		let action = if to.is_empty() {
+			TransactionAction::Create
+		} else {
+			TransactionAction::Call(to.as_val()?)
+		};
+
+		let mut r = H256::zero();
+		rlp.val_at::<U256>(7)?.to_big_endian(r.as_bytes_mut());
+		let mut s = H256::zero();
+		rlp.val_at::<U256>(8)?.to_big_endian(s.as_bytes_mut());
+
+		Ok(EthereumTransaction {
+			nonce: rlp.val_at(0)?,
+			gas_price: rlp.val_at(1)?,
+			gas_limit: rlp.val_at(2)?,
+			action,
+			value: rlp.val_at(4)?,
+			input: rlp.val_at(5)?,
+			signature: TransactionSignature {
+				v: rlp.val_at(6)?,
+				r,
+				s,
+			},
+		})
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use hex_literal::hex;
+
+	// The example of EIP-155: nonce 9, 20 gwei gas price, 21000 gas, sending 1 ether to
+	// 0x3535...35 on chain 1, signed with the private key 0x4646...46.
+	const EIP155_TRANSACTION: [u8; 110] = hex!("f86c098504a817c800825208943535353535353535353535353535353535353535880de0b6b3a76400008025a028ef61340bd939bc2195fe537567866003e1a15d3c71ff63e1590620aa636276a067cbe9d8997f761aecb703304b3800ccf555c9f3dc64214b297fb1966a3b6d83");
+	// The same transaction, signed without a chain id.
+	const LEGACY_TRANSACTION: [u8; 110] = hex!("f86c098504a817c800825208943535353535353535353535353535353535353535880de0b6b3a7640000801ba049bb0d7bdc597e0ff348076cc2afc46a93715ff0d0f2c49ba8a72521e710fb31a03d6c17f71a19fca5628884764168ad3879c4e7c381141a0d43fdce6069e449e0");
+	const SIGNER: [u8; 20] = hex!("9d8a62f656a8d1615c1294fd71e9cfb3e4855a4f");
+
+	#[test]
+	fn recovers_eip155_signer() {
+		let transaction = EthereumTransaction::decode_rlp(&EIP155_TRANSACTION).unwrap();
+
+		assert_eq!(transaction.nonce, U256::from(9));
+		assert_eq!(transaction.action, TransactionAction::Call(H160::repeat_byte(0x35)));
+		assert_eq!(transaction.signature.chain_id(), Some(1));
+		assert_eq!(rlp::encode(&transaction), EIP155_TRANSACTION.to_vec());
+		assert_eq!(transaction.recover_signer(1), Some(H160::from(SIGNER)));
+		assert_eq!(transaction.recover_signer(2), None);
+	}
+
+	#[test]
+	fn rejects_legacy_signature() {
+		let transaction = EthereumTransaction::decode_rlp(&LEGACY_TRANSACTION).unwrap();
+
+		assert_eq!(transaction.signature.v, 27);
+		assert_eq!(transaction.signature.chain_id(), None);
+		assert_eq!(transaction.signature.recovery_id(), 0);
+		assert_eq!(transaction.recover_signer(1), None);
+	}
+
+	#[test]
+	fn rejects_high_s_signature() {
+		let mut transaction = EthereumTransaction::decode_rlp(&EIP155_TRANSACTION).unwrap();
+		// `n - s` with the flipped recovery id is the other valid signature of the same message.
+		transaction.signature.s = H256::from(
+			hex!("98341627668089e51348fccfb4c7ff31c55912f2d2e47ef09652acf665fad3be"),
+		);
+		transaction.signature.v = 38;
+
+		assert_eq!(transaction.recover_signer(1), None);
+	}
+
+	#[test]
+	fn gas_limit_must_fit_u32() {
+		let mut transaction = EthereumTransaction::decode_rlp(&EIP155_TRANSACTION).unwrap();
+		assert_eq!(transaction.gas_limit_u32(), Some(21000));
+
+		transaction.gas_limit = U256::from(u32::max_value()) + 1;
+		assert_eq!(transaction.gas_limit_u32(), None);
+	}
+}
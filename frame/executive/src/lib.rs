@@ -511,6 +511,7 @@ mod tests {
 		type Call = <Self as system::Trait>::Call;
 		type Doughnut = <Self as system::Trait>::Doughnut;
 		type TimestampProvider = TimestampProvider;
+		type Authorisations = ();
 	}
 	parameter_types! {
 		pub const ExistentialDeposit: u64 = 1;
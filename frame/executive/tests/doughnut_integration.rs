@@ -138,6 +138,7 @@ impl DoughnutRuntime for Runtime {
 	type Call = <Self as frame_system::Trait>::Call;
 	type Doughnut = <Self as frame_system::Trait>::Doughnut;
 	type TimestampProvider = TimestampProvider;
+	type Authorisations = ();
 }
 parameter_types! {
 	pub const ExistentialDeposit: u64 = 1;
@@ -17,11 +17,13 @@
 //! Extra trait implementations for the `GenericAsset` module
 
 use crate::{Error, Module, NegativeImbalance, PositiveImbalance, SpendingAssetIdAuthority, Trait};
-use sp_std::result;
+use sp_std::{result, prelude::Vec};
 use sp_runtime::{traits::{CheckedSub, Zero,}, DispatchError, DispatchResult,};
 use frame_support::{
-	additional_traits::{AssetIdAuthority, MultiCurrencyAccounting},
-	traits::{ExistenceRequirement, Imbalance, SignedImbalance, UpdateBalanceOutcome, WithdrawReasons},
+	additional_traits::{AssetIdAuthority, MultiCurrencyAccounting, MultiLockableCurrency},
+	traits::{
+		ExistenceRequirement, Imbalance, LockIdentifier, SignedImbalance, UpdateBalanceOutcome, WithdrawReasons,
+	},
 };
 
 impl<T: Trait> MultiCurrencyAccounting for Module<T> {
@@ -67,6 +69,10 @@ impl<T: Trait> MultiCurrencyAccounting for Module<T> {
 		Ok(Self::deposit_creating(who, currency, value))
 	}
 
+	fn currencies_held(who: &T::AccountId) -> Vec<T::AssetId> {
+		<Module<T>>::assets_held(who)
+	}
+
 	fn ensure_can_withdraw(
 		who: &T::AccountId,
 		currency: Option<T::AssetId>,
@@ -139,12 +145,38 @@ impl<T: Trait> MultiCurrencyAccounting for Module<T> {
 	}
 }
 
+impl<T: Trait> MultiLockableCurrency for Module<T> {
+	fn set_lock(
+		id: LockIdentifier,
+		who: &T::AccountId,
+		currency: T::AssetId,
+		amount: Self::Balance,
+		reasons: WithdrawReasons,
+	) {
+		<Module<T>>::set_asset_lock(&currency, id, who, amount, reasons)
+	}
+
+	fn extend_lock(
+		id: LockIdentifier,
+		who: &T::AccountId,
+		currency: T::AssetId,
+		amount: Self::Balance,
+		reasons: WithdrawReasons,
+	) {
+		<Module<T>>::extend_asset_lock(&currency, id, who, amount, reasons)
+	}
+
+	fn remove_lock(id: LockIdentifier, who: &T::AccountId, currency: T::AssetId) {
+		<Module<T>>::remove_asset_lock(&currency, id, who)
+	}
+}
+
 #[cfg(test)]
 mod tests {
 	use super::*;
-	use crate::mock::{ExtBuilder, GenericAsset, Test};
+	use crate::{mock::{ExtBuilder, GenericAsset, Test}, BalanceLock};
 	use sp_runtime::traits::Zero;
-	use frame_support::assert_noop;
+	use frame_support::{assert_noop, assert_ok, traits::WithdrawReason};
 
 	#[test]
 	fn multi_accounting_minimum_balance() {
@@ -575,4 +607,64 @@ mod tests {
 				);
 			})
 	}
+
+	#[test]
+	fn multi_lockable_set_lock_restricts_any_asset() {
+		let (alice, bob, asset_id, amount) = (1, 2, 16003, 100);
+		ExtBuilder::default()
+			.free_balance((asset_id, alice, amount))
+			.build()
+			.execute_with(|| {
+				<GenericAsset as MultiLockableCurrency>::set_lock(
+					*b"testlock", &alice, asset_id, 60, WithdrawReasons::all(),
+				);
+				assert_noop!(
+					<GenericAsset as MultiCurrencyAccounting>::transfer(
+						&alice, &bob, Some(asset_id), 50, ExistenceRequirement::KeepAlive,
+					),
+					Error::<Test>::LiquidityRestrictions
+				);
+				assert_ok!(<GenericAsset as MultiCurrencyAccounting>::transfer(
+					&alice, &bob, Some(asset_id), 40, ExistenceRequirement::KeepAlive,
+				));
+
+				// A lock on one asset does not restrict another
+				assert_eq!(GenericAsset::asset_locks(16000, alice), vec![]);
+			});
+	}
+
+	#[test]
+	fn multi_lockable_extend_and_remove_lock() {
+		let (alice, asset_id, amount) = (1, 16003, 100);
+		ExtBuilder::default()
+			.free_balance((asset_id, alice, amount))
+			.build()
+			.execute_with(|| {
+				let id = *b"testlock";
+				<GenericAsset as MultiLockableCurrency>::set_lock(
+					id, &alice, asset_id, 60, WithdrawReason::Transfer.into(),
+				);
+				<GenericAsset as MultiLockableCurrency>::extend_lock(
+					id, &alice, asset_id, 30, WithdrawReason::Reserve.into(),
+				);
+				assert_eq!(
+					GenericAsset::asset_locks(asset_id, alice),
+					vec![BalanceLock {
+						id,
+						amount: 60,
+						reasons: WithdrawReason::Transfer | WithdrawReason::Reserve,
+					}]
+				);
+
+				<GenericAsset as MultiLockableCurrency>::remove_lock(id, &alice, asset_id);
+				assert_eq!(GenericAsset::asset_locks(asset_id, alice), vec![]);
+				assert_ok!(<GenericAsset as MultiCurrencyAccounting>::ensure_can_withdraw(
+					&alice,
+					Some(asset_id),
+					amount,
+					WithdrawReasons::all(),
+					Zero::zero(),
+				));
+			});
+	}
 }
@@ -63,6 +63,7 @@
 //! - `Currency`: Functions for dealing with a fungible assets system.
 //! - `ReservableCurrency`: Functions for dealing with assets that can be reserved from an account.
 //! - `LockableCurrency`: Functions for dealing with accounts that allow liquidity restrictions.
+//! - `MultiLockableCurrency`: Functions for placing liquidity restrictions on the balance of any asset.
 //! - `Imbalance`: Functions for handling imbalances between total issuance in the system and account balances.
 //! Must be used when a function creates new assets (e.g. a reward) or destroys some assets (e.g. a system fee).
 //!
@@ -83,6 +84,11 @@
 //! - `mint`: Mint an asset, increases its total issuance. The origin of this call must have mint permissions.
 //! - `burn`: Burn an asset, decreases its total issuance. The origin of this call must have burn permissions.
 //! - `create_reserved`: Create a new kind of reserved asset. The origin of this call must be root.
+//! - `transfer_batch`: Transfer several assets to several accounts. Either every transfer succeeds or none of
+//! them is applied.
+//! - `create_swap`: Lock some of an asset in reserve for a target account, claimable with the preimage of a hash.
+//! - `claim_swap`: Claim a pending swap by revealing the proof matching its hash-lock.
+//! - `cancel_swap`: Return the reserved funds of an expired, unclaimed swap to its creator.
 //!
 //! ### Public Functions
 //!
@@ -106,6 +112,7 @@
 //! - `check_permission`: Check permission to perform burn, mint or update.
 //! - `ensure_can_withdraw`: Check if the account is able to make a withdrawal of the given amount
 //!	for the given reason.
+//! - `make_batch_transfer`: Transfer several assets from one account to others, all or nothing.
 //!
 //! ### Usage
 //!
@@ -158,11 +165,11 @@ use codec::{Decode, Encode, Error as CodecError, HasCompact, Input, Output};
 
 use sp_runtime::{RuntimeDebug, DispatchResult, DispatchError};
 use sp_runtime::traits::{
-	Bounded, CheckedAdd, CheckedSub, MaybeSerializeDeserialize, Member, One, Saturating, AtLeast32Bit, Zero,
+	Bounded, CheckedAdd, CheckedSub, Hash, MaybeSerializeDeserialize, Member, One, Saturating, AtLeast32Bit, Zero,
 };
 
 use sp_std::prelude::*;
-use sp_std::{result, fmt::Debug};
+use sp_std::{collections::btree_map::BTreeMap, result, fmt::Debug};
 use frame_support::{
 	decl_event, decl_module, decl_storage, ensure, decl_error,
 	traits::{
@@ -170,7 +177,7 @@ use frame_support::{
 		SignedImbalance, UpdateBalanceOutcome, WithdrawReason, WithdrawReasons, TryDrop,
 	},
 	additional_traits::{AssetIdAuthority, DummyDispatchVerifier},
-	weights::SimpleDispatchInfo,
+	weights::{DispatchClass, FunctionOf, SimpleDispatchInfo, Weight},
 	Parameter, StorageMap, IterableStorageMap,
 };
 use frame_system::{self as system, ensure_signed, ensure_root};
@@ -225,6 +232,16 @@ impl AssetInfo {
 	pub fn new(symbol: Vec<u8>, decimal_places: u8) -> Self {
 		Self { symbol, decimal_places }
 	}
+
+	/// The name/symbol of the asset.
+	pub fn symbol(&self) -> &[u8] {
+		&self.symbol
+	}
+
+	/// The number of decimal places in the asset's balance.
+	pub fn decimal_places(&self) -> u8 {
+		self.decimal_places
+	}
 }
 impl Default for AssetInfo {
 	fn default() -> Self {
@@ -360,6 +377,45 @@ impl<AccountId> Into<PermissionVersions<AccountId>> for PermissionLatest<Account
 	}
 }
 
+/// Weight of a single `transfer`, also charged for each transfer of a `transfer_batch`.
+pub const TRANSFER_WEIGHT: Weight = 1_000_000;
+
+/// Weight charged by a `transfer_batch` on top of the weight of its transfers.
+pub const TRANSFER_BATCH_BASE_WEIGHT: Weight = 500_000;
+
+/// Maximum number of transfers in a `transfer_batch`.
+pub const MAX_BATCH_TRANSFERS: usize = 100;
+
+/// Weight charged by a `claim_swap` on top of the weight of the bytes of its proof.
+pub const CLAIM_SWAP_BASE_WEIGHT: Weight = 1_000_000;
+
+/// Weight charged by a `claim_swap` per byte of its proof, which is hashed and deposited in an event.
+pub const CLAIM_SWAP_PROOF_BYTE_WEIGHT: Weight = 1_000;
+
+/// Maximum length of the proof of a `claim_swap`.
+pub const MAX_SWAP_PROOF_LEN: usize = 1024;
+
+/// A hash-locked amount of an asset, reserved by `source` and claimable by the swap's target.
+#[derive(Clone, Encode, Decode, PartialEq, Eq, RuntimeDebug)]
+pub struct PendingSwap<AccountId, AssetId, Balance, BlockNumber> {
+	/// The account which created the swap and whose funds are held in reserve.
+	pub source: AccountId,
+	/// The asset being swapped.
+	pub asset_id: AssetId,
+	/// The amount reserved from `source`.
+	pub amount: Balance,
+	/// The last block at which the swap can be claimed. It can be cancelled afterwards.
+	pub end_block: BlockNumber,
+}
+
+/// Alias to the `PendingSwap` type of a runtime.
+pub type PendingSwapOf<T> = PendingSwap<
+	<T as frame_system::Trait>::AccountId,
+	<T as Trait>::AssetId,
+	<T as Trait>::Balance,
+	<T as frame_system::Trait>::BlockNumber,
+>;
+
 decl_error! {
 	/// Error for the generic-asset module.
 	pub enum Error for Module<T: Trait> {
@@ -391,6 +447,22 @@ decl_error! {
 		TransferOverflow,
 		/// The account liquidity restrictions prevent withdrawal.
 		LiquidityRestrictions,
+		/// A swap with the same target and hashed proof already exists.
+		SwapAlreadyExists,
+		/// No pending swap matches the given target and hashed proof.
+		SwapNotFound,
+		/// Only the creator of a swap can cancel it.
+		NotSwapSource,
+		/// The swap can no longer be claimed.
+		SwapExpired,
+		/// The swap cannot be cancelled before its end block.
+		SwapNotExpired,
+		/// The reserved balance of the swap source no longer covers the swap.
+		SwapUnderfunded,
+		/// The batch contains more than `MAX_BATCH_TRANSFERS` transfers.
+		TooManyTransfers,
+		/// The swap proof is longer than `MAX_SWAP_PROOF_LEN` bytes.
+		SwapProofTooLong,
 	}
 }
 
@@ -434,7 +506,7 @@ decl_module! {
 		/// - It contains a limited number of reads and writes internally and no complex computation.
 		///
 		/// # </weight>
-		#[weight = SimpleDispatchInfo::FixedNormal(1_000_000)]
+		#[weight = SimpleDispatchInfo::FixedNormal(TRANSFER_WEIGHT)]
 		pub fn transfer(origin, #[compact] asset_id: T::AssetId, to: T::AccountId, #[compact] amount: T::Balance) {
 			let origin = ensure_signed(origin)?;
 			ensure!(!amount.is_zero(), Error::<T>::ZeroAmount);
@@ -537,6 +609,146 @@ decl_module! {
 			ensure_root(origin)?;
 			Self::create_asset(Some(asset_id), None, options, info)
 		}
+
+		/// Transfer some liquid free balance of several assets to several accounts.
+		///
+		/// The batch is all-or-nothing: every transfer is checked against the sender's balances and
+		/// locks before any balance is touched, so no transfer is applied if one of them would fail.
+		///
+		/// The dispatch origin for this call must be `Signed` by the transactor.
+		///
+		/// At most `MAX_BATCH_TRANSFERS` transfers can be made in a batch.
+		///
+		/// # <weight>
+		/// - O(T) where T is the number of transfers in the batch.
+		/// - One balance read per distinct asset of the sender and per distinct recipient.
+		/// - A base weight plus the weight of a single `transfer` per transfer.
+		/// # </weight>
+		#[weight = FunctionOf(
+			|args: (&Vec<(T::AssetId, T::AccountId, T::Balance)>,)| {
+				(args.0.len() as Weight)
+					.saturating_mul(TRANSFER_WEIGHT)
+					.saturating_add(TRANSFER_BATCH_BASE_WEIGHT)
+			},
+			DispatchClass::Normal,
+			true
+		)]
+		pub fn transfer_batch(origin, transfers: Vec<(T::AssetId, T::AccountId, T::Balance)>) {
+			let origin = ensure_signed(origin)?;
+			ensure!(transfers.len() <= MAX_BATCH_TRANSFERS, Error::<T>::TooManyTransfers);
+			Self::make_batch_transfer(&origin, &transfers)?;
+		}
+
+		/// Create a hash-locked swap of `amount` of `asset_id` for `target`.
+		///
+		/// The funds are moved to the reserved balance of the origin. `target` may claim them with
+		/// the preimage of `hashed_proof` up to `duration` blocks from now, after which the origin
+		/// may cancel the swap and get its funds back.
+		///
+		/// Two accounts swap assets atomically by creating swaps for each other under the same
+		/// `hashed_proof`: claiming one swap reveals the proof needed to claim the other.
+		///
+		/// The dispatch origin for this call must be `Signed` by the source of the funds.
+		///
+		/// Weights:
+		/// O(1) Limited number of reads/writes.
+		#[weight = SimpleDispatchInfo::FixedNormal(1_000_000)]
+		pub fn create_swap(
+			origin,
+			target: T::AccountId,
+			hashed_proof: T::Hash,
+			#[compact] asset_id: T::AssetId,
+			#[compact] amount: T::Balance,
+			duration: T::BlockNumber,
+		) -> DispatchResult {
+			let source = ensure_signed(origin)?;
+			ensure!(!amount.is_zero(), Error::<T>::ZeroAmount);
+			ensure!(!<PendingSwaps<T>>::contains_key(&target, hashed_proof), Error::<T>::SwapAlreadyExists);
+
+			let new_balance = Self::free_balance(&asset_id, &source)
+				.checked_sub(&amount)
+				.ok_or(Error::<T>::InsufficientBalance)?;
+			Self::ensure_can_withdraw(&asset_id, &source, amount, WithdrawReason::Reserve.into(), new_balance)?;
+			Self::reserve(&asset_id, &source, amount)?;
+
+			let end_block = <frame_system::Module<T>>::block_number().saturating_add(duration);
+			<PendingSwaps<T>>::insert(&target, hashed_proof, PendingSwap {
+				source: source.clone(),
+				asset_id,
+				amount,
+				end_block,
+			});
+
+			Self::deposit_event(RawEvent::SwapCreated(source, target, hashed_proof, asset_id, amount, end_block));
+			Ok(())
+		}
+
+		/// Claim a pending swap targeting the origin by revealing the preimage of its hash-lock.
+		///
+		/// The reserved funds of the swap source are repatriated to the free balance of the origin.
+		///
+		/// The dispatch origin for this call must be `Signed` by the swap target.
+		///
+		/// The proof is at most `MAX_SWAP_PROOF_LEN` bytes long.
+		///
+		/// # <weight>
+		/// - O(P) where P is the length of the proof.
+		/// - A base weight plus the weight of every byte of the proof.
+		/// # </weight>
+		#[weight = FunctionOf(
+			|args: (&Vec<u8>,)| {
+				(args.0.len() as Weight)
+					.saturating_mul(CLAIM_SWAP_PROOF_BYTE_WEIGHT)
+					.saturating_add(CLAIM_SWAP_BASE_WEIGHT)
+			},
+			DispatchClass::Normal,
+			true
+		)]
+		pub fn claim_swap(origin, proof: Vec<u8>) -> DispatchResult {
+			let target = ensure_signed(origin)?;
+			ensure!(proof.len() <= MAX_SWAP_PROOF_LEN, Error::<T>::SwapProofTooLong);
+			let hashed_proof = T::Hashing::hash(&proof);
+
+			let swap = Self::pending_swaps(&target, hashed_proof).ok_or(Error::<T>::SwapNotFound)?;
+			ensure!(
+				<frame_system::Module<T>>::block_number() <= swap.end_block,
+				Error::<T>::SwapExpired
+			);
+			ensure!(
+				Self::reserved_balance(&swap.asset_id, &swap.source) >= swap.amount,
+				Error::<T>::SwapUnderfunded
+			);
+
+			Self::repatriate_reserved(&swap.asset_id, &swap.source, &target, swap.amount);
+			<PendingSwaps<T>>::remove(&target, hashed_proof);
+
+			Self::deposit_event(RawEvent::SwapClaimed(swap.source, target, hashed_proof, proof));
+			Ok(())
+		}
+
+		/// Cancel an expired swap created by the origin, returning its funds to the free balance.
+		///
+		/// The dispatch origin for this call must be `Signed` by the swap source.
+		///
+		/// Weights:
+		/// O(1) Limited number of reads/writes.
+		#[weight = SimpleDispatchInfo::FixedNormal(500_000)]
+		pub fn cancel_swap(origin, target: T::AccountId, hashed_proof: T::Hash) -> DispatchResult {
+			let source = ensure_signed(origin)?;
+
+			let swap = Self::pending_swaps(&target, hashed_proof).ok_or(Error::<T>::SwapNotFound)?;
+			ensure!(swap.source == source, Error::<T>::NotSwapSource);
+			ensure!(
+				<frame_system::Module<T>>::block_number() > swap.end_block,
+				Error::<T>::SwapNotExpired
+			);
+
+			Self::unreserve(&swap.asset_id, &source, swap.amount);
+			<PendingSwaps<T>>::remove(&target, hashed_proof);
+
+			Self::deposit_event(RawEvent::SwapCancelled(source, target, hashed_proof));
+			Ok(())
+		}
 	}
 }
 
@@ -585,6 +797,15 @@ decl_storage! {
 		pub Locks get(fn locks):
 			map hasher(blake2_128_concat) T::AccountId => Vec<BalanceLock<T::Balance>>;
 
+		/// Any liquidity locks on the balance of a given asset under an account.
+		///
+		/// These apply to every asset, in addition to `Locks` which only apply to the staking asset.
+		///
+		/// TWOX-NOTE: `AssetId` is trusted.
+		pub AssetLocks get(fn asset_locks):
+			double_map hasher(twox_64_concat) T::AssetId, hasher(blake2_128_concat) T::AccountId
+			=> Vec<BalanceLock<T::Balance>>;
+
 		/// The identity of the asset which is the one that is designated for the chain's staking system.
 		pub StakingAssetId get(fn staking_asset_id) config(): T::AssetId;
 
@@ -593,6 +814,11 @@ decl_storage! {
 
 		/// The info for assets
 		pub AssetMeta get(fn asset_meta) config(): map hasher(twox_64_concat) T::AssetId => AssetInfo;
+
+		/// Pending hash-locked swaps, keyed by target account and hashed proof.
+		pub PendingSwaps get(fn pending_swaps):
+			double_map hasher(blake2_128_concat) T::AccountId, hasher(blake2_128_concat) T::Hash
+			=> Option<PendingSwapOf<T>>;
 	}
 	add_extra_genesis {
 		config(assets): Vec<T::AssetId>;
@@ -613,6 +839,8 @@ decl_storage! {
 decl_event!(
 	pub enum Event<T> where
 		<T as frame_system::Trait>::AccountId,
+		<T as frame_system::Trait>::Hash,
+		<T as frame_system::Trait>::BlockNumber,
 		<T as Trait>::Balance,
 		<T as Trait>::AssetId,
 		AssetOptions = AssetOptions<<T as Trait>::Balance, <T as frame_system::Trait>::AccountId>
@@ -629,6 +857,12 @@ decl_event!(
 		Minted(AssetId, AccountId, Balance),
 		/// Asset burned (asset_id, account, amount).
 		Burned(AssetId, AccountId, Balance),
+		/// Swap created (source, target, hashed_proof, asset_id, amount, end_block).
+		SwapCreated(AccountId, AccountId, Hash, AssetId, Balance, BlockNumber),
+		/// Swap claimed (source, target, hashed_proof, proof).
+		SwapClaimed(AccountId, AccountId, Hash, Vec<u8>),
+		/// Swap cancelled (source, target, hashed_proof).
+		SwapCancelled(AccountId, AccountId, Hash),
 	}
 );
 
@@ -780,6 +1014,48 @@ impl<T: Trait> Module<T> {
 		Ok(())
 	}
 
+	/// Transfer some liquid free balance of several assets from one account to others.
+	/// This will emit a `Transferred` event per transfer.
+	///
+	/// Every transfer is checked before any balance is modified, so either all of them are
+	/// applied or an `Err` is returned and storage is left untouched.
+	pub fn make_batch_transfer(
+		from: &T::AccountId,
+		transfers: &[(T::AssetId, T::AccountId, T::Balance)],
+	) -> DispatchResult {
+		let mut debits = BTreeMap::<T::AssetId, T::Balance>::new();
+		let mut credits = BTreeMap::<(T::AssetId, T::AccountId), T::Balance>::new();
+		for (asset_id, to, amount) in transfers {
+			ensure!(!amount.is_zero(), Error::<T>::ZeroAmount);
+			let debit = debits.entry(*asset_id).or_insert_with(Zero::zero);
+			*debit = debit.checked_add(amount).ok_or(Error::<T>::InsufficientBalance)?;
+			if to != from {
+				let credit = credits.entry((*asset_id, to.clone())).or_insert_with(Zero::zero);
+				*credit = credit.checked_add(amount).ok_or(Error::<T>::TransferOverflow)?;
+			}
+		}
+
+		for (asset_id, amount) in debits {
+			let new_from_balance = Self::free_balance(&asset_id, from)
+				.checked_sub(&amount)
+				.ok_or(Error::<T>::InsufficientBalance)?;
+			Self::ensure_can_withdraw(&asset_id, from, amount, WithdrawReason::Transfer.into(), new_from_balance)?;
+		}
+		for ((asset_id, to), amount) in credits {
+			Self::free_balance(&asset_id, &to)
+				.checked_add(&amount)
+				.ok_or(Error::<T>::TransferOverflow)?;
+		}
+
+		// Every transfer is now known to succeed: intermediate balances are bounded by the
+		// totals checked above.
+		for (asset_id, to, amount) in transfers {
+			Self::make_transfer_with_event(asset_id, from, to, *amount)?;
+		}
+
+		Ok(())
+	}
+
 	/// Move `amount` from free balance to reserved balance.
 	///
 	/// If the free balance is lower than `amount`, then no funds will be moved and an `Err` will
@@ -925,15 +1201,15 @@ impl<T: Trait> Module<T> {
 		reasons: WithdrawReasons,
 		new_balance: T::Balance,
 	) -> DispatchResult {
-		if asset_id != &Self::staking_asset_id() {
-			return Ok(());
+		let mut locks = Self::asset_locks(asset_id, who);
+		if asset_id == &Self::staking_asset_id() {
+			locks.extend(Self::locks(who));
 		}
 
-		let locks = Self::locks(who);
 		if locks.is_empty() {
 			return Ok(());
 		}
-		if Self::locks(who)
+		if locks
 			.into_iter().all(|l| new_balance >= l.amount || !l.reasons.intersects(reasons))
 		{
 			Ok(())
@@ -946,6 +1222,14 @@ impl<T: Trait> Module<T> {
 		<AssetMeta<T> as IterableStorageMap<T::AssetId, AssetInfo>>::iter().collect()
 	}
 
+	/// The assets `who` has a non-zero free balance of, looked up among all issued assets.
+	pub fn assets_held(who: &T::AccountId) -> Vec<T::AssetId> {
+		<TotalIssuance<T> as IterableStorageMap<T::AssetId, T::Balance>>::iter()
+			.map(|(asset_id, _)| asset_id)
+			.filter(|asset_id| !Self::free_balance(asset_id, who).is_zero())
+			.collect()
+	}
+
 	// PRIVATE MUTABLES
 
 	/// NOTE: LOW-LEVEL: This will not attempt to maintain total issuance. It is expected that
@@ -1023,6 +1307,50 @@ impl<T: Trait> Module<T> {
 		locks.retain(|l| l.id != id);
 		<Locks<T>>::insert(who, locks);
 	}
+
+	fn set_asset_lock(
+		asset_id: &T::AssetId,
+		id: LockIdentifier,
+		who: &T::AccountId,
+		amount: T::Balance,
+		reasons: WithdrawReasons,
+	) {
+		<AssetLocks<T>>::mutate(asset_id, who, |locks| {
+			if let Some(lock) = locks.iter_mut().find(|l| l.id == id) {
+				lock.amount = amount;
+				lock.reasons = reasons;
+			} else {
+				locks.push(BalanceLock { id, amount, reasons });
+			}
+		});
+	}
+
+	fn extend_asset_lock(
+		asset_id: &T::AssetId,
+		id: LockIdentifier,
+		who: &T::AccountId,
+		amount: T::Balance,
+		reasons: WithdrawReasons,
+	) {
+		<AssetLocks<T>>::mutate(asset_id, who, |locks| {
+			if let Some(lock) = locks.iter_mut().find(|l| l.id == id) {
+				lock.amount = lock.amount.max(amount);
+				lock.reasons = lock.reasons | reasons;
+			} else {
+				locks.push(BalanceLock { id, amount, reasons });
+			}
+		});
+	}
+
+	fn remove_asset_lock(asset_id: &T::AssetId, id: LockIdentifier, who: &T::AccountId) {
+		let mut locks = Self::asset_locks(asset_id, who);
+		locks.retain(|l| l.id != id);
+		if locks.is_empty() {
+			<AssetLocks<T>>::remove(asset_id, who);
+		} else {
+			<AssetLocks<T>>::insert(asset_id, who, locks);
+		}
+	}
 }
 
 // wrapping these imbalances in a private module is necessary to ensure absolute privacy
@@ -1583,3 +1583,236 @@ fn non_owner_permitted_update_asset_info() {
 			assert_eq!(<AssetMeta<Test>>::get(ASSET_ID), web3_asset_info);
 		});
 }
+
+#[test]
+fn transfer_batch_should_move_several_assets() {
+	ExtBuilder::default()
+		.free_balance((STAKING_ASSET_ID, ALICE, INITIAL_BALANCE))
+		.build()
+		.execute_with(|| {
+			assert_ok!(GenericAsset::create(
+				Origin::ROOT,
+				ALICE,
+				asset_options(PermissionLatest::new(ALICE)),
+				AssetInfo::default()
+			));
+
+			assert_ok!(GenericAsset::transfer_batch(
+				Origin::signed(ALICE),
+				vec![(STAKING_ASSET_ID, BOB, 40), (ASSET_ID, CHARLIE, 300), (STAKING_ASSET_ID, CHARLIE, 10)],
+			));
+
+			assert_eq!(GenericAsset::free_balance(&STAKING_ASSET_ID, &ALICE), INITIAL_BALANCE - 50);
+			assert_eq!(GenericAsset::free_balance(&STAKING_ASSET_ID, &BOB), 40);
+			assert_eq!(GenericAsset::free_balance(&STAKING_ASSET_ID, &CHARLIE), 10);
+			assert_eq!(GenericAsset::free_balance(&ASSET_ID, &ALICE), INITIAL_ISSUANCE - 300);
+			assert_eq!(GenericAsset::free_balance(&ASSET_ID, &CHARLIE), 300);
+
+			let expected_event = TestEvent::generic_asset(RawEvent::Transferred(ASSET_ID, ALICE, CHARLIE, 300));
+			assert!(System::events().iter().any(|record| record.event == expected_event));
+		});
+}
+
+#[test]
+fn transfer_batch_should_not_apply_any_transfer_when_one_fails() {
+	ExtBuilder::default()
+		.free_balance((STAKING_ASSET_ID, ALICE, INITIAL_BALANCE))
+		.build()
+		.execute_with(|| {
+			// Each transfer is affordable on its own but not together.
+			assert_noop!(
+				GenericAsset::transfer_batch(
+					Origin::signed(ALICE),
+					vec![(STAKING_ASSET_ID, BOB, 60), (STAKING_ASSET_ID, CHARLIE, 60)],
+				),
+				Error::<Test>::InsufficientBalance
+			);
+			assert_noop!(
+				GenericAsset::transfer_batch(
+					Origin::signed(ALICE),
+					vec![(STAKING_ASSET_ID, BOB, 60), (STAKING_ASSET_ID, CHARLIE, 0)],
+				),
+				Error::<Test>::ZeroAmount
+			);
+		});
+}
+
+#[test]
+fn transfer_batch_should_respect_locks() {
+	ExtBuilder::default()
+		.free_balance((STAKING_ASSET_ID, ALICE, INITIAL_BALANCE))
+		.build()
+		.execute_with(|| {
+			GenericAsset::set_lock(*b"testlock", &ALICE, 50, WithdrawReasons::all());
+
+			assert_noop!(
+				GenericAsset::transfer_batch(
+					Origin::signed(ALICE),
+					vec![(STAKING_ASSET_ID, BOB, 30), (STAKING_ASSET_ID, CHARLIE, 30)],
+				),
+				Error::<Test>::LiquidityRestrictions
+			);
+			assert_ok!(GenericAsset::transfer_batch(
+				Origin::signed(ALICE),
+				vec![(STAKING_ASSET_ID, BOB, 30), (STAKING_ASSET_ID, CHARLIE, 20)],
+			));
+		});
+}
+
+#[test]
+fn transfer_batch_should_reject_too_many_transfers() {
+	ExtBuilder::default()
+		.free_balance((STAKING_ASSET_ID, ALICE, INITIAL_BALANCE))
+		.build()
+		.execute_with(|| {
+			assert_noop!(
+				GenericAsset::transfer_batch(
+					Origin::signed(ALICE),
+					vec![(STAKING_ASSET_ID, BOB, 1); MAX_BATCH_TRANSFERS + 1],
+				),
+				Error::<Test>::TooManyTransfers
+			);
+		});
+}
+
+#[test]
+fn transfer_batch_weight_should_cover_each_transfer() {
+	use frame_support::weights::GetDispatchInfo;
+
+	let empty = Call::<Test>::transfer_batch(vec![]).get_dispatch_info();
+	assert_eq!(empty.weight, TRANSFER_BATCH_BASE_WEIGHT);
+
+	let batch = Call::<Test>::transfer_batch(vec![(STAKING_ASSET_ID, BOB, 1); 3]).get_dispatch_info();
+	let transfer = Call::<Test>::transfer(STAKING_ASSET_ID, BOB, 1).get_dispatch_info();
+	assert_eq!(batch.weight, TRANSFER_BATCH_BASE_WEIGHT + 3 * transfer.weight);
+}
+
+#[test]
+fn two_party_swap_should_work() {
+	ExtBuilder::default()
+		.free_balance((STAKING_ASSET_ID, ALICE, INITIAL_BALANCE))
+		.build()
+		.execute_with(|| {
+			assert_ok!(GenericAsset::create(
+				Origin::ROOT,
+				BOB,
+				asset_options(PermissionLatest::new(BOB)),
+				AssetInfo::default()
+			));
+			let proof = b"secret".to_vec();
+			let hashed_proof = <Test as frame_system::Trait>::Hashing::hash(&proof);
+
+			assert_ok!(GenericAsset::create_swap(Origin::signed(ALICE), BOB, hashed_proof, STAKING_ASSET_ID, 70, 20));
+			assert_ok!(GenericAsset::create_swap(Origin::signed(BOB), ALICE, hashed_proof, ASSET_ID, 500, 10));
+			assert_eq!(GenericAsset::reserved_balance(&STAKING_ASSET_ID, &ALICE), 70);
+			assert_eq!(GenericAsset::reserved_balance(&ASSET_ID, &BOB), 500);
+
+			// Alice reveals the proof by claiming, which lets Bob claim in turn.
+			assert_ok!(GenericAsset::claim_swap(Origin::signed(ALICE), proof.clone()));
+			assert_ok!(GenericAsset::claim_swap(Origin::signed(BOB), proof.clone()));
+
+			assert_eq!(GenericAsset::free_balance(&STAKING_ASSET_ID, &ALICE), INITIAL_BALANCE - 70);
+			assert_eq!(GenericAsset::free_balance(&STAKING_ASSET_ID, &BOB), 70);
+			assert_eq!(GenericAsset::free_balance(&ASSET_ID, &ALICE), 500);
+			assert_eq!(GenericAsset::free_balance(&ASSET_ID, &BOB), INITIAL_ISSUANCE - 500);
+			assert_eq!(GenericAsset::reserved_balance(&STAKING_ASSET_ID, &ALICE), 0);
+			assert_eq!(GenericAsset::reserved_balance(&ASSET_ID, &BOB), 0);
+			assert_eq!(GenericAsset::pending_swaps(&BOB, hashed_proof), None);
+
+			let expected_event = TestEvent::generic_asset(RawEvent::SwapClaimed(BOB, ALICE, hashed_proof, proof));
+			assert!(System::events().iter().any(|record| record.event == expected_event));
+		});
+}
+
+#[test]
+fn claim_swap_with_wrong_proof_should_fail() {
+	ExtBuilder::default()
+		.free_balance((STAKING_ASSET_ID, ALICE, INITIAL_BALANCE))
+		.build()
+		.execute_with(|| {
+			let hashed_proof = <Test as frame_system::Trait>::Hashing::hash(b"secret");
+			assert_ok!(GenericAsset::create_swap(Origin::signed(ALICE), BOB, hashed_proof, STAKING_ASSET_ID, 70, 20));
+
+			assert_noop!(
+				GenericAsset::claim_swap(Origin::signed(BOB), b"guess".to_vec()),
+				Error::<Test>::SwapNotFound
+			);
+			assert_noop!(
+				GenericAsset::create_swap(Origin::signed(ALICE), BOB, hashed_proof, STAKING_ASSET_ID, 10, 20),
+				Error::<Test>::SwapAlreadyExists
+			);
+		});
+}
+
+#[test]
+fn claim_swap_weight_should_cover_the_proof() {
+	use frame_support::weights::GetDispatchInfo;
+
+	let claim = Call::<Test>::claim_swap(vec![0; 32]).get_dispatch_info();
+	assert_eq!(claim.weight, CLAIM_SWAP_BASE_WEIGHT + 32 * CLAIM_SWAP_PROOF_BYTE_WEIGHT);
+}
+
+#[test]
+fn claim_swap_with_too_long_proof_should_fail() {
+	ExtBuilder::default()
+		.free_balance((STAKING_ASSET_ID, ALICE, INITIAL_BALANCE))
+		.build()
+		.execute_with(|| {
+			let proof = vec![0; MAX_SWAP_PROOF_LEN + 1];
+			let hashed_proof = <Test as frame_system::Trait>::Hashing::hash(&proof);
+			assert_ok!(GenericAsset::create_swap(Origin::signed(ALICE), BOB, hashed_proof, STAKING_ASSET_ID, 70, 20));
+
+			assert_noop!(
+				GenericAsset::claim_swap(Origin::signed(BOB), proof),
+				Error::<Test>::SwapProofTooLong
+			);
+		});
+}
+
+#[test]
+fn swap_should_only_be_cancelled_by_its_source_after_expiry() {
+	ExtBuilder::default()
+		.free_balance((STAKING_ASSET_ID, ALICE, INITIAL_BALANCE))
+		.build()
+		.execute_with(|| {
+			System::set_block_number(1);
+			let proof = b"secret".to_vec();
+			let hashed_proof = <Test as frame_system::Trait>::Hashing::hash(&proof);
+			assert_ok!(GenericAsset::create_swap(Origin::signed(ALICE), BOB, hashed_proof, STAKING_ASSET_ID, 70, 10));
+
+			assert_noop!(
+				GenericAsset::cancel_swap(Origin::signed(ALICE), BOB, hashed_proof),
+				Error::<Test>::SwapNotExpired
+			);
+
+			System::set_block_number(12);
+			assert_noop!(
+				GenericAsset::claim_swap(Origin::signed(BOB), proof),
+				Error::<Test>::SwapExpired
+			);
+			assert_noop!(
+				GenericAsset::cancel_swap(Origin::signed(BOB), BOB, hashed_proof),
+				Error::<Test>::NotSwapSource
+			);
+			assert_ok!(GenericAsset::cancel_swap(Origin::signed(ALICE), BOB, hashed_proof));
+
+			assert_eq!(GenericAsset::free_balance(&STAKING_ASSET_ID, &ALICE), INITIAL_BALANCE);
+			assert_eq!(GenericAsset::reserved_balance(&STAKING_ASSET_ID, &ALICE), 0);
+			assert_eq!(GenericAsset::pending_swaps(&BOB, hashed_proof), None);
+		});
+}
+
+#[test]
+fn assets_held_lists_assets_with_a_free_balance() {
+	ExtBuilder::default()
+		.free_balance((STAKING_ASSET_ID, ALICE, INITIAL_BALANCE))
+		.build()
+		.execute_with(|| {
+			assert_eq!(GenericAsset::assets_held(&ALICE), vec![STAKING_ASSET_ID]);
+			assert!(GenericAsset::assets_held(&BOB).is_empty());
+
+			assert_ok!(GenericAsset::make_transfer(&STAKING_ASSET_ID, &ALICE, &BOB, INITIAL_BALANCE));
+			assert!(GenericAsset::assets_held(&ALICE).is_empty());
+			assert_eq!(GenericAsset::assets_held(&BOB), vec![STAKING_ASSET_ID]);
+		});
+}
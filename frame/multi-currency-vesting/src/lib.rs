@@ -0,0 +1,516 @@
+// Copyright 2019-2020 Plug New Zealand Ltd.
+// This file is part of Plug.
+
+// Plug is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Plug is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Plug.  If not, see <http://www.gnu.org/licenses/>.
+
+//! # Multi-Currency Vesting Module
+//!
+//! - [`multi_currency_vesting::Trait`](./trait.Trait.html)
+//! - [`Call`](./enum.Call.html)
+//!
+//! ## Overview
+//!
+//! A variant of the Vesting module for multi-currency systems such as the Generic Asset module.
+//! Each vesting schedule names the currency it applies to, so an account may vest grants in several
+//! currencies at once, each under its own linear curve.
+//!
+//! As with the Vesting module, the *unvested* amount of a currency is held by a lock and explicit
+//! action is needed to reduce that lock as funds vest. This is done through a dispatchable function,
+//! either `vest` (in typical case where the sender is calling on their own behalf) or `vest_other`
+//! in case the sender is calling on another account's behalf.
+//!
+//! ## Interface
+//!
+//! ### Dispatchable Functions
+//!
+//! - `vest` - Update the lock on a currency, reducing it in line with the amount "vested" so far.
+//! - `vest_other` - Update the lock on a currency of another account, reducing it in line with the
+//!   amount "vested" so far.
+//! - `vested_transfer` - Transfer some of a currency to an account and vest it under a schedule.
+//!
+//! [`Call`]: ./enum.Call.html
+//! [`Trait`]: ./trait.Trait.html
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use sp_std::prelude::*;
+use sp_runtime::{DispatchResult, traits::{StaticLookup, Zero, Saturating, Convert, MaybeSerializeDeserialize}};
+use frame_support::{decl_module, decl_event, decl_storage, decl_error, ensure, Parameter};
+use frame_support::additional_traits::{MultiCurrencyAccounting, MultiLockableCurrency};
+use frame_support::traits::{WithdrawReason, LockIdentifier, ExistenceRequirement, Get};
+use frame_support::weights::SimpleDispatchInfo;
+use frame_system::{self as system, ensure_signed};
+
+pub use pallet_vesting::VestingInfo;
+
+type BalanceOf<T> = <<T as Trait>::MultiCurrency as MultiCurrencyAccounting>::Balance;
+
+pub trait Trait: frame_system::Trait {
+	/// The overarching event type.
+	type Event: From<Event<Self>> + Into<<Self as frame_system::Trait>::Event>;
+
+	/// The identifier of a currency.
+	type CurrencyId: Parameter + Copy + Default + MaybeSerializeDeserialize;
+
+	/// The multi-currency system holding the vested balances.
+	type MultiCurrency: MultiLockableCurrency<AccountId = Self::AccountId, CurrencyId = Self::CurrencyId>;
+
+	/// Convert the block number into a balance.
+	type BlockNumberToBalance: Convert<Self::BlockNumber, BalanceOf<Self>>;
+
+	/// The minimum amount transferred to call `vested_transfer`.
+	type MinVestedTransfer: Get<BalanceOf<Self>>;
+}
+
+const VESTING_ID: LockIdentifier = *b"vesting ";
+
+decl_storage! {
+	trait Store for Module<T: Trait> as MultiCurrencyVesting {
+		/// Information regarding the vesting of a given currency under an account.
+		pub Vesting get(fn vesting):
+			double_map hasher(blake2_128_concat) T::AccountId, hasher(blake2_128_concat) T::CurrencyId
+			=> Option<VestingInfo<BalanceOf<T>, T::BlockNumber>>;
+	}
+	add_extra_genesis {
+		config(vesting): Vec<(T::AccountId, T::CurrencyId, T::BlockNumber, T::BlockNumber, BalanceOf<T>)>;
+		build(|config: &GenesisConfig<T>| {
+			// Generate initial vesting configuration
+			// * who - Account which we are generating vesting configuration for
+			// * currency - Currency which will be vested
+			// * begin - Block when the account will start to vest
+			// * length - Number of blocks from `begin` until fully vested
+			// * liquid - Number of units which can be spent before vesting begins
+			for &(ref who, currency, begin, length, liquid) in config.vesting.iter() {
+				let balance = T::MultiCurrency::free_balance(who, Some(currency));
+				assert!(!balance.is_zero(), "Currencies must be init'd before vesting");
+				// Total genesis `balance` minus `liquid` equals funds locked for vesting
+				let locked = balance.saturating_sub(liquid);
+				let length_as_balance = T::BlockNumberToBalance::convert(length);
+				let per_block = locked / length_as_balance.max(sp_runtime::traits::One::one());
+
+				Vesting::<T>::insert(who, currency, VestingInfo {
+					locked: locked,
+					per_block: per_block,
+					starting_block: begin
+				});
+				let reasons = WithdrawReason::Transfer | WithdrawReason::Reserve;
+				T::MultiCurrency::set_lock(VESTING_ID, who, currency, locked, reasons);
+			}
+		})
+	}
+}
+
+decl_event!(
+	pub enum Event<T> where
+		AccountId = <T as frame_system::Trait>::AccountId,
+		CurrencyId = <T as Trait>::CurrencyId,
+		Balance = BalanceOf<T>
+	{
+		/// The amount vested has been updated. This could indicate more funds are available. The
+		/// balance given is the amount of the currency which is left unvested (and thus locked).
+		VestingUpdated(AccountId, CurrencyId, Balance),
+		/// An account (given) has become fully vested in a currency. No further vesting can happen.
+		VestingCompleted(AccountId, CurrencyId),
+	}
+);
+
+decl_error! {
+	/// Error for the multi-currency vesting module.
+	pub enum Error for Module<T: Trait> {
+		/// The account given is not vesting the currency.
+		NotVesting,
+		/// An existing vesting schedule already exists for this account and currency that cannot be
+		/// clobbered.
+		ExistingVestingSchedule,
+		/// Amount being transferred is too low to create a vesting schedule.
+		AmountLow,
+	}
+}
+
+decl_module! {
+	pub struct Module<T: Trait> for enum Call where origin: T::Origin {
+		type Error = Error<T>;
+
+		/// The minimum amount to be transferred to create a new vesting schedule.
+		const MinVestedTransfer: BalanceOf<T> = T::MinVestedTransfer::get();
+
+		fn deposit_event() = default;
+
+		/// Unlock any vested funds of `currency` in the sender account.
+		///
+		/// The dispatch origin for this call must be _Signed_ and the sender must have funds of
+		/// `currency` still locked under this module.
+		///
+		/// Emits either `VestingCompleted` or `VestingUpdated`.
+		///
+		/// # <weight>
+		/// - `O(1)`.
+		/// - One balance-lock operation.
+		/// - One storage read (codec `O(1)`) and up to one removal.
+		/// - One event.
+		/// # </weight>
+		fn vest(origin, currency: T::CurrencyId) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			Self::update_lock(who, currency)
+		}
+
+		/// Unlock any vested funds of `currency` in a `target` account.
+		///
+		/// The dispatch origin for this call must be _Signed_.
+		///
+		/// - `target`: The account whose vested funds should be unlocked. Must have funds of
+		/// `currency` still locked under this module.
+		///
+		/// Emits either `VestingCompleted` or `VestingUpdated`.
+		///
+		/// # <weight>
+		/// - `O(1)`.
+		/// - Up to one account lookup.
+		/// - One balance-lock operation.
+		/// - One storage read (codec `O(1)`) and up to one removal.
+		/// - One event.
+		/// # </weight>
+		fn vest_other(
+			origin,
+			target: <T::Lookup as StaticLookup>::Source,
+			currency: T::CurrencyId,
+		) -> DispatchResult {
+			ensure_signed(origin)?;
+			Self::update_lock(T::Lookup::lookup(target)?, currency)
+		}
+
+		/// Create a vested transfer of `currency`.
+		///
+		/// The dispatch origin for this call must be _Signed_.
+		///
+		/// - `target`: The account that should be transferred the vested funds.
+		/// - `currency`: The currency to transfer and vest.
+		/// - `schedule`: The vesting schedule attached to the transfer.
+		///
+		/// Emits `VestingUpdated`.
+		///
+		/// # <weight>
+		/// - Creates a new storage entry, but is protected by a minimum transfer
+		///	   amount needed to succeed.
+		/// # </weight>
+		#[weight = SimpleDispatchInfo::FixedNormal(1_000_000)]
+		pub fn vested_transfer(
+			origin,
+			target: <T::Lookup as StaticLookup>::Source,
+			currency: T::CurrencyId,
+			schedule: VestingInfo<BalanceOf<T>, T::BlockNumber>,
+		) -> DispatchResult {
+			let transactor = ensure_signed(origin)?;
+			ensure!(schedule.locked >= T::MinVestedTransfer::get(), Error::<T>::AmountLow);
+
+			let who = T::Lookup::lookup(target)?;
+			ensure!(!Vesting::<T>::contains_key(&who, currency), Error::<T>::ExistingVestingSchedule);
+
+			T::MultiCurrency::transfer(
+				&transactor,
+				&who,
+				Some(currency),
+				schedule.locked,
+				ExistenceRequirement::AllowDeath,
+			)?;
+
+			Self::add_vesting_schedule(&who, currency, schedule.locked, schedule.per_block, schedule.starting_block)
+				.expect("user does not have an existing vesting schedule; q.e.d.");
+
+			Ok(())
+		}
+	}
+}
+
+impl<T: Trait> Module<T> {
+	/// Get the amount of `currency` that is currently being vested and cannot be transferred out of
+	/// this account.
+	pub fn vesting_balance(who: &T::AccountId, currency: T::CurrencyId) -> Option<BalanceOf<T>> {
+		if let Some(v) = Self::vesting(who, currency) {
+			let now = <frame_system::Module<T>>::block_number();
+			let locked_now = v.locked_at::<T::BlockNumberToBalance>(now);
+			Some(T::MultiCurrency::free_balance(who, Some(currency)).min(locked_now))
+		} else {
+			None
+		}
+	}
+
+	/// Adds a vesting schedule of `currency` to a given account.
+	///
+	/// If there already exists a vesting schedule for the given account and currency, an `Err` is
+	/// returned and nothing is updated.
+	///
+	/// On success, a linearly reducing amount of funds will be locked. In order to realise any
+	/// reduction of the lock over time as it diminishes, the account owner must use `vest` or
+	/// `vest_other`.
+	///
+	/// Is a no-op if the amount to be vested is zero.
+	pub fn add_vesting_schedule(
+		who: &T::AccountId,
+		currency: T::CurrencyId,
+		locked: BalanceOf<T>,
+		per_block: BalanceOf<T>,
+		starting_block: T::BlockNumber
+	) -> DispatchResult {
+		if locked.is_zero() { return Ok(()) }
+		if Vesting::<T>::contains_key(who, currency) {
+			Err(Error::<T>::ExistingVestingSchedule)?
+		}
+		let vesting_schedule = VestingInfo {
+			locked,
+			per_block,
+			starting_block
+		};
+		Vesting::<T>::insert(who, currency, vesting_schedule);
+		// it can't fail, but even if somehow it did, we don't really care.
+		let _ = Self::update_lock(who.clone(), currency);
+		Ok(())
+	}
+
+	/// Remove a vesting schedule of `currency` for a given account.
+	pub fn remove_vesting_schedule(who: &T::AccountId, currency: T::CurrencyId) {
+		Vesting::<T>::remove(who, currency);
+		T::MultiCurrency::remove_lock(VESTING_ID, who, currency);
+	}
+
+	/// (Re)set or remove the module's lock on the `currency` balance of `who` in accordance with
+	/// their current unvested amount.
+	fn update_lock(who: T::AccountId, currency: T::CurrencyId) -> DispatchResult {
+		let vesting = Self::vesting(&who, currency).ok_or(Error::<T>::NotVesting)?;
+		let now = <frame_system::Module<T>>::block_number();
+		let locked_now = vesting.locked_at::<T::BlockNumberToBalance>(now);
+
+		if locked_now.is_zero() {
+			T::MultiCurrency::remove_lock(VESTING_ID, &who, currency);
+			Vesting::<T>::remove(&who, currency);
+			Self::deposit_event(RawEvent::VestingCompleted(who, currency));
+		} else {
+			let reasons = WithdrawReason::Transfer | WithdrawReason::Reserve;
+			T::MultiCurrency::set_lock(VESTING_ID, &who, currency, locked_now, reasons);
+			Self::deposit_event(RawEvent::VestingUpdated(who, currency, locked_now));
+		}
+		Ok(())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	use frame_support::{assert_ok, assert_noop, impl_outer_origin, parameter_types, weights::Weight};
+	use sp_core::H256;
+	// The testing primitives are very useful for avoiding having to work with signatures
+	// or public keys. `u64` is used as the `AccountId` and no `Signature`s are required.
+	use sp_runtime::{
+		Perbill,
+		testing::Header,
+		traits::{BlakeTwo256, IdentityLookup, Identity},
+	};
+
+	impl_outer_origin! {
+		pub enum Origin for Test  where system = frame_system {}
+	}
+
+	const STAKING_ASSET_ID: u32 = 16000;
+	const SPENDING_ASSET_ID: u32 = 16001;
+	const GRANT_ASSET_ID: u32 = 16002;
+
+	// For testing the pallet, we construct most of a mock runtime. This means
+	// first constructing a configuration type (`Test`) which `impl`s each of the
+	// configuration traits of pallets we want to use.
+	#[derive(Clone, Eq, PartialEq)]
+	pub struct Test;
+	parameter_types! {
+		pub const BlockHashCount: u64 = 250;
+		pub const MaximumBlockWeight: Weight = 1024;
+		pub const MaximumBlockLength: u32 = 2 * 1024;
+		pub const AvailableBlockRatio: Perbill = Perbill::one();
+	}
+	impl frame_system::Trait for Test {
+		type Origin = Origin;
+		type Index = u64;
+		type BlockNumber = u64;
+		type Hash = H256;
+		type Call = ();
+		type Hashing = BlakeTwo256;
+		type AccountId = u64;
+		type Lookup = IdentityLookup<Self::AccountId>;
+		type Header = Header;
+		type Event = ();
+		type BlockHashCount = BlockHashCount;
+		type MaximumBlockWeight = MaximumBlockWeight;
+		type MaximumBlockLength = MaximumBlockLength;
+		type AvailableBlockRatio = AvailableBlockRatio;
+		type Version = ();
+		type ModuleToIndex = ();
+		type DelegatedDispatchVerifier = ();
+		type Doughnut = ();
+	}
+	impl pallet_generic_asset::Trait for Test {
+		type Balance = u64;
+		type AssetId = u32;
+		type Event = ();
+	}
+	parameter_types! {
+		pub const MinVestedTransfer: u64 = 256 * 2;
+	}
+	impl Trait for Test {
+		type Event = ();
+		type CurrencyId = u32;
+		type MultiCurrency = GenericAsset;
+		type BlockNumberToBalance = Identity;
+		type MinVestedTransfer = MinVestedTransfer;
+	}
+	type System = frame_system::Module<Test>;
+	type GenericAsset = pallet_generic_asset::Module<Test>;
+	type Vesting = Module<Test>;
+
+	fn new_test_ext() -> sp_io::TestExternalities {
+		let mut t = frame_system::GenesisConfig::default().build_storage::<Test>().unwrap();
+		pallet_generic_asset::GenesisConfig::<Test> {
+			assets: vec![STAKING_ASSET_ID, SPENDING_ASSET_ID, GRANT_ASSET_ID],
+			initial_balance: 2560,
+			endowed_accounts: vec![1, 2, 3],
+			next_asset_id: 17000,
+			staking_asset_id: STAKING_ASSET_ID,
+			spending_asset_id: SPENDING_ASSET_ID,
+			permissions: vec![],
+			asset_meta: vec![],
+		}.assimilate_storage(&mut t).unwrap();
+		GenesisConfig::<Test> {
+			vesting: vec![
+				(1, STAKING_ASSET_ID, 0, 10, 1280),
+				(1, GRANT_ASSET_ID, 10, 20, 0),
+			],
+		}.assimilate_storage(&mut t).unwrap();
+		let mut ext = sp_io::TestExternalities::new(t);
+		ext.execute_with(|| System::set_block_number(1));
+		ext
+	}
+
+	fn transfer(from: u64, to: u64, currency: u32, amount: u64) -> DispatchResult {
+		<GenericAsset as MultiCurrencyAccounting>::transfer(
+			&from,
+			&to,
+			Some(currency),
+			amount,
+			ExistenceRequirement::AllowDeath,
+		)
+	}
+
+	#[test]
+	fn schedules_in_different_currencies_vest_independently() {
+		new_test_ext().execute_with(|| {
+			assert_eq!(
+				Vesting::vesting(&1, STAKING_ASSET_ID),
+				Some(VestingInfo { locked: 1280, per_block: 128, starting_block: 0 })
+			);
+			assert_eq!(
+				Vesting::vesting(&1, GRANT_ASSET_ID),
+				Some(VestingInfo { locked: 2560, per_block: 128, starting_block: 10 })
+			);
+			assert_eq!(Vesting::vesting(&1, SPENDING_ASSET_ID), None);
+
+			// 128 units of the staking asset are vested at block 1
+			assert_eq!(Vesting::vesting_balance(&1, STAKING_ASSET_ID), Some(128 * 9));
+			// The grant has not started vesting
+			assert_eq!(Vesting::vesting_balance(&1, GRANT_ASSET_ID), Some(2560));
+			assert_eq!(Vesting::vesting_balance(&1, SPENDING_ASSET_ID), None);
+
+			System::set_block_number(20);
+			assert_eq!(Vesting::vesting_balance(&1, STAKING_ASSET_ID), Some(0));
+			assert_eq!(Vesting::vesting_balance(&1, GRANT_ASSET_ID), Some(1280));
+		});
+	}
+
+	#[test]
+	fn unvested_balance_should_not_transfer() {
+		new_test_ext().execute_with(|| {
+			assert_noop!(
+				transfer(1, 2, GRANT_ASSET_ID, 1),
+				pallet_generic_asset::Error::<Test>::LiquidityRestrictions,
+			);
+			assert_noop!(
+				transfer(1, 2, STAKING_ASSET_ID, 1281),
+				pallet_generic_asset::Error::<Test>::LiquidityRestrictions,
+			);
+			// Currencies without a schedule are unaffected
+			assert_ok!(transfer(1, 2, SPENDING_ASSET_ID, 2560));
+		});
+	}
+
+	#[test]
+	fn vest_should_only_release_the_given_currency() {
+		new_test_ext().execute_with(|| {
+			System::set_block_number(20);
+
+			assert_ok!(Vesting::vest(Origin::signed(1), GRANT_ASSET_ID));
+			assert_ok!(transfer(1, 2, GRANT_ASSET_ID, 1280));
+			assert_noop!(
+				transfer(1, 2, GRANT_ASSET_ID, 1),
+				pallet_generic_asset::Error::<Test>::LiquidityRestrictions,
+			);
+			// The staking asset lock has not been updated yet
+			assert_noop!(
+				transfer(1, 2, STAKING_ASSET_ID, 1281),
+				pallet_generic_asset::Error::<Test>::LiquidityRestrictions,
+			);
+
+			assert_ok!(Vesting::vest_other(Origin::signed(2), 1, STAKING_ASSET_ID));
+			assert_eq!(Vesting::vesting(&1, STAKING_ASSET_ID), None);
+			assert_ok!(transfer(1, 2, STAKING_ASSET_ID, 2560));
+
+			assert_noop!(Vesting::vest(Origin::signed(2), GRANT_ASSET_ID), Error::<Test>::NotVesting);
+		});
+	}
+
+	#[test]
+	fn vested_transfer_works() {
+		new_test_ext().execute_with(|| {
+			let schedule = VestingInfo { locked: 256 * 5, per_block: 64, starting_block: 10 };
+			assert_ok!(Vesting::vested_transfer(Origin::signed(3), 2, GRANT_ASSET_ID, schedule));
+
+			assert_eq!(Vesting::vesting(&2, GRANT_ASSET_ID), Some(schedule));
+			assert_eq!(GenericAsset::free_balance(&GRANT_ASSET_ID, &3), 2560 - 256 * 5);
+			assert_eq!(GenericAsset::free_balance(&GRANT_ASSET_ID, &2), 2560 + 256 * 5);
+			assert_eq!(Vesting::vesting_balance(&2, GRANT_ASSET_ID), Some(256 * 5));
+
+			System::set_block_number(20);
+			assert_eq!(Vesting::vesting_balance(&2, GRANT_ASSET_ID), Some(10 * 64));
+
+			System::set_block_number(30);
+			assert_eq!(Vesting::vesting_balance(&2, GRANT_ASSET_ID), Some(0));
+		});
+	}
+
+	#[test]
+	fn vested_transfer_correctly_fails() {
+		new_test_ext().execute_with(|| {
+			let schedule = VestingInfo { locked: 256 * 5, per_block: 64, starting_block: 10 };
+			assert_noop!(
+				Vesting::vested_transfer(Origin::signed(3), 1, GRANT_ASSET_ID, schedule),
+				Error::<Test>::ExistingVestingSchedule,
+			);
+
+			let schedule_too_low = VestingInfo { locked: 256, per_block: 64, starting_block: 10 };
+			assert_noop!(
+				Vesting::vested_transfer(Origin::signed(3), 2, GRANT_ASSET_ID, schedule_too_low),
+				Error::<Test>::AmountLow,
+			);
+
+			// The same account may still vest the currency it does not vest yet
+			assert_ok!(Vesting::vested_transfer(Origin::signed(3), 1, SPENDING_ASSET_ID, schedule));
+		});
+	}
+}
@@ -3,9 +3,9 @@
 
 use crate::dispatch::{Parameter, DispatchError, DispatchResult};
 use crate::traits::{
-	ExistenceRequirement, Imbalance, SignedImbalance, UpdateBalanceOutcome, WithdrawReasons,
+	ExistenceRequirement, Imbalance, LockIdentifier, SignedImbalance, UpdateBalanceOutcome, WithdrawReasons,
 };
-use codec::FullCodec;
+use codec::{Encode, FullCodec};
 use sp_std::{fmt::Debug, marker::PhantomData, result, prelude::Vec, any::Any};
 use sp_runtime::traits::{
 	PlugDoughnutApi, MaybeSerializeDeserialize, AtLeast32Bit, Zero,
@@ -92,6 +92,26 @@ pub trait DelegatedDispatchVerifier {
 	) -> Result<(), &'static str> {
 		Err("Doughnut contract to contract call verification is not implemented for this domain")
 	}
+
+	/// Check the doughnut is signed by its issuer, i.e. that the issuer granted the delegation.
+	fn verify_signature(_doughnut: &Self::Doughnut) -> Result<(), &'static str> {
+		Err("Doughnut signature verification is not implemented for this domain")
+	}
+}
+
+/// Doughnut authorisations recorded on-chain.
+///
+/// An issuer which can't sign a doughnut, e.g. a contract, authorises it on-chain instead. Such a
+/// doughnut is accepted in place of a signed one.
+pub trait DoughnutAuthorisations<AccountId> {
+	/// Return whether `issuer` has authorised the given doughnut on-chain
+	fn is_authorised<Doughnut: Encode>(issuer: &AccountId, doughnut: &Doughnut) -> bool;
+}
+
+impl<AccountId> DoughnutAuthorisations<AccountId> for () {
+	fn is_authorised<Doughnut: Encode>(_issuer: &AccountId, _doughnut: &Doughnut) -> bool {
+		false
+	}
 }
 
 pub struct DummyDispatchVerifier<D, A>(PhantomData<(D, A)>);
@@ -119,6 +139,10 @@ impl<D: PlugDoughnutApi, A: Parameter> DelegatedDispatchVerifier for DummyDispat
 	) -> Result<(), &'static str> {
 		Ok(())
 	}
+
+	fn verify_signature(_doughnut: &Self::Doughnut) -> Result<(), &'static str> {
+		Ok(())
+	}
 }
 
 impl DelegatedDispatchVerifier for () {
@@ -149,6 +173,10 @@ impl DelegatedDispatchVerifier for () {
 	) -> Result<(), &'static str> {
 		DummyDispatchVerifier::<Self::Doughnut, Self::AccountId>::verify_contract_to_contract_call(caller, doughnut, addr)
 	}
+
+	fn verify_signature(doughnut: &Self::Doughnut) -> Result<(), &'static str> {
+		DummyDispatchVerifier::<Self::Doughnut, Self::AccountId>::verify_signature(doughnut)
+	}
 }
 
 /// Something which may have doughnut. Returns a ref to the doughnut, if any.
@@ -214,6 +242,9 @@ pub trait MultiCurrencyAccounting {
 	/// collapsed to zero if it ever becomes less than `ExistentialDeposit`.
 	fn free_balance(who: &Self::AccountId, currency: Option<Self::CurrencyId>) -> Self::Balance;
 
+	/// The currencies `who` has a non-zero free balance of, including the default one.
+	fn currencies_held(who: &Self::AccountId) -> Vec<Self::CurrencyId>;
+
 	/// Returns `Ok` iff the account is able to make a withdrawal of the given amount
 	/// for the given reason. Basically, it's just a dry-run of `withdraw`.
 	///
@@ -290,6 +321,43 @@ pub trait MultiCurrencyAccounting {
 
 }
 
+/// A `MultiCurrencyAccounting` system which can place liquidity restrictions on the balance of each
+/// currency independently.
+pub trait MultiLockableCurrency: MultiCurrencyAccounting {
+	/// Create a new balance lock on the `currency` balance of account `who`.
+	///
+	/// If the new lock is valid (i.e. not already expired), it will push the struct to
+	/// the `Locks` vec in storage. Note that you can lock more funds than a user has.
+	///
+	/// If the lock `id` already exists for this account and currency, update it.
+	fn set_lock(
+		id: LockIdentifier,
+		who: &Self::AccountId,
+		currency: Self::CurrencyId,
+		amount: Self::Balance,
+		reasons: WithdrawReasons,
+	);
+
+	/// Changes a balance lock (selected by `id`) so that it becomes less liquid in all
+	/// parameters or creates a new one if it does not exist.
+	///
+	/// Calling `extend_lock` on an existing lock `id` differs from `set_lock` in that it
+	/// applies the most severe constraints of the two, while `set_lock` replaces the lock
+	/// with the new parameters. As in, `extend_lock` will set:
+	/// - maximum `amount`
+	/// - bitwise mask of all `reasons`
+	fn extend_lock(
+		id: LockIdentifier,
+		who: &Self::AccountId,
+		currency: Self::CurrencyId,
+		amount: Self::Balance,
+		reasons: WithdrawReasons,
+	);
+
+	/// Remove an existing lock from the `currency` balance of `who`.
+	fn remove_lock(id: LockIdentifier, who: &Self::AccountId, currency: Self::CurrencyId);
+}
+
 /// A type which provides an ID with authority from chain storage
 pub trait AssetIdAuthority {
 	/// The asset ID type e.g a `u32`
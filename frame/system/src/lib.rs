@@ -766,6 +766,16 @@ impl<T: Trait> Module<T> {
 		AllExtrinsicsWeight::put(next_weight);
 	}
 
+	/// Inform the system module that some of the weight accounted for the current extrinsic was
+	/// not used, e.g. the unused gas of a smart contract call weighed by its gas limit.
+	///
+	/// NOTE: the caller must not refund more weight than its extrinsic was accounted for, otherwise
+	/// the weight of other extrinsics of the block is released too.
+	pub fn refund_unused_weight(weight: Weight) {
+		let current_weight = AllExtrinsicsWeight::get().unwrap_or_default();
+		AllExtrinsicsWeight::put(current_weight.saturating_sub(weight));
+	}
+
 	/// Start the execution of a particular block.
 	pub fn initialize(
 		number: &T::BlockNumber,
@@ -1675,6 +1685,21 @@ mod tests {
 		})
 	}
 
+	#[test]
+	fn refund_unused_weight_works() {
+		new_test_ext().execute_with(|| {
+			let info = DispatchInfo { weight: 1_000, ..Default::default() };
+			let len = 0_usize;
+
+			assert!(CheckWeight::<Test>(PhantomData).pre_dispatch(&1, CALL, info, len).is_ok());
+			assert_eq!(System::all_extrinsics_weight(), 1_000);
+			System::refund_unused_weight(400);
+			assert_eq!(System::all_extrinsics_weight(), 600);
+			System::refund_unused_weight(1_000);
+			assert_eq!(System::all_extrinsics_weight(), 0);
+		})
+	}
+
 	#[test]
 	fn signed_ext_check_weight_works_operational_tx() {
 		new_test_ext().execute_with(|| {
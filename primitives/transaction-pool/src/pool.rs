@@ -238,6 +238,19 @@ pub trait TransactionPool: Send + Sync {
 	/// Notify the pool about transactions broadcast.
 	fn on_broadcasted(&self, propagations: HashMap<TxHash<Self>, Vec<String>>);
 
+	/// Returns a future that imports one unverified transaction received from the network
+	/// to the pool.
+	///
+	/// Unlike transactions submitted through `submit_one`, the transaction is expected to
+	/// be gossiped again, so that pools may handle it differently.
+	fn submit_from_network(
+		&self,
+		at: &BlockId<Self::Block>,
+		xt: TransactionFor<Self>,
+	) -> PoolFuture<TxHash<Self>, Self::Error> {
+		self.submit_one(at, TransactionSource::External, xt)
+	}
+
 	/// Returns transaction hash
 	fn hash_of(&self, xt: &TransactionFor<Self>) -> TxHash<Self>;
 
@@ -40,6 +40,7 @@ pub use storage_proof::StorageProof;
 /// Various re-exports from the `trie-db` crate.
 pub use trie_db::{
 	Trie, TrieMut, DBValue, Recorder, CError, Query, TrieLayout, TrieConfiguration, nibble_ops, TrieDBIterator,
+	trie_visit, ProcessEncodedNode, ChildReference,
 };
 /// Various re-exports from the `memory-db` crate.
 pub use memory_db::KeyFunction;
@@ -22,6 +22,7 @@ use sp_runtime::{
 	transaction_validity::{InvalidTransaction, TransactionValidityError, ValidTransaction},
 };
 use frame_support::{
+	additional_traits::DoughnutAuthorisations,
 	dispatch::DispatchInfo,
 	traits::Time,
 };
@@ -105,8 +106,13 @@ where
 	fn additional_signed(&self) -> sp_std::result::Result<(), TransactionValidityError> { Ok(()) }
 	fn validate(&self, who: &Self::AccountId, _call: &Self::Call, _info: Self::DispatchInfo, _len: usize) -> Result<ValidTransaction, TransactionValidityError>
 	{
-		// Check doughnut signature verifies
-		if let Err(err) = self.verify() {
+		// Check doughnut signature verifies, unless the issuer authorised the doughnut on-chain
+		let verified = if Runtime::Authorisations::is_authorised(&PlugDoughnutApi::issuer(self), self) {
+			Ok(())
+		} else {
+			self.verify()
+		};
+		if let Err(err) = verified {
 			let code = match err {
 				VerifyError::Invalid => error_code::VERIFY_INVALID,
 				VerifyError::UnsupportedVersion => error_code::VERIFY_UNSUPPORTED_VERSION,
@@ -153,11 +159,20 @@ mod tests {
 		}
 	}
 
+	/// Authorises every doughnut issued by Charlie on-chain
+	pub struct MockAuthorisations;
+	impl DoughnutAuthorisations<AccountId> for MockAuthorisations {
+		fn is_authorised<Doughnut: codec::Encode>(issuer: &AccountId, _doughnut: &Doughnut) -> bool {
+			*issuer == AccountKeyring::Charlie.to_account_id()
+		}
+	}
+
 	impl DoughnutRuntime for Runtime {
 		type AccountId = AccountId;
 		type Call = ();
 		type Doughnut = PlugDoughnut<Self>;
 		type TimestampProvider = FixedTimestampProvider;
+		type Authorisations = MockAuthorisations;
 	}
 
 	// Helper function to create a DoughnutV0
@@ -372,4 +387,21 @@ mod tests {
 			Err(InvalidTransaction::Custom(error_code::VERIFY_INVALID).into())
 		);
 	}
+
+	#[test]
+	fn plug_doughnut_validates_unsigned_when_authorised_on_chain() {
+		let (issuer, holder) = (AccountKeyring::Charlie, AccountKeyring::Bob);
+		let doughnut = make_doughnut(issuer.to_raw_public(), holder.to_raw_public());
+
+		let plug_doughnut = PlugDoughnut::<Runtime>::new(Doughnut::V0(doughnut));
+		assert!(
+			<PlugDoughnut<_> as SignedExtension>::validate(
+				&plug_doughnut,
+				&holder.to_account_id(), // who
+				&(), // Call
+				Default::default(), // DispatchInfo
+				0usize // len
+			).is_ok()
+		);
+	}
 }
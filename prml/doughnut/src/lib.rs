@@ -23,10 +23,10 @@ use codec::{Encode, Decode};
 use sp_std::{self, prelude::Vec, any::Any};
 use sp_runtime::{
 	Doughnut,
-	traits::{PlugDoughnutApi, Member},
+	traits::{PlugDoughnutApi, DoughnutVerify, Member},
 };
 use frame_support::{
-	additional_traits::DelegatedDispatchVerifier,
+	additional_traits::{DelegatedDispatchVerifier, DoughnutAuthorisations},
 	traits::Time,
 	Parameter,
 };
@@ -42,6 +42,8 @@ pub trait DoughnutRuntime {
 	type Call;
 	type Doughnut: Member + Parameter + PlugDoughnutApi;
 	type TimestampProvider: Time;
+	/// Doughnuts authorised on-chain by issuers which can't sign them, e.g. contracts
+	type Authorisations: DoughnutAuthorisations<Self::AccountId>;
 }
 
 /// A doughnut wrapped for compatibility with the extrinsic transport layer and the plug runtime types.
@@ -71,7 +73,11 @@ where
 /// It verifies that a doughnut allows execution of a module+method combination
 pub struct PlugDoughnutDispatcher<Runtime: DoughnutRuntime>(sp_std::marker::PhantomData<Runtime>);
 
-impl<Runtime: DoughnutRuntime> DelegatedDispatchVerifier for PlugDoughnutDispatcher<Runtime> {
+impl<Runtime> DelegatedDispatchVerifier for PlugDoughnutDispatcher<Runtime>
+where
+	Runtime: DoughnutRuntime,
+	Runtime::Doughnut: DoughnutVerify,
+{
 	type Doughnut = Runtime::Doughnut;
 	type AccountId = Runtime::AccountId;
 	const DOMAIN: &'static str = "plug";
@@ -84,4 +90,8 @@ impl<Runtime: DoughnutRuntime> DelegatedDispatchVerifier for PlugDoughnutDispatc
 	) -> Result<(), &'static str> {
 		Err("Doughnut dispatch verification is not implemented for this domain")
 	}
+	/// Verify the doughnut signature of the issuer
+	fn verify_signature(doughnut: &Runtime::Doughnut) -> Result<(), &'static str> {
+		doughnut.verify().map_err(|_| "Doughnut signature verification failed")
+	}
 }
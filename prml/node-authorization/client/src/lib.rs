@@ -0,0 +1,375 @@
+// Copyright 2020 Plug New Zealand Limited
+// This file is part of Plug.
+
+// Plug is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Plug is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Plug. If not, see <http://www.gnu.org/licenses/>.
+
+//! # Node Authorization client.
+//!
+//! Enforces the authorization state kept by the `prml-node-authorization`
+//! module on the local node.
+//!
+//! On every finalized block the nodes the local node is authorized to connect
+//! to are read from the state and set, along with the reserved nodes of the
+//! node configuration, as the reserved nodes of the peerset, which then only
+//! accepts reserved peers. Peers which lose their authorization are
+//! disconnected without restarting the node. The peerset is left untouched
+//! while the node is major syncing.
+//!
+//! As long as there are no well known nodes on chain, the peerset keeps the
+//! reserved nodes and reserved only mode of the node configuration.
+//!
+//! ```ignore
+//! service.spawn_task(
+//!     "node-authorization",
+//!     prml_node_authorization_client::run(
+//!         service.client(),
+//!         service.network(),
+//!         reserved_nodes,
+//!         reserved_only,
+//!     ),
+//! );
+//! ```
+
+use std::{
+	collections::{BTreeSet, HashSet},
+	sync::Arc,
+};
+
+use codec::{Decode, Encode};
+use futures::{future, Future, StreamExt};
+use log::{debug, info, warn};
+use sc_client_api::{Backend, BlockchainEvents, StorageProvider};
+use sc_network::{ExHashT, NetworkStateInfo, PeerId};
+use sp_core::{
+	hashing::{blake2_128, twox_128},
+	storage::StorageKey,
+};
+use sp_runtime::{generic::BlockId, traits::Block as BlockT};
+
+/// The storage prefix of the node authorization module.
+const MODULE_PREFIX: &[u8] = b"NodeAuthorization";
+
+/// Provides the hooks into the networking the node authorization needs.
+///
+/// Using this trait instead of `NetworkService` directly allows to unit test
+/// `NodeAuthorization`.
+pub trait NetworkProvider: NetworkStateInfo {
+	/// Set the reserved nodes of the peerset.
+	fn set_reserved_nodes(&self, reserved_nodes: HashSet<PeerId>);
+
+	/// Set whether the peerset only accepts reserved nodes.
+	fn set_reserved_only(&self, reserved_only: bool);
+
+	/// Returns whether the node is major syncing.
+	fn is_major_syncing(&self) -> bool;
+}
+
+impl<B, H> NetworkProvider for sc_network::NetworkService<B, H>
+where
+	B: BlockT + 'static,
+	H: ExHashT,
+{
+	fn set_reserved_nodes(&self, reserved_nodes: HashSet<PeerId>) {
+		self.set_reserved_nodes(reserved_nodes)
+	}
+
+	fn set_reserved_only(&self, reserved_only: bool) {
+		if reserved_only {
+			self.deny_unreserved_peers()
+		} else {
+			self.accept_unreserved_peers()
+		}
+	}
+
+	fn is_major_syncing(&self) -> bool {
+		self.is_major_syncing()
+	}
+}
+
+/// The storage key of the well known nodes.
+fn well_known_nodes_key() -> StorageKey {
+	StorageKey([&twox_128(MODULE_PREFIX)[..], &twox_128(b"WellKnownNodes")[..]].concat())
+}
+
+/// The storage key of the additional connections of `node`.
+fn additional_connections_key(node: &[u8]) -> StorageKey {
+	let encoded_node = node.encode();
+	StorageKey([
+		&twox_128(MODULE_PREFIX)[..],
+		&twox_128(b"AdditionalConnections")[..],
+		&blake2_128(&encoded_node)[..],
+		&encoded_node[..],
+	].concat())
+}
+
+/// Returns the peers `local` is authorized to connect to, given the well known
+/// nodes and the additional connections of `local`.
+fn authorized_peers(
+	well_known_nodes: BTreeSet<Vec<u8>>,
+	mut connections: BTreeSet<Vec<u8>>,
+	local: &PeerId,
+) -> HashSet<PeerId> {
+	if well_known_nodes.contains(local.as_bytes()) {
+		connections.extend(well_known_nodes);
+	}
+
+	connections
+		.into_iter()
+		.filter_map(|node| match PeerId::from_bytes(node) {
+			Ok(peer_id) => Some(peer_id),
+			Err(node) => {
+				warn!(target: "node-authorization", "Ignoring invalid peer id: {:?}", node);
+				None
+			},
+		})
+		.filter(|peer_id| peer_id != local)
+		.collect()
+}
+
+/// Reads the peers the local node is authorized to connect to at block `at`.
+///
+/// Returns `None` if there are no well known nodes, i.e. node authorization
+/// isn't in use.
+fn read_authorized_peers<B, BE, C>(
+	client: &C,
+	at: &BlockId<B>,
+	local: &PeerId,
+) -> Result<Option<HashSet<PeerId>>, String>
+where
+	B: BlockT,
+	BE: Backend<B>,
+	C: StorageProvider<B, BE>,
+{
+	let read = |key: StorageKey| -> Result<BTreeSet<Vec<u8>>, String> {
+		match client.storage(at, &key).map_err(|e| format!("{:?}", e))? {
+			Some(data) => Decode::decode(&mut &data.0[..]).map_err(|e| format!("{:?}", e)),
+			None => Ok(BTreeSet::new()),
+		}
+	};
+
+	let well_known_nodes = read(well_known_nodes_key())?;
+	if well_known_nodes.is_empty() {
+		return Ok(None);
+	}
+	let connections = read(additional_connections_key(local.as_bytes()))?;
+
+	Ok(Some(authorized_peers(well_known_nodes, connections, local)))
+}
+
+/// Keeps the reserved nodes of the peerset in sync with the authorized peers.
+pub struct NodeAuthorization<N> {
+	network: Arc<N>,
+	/// The reserved nodes of the node configuration.
+	reserved_nodes: HashSet<PeerId>,
+	/// Whether the node configuration only accepts reserved nodes.
+	reserved_only: bool,
+	/// The authorized peers last set as reserved nodes, `None` if node
+	/// authorization isn't in use.
+	authorized: Option<HashSet<PeerId>>,
+}
+
+impl<N: NetworkProvider> NodeAuthorization<N> {
+	/// Creates a new `NodeAuthorization` driving the given network, configured
+	/// with the given reserved nodes and reserved only mode.
+	pub fn new(network: Arc<N>, reserved_nodes: HashSet<PeerId>, reserved_only: bool) -> Self {
+		NodeAuthorization { network, reserved_nodes, reserved_only, authorized: None }
+	}
+
+	/// Updates the peerset with the currently authorized peers, unless the node
+	/// is major syncing.
+	pub fn on_authorized_peers(&mut self, authorized: Option<HashSet<PeerId>>) {
+		if authorized == self.authorized || self.network.is_major_syncing() {
+			return;
+		}
+
+		match &authorized {
+			Some(peers) => {
+				debug!(target: "node-authorization", "Authorized peers: {:?}", peers);
+				self.network.set_reserved_nodes(peers.union(&self.reserved_nodes).cloned().collect());
+				if self.authorized.is_none() {
+					info!(target: "node-authorization", "Node authorization enabled");
+					if !self.reserved_only {
+						self.network.set_reserved_only(true);
+					}
+				}
+			},
+			None => {
+				info!(target: "node-authorization", "Node authorization disabled");
+				self.network.set_reserved_nodes(self.reserved_nodes.clone());
+				if !self.reserved_only {
+					self.network.set_reserved_only(false);
+				}
+			},
+		}
+
+		self.authorized = authorized;
+	}
+}
+
+/// Returns a future which updates the peerset of `network` on every finalized
+/// block of `client`, keeping the `reserved_nodes` and `reserved_only` mode the
+/// node is configured with.
+pub fn run<B, BE, C, N>(
+	client: Arc<C>,
+	network: Arc<N>,
+	reserved_nodes: HashSet<PeerId>,
+	reserved_only: bool,
+) -> impl Future<Output = ()>
+where
+	B: BlockT,
+	BE: Backend<B>,
+	C: BlockchainEvents<B> + StorageProvider<B, BE>,
+	N: NetworkProvider,
+{
+	let local = network.local_peer_id();
+	let mut node_authorization = NodeAuthorization::new(network.clone(), reserved_nodes, reserved_only);
+
+	client.finality_notification_stream().for_each(move |notification| {
+		if network.is_major_syncing() {
+			return future::ready(());
+		}
+
+		match read_authorized_peers(&*client, &BlockId::Hash(notification.hash), &local) {
+			Ok(authorized) => node_authorization.on_authorized_peers(authorized),
+			Err(e) => warn!(
+				target: "node-authorization",
+				"Failed to read authorized peers at {:?}: {}", notification.hash, e,
+			),
+		}
+		future::ready(())
+	})
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use frame_support::storage::{StorageMap, StorageValue};
+	use prml_node_authorization::{AdditionalConnections, WellKnownNodes};
+	use sc_network::Multiaddr;
+	use std::sync::Mutex;
+
+	#[derive(Default)]
+	struct TestNetwork {
+		local: Option<PeerId>,
+		reserved_nodes: Mutex<Vec<HashSet<PeerId>>>,
+		reserved_only: Mutex<Vec<bool>>,
+		major_syncing: Mutex<bool>,
+	}
+
+	impl NetworkStateInfo for TestNetwork {
+		fn external_addresses(&self) -> Vec<Multiaddr> {
+			Vec::new()
+		}
+
+		fn local_peer_id(&self) -> PeerId {
+			self.local.clone().unwrap()
+		}
+	}
+
+	impl NetworkProvider for TestNetwork {
+		fn set_reserved_nodes(&self, reserved_nodes: HashSet<PeerId>) {
+			self.reserved_nodes.lock().unwrap().push(reserved_nodes);
+		}
+
+		fn set_reserved_only(&self, reserved_only: bool) {
+			self.reserved_only.lock().unwrap().push(reserved_only);
+		}
+
+		fn is_major_syncing(&self) -> bool {
+			*self.major_syncing.lock().unwrap()
+		}
+	}
+
+	#[test]
+	fn storage_keys_match_module() {
+		let node = PeerId::random().into_bytes();
+
+		assert_eq!(well_known_nodes_key().0, WellKnownNodes::hashed_key().to_vec());
+		assert_eq!(additional_connections_key(&node).0, AdditionalConnections::hashed_key_for(&node));
+	}
+
+	#[test]
+	fn well_known_node_connects_to_well_known_nodes_and_connections() {
+		let (local, well_known, other) = (PeerId::random(), PeerId::random(), PeerId::random());
+		let well_known_nodes = [&local, &well_known].iter().map(|p| p.as_bytes().to_vec()).collect();
+		let connections = [other.as_bytes().to_vec()].iter().cloned().collect();
+
+		assert_eq!(
+			authorized_peers(well_known_nodes, connections, &local),
+			[well_known, other].iter().cloned().collect(),
+		);
+	}
+
+	#[test]
+	fn other_node_connects_to_connections_only() {
+		let (local, well_known, other) = (PeerId::random(), PeerId::random(), PeerId::random());
+		let well_known_nodes = [well_known.as_bytes().to_vec()].iter().cloned().collect();
+		let connections = [other.as_bytes().to_vec(), vec![1, 2, 3]].iter().cloned().collect();
+
+		assert_eq!(
+			authorized_peers(well_known_nodes, connections, &local),
+			[other].iter().cloned().collect(),
+		);
+	}
+
+	#[test]
+	fn updates_peerset_on_changes_only() {
+		let network = Arc::new(TestNetwork::default());
+		let mut node_authorization = NodeAuthorization::new(network.clone(), HashSet::new(), false);
+		let peers: HashSet<PeerId> = [PeerId::random()].iter().cloned().collect();
+
+		node_authorization.on_authorized_peers(None);
+		assert!(network.reserved_nodes.lock().unwrap().is_empty());
+
+		node_authorization.on_authorized_peers(Some(peers.clone()));
+		node_authorization.on_authorized_peers(Some(peers.clone()));
+		node_authorization.on_authorized_peers(Some(HashSet::new()));
+		assert_eq!(*network.reserved_nodes.lock().unwrap(), vec![peers, HashSet::new()]);
+		assert_eq!(*network.reserved_only.lock().unwrap(), vec![true]);
+
+		node_authorization.on_authorized_peers(None);
+		assert_eq!(*network.reserved_only.lock().unwrap(), vec![true, false]);
+	}
+
+	#[test]
+	fn keeps_configured_reserved_nodes() {
+		let network = Arc::new(TestNetwork::default());
+		let configured: HashSet<PeerId> = [PeerId::random()].iter().cloned().collect();
+		let mut node_authorization = NodeAuthorization::new(network.clone(), configured.clone(), true);
+		let peer = PeerId::random();
+
+		node_authorization.on_authorized_peers(Some([peer.clone()].iter().cloned().collect()));
+		node_authorization.on_authorized_peers(None);
+		assert_eq!(
+			*network.reserved_nodes.lock().unwrap(),
+			vec![configured.iter().cloned().chain(Some(peer)).collect(), configured],
+		);
+		assert!(network.reserved_only.lock().unwrap().is_empty());
+	}
+
+	#[test]
+	fn leaves_peerset_untouched_while_major_syncing() {
+		let network = Arc::new(TestNetwork::default());
+		let mut node_authorization = NodeAuthorization::new(network.clone(), HashSet::new(), false);
+		let peers: HashSet<PeerId> = [PeerId::random()].iter().cloned().collect();
+
+		*network.major_syncing.lock().unwrap() = true;
+		node_authorization.on_authorized_peers(Some(peers.clone()));
+		assert!(network.reserved_nodes.lock().unwrap().is_empty());
+
+		*network.major_syncing.lock().unwrap() = false;
+		node_authorization.on_authorized_peers(Some(peers.clone()));
+		assert_eq!(*network.reserved_nodes.lock().unwrap(), vec![peers]);
+	}
+}
@@ -0,0 +1,401 @@
+// Copyright 2020 Plug New Zealand Limited
+// This file is part of Plug.
+
+// Plug is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Plug is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Plug. If not, see <http://www.gnu.org/licenses/>.
+
+//! # Node Authorization module.
+//!
+//! This module keeps the set of nodes allowed to join a permissioned network,
+//! identified by their `PeerId`. The set of well known nodes is governed by
+//! `AdminOrigin`. Every node has an owner, who can allow additional connections
+//! from nodes outside of the set.
+//!
+//! The module only stores the authorization state. It is enforced by the node
+//! client (see `prml-node-authorization-client`) which follows finalized blocks
+//! and updates the reserved nodes of its peerset.
+//!
+//! ## Dispatchable methods
+//!
+//! The following methods require `AdminOrigin`.
+//!
+//! ```ignore
+//! pub fn add_well_known_node(origin, node: PeerId, owner: T::AccountId) { ... }
+//! pub fn remove_well_known_node(origin, node: PeerId) { ... }
+//! pub fn swap_well_known_node(origin, remove: PeerId, add: PeerId) { ... }
+//! pub fn reset_well_known_nodes(origin, nodes: Vec<(PeerId, T::AccountId)>) { ... }
+//! ```
+//!
+//! The following methods require a signed origin, all but `claim_node` the
+//! owner of `node`.
+//!
+//! ```ignore
+//! pub fn claim_node(origin, node: PeerId) { ... }
+//! pub fn remove_claim(origin, node: PeerId) { ... }
+//! pub fn transfer_node(origin, node: PeerId, owner: T::AccountId) { ... }
+//! pub fn add_connections(origin, node: PeerId, connections: Vec<PeerId>) { ... }
+//! pub fn remove_connections(origin, node: PeerId, connections: Vec<PeerId>) { ... }
+//! ```
+//!
+//! A well known node connects to all other well known nodes and to its
+//! additional connections. Any other node, once claimed, connects to its
+//! additional connections only.
+
+// Ensure we're `no_std` when compiling for Wasm.
+#![cfg_attr(not(feature = "std"), no_std)]
+
+#[cfg(test)]
+mod mock;
+#[cfg(test)]
+mod tests;
+
+use frame_support::{
+	decl_error, decl_event, decl_module, decl_storage, ensure,
+	traits::{EnsureOrigin, Get},
+	weights::{DispatchClass, FunctionOf, SimpleDispatchInfo, Weight},
+};
+use frame_system::ensure_signed;
+use sp_std::{collections::btree_set::BTreeSet, prelude::*};
+
+/// The encoded `PeerId` of a node, i.e. the bytes of its multihash.
+pub type PeerId = Vec<u8>;
+
+/// The weight of a call updating a single node.
+const NODE_WEIGHT: Weight = 50_000;
+/// The weight of each node given to a call updating several nodes.
+const NODE_ITEM_WEIGHT: Weight = 10_000;
+
+/// The weight of a call updating `len` nodes.
+fn nodes_weight(len: usize) -> Weight {
+	(len as Weight).saturating_mul(NODE_ITEM_WEIGHT).saturating_add(NODE_WEIGHT)
+}
+
+/// The module's config trait.
+pub trait Trait: frame_system::Trait {
+	/// The overarching event type.
+	type Event: From<Event<Self>> + Into<<Self as frame_system::Trait>::Event>;
+	/// The maximum number of well known nodes.
+	type MaxWellKnownNodes: Get<u32>;
+	/// The maximum length in bytes of a `PeerId`.
+	type MaxPeerIdLength: Get<u32>;
+	/// The maximum number of additional connections of a node.
+	type MaxAdditionalConnections: Get<u32>;
+	/// The origin which can add, remove, swap and reset well known nodes.
+	type AdminOrigin: EnsureOrigin<Self::Origin>;
+}
+
+decl_storage! {
+	trait Store for Module<T: Trait> as NodeAuthorization {
+		/// The set of well known nodes.
+		pub WellKnownNodes get(fn well_known_nodes): BTreeSet<PeerId>;
+		/// The owner of each node.
+		pub Owners get(fn owners): map hasher(blake2_128_concat) PeerId => Option<T::AccountId>;
+		/// The nodes, besides the well known nodes, each node may connect to.
+		pub AdditionalConnections get(fn additional_connections):
+			map hasher(blake2_128_concat) PeerId => BTreeSet<PeerId>;
+	}
+	add_extra_genesis {
+		config(nodes): Vec<(PeerId, T::AccountId)>;
+		build(|config: &GenesisConfig<T>| {
+			<Module<T>>::initialize_nodes(&config.nodes)
+		})
+	}
+}
+
+decl_event!(
+	pub enum Event<T>
+	where
+		AccountId = <T as frame_system::Trait>::AccountId,
+	{
+		/// The given well known node was added.
+		NodeAdded(PeerId, AccountId),
+		/// The given well known node was removed.
+		NodeRemoved(PeerId),
+		/// The first well known node was swapped with the second node.
+		NodeSwapped(PeerId, PeerId),
+		/// The well known nodes were reset.
+		NodesReset(Vec<(PeerId, AccountId)>),
+		/// The given node was claimed by a user.
+		NodeClaimed(PeerId, AccountId),
+		/// The claim of the given node was removed by its owner.
+		ClaimRemoved(PeerId, AccountId),
+		/// The node was transferred to another account.
+		NodeTransferred(PeerId, AccountId),
+		/// The allowed connections were added to a node.
+		ConnectionsAdded(PeerId, Vec<PeerId>),
+		/// The allowed connections were removed from a node.
+		ConnectionsRemoved(PeerId, Vec<PeerId>),
+	}
+);
+
+decl_error! {
+	pub enum Error for Module<T: Trait> {
+		/// The `PeerId` is longer than `MaxPeerIdLength`.
+		PeerIdTooLong,
+		/// There would be more than `MaxWellKnownNodes` well known nodes.
+		TooManyNodes,
+		/// The node is already a well known node.
+		AlreadyJoined,
+		/// The node is not a well known node.
+		NotExist,
+		/// The node is already claimed by a user.
+		AlreadyClaimed,
+		/// The node hasn't been claimed yet.
+		NotClaimed,
+		/// The origin is not the owner of the node.
+		NotOwner,
+		/// The claim of a well known node can't be removed.
+		PermissionDenied,
+		/// There would be more than `MaxAdditionalConnections` additional
+		/// connections.
+		TooManyConnections,
+	}
+}
+
+decl_module! {
+	pub struct Module<T: Trait> for enum Call where origin: T::Origin, system = frame_system {
+		// Initialises errors.
+		type Error = Error<T>;
+
+		/// The maximum number of well known nodes.
+		const MaxWellKnownNodes: u32 = T::MaxWellKnownNodes::get();
+
+		/// The maximum length in bytes of a `PeerId`.
+		const MaxPeerIdLength: u32 = T::MaxPeerIdLength::get();
+
+		/// The maximum number of additional connections of a node.
+		const MaxAdditionalConnections: u32 = T::MaxAdditionalConnections::get();
+
+		// Initialises events.
+		fn deposit_event() = default;
+
+		/// Adds a well known node owned by `owner`. Requires `AdminOrigin`.
+		#[weight = SimpleDispatchInfo::FixedNormal(NODE_WEIGHT)]
+		pub fn add_well_known_node(origin, node: PeerId, owner: T::AccountId) {
+			T::AdminOrigin::ensure_origin(origin)?;
+			Self::ensure_peer_id_length(&node)?;
+
+			let mut nodes = WellKnownNodes::get();
+			ensure!(nodes.len() < T::MaxWellKnownNodes::get() as usize, Error::<T>::TooManyNodes);
+			ensure!(!nodes.contains(&node), Error::<T>::AlreadyJoined);
+
+			nodes.insert(node.clone());
+			WellKnownNodes::put(&nodes);
+			<Owners<T>>::insert(&node, &owner);
+
+			Self::deposit_event(RawEvent::NodeAdded(node, owner));
+		}
+
+		/// Removes a well known node along with its owner and additional
+		/// connections. Requires `AdminOrigin`.
+		#[weight = SimpleDispatchInfo::FixedNormal(NODE_WEIGHT)]
+		pub fn remove_well_known_node(origin, node: PeerId) {
+			T::AdminOrigin::ensure_origin(origin)?;
+			Self::ensure_peer_id_length(&node)?;
+
+			let mut nodes = WellKnownNodes::get();
+			ensure!(nodes.contains(&node), Error::<T>::NotExist);
+
+			nodes.remove(&node);
+			WellKnownNodes::put(&nodes);
+			<Owners<T>>::remove(&node);
+			AdditionalConnections::remove(&node);
+
+			Self::deposit_event(RawEvent::NodeRemoved(node));
+		}
+
+		/// Swaps a well known node for another node, which takes over its owner
+		/// and additional connections. Requires `AdminOrigin`.
+		#[weight = SimpleDispatchInfo::FixedNormal(NODE_WEIGHT)]
+		pub fn swap_well_known_node(origin, remove: PeerId, add: PeerId) {
+			T::AdminOrigin::ensure_origin(origin)?;
+			Self::ensure_peer_id_length(&remove)?;
+			Self::ensure_peer_id_length(&add)?;
+
+			if remove == add { return Ok(()) }
+
+			let mut nodes = WellKnownNodes::get();
+			ensure!(nodes.contains(&remove), Error::<T>::NotExist);
+			ensure!(!nodes.contains(&add), Error::<T>::AlreadyJoined);
+
+			nodes.remove(&remove);
+			nodes.insert(add.clone());
+			WellKnownNodes::put(&nodes);
+			<Owners<T>>::swap(&remove, &add);
+			AdditionalConnections::swap(&remove, &add);
+
+			Self::deposit_event(RawEvent::NodeSwapped(remove, add));
+		}
+
+		/// Replaces all well known nodes and their owners. The owners and
+		/// additional connections of nodes which are no longer well known are
+		/// removed. Requires `AdminOrigin`.
+		#[weight = FunctionOf(
+			|args: (&Vec<(PeerId, T::AccountId)>,)| nodes_weight(args.0.len()),
+			DispatchClass::Normal,
+			true
+		)]
+		pub fn reset_well_known_nodes(origin, nodes: Vec<(PeerId, T::AccountId)>) {
+			T::AdminOrigin::ensure_origin(origin)?;
+			ensure!(nodes.len() <= T::MaxWellKnownNodes::get() as usize, Error::<T>::TooManyNodes);
+			for (node, _) in nodes.iter() {
+				Self::ensure_peer_id_length(node)?;
+			}
+
+			for node in WellKnownNodes::get() {
+				if !nodes.iter().any(|(new_node, _)| *new_node == node) {
+					<Owners<T>>::remove(&node);
+					AdditionalConnections::remove(&node);
+				}
+			}
+			Self::initialize_nodes(&nodes);
+
+			Self::deposit_event(RawEvent::NodesReset(nodes));
+		}
+
+		/// Claims a node which isn't owned yet, so the sender can manage its
+		/// additional connections.
+		#[weight = SimpleDispatchInfo::FixedNormal(NODE_WEIGHT)]
+		pub fn claim_node(origin, node: PeerId) {
+			let sender = ensure_signed(origin)?;
+			Self::ensure_peer_id_length(&node)?;
+
+			ensure!(!<Owners<T>>::contains_key(&node), Error::<T>::AlreadyClaimed);
+
+			<Owners<T>>::insert(&node, &sender);
+			Self::deposit_event(RawEvent::NodeClaimed(node, sender));
+		}
+
+		/// Removes the claim of a node which isn't well known, along with its
+		/// additional connections. Requires the owner of `node`.
+		#[weight = SimpleDispatchInfo::FixedNormal(NODE_WEIGHT)]
+		pub fn remove_claim(origin, node: PeerId) {
+			let sender = ensure_signed(origin)?;
+			Self::ensure_peer_id_length(&node)?;
+			Self::ensure_owner(&node, &sender)?;
+
+			ensure!(!WellKnownNodes::get().contains(&node), Error::<T>::PermissionDenied);
+
+			<Owners<T>>::remove(&node);
+			AdditionalConnections::remove(&node);
+
+			Self::deposit_event(RawEvent::ClaimRemoved(node, sender));
+		}
+
+		/// Transfers the ownership of a node to `owner`. Requires the owner of
+		/// `node`.
+		#[weight = SimpleDispatchInfo::FixedNormal(NODE_WEIGHT)]
+		pub fn transfer_node(origin, node: PeerId, owner: T::AccountId) {
+			let sender = ensure_signed(origin)?;
+			Self::ensure_peer_id_length(&node)?;
+			Self::ensure_owner(&node, &sender)?;
+
+			<Owners<T>>::insert(&node, &owner);
+
+			Self::deposit_event(RawEvent::NodeTransferred(node, owner));
+		}
+
+		/// Allows `node` to connect to the given nodes, up to
+		/// `MaxAdditionalConnections` in total. Requires the owner of `node`.
+		#[weight = FunctionOf(
+			|args: (&PeerId, &Vec<PeerId>)| nodes_weight(args.1.len()),
+			DispatchClass::Normal,
+			true
+		)]
+		pub fn add_connections(origin, node: PeerId, connections: Vec<PeerId>) {
+			let sender = ensure_signed(origin)?;
+			Self::ensure_peer_id_length(&node)?;
+			Self::ensure_owner(&node, &sender)?;
+			Self::ensure_connections_count(connections.len())?;
+
+			let mut nodes = AdditionalConnections::get(&node);
+			for connection in connections.iter() {
+				Self::ensure_peer_id_length(connection)?;
+				if *connection != node {
+					nodes.insert(connection.clone());
+				}
+			}
+			Self::ensure_connections_count(nodes.len())?;
+			AdditionalConnections::insert(&node, nodes);
+
+			Self::deposit_event(RawEvent::ConnectionsAdded(node, connections));
+		}
+
+		/// Disallows `node` to connect to the given nodes. Requires the owner
+		/// of `node`.
+		#[weight = FunctionOf(
+			|args: (&PeerId, &Vec<PeerId>)| nodes_weight(args.1.len()),
+			DispatchClass::Normal,
+			true
+		)]
+		pub fn remove_connections(origin, node: PeerId, connections: Vec<PeerId>) {
+			let sender = ensure_signed(origin)?;
+			Self::ensure_peer_id_length(&node)?;
+			Self::ensure_owner(&node, &sender)?;
+			Self::ensure_connections_count(connections.len())?;
+
+			let mut nodes = AdditionalConnections::get(&node);
+			for connection in connections.iter() {
+				nodes.remove(connection);
+			}
+			AdditionalConnections::insert(&node, nodes);
+
+			Self::deposit_event(RawEvent::ConnectionsRemoved(node, connections));
+		}
+	}
+}
+
+impl<T: Trait> Module<T> {
+	/// Puts the given nodes as the well known nodes and sets their owners.
+	fn initialize_nodes(nodes: &Vec<(PeerId, T::AccountId)>) {
+		let peer_ids = nodes
+			.iter()
+			.map(|(node, _)| node.clone())
+			.collect::<BTreeSet<PeerId>>();
+		WellKnownNodes::put(&peer_ids);
+
+		for (node, owner) in nodes.iter() {
+			<Owners<T>>::insert(node, owner);
+		}
+	}
+
+	fn ensure_peer_id_length(node: &PeerId) -> Result<(), Error<T>> {
+		ensure!(node.len() <= T::MaxPeerIdLength::get() as usize, Error::<T>::PeerIdTooLong);
+		Ok(())
+	}
+
+	fn ensure_connections_count(count: usize) -> Result<(), Error<T>> {
+		ensure!(
+			count <= T::MaxAdditionalConnections::get() as usize,
+			Error::<T>::TooManyConnections
+		);
+		Ok(())
+	}
+
+	fn ensure_owner(node: &PeerId, who: &T::AccountId) -> Result<(), Error<T>> {
+		let owner = <Owners<T>>::get(node).ok_or(Error::<T>::NotClaimed)?;
+		ensure!(owner == *who, Error::<T>::NotOwner);
+		Ok(())
+	}
+
+	/// Returns the nodes `node` is allowed to connect to.
+	pub fn authorized_connections(node: &PeerId) -> BTreeSet<PeerId> {
+		let mut connections = AdditionalConnections::get(node);
+		let nodes = WellKnownNodes::get();
+		if nodes.contains(node) {
+			connections.extend(nodes);
+		}
+		connections.remove(node);
+		connections
+	}
+}
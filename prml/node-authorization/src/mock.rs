@@ -0,0 +1,114 @@
+// Copyright 2020 Plug New Zealand Limited
+// This file is part of Plug.
+
+// Plug is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Plug is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Plug. If not, see <http://www.gnu.org/licenses/>.
+
+use super::*;
+use frame_support::{
+	additional_traits::DummyDispatchVerifier, impl_outer_event, impl_outer_origin,
+	parameter_types, weights::Weight,
+};
+use frame_system::EnsureRoot;
+use sp_core::H256;
+use sp_io::TestExternalities;
+use sp_runtime::{
+	testing::Header,
+	traits::{BlakeTwo256, IdentityLookup},
+	Perbill,
+};
+
+pub type MockSystem = frame_system::Module<Test>;
+pub type NodeAuthorization = Module<Test>;
+pub type AccountId = u64;
+
+impl_outer_origin! {
+	pub enum Origin for Test where system = frame_system {}
+}
+
+mod node_authorization {
+	pub use crate::Event;
+}
+
+impl_outer_event! {
+	pub enum TestEvent for Test {
+		frame_system,
+		node_authorization<T>,
+	}
+}
+
+#[derive(Clone, Eq, PartialEq)]
+pub struct Test;
+
+parameter_types! {
+	pub const BlockHashCount: u64 = 250;
+	pub const MaximumBlockWeight: Weight = 1024;
+	pub const MaximumBlockLength: u32 = 2 * 1024;
+	pub const AvailableBlockRatio: Perbill = Perbill::one();
+	pub const MaxWellKnownNodes: u32 = 4;
+	pub const MaxPeerIdLength: u32 = 2;
+	pub const MaxAdditionalConnections: u32 = 3;
+}
+
+impl frame_system::Trait for Test {
+	type Origin = Origin;
+	type Index = u64;
+	type BlockNumber = u64;
+	type Call = ();
+	type Hash = H256;
+	type Hashing = BlakeTwo256;
+	type AccountId = AccountId;
+	type Lookup = IdentityLookup<Self::AccountId>;
+	type Header = Header;
+	type Event = TestEvent;
+	type BlockHashCount = BlockHashCount;
+	type MaximumBlockWeight = MaximumBlockWeight;
+	type AvailableBlockRatio = AvailableBlockRatio;
+	type MaximumBlockLength = MaximumBlockLength;
+	type Doughnut = ();
+	type DelegatedDispatchVerifier = DummyDispatchVerifier<Self::Doughnut, Self::AccountId>;
+	type Version = ();
+	type ModuleToIndex = ();
+}
+
+impl Trait for Test {
+	type Event = TestEvent;
+	type MaxWellKnownNodes = MaxWellKnownNodes;
+	type MaxPeerIdLength = MaxPeerIdLength;
+	type MaxAdditionalConnections = MaxAdditionalConnections;
+	type AdminOrigin = EnsureRoot<AccountId, ()>;
+}
+
+#[derive(Default)]
+pub struct ExtBuilder {
+	nodes: Vec<(PeerId, AccountId)>,
+}
+
+impl ExtBuilder {
+	pub fn node(mut self, node: PeerId, owner: AccountId) -> Self {
+		self.nodes.push((node, owner));
+		self
+	}
+
+	pub fn build(self) -> TestExternalities {
+		let mut t = frame_system::GenesisConfig::default()
+			.build_storage::<Test>()
+			.unwrap();
+		GenesisConfig::<Test> {
+			nodes: self.nodes,
+		}
+		.assimilate_storage(&mut t)
+		.unwrap();
+		t.into()
+	}
+}
@@ -0,0 +1,328 @@
+// Copyright 2020 Plug New Zealand Limited
+// This file is part of Plug.
+
+// Plug is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Plug is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Plug. If not, see <http://www.gnu.org/licenses/>.
+
+use super::*;
+use crate::mock::*;
+use frame_support::{assert_noop, assert_ok};
+use sp_runtime::DispatchError::BadOrigin;
+
+const ALICE: AccountId = 1;
+const BOB: AccountId = 2;
+const CHARLIE: AccountId = 3;
+
+fn peer(id: u8) -> PeerId {
+	vec![id]
+}
+
+fn peers(ids: &[u8]) -> BTreeSet<PeerId> {
+	ids.iter().map(|id| peer(*id)).collect()
+}
+
+#[test]
+fn genesis_nodes_are_well_known() {
+	ExtBuilder::default()
+		.node(peer(10), ALICE)
+		.node(peer(20), BOB)
+		.build()
+		.execute_with(|| {
+			assert_eq!(NodeAuthorization::well_known_nodes(), peers(&[10, 20]));
+			assert_eq!(NodeAuthorization::owners(peer(10)), Some(ALICE));
+			assert_eq!(NodeAuthorization::owners(peer(20)), Some(BOB));
+		});
+}
+
+#[test]
+fn add_well_known_node_works() {
+	ExtBuilder::default().node(peer(10), ALICE).build().execute_with(|| {
+		assert_noop!(
+			NodeAuthorization::add_well_known_node(Origin::signed(ALICE), peer(20), BOB),
+			BadOrigin
+		);
+		assert_noop!(
+			NodeAuthorization::add_well_known_node(Origin::ROOT, vec![1, 2, 3], BOB),
+			Error::<Test>::PeerIdTooLong
+		);
+		assert_noop!(
+			NodeAuthorization::add_well_known_node(Origin::ROOT, peer(10), BOB),
+			Error::<Test>::AlreadyJoined
+		);
+
+		assert_ok!(NodeAuthorization::add_well_known_node(Origin::ROOT, peer(20), BOB));
+		assert_eq!(NodeAuthorization::well_known_nodes(), peers(&[10, 20]));
+		assert_eq!(NodeAuthorization::owners(peer(20)), Some(BOB));
+		assert_eq!(
+			MockSystem::events().last().unwrap().event,
+			TestEvent::node_authorization(RawEvent::NodeAdded(peer(20), BOB))
+		);
+
+		assert_ok!(NodeAuthorization::add_well_known_node(Origin::ROOT, peer(30), BOB));
+		assert_ok!(NodeAuthorization::add_well_known_node(Origin::ROOT, peer(40), BOB));
+		assert_noop!(
+			NodeAuthorization::add_well_known_node(Origin::ROOT, peer(50), BOB),
+			Error::<Test>::TooManyNodes
+		);
+	});
+}
+
+#[test]
+fn remove_well_known_node_works() {
+	ExtBuilder::default()
+		.node(peer(10), ALICE)
+		.node(peer(20), BOB)
+		.build()
+		.execute_with(|| {
+			assert_ok!(NodeAuthorization::add_connections(
+				Origin::signed(BOB),
+				peer(20),
+				vec![peer(30)]
+			));
+
+			assert_noop!(
+				NodeAuthorization::remove_well_known_node(Origin::signed(ALICE), peer(20)),
+				BadOrigin
+			);
+			assert_noop!(
+				NodeAuthorization::remove_well_known_node(Origin::ROOT, peer(30)),
+				Error::<Test>::NotExist
+			);
+
+			assert_ok!(NodeAuthorization::remove_well_known_node(Origin::ROOT, peer(20)));
+			assert_eq!(NodeAuthorization::well_known_nodes(), peers(&[10]));
+			assert_eq!(NodeAuthorization::owners(peer(20)), None);
+			assert!(NodeAuthorization::additional_connections(peer(20)).is_empty());
+		});
+}
+
+#[test]
+fn swap_well_known_node_works() {
+	ExtBuilder::default()
+		.node(peer(10), ALICE)
+		.node(peer(20), BOB)
+		.build()
+		.execute_with(|| {
+			assert_ok!(NodeAuthorization::add_connections(
+				Origin::signed(BOB),
+				peer(20),
+				vec![peer(30)]
+			));
+
+			assert_noop!(
+				NodeAuthorization::swap_well_known_node(Origin::signed(BOB), peer(20), peer(40)),
+				BadOrigin
+			);
+			assert_noop!(
+				NodeAuthorization::swap_well_known_node(Origin::ROOT, peer(30), peer(40)),
+				Error::<Test>::NotExist
+			);
+			assert_noop!(
+				NodeAuthorization::swap_well_known_node(Origin::ROOT, peer(20), peer(10)),
+				Error::<Test>::AlreadyJoined
+			);
+
+			assert_ok!(NodeAuthorization::swap_well_known_node(Origin::ROOT, peer(20), peer(40)));
+			assert_eq!(NodeAuthorization::well_known_nodes(), peers(&[10, 40]));
+			assert_eq!(NodeAuthorization::owners(peer(20)), None);
+			assert_eq!(NodeAuthorization::owners(peer(40)), Some(BOB));
+			assert_eq!(NodeAuthorization::additional_connections(peer(40)), peers(&[30]));
+		});
+}
+
+#[test]
+fn reset_well_known_nodes_works() {
+	ExtBuilder::default()
+		.node(peer(10), ALICE)
+		.node(peer(20), BOB)
+		.build()
+		.execute_with(|| {
+			assert_noop!(
+				NodeAuthorization::reset_well_known_nodes(Origin::signed(ALICE), vec![]),
+				BadOrigin
+			);
+			assert_noop!(
+				NodeAuthorization::reset_well_known_nodes(
+					Origin::ROOT,
+					(1..=5).map(|id| (peer(id), ALICE)).collect()
+				),
+				Error::<Test>::TooManyNodes
+			);
+
+			assert_ok!(NodeAuthorization::reset_well_known_nodes(
+				Origin::ROOT,
+				vec![(peer(10), CHARLIE), (peer(30), BOB)]
+			));
+			assert_eq!(NodeAuthorization::well_known_nodes(), peers(&[10, 30]));
+			assert_eq!(NodeAuthorization::owners(peer(10)), Some(CHARLIE));
+			assert_eq!(NodeAuthorization::owners(peer(20)), None);
+			assert_eq!(NodeAuthorization::owners(peer(30)), Some(BOB));
+		});
+}
+
+#[test]
+fn claim_node_works() {
+	ExtBuilder::default().node(peer(10), ALICE).build().execute_with(|| {
+		assert_noop!(
+			NodeAuthorization::claim_node(Origin::signed(BOB), peer(10)),
+			Error::<Test>::AlreadyClaimed
+		);
+
+		assert_ok!(NodeAuthorization::claim_node(Origin::signed(BOB), peer(20)));
+		assert_eq!(NodeAuthorization::owners(peer(20)), Some(BOB));
+		assert_noop!(
+			NodeAuthorization::claim_node(Origin::signed(CHARLIE), peer(20)),
+			Error::<Test>::AlreadyClaimed
+		);
+	});
+}
+
+#[test]
+fn remove_claim_works() {
+	ExtBuilder::default().node(peer(10), ALICE).build().execute_with(|| {
+		assert_ok!(NodeAuthorization::claim_node(Origin::signed(BOB), peer(20)));
+
+		assert_noop!(
+			NodeAuthorization::remove_claim(Origin::signed(ALICE), peer(10)),
+			Error::<Test>::PermissionDenied
+		);
+		assert_noop!(
+			NodeAuthorization::remove_claim(Origin::signed(CHARLIE), peer(20)),
+			Error::<Test>::NotOwner
+		);
+		assert_noop!(
+			NodeAuthorization::remove_claim(Origin::signed(BOB), peer(30)),
+			Error::<Test>::NotClaimed
+		);
+
+		assert_ok!(NodeAuthorization::remove_claim(Origin::signed(BOB), peer(20)));
+		assert_eq!(NodeAuthorization::owners(peer(20)), None);
+	});
+}
+
+#[test]
+fn transfer_node_works() {
+	ExtBuilder::default().node(peer(10), ALICE).build().execute_with(|| {
+		assert_noop!(
+			NodeAuthorization::transfer_node(Origin::signed(BOB), peer(10), CHARLIE),
+			Error::<Test>::NotOwner
+		);
+
+		assert_ok!(NodeAuthorization::transfer_node(Origin::signed(ALICE), peer(10), BOB));
+		assert_eq!(NodeAuthorization::owners(peer(10)), Some(BOB));
+		assert_eq!(
+			MockSystem::events().last().unwrap().event,
+			TestEvent::node_authorization(RawEvent::NodeTransferred(peer(10), BOB))
+		);
+	});
+}
+
+#[test]
+fn connections_are_managed_by_owner() {
+	ExtBuilder::default().node(peer(10), ALICE).build().execute_with(|| {
+		assert_noop!(
+			NodeAuthorization::add_connections(Origin::signed(BOB), peer(10), vec![peer(20)]),
+			Error::<Test>::NotOwner
+		);
+
+		assert_ok!(NodeAuthorization::add_connections(
+			Origin::signed(ALICE),
+			peer(10),
+			vec![peer(10), peer(20), peer(30)]
+		));
+		assert_eq!(NodeAuthorization::additional_connections(peer(10)), peers(&[20, 30]));
+
+		assert_noop!(
+			NodeAuthorization::remove_connections(Origin::signed(BOB), peer(10), vec![peer(20)]),
+			Error::<Test>::NotOwner
+		);
+		assert_ok!(NodeAuthorization::remove_connections(
+			Origin::signed(ALICE),
+			peer(10),
+			vec![peer(20)]
+		));
+		assert_eq!(NodeAuthorization::additional_connections(peer(10)), peers(&[30]));
+	});
+}
+
+#[test]
+fn additional_connections_are_bounded() {
+	ExtBuilder::default().node(peer(10), ALICE).build().execute_with(|| {
+		assert_noop!(
+			NodeAuthorization::add_connections(
+				Origin::signed(ALICE),
+				peer(10),
+				vec![peer(20), peer(30), peer(40), peer(50)]
+			),
+			Error::<Test>::TooManyConnections
+		);
+
+		assert_ok!(NodeAuthorization::add_connections(
+			Origin::signed(ALICE),
+			peer(10),
+			vec![peer(20), peer(30)]
+		));
+		assert_noop!(
+			NodeAuthorization::add_connections(
+				Origin::signed(ALICE),
+				peer(10),
+				vec![peer(40), peer(50)]
+			),
+			Error::<Test>::TooManyConnections
+		);
+		// Connections already allowed don't count twice.
+		assert_ok!(NodeAuthorization::add_connections(
+			Origin::signed(ALICE),
+			peer(10),
+			vec![peer(20), peer(40)]
+		));
+		assert_eq!(NodeAuthorization::additional_connections(peer(10)), peers(&[20, 30, 40]));
+
+		assert_noop!(
+			NodeAuthorization::remove_connections(
+				Origin::signed(ALICE),
+				peer(10),
+				vec![peer(20), peer(30), peer(40), peer(50)]
+			),
+			Error::<Test>::TooManyConnections
+		);
+	});
+}
+
+#[test]
+fn authorized_connections_works() {
+	ExtBuilder::default()
+		.node(peer(10), ALICE)
+		.node(peer(20), BOB)
+		.build()
+		.execute_with(|| {
+			assert_ok!(NodeAuthorization::add_connections(
+				Origin::signed(ALICE),
+				peer(10),
+				vec![peer(30)]
+			));
+			assert_ok!(NodeAuthorization::claim_node(Origin::signed(CHARLIE), peer(30)));
+			assert_ok!(NodeAuthorization::add_connections(
+				Origin::signed(CHARLIE),
+				peer(30),
+				vec![peer(10)]
+			));
+
+			// A well known node connects to the other well known nodes and its connections.
+			assert_eq!(NodeAuthorization::authorized_connections(&peer(10)), peers(&[20, 30]));
+			assert_eq!(NodeAuthorization::authorized_connections(&peer(20)), peers(&[10]));
+			// Any other node connects to its connections only.
+			assert_eq!(NodeAuthorization::authorized_connections(&peer(30)), peers(&[10]));
+			assert!(NodeAuthorization::authorized_connections(&peer(40)).is_empty());
+		});
+}